@@ -0,0 +1,17 @@
+-- Working-hours template per user (groundwork for VAVAILABILITY)
+CREATE TABLE IF NOT EXISTS user_availability (
+    user_id TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Working-hours windows, weekday is 0 (Monday) through 6 (Sunday)
+CREATE TABLE IF NOT EXISTS availability_windows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL REFERENCES user_availability(user_id) ON DELETE CASCADE,
+    weekday INTEGER NOT NULL CHECK (weekday BETWEEN 0 AND 6),
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_availability_windows_user_id ON availability_windows(user_id);
@@ -0,0 +1,2 @@
+-- Comma-separated component types a calendar accepts (supported-calendar-component-set)
+ALTER TABLE calendars ADD COLUMN component_types TEXT NOT NULL DEFAULT 'VEVENT,VTODO';
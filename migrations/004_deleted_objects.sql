@@ -0,0 +1,18 @@
+-- Tombstones of deleted calendar objects, kept for a retention window so a
+-- mistaken delete can be undone
+CREATE TABLE IF NOT EXISTS deleted_objects (
+    id TEXT PRIMARY KEY,
+    calendar_id TEXT NOT NULL REFERENCES calendars(id) ON DELETE CASCADE,
+    uid TEXT NOT NULL,
+    etag TEXT NOT NULL,
+    ical_data TEXT NOT NULL,
+    component_type TEXT NOT NULL DEFAULT 'VEVENT',
+    dtstart TEXT,
+    dtend TEXT,
+    summary TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    deleted_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(calendar_id, uid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_deleted_objects_deleted_at ON deleted_objects(deleted_at);
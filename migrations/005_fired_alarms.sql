@@ -0,0 +1,10 @@
+-- Alarm triggers the reminder dispatcher has already delivered, so each one
+-- fires at most once. `alarm_key` identifies the VALARM within its object.
+CREATE TABLE IF NOT EXISTS fired_alarms (
+    calendar_id TEXT NOT NULL REFERENCES calendars(id) ON DELETE CASCADE,
+    object_uid TEXT NOT NULL,
+    alarm_key TEXT NOT NULL,
+    trigger_at TEXT NOT NULL,
+    fired_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (calendar_id, object_uid, alarm_key, trigger_at)
+);
@@ -0,0 +1,9 @@
+-- Clients (by User-Agent) that have made authenticated CalDAV requests, so
+-- users can see which devices are syncing their calendars
+CREATE TABLE IF NOT EXISTS sync_clients (
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_agent TEXT NOT NULL,
+    first_seen TEXT NOT NULL DEFAULT (datetime('now')),
+    last_seen TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (user_id, user_agent)
+);
@@ -0,0 +1,2 @@
+-- Client-chosen sort position of a calendar (Apple calendar-order)
+ALTER TABLE calendars ADD COLUMN calendar_order INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Whether a calendar's events count as busy time (schedule-calendar-transp)
+ALTER TABLE calendars ADD COLUMN transp TEXT NOT NULL DEFAULT 'opaque';
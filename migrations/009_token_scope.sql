@@ -0,0 +1,2 @@
+-- What an MCP token may do: 'read' or 'read-write'
+ALTER TABLE mcp_tokens ADD COLUMN scope TEXT NOT NULL DEFAULT 'read-write';
@@ -0,0 +1,5 @@
+-- Range queries filter a calendar's objects by start and end
+CREATE INDEX IF NOT EXISTS idx_calendar_objects_calendar_range ON calendar_objects(calendar_id, dtstart, dtend);
+
+-- Sync reports read a calendar's changes in insertion order
+CREATE INDEX IF NOT EXISTS idx_sync_changes_calendar_id_id ON sync_changes(calendar_id, id);
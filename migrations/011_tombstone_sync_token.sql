@@ -0,0 +1,3 @@
+-- The calendar sync token a delete moved to, so a sync from an older token
+-- can still report the deletion after its change row has been pruned
+ALTER TABLE deleted_objects ADD COLUMN deleted_sync_token TEXT;
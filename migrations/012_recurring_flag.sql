@@ -0,0 +1,6 @@
+-- Whether an object's data mentions RRULE or RDATE. The flag is coarse (the
+-- text could also appear in a description), but it lets range queries fetch
+-- recurring objects through an index and parse only those
+ALTER TABLE calendar_objects ADD COLUMN recurring INTEGER GENERATED ALWAYS AS (instr(ical_data, 'RRULE') > 0 OR instr(ical_data, 'RDATE') > 0) VIRTUAL;
+
+CREATE INDEX IF NOT EXISTS idx_calendar_objects_calendar_recurring ON calendar_objects(calendar_id, recurring);
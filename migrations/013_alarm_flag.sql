@@ -0,0 +1,10 @@
+-- Whether an object's data contains a VALARM, so the reminder dispatcher
+-- reads only those rows through an index instead of scanning every object.
+-- Like `recurring`, the flag is a text match and the rows are parsed to
+-- find the actual triggers
+ALTER TABLE calendar_objects ADD COLUMN has_alarm INTEGER GENERATED ALWAYS AS (instr(ical_data, 'BEGIN:VALARM') > 0) VIRTUAL;
+
+CREATE INDEX IF NOT EXISTS idx_calendar_objects_has_alarm ON calendar_objects(has_alarm);
+
+-- Delivered triggers are pruned by time once they can no longer come due
+CREATE INDEX IF NOT EXISTS idx_fired_alarms_trigger_at ON fired_alarms(trigger_at);
@@ -5,7 +5,14 @@ use axum::{
 use sqlx::SqlitePool;
 
 use crate::db::models::User;
-use crate::db::users;
+use crate::db::{sync_clients, users};
+
+tokio::task_local! {
+    /// The User-Agent of the request being handled, set by
+    /// [`super::track_user_agent`] so that successful logins can be recorded
+    /// as sync clients without threading the header through every handler.
+    pub static USER_AGENT: Option<String>;
+}
 
 /// Parse HTTP Basic Auth header and verify credentials.
 async fn parse_basic_auth(pool: &SqlitePool, header: &str) -> Result<Option<User>, ()> {
@@ -21,19 +28,25 @@ async fn parse_basic_auth(pool: &SqlitePool, header: &str) -> Result<Option<User
 }
 
 /// Build a 401 Unauthorized response with WWW-Authenticate header.
-/// Includes DAV headers so Apple Calendar's accountsd recognizes this as
-/// a CalDAV server and prompts for credentials.
+/// The router adds DAV headers so Apple Calendar's accountsd recognizes
+/// this as a CalDAV server and prompts for credentials.
 pub fn unauthorized_response_fn() -> Response {
     Response::builder()
         .status(StatusCode::UNAUTHORIZED)
         .header(header::WWW_AUTHENTICATE, "Basic realm=\"CalDAV\"")
-        .header("DAV", "1, 2, 3, calendar-access, calendar-schedule")
         .body(axum::body::Body::from("Unauthorized"))
         .unwrap()
 }
 
 /// Try to authenticate from a raw Authorization header value.
 /// Returns the User if valid, None otherwise.
+/// The request's User-Agent, if any, is recorded as a sync client.
 pub async fn try_basic_auth(pool: &SqlitePool, header: &str) -> Option<User> {
-    parse_basic_auth(pool, header).await.ok().flatten()
+    let user = parse_basic_auth(pool, header).await.ok().flatten()?;
+    if let Ok(Some(user_agent)) = USER_AGENT.try_with(Option::clone)
+        && let Err(e) = sync_clients::record_client(pool, &user.id, &user_agent).await
+    {
+        tracing::warn!("Failed to record sync client: {e}");
+    }
+    Some(user)
 }
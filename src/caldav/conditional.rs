@@ -0,0 +1,254 @@
+//! Minimal support for the WebDAV `If` request header (RFC 4918 §10.4).
+//!
+//! Only entity-tag conditions are meaningful here: the server does not
+//! implement locking, so a state token (`<urn:uuid:...>`) never matches and
+//! `Not <token>` always holds. Both the untagged form
+//! `If: (["etag"])` and the tagged form `If: </path/a.ics> (["etag"])` are
+//! accepted.
+
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use sqlx::SqlitePool;
+
+use crate::db::events;
+
+/// One condition inside a parenthesised list.
+#[derive(Debug, PartialEq)]
+struct Condition {
+    negated: bool,
+    kind: ConditionKind,
+}
+
+#[derive(Debug, PartialEq)]
+enum ConditionKind {
+    StateToken,
+    ETag(String),
+}
+
+/// A parenthesised list of conditions, optionally tagged with the resource
+/// it applies to.
+#[derive(Debug, PartialEq)]
+struct List {
+    resource: Option<String>,
+    conditions: Vec<Condition>,
+}
+
+/// Evaluate an `If` header for the resource at `request_path`, whose current
+/// ETag is `current_etag` (`None` if it does not exist). Returns `None` if the
+/// header is malformed.
+///
+/// The header holds if any list that applies to this resource has all of its
+/// conditions true. Lists tagged with a different resource are ignored; if
+/// no list applies the header holds.
+fn evaluate_if(value: &str, request_path: &str, current_etag: Option<&str>) -> Option<bool> {
+    let lists = parse_if(value)?;
+    let mut applicable = lists
+        .iter()
+        .filter(|list| {
+            list.resource
+                .as_deref()
+                .is_none_or(|tag| same_resource(tag, request_path))
+        })
+        .peekable();
+    if applicable.peek().is_none() {
+        return Some(true);
+    }
+    Some(applicable.any(|list| {
+        list.conditions.iter().all(|c| {
+            let matches = match &c.kind {
+                ConditionKind::StateToken => false,
+                ConditionKind::ETag(etag) => current_etag == Some(etag.as_str()),
+            };
+            matches != c.negated
+        })
+    }))
+}
+
+/// Check the `If` header of a request against the object `uid` in
+/// `calendar_id`. Returns the error response to send if the header is
+/// malformed (400) or its condition fails (412), or `None` if the request
+/// may proceed.
+pub async fn check_if_header(
+    pool: &SqlitePool,
+    headers: &HeaderMap,
+    request_path: &str,
+    calendar_id: &str,
+    uid: &str,
+) -> Option<Response> {
+    let value = headers.get("if")?;
+    let Some(value) = value.to_str().ok() else {
+        return Some((StatusCode::BAD_REQUEST, "Invalid If header").into_response());
+    };
+    let current_etag = match events::get_object_by_uid(pool, calendar_id, uid).await {
+        Ok(obj) => obj.map(|o| o.etag),
+        Err(e) => {
+            tracing::error!("Failed to check existing object: {e}");
+            return Some((StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response());
+        }
+    };
+    match evaluate_if(value, request_path, current_etag.as_deref()) {
+        Some(true) => None,
+        Some(false) => Some(
+            (
+                StatusCode::PRECONDITION_FAILED,
+                "If header condition failed",
+            )
+                .into_response(),
+        ),
+        None => Some((StatusCode::BAD_REQUEST, "Invalid If header").into_response()),
+    }
+}
+
+/// Parse the header into its lists.
+fn parse_if(value: &str) -> Option<Vec<List>> {
+    let mut lists = Vec::new();
+    let mut resource = None;
+    let mut rest = value.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('<') {
+            let end = after.find('>')?;
+            resource = Some(after[..end].to_string());
+            rest = after[end + 1..].trim_start();
+        } else if let Some(after) = rest.strip_prefix('(') {
+            let (conditions, remainder) = parse_conditions(after)?;
+            lists.push(List {
+                resource: resource.clone(),
+                conditions,
+            });
+            rest = remainder.trim_start();
+        } else {
+            return None;
+        }
+    }
+
+    (!lists.is_empty()).then_some(lists)
+}
+
+/// Parse the conditions of one list up to and including its closing `)`.
+/// Returns the conditions and the input after the `)`.
+fn parse_conditions(mut rest: &str) -> Option<(Vec<Condition>, &str)> {
+    let mut conditions = Vec::new();
+    let mut negated = false;
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix(')') {
+            if negated || conditions.is_empty() {
+                return None;
+            }
+            return Some((conditions, after));
+        }
+        if rest.len() >= 3 && rest[..3].eq_ignore_ascii_case("not") {
+            negated = true;
+            rest = &rest[3..];
+            continue;
+        }
+        let kind = if let Some(after) = rest.strip_prefix('<') {
+            let end = after.find('>')?;
+            rest = &after[end + 1..];
+            ConditionKind::StateToken
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let end = etag_end(after)?;
+            let etag = after[..end].trim().to_string();
+            rest = &after[end + 1..];
+            ConditionKind::ETag(etag)
+        } else {
+            return None;
+        };
+        conditions.push(Condition { negated, kind });
+        negated = false;
+    }
+}
+
+/// Index of the `]` closing an entity tag, skipping over a quoted value.
+fn etag_end(value: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ']' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether a resource tag (absolute URL or path) names `request_path`.
+fn same_resource(tag: &str, request_path: &str) -> bool {
+    let path = match tag.find("://") {
+        Some(scheme_end) => {
+            let after = &tag[scheme_end + 3..];
+            after.find('/').map_or("/", |i| &after[i..])
+        }
+        None => tag,
+    };
+    path.trim_end_matches('/') == request_path.trim_end_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "/caldav/users/alice/cal/event.ics";
+
+    #[test]
+    fn test_untagged_etag_conditions() {
+        assert_eq!(
+            evaluate_if(r#"(["abc"])"#, PATH, Some("\"abc\"")),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_if(r#"(["abc"])"#, PATH, Some("\"xyz\"")),
+            Some(false)
+        );
+        assert_eq!(evaluate_if(r#"(["abc"])"#, PATH, None), Some(false));
+        assert_eq!(
+            evaluate_if(r#"(Not ["abc"])"#, PATH, Some("\"xyz\"")),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_if(r#"(not ["abc"])"#, PATH, Some("\"abc\"")),
+            Some(false)
+        );
+        // Any list may match
+        assert_eq!(
+            evaluate_if(r#"(["old"]) (["abc"])"#, PATH, Some("\"abc\"")),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_state_tokens_never_match() {
+        assert_eq!(
+            evaluate_if(
+                "(<urn:uuid:181d4fae-7d8c-11d0-a765-00a0c91e6bf2>)",
+                PATH,
+                Some("\"a\"")
+            ),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate_if(r#"(Not <DAV:no-lock> ["a"])"#, PATH, Some("\"a\"")),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_tagged_lists_apply_to_their_resource() {
+        let header = format!(r#"<http://example.com{PATH}> (["abc"])"#);
+        assert_eq!(evaluate_if(&header, PATH, Some("\"abc\"")), Some(true));
+        assert_eq!(evaluate_if(&header, PATH, Some("\"xyz\"")), Some(false));
+        // A list for another resource does not constrain this one
+        let other = r#"</caldav/users/alice/cal/other.ics> (["abc"])"#;
+        assert_eq!(evaluate_if(other, PATH, Some("\"xyz\"")), Some(true));
+    }
+
+    #[test]
+    fn test_malformed_headers() {
+        assert_eq!(evaluate_if("", PATH, None), None);
+        assert_eq!(evaluate_if(r#"["abc"]"#, PATH, None), None);
+        assert_eq!(evaluate_if(r#"(["abc"]"#, PATH, None), None);
+        assert_eq!(evaluate_if("()", PATH, None), None);
+        assert_eq!(evaluate_if("(Not)", PATH, None), None);
+    }
+}
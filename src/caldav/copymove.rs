@@ -0,0 +1,219 @@
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::{Request, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use sqlx::SqlitePool;
+
+use super::HrefContext;
+use super::xml::properties;
+use crate::db::models::User;
+use crate::db::{calendars, events};
+use crate::ical::parser;
+
+/// Handle COPY or MOVE for a calendar object (RFC 4918 §9.8, §9.9):
+/// /caldav/users/{username}/{calendar_id}/{uid}.ics
+///
+/// The target comes from the `Destination` header and may be in another
+/// calendar the user can access. Objects are stored under their UID, so a
+/// destination filename that differs from the UID gives the copy that UID.
+/// Returns `201 Created` for a new resource or `204 No Content` when an
+/// existing one was overwritten; `Overwrite: F` turns the latter into 412.
+/// The written object's ETag follows `ETAG_MODE`, as for a PUT.
+pub async fn handle_copy_move(
+    State(pool): State<SqlitePool>,
+    Path((username, calendar_id, filename)): Path<(String, String, String)>,
+    request: Request<Body>,
+    is_move: bool,
+) -> Response {
+    let user = request.extensions().get::<User>().unwrap().clone();
+    let config = super::request_config(&request);
+    let href_ctx = request
+        .extensions()
+        .get::<HrefContext>()
+        .cloned()
+        .unwrap_or(HrefContext {
+            email: None,
+            username,
+        });
+
+    let Some(destination) = request
+        .headers()
+        .get("Destination")
+        .and_then(|v| v.to_str().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "Missing Destination header").into_response();
+    };
+    let Some((target_calendar_id, target_filename)) = parse_destination(destination) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Destination is not a calendar object",
+        )
+            .into_response();
+    };
+    let overwrite = request
+        .headers()
+        .get("Overwrite")
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|v| !v.trim().eq_ignore_ascii_case("F"));
+
+    if super::verify_calendar_access(&pool, &user, &target_calendar_id)
+        .await
+        .is_none()
+    {
+        // RFC 4918 §9.8.5: 409 when the destination collection is missing
+        return match calendars::get_calendar_by_id(&pool, &target_calendar_id).await {
+            Ok(None) => (StatusCode::CONFLICT, "Destination calendar not found").into_response(),
+            _ => (StatusCode::FORBIDDEN, "Access denied").into_response(),
+        };
+    }
+    if !super::verify_calendar_write(&pool, &user, &target_calendar_id).await {
+        return super::read_only_denied();
+    }
+
+    let source_uid = filename.trim_end_matches(".ics");
+    let target_uid = target_filename.trim_end_matches(".ics");
+    if target_calendar_id == calendar_id && target_uid == source_uid {
+        return (StatusCode::FORBIDDEN, "Source and destination are the same").into_response();
+    }
+
+    let source = match events::get_object_by_uid(&pool, &calendar_id, source_uid).await {
+        Ok(Some(obj)) => obj,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Object not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get object: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+    let existing = match events::get_object_by_uid(&pool, &target_calendar_id, target_uid).await {
+        Ok(obj) => obj,
+        Err(e) => {
+            tracing::error!("Failed to check destination: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+    if existing.is_some() && !overwrite {
+        return (StatusCode::PRECONDITION_FAILED, "Destination exists").into_response();
+    }
+    if let Some(response) = super::put::check_supported_component(
+        &pool,
+        &target_calendar_id,
+        target_uid,
+        &source.component_type,
+    )
+    .await
+    {
+        return response;
+    }
+
+    let ical_data = if target_uid == source.uid {
+        source.ical_data.clone()
+    } else {
+        with_uid(&source.ical_data, &source.uid, target_uid)
+    };
+    let fields = parser::extract_fields(&ical_data);
+    // The destination write and, for MOVE, the source delete commit
+    // together, so a failure cannot leave the object in both places.
+    let written = async {
+        let mut tx = pool.begin().await?;
+        let written = events::upsert_object_in(
+            &mut tx,
+            &target_calendar_id,
+            target_uid,
+            &ical_data,
+            events::ObjectFields {
+                component_type: &fields.component_type,
+                dtstart: fields.dtstart.as_deref(),
+                dtend: fields.dtend.as_deref(),
+                summary: fields.summary.as_deref(),
+            },
+            config.etag_mode,
+        )
+        .await?;
+        if is_move {
+            events::delete_object_in(&mut tx, &calendar_id, &source.uid).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, crate::error::AppError>(written)
+    };
+    let (obj, created) = match written.await {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("Failed to write destination object: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to save event").into_response();
+        }
+    };
+
+    if created {
+        let href = properties::calendar_object_href_for_context(
+            &href_ctx,
+            &target_calendar_id,
+            &super::put::encode_path_segment(&obj.uid),
+        );
+        Response::builder()
+            .status(StatusCode::CREATED)
+            .header(header::ETAG, &obj.etag)
+            .header(header::LOCATION, href)
+            .body(Body::empty())
+            .unwrap()
+    } else {
+        Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header(header::ETAG, &obj.etag)
+            .body(Body::empty())
+            .unwrap()
+    }
+}
+
+/// Resolve a `Destination` header (absolute URL or path) to a
+/// `(calendar_id, filename)` pair. Both the username-based and the
+/// email-based object paths are accepted.
+fn parse_destination(destination: &str) -> Option<(String, String)> {
+    let path = match destination.split_once("://") {
+        Some((_, rest)) => &rest[rest.find('/')?..],
+        None => destination,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let (calendar_id, filename) = match segments.as_slice() {
+        ["caldav", "users", _, calendar_id, filename] => (calendar_id, filename),
+        ["calendar", "dav", _, "user", calendar_id, filename] => (calendar_id, filename),
+        _ => return None,
+    };
+    Some((
+        super::report::percent_decode(calendar_id),
+        super::report::percent_decode(filename),
+    ))
+}
+
+/// Rewrite the UID of every component carrying `old_uid` (the master and
+/// any overrides) to `new_uid`.
+fn with_uid(ical_data: &str, old_uid: &str, new_uid: &str) -> String {
+    let Some(mut calendar) = parser::parse_calendar(ical_data) else {
+        return ical_data.to_string();
+    };
+    for component in &mut calendar.components {
+        if component.property_value("UID") == Some(old_uid) {
+            component.set_property(parser::Property::new("UID", new_uid));
+        }
+    }
+    calendar.to_ical()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_destination() {
+        assert_eq!(
+            parse_destination("https://cal.example.com/caldav/users/alice/work/a%40b.ics"),
+            Some(("work".to_string(), "a@b.ics".to_string()))
+        );
+        assert_eq!(
+            parse_destination("/calendar/dav/alice%40example.com/user/home/x.ics"),
+            Some(("home".to_string(), "x.ics".to_string()))
+        );
+        assert_eq!(parse_destination("/caldav/users/alice/work/"), None);
+        assert_eq!(parse_destination("https://example.com"), None);
+    }
+}
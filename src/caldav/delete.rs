@@ -1,17 +1,32 @@
+use axum::body::Body;
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{Request, StatusCode};
 use axum::response::{IntoResponse, Response};
 use sqlx::SqlitePool;
 
+use crate::db::models::User;
 use crate::db::{calendars, events};
 
 /// Handle DELETE for a calendar object: /caldav/users/{username}/{calendar_id}/{uid}.ics
 pub async fn handle_delete_object(
     State(pool): State<SqlitePool>,
     Path((_username, calendar_id, filename)): Path<(String, String, String)>,
+    request: Request<Body>,
 ) -> Response {
     let uid = filename.trim_end_matches(".ics");
 
+    if let Some(response) = super::conditional::check_if_header(
+        &pool,
+        request.headers(),
+        request.uri().path(),
+        &calendar_id,
+        uid,
+    )
+    .await
+    {
+        return response;
+    }
+
     match events::delete_object(&pool, &calendar_id, uid).await {
         Ok(()) => (StatusCode::NO_CONTENT, "").into_response(),
         Err(crate::error::AppError::NotFound(_)) => {
@@ -25,10 +40,22 @@ pub async fn handle_delete_object(
 }
 
 /// Handle DELETE for a calendar collection: /caldav/users/{username}/{calendar_id}/
+/// Only the owner may delete a calendar; sharees, even read-write ones, get 403.
 pub async fn handle_delete_calendar(
     State(pool): State<SqlitePool>,
     Path((_username, calendar_id)): Path<(String, String)>,
+    request: Request<Body>,
 ) -> Response {
+    let user = request.extensions().get::<User>().unwrap();
+    if let Ok(Some(cal)) = calendars::get_calendar_by_id(&pool, &calendar_id).await
+        && cal.owner_id != user.id
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            "Only the owner can delete a calendar",
+        )
+            .into_response();
+    }
     match calendars::delete_calendar(&pool, &calendar_id).await {
         Ok(()) => (StatusCode::NO_CONTENT, "").into_response(),
         Err(crate::error::AppError::NotFound(_)) => {
@@ -4,16 +4,36 @@ use axum::http::{StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use sqlx::SqlitePool;
 
-use crate::db::events;
+use crate::db::{calendars, events};
+use crate::ical::builder;
 
 /// Handle GET for a calendar object: /caldav/users/{username}/{calendar_id}/{uid}.ics
 pub async fn handle_get(
     State(pool): State<SqlitePool>,
     Path((_username, calendar_id, filename)): Path<(String, String, String)>,
+) -> Response {
+    object_response(&pool, &calendar_id, &filename, true).await
+}
+
+/// Handle HEAD for a calendar object: the headers GET would send (ETag,
+/// Content-Type, Content-Length) with an empty body.
+pub async fn handle_head(
+    State(pool): State<SqlitePool>,
+    Path((_username, calendar_id, filename)): Path<(String, String, String)>,
+) -> Response {
+    object_response(&pool, &calendar_id, &filename, false).await
+}
+
+/// Look up an object by filename and build the GET/HEAD response.
+async fn object_response(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    filename: &str,
+    include_body: bool,
 ) -> Response {
     let uid = filename.trim_end_matches(".ics");
 
-    let object = match events::get_object_by_uid(&pool, &calendar_id, uid).await {
+    let object = match events::get_object_by_uid(pool, calendar_id, uid).await {
         Ok(Some(obj)) => obj,
         Ok(None) => {
             return (StatusCode::NOT_FOUND, "Object not found").into_response();
@@ -24,10 +44,52 @@ pub async fn handle_get(
         }
     };
 
+    let body = if include_body {
+        Body::from(object.ical_data.clone())
+    } else {
+        Body::empty()
+    };
     Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+        .header(header::CONTENT_LENGTH, object.ical_data.len())
         .header(header::ETAG, &object.etag)
-        .body(Body::from(object.ical_data))
+        .body(body)
+        .unwrap()
+}
+
+/// Handle GET for /caldav/users/{username}/{calendar_id}/export: every object
+/// in the calendar merged into one VCALENDAR, served as a download.
+pub async fn handle_export(pool: &SqlitePool, calendar_id: &str) -> Response {
+    let calendar = match calendars::get_calendar_by_id(pool, calendar_id).await {
+        Ok(Some(cal)) => cal,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Calendar not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get calendar: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+    let objects = match events::list_objects(pool, calendar_id).await {
+        Ok(objects) => objects,
+        Err(e) => {
+            tracing::error!("Failed to list objects: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+
+    let ics = builder::merge_calendars(
+        objects.iter().map(|obj| obj.ical_data.as_str()),
+        Some(&calendar.name),
+    );
+    let filename = builder::export_filename(&calendar.name);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(Body::from(ics))
         .unwrap()
 }
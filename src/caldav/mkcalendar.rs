@@ -1,6 +1,6 @@
 use axum::body::Body;
 use axum::extract::{Path, State};
-use axum::http::{Request, StatusCode};
+use axum::http::{Request, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use sqlx::SqlitePool;
 
@@ -25,9 +25,23 @@ pub async fn handle_mkcalendar(
             .into_response();
     }
 
+    // The ID becomes a path segment in every href, so keep it URL-safe
+    if !is_valid_calendar_id(&calendar_id) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Invalid calendar ID: use only letters, digits, '-' and '_'",
+        )
+            .into_response();
+    }
+
     // Check if calendar already exists
     if let Ok(Some(_)) = calendars::get_calendar_by_id(&pool, &calendar_id).await {
-        return (StatusCode::METHOD_NOT_ALLOWED, "Calendar already exists").into_response();
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(header::ALLOW, super::wellknown::COLLECTION_METHODS)],
+            "Calendar already exists",
+        )
+            .into_response();
     }
 
     // Parse the request body for calendar properties (optional)
@@ -37,19 +51,32 @@ pub async fn handle_mkcalendar(
 
     let name = extract_displayname(&body).unwrap_or_else(|| calendar_id.clone());
     let color = extract_calendar_color(&body).unwrap_or_else(|| "#0E61B9".to_string());
+    let component_types = match extract_component_set(&body) {
+        Some(Ok(types)) => Some(types),
+        Some(Err(())) => {
+            return super::put::supported_component_error(
+                "Requested component set contains no supported component type",
+            );
+        }
+        None => None,
+    };
 
-    match calendars::create_calendar_with_id(
-        &pool,
-        &calendar_id,
-        &user.id,
-        &name,
-        "",
-        &color,
-        "UTC",
-    )
-    .await
-    {
-        Ok(_cal) => (StatusCode::CREATED, "Calendar created").into_response(),
+    // The calendar and its component set are created together, so a failed
+    // restriction never leaves behind a calendar that accepts everything
+    let created = async {
+        let mut tx = pool.begin().await?;
+        calendars::create_calendar_in(&mut tx, &calendar_id, &user.id, &name, "", &color, "UTC")
+            .await?;
+        if let Some(types) = component_types {
+            calendars::set_component_types_in(&mut tx, &calendar_id, &types).await?;
+        }
+        tx.commit().await?;
+        Ok::<_, crate::error::AppError>(())
+    }
+    .await;
+
+    match created {
+        Ok(()) => (StatusCode::CREATED, "Calendar created").into_response(),
         Err(e) => {
             tracing::error!("Failed to create calendar: {e}");
             (
@@ -61,6 +88,15 @@ pub async fn handle_mkcalendar(
     }
 }
 
+/// Whether a client-chosen calendar ID is safe to use unescaped in hrefs.
+/// UUIDs generated for MCP-created calendars always pass.
+fn is_valid_calendar_id(calendar_id: &str) -> bool {
+    !calendar_id.is_empty()
+        && calendar_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 /// Extract displayname from MKCALENDAR XML body.
 fn extract_displayname(body: &[u8]) -> Option<String> {
     if body.is_empty() {
@@ -79,6 +115,71 @@ fn extract_calendar_color(body: &[u8]) -> Option<String> {
     extract_xml_value(&text, "calendar-color")
 }
 
+/// Extract the component types from a `supported-calendar-component-set`
+/// in the MKCALENDAR body (RFC 4791 §5.2.3). `None` when the property is
+/// absent; `Err` when it names none of the types we support. Unknown names
+/// such as VFREEBUSY are skipped.
+fn extract_component_set(body: &[u8]) -> Option<Result<Vec<&'static str>, ()>> {
+    use quick_xml::events::Event;
+    use quick_xml::reader::Reader;
+
+    if body.is_empty() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(body);
+    let mut reader = Reader::from_str(&text);
+    let mut buf = Vec::new();
+    let mut in_set = false;
+    let mut found = false;
+    let mut types = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => match e.local_name().as_ref() {
+                b"supported-calendar-component-set" => {
+                    in_set = true;
+                    found = true;
+                }
+                b"comp" if in_set => {
+                    let name = e
+                        .attributes()
+                        .flatten()
+                        .find(|attr| attr.key.as_ref() == b"name")
+                        .map(|attr| String::from_utf8_lossy(&attr.value).to_string())
+                        .unwrap_or_default();
+                    let supported = calendars::COMPONENT_TYPES
+                        .iter()
+                        .find(|c| c.eq_ignore_ascii_case(&name))
+                        .copied();
+                    if let Some(c) = supported
+                        && !types.contains(&c)
+                    {
+                        types.push(c);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::End(ref e))
+                if e.local_name().as_ref() == b"supported-calendar-component-set" =>
+            {
+                in_set = false;
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !found {
+        None
+    } else if types.is_empty() {
+        Some(Err(()))
+    } else {
+        Some(Ok(types))
+    }
+}
+
 /// Simple XML value extraction by local element name.
 fn extract_xml_value(xml: &str, local_name: &str) -> Option<String> {
     use quick_xml::events::Event;
@@ -1,4 +1,6 @@
 mod auth;
+mod conditional;
+mod copymove;
 mod delete;
 mod get;
 mod mkcalendar;
@@ -9,15 +11,21 @@ mod report;
 mod wellknown;
 pub mod xml;
 
+use std::sync::Arc;
+
 use axum::Router;
 use axum::body::Body;
-use axum::extract::{Path, State};
-use axum::http::{Request, StatusCode};
+use axum::extract::{Extension, Path, State};
+use axum::http::{HeaderValue, Method, Request, StatusCode};
+use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
 use axum::routing::any;
 use sqlx::SqlitePool;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 
+use crate::config::Config;
+
 /// Context for building hrefs in responses. When email is set, hrefs use the
 /// email-based path (`/calendar/dav/{email}/user/...`); otherwise they use the
 /// username-based path (`/caldav/users/{username}/...`).
@@ -40,10 +48,23 @@ pub fn encode_email_for_path(email: &str) -> String {
 /// dataaccessd only sends credentials to URLs where it has previously
 /// authenticated. Middleware-based 401s on new URLs cause sync failures
 /// because dataaccessd doesn't retry with credentials.
-pub fn router(pool: SqlitePool) -> Router {
-    Router::new()
+///
+/// The config is attached to every request as an `Arc<Config>` extension;
+/// handlers read it with [`request_config`]. The `DAV` header is added to
+/// responses by [`dav_header`] so it always reflects the enabled features.
+///
+/// Only the unauthenticated discovery routes get CORS headers (see
+/// [`discovery_cors`]); the data routes are never reachable cross-origin.
+pub fn router(pool: SqlitePool, config: Config) -> Router {
+    let mut discovery = Router::new()
         .route("/.well-known/caldav", any(wellknown::handle_well_known))
-        .route("/", any(handle_server_root))
+        .route("/", any(handle_server_root));
+    if let Some(cors) = discovery_cors(&config.discovery_cors_origins) {
+        discovery = discovery.layer(cors);
+    }
+
+    Router::new()
+        .merge(discovery)
         .route("/caldav/", any(handle_caldav_root))
         .route("/caldav", any(handle_caldav_root))
         .route(
@@ -82,10 +103,86 @@ pub fn router(pool: SqlitePool) -> Router {
             "/caldav/users/{username}/{calendar_id}/{filename}",
             any(handle_object),
         )
+        .layer(middleware::from_fn(dav_header))
+        .layer(middleware::from_fn(track_user_agent))
+        .layer(Extension(Arc::new(config)))
         .layer(TraceLayer::new_for_http())
         .with_state(pool)
 }
 
+/// CORS policy for the discovery routes, so browser-based tools can probe
+/// `/.well-known/caldav`. Returns `None` when no origins are configured.
+fn discovery_cors(origins: &[String]) -> Option<CorsLayer> {
+    if origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|o| HeaderValue::from_str(o).ok()))
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::from_bytes(b"PROPFIND").unwrap(),
+            ])
+            .allow_headers([
+                axum::http::header::AUTHORIZATION,
+                axum::http::header::CONTENT_TYPE,
+                axum::http::HeaderName::from_static("depth"),
+            ])
+            .expose_headers([
+                axum::http::header::LOCATION,
+                axum::http::HeaderName::from_static("dav"),
+            ]),
+    )
+}
+
+/// The server config attached to the request by [`router`].
+fn request_config(request: &Request<Body>) -> Arc<Config> {
+    request
+        .extensions()
+        .get::<Arc<Config>>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Add the `DAV` compliance header to OPTIONS responses, 207 Multi-Status
+/// bodies, 401 challenges and discovery redirects. Clients use it to decide
+/// which CalDAV extensions to attempt, so it is built from the config rather
+/// than hard-coded in each handler.
+async fn dav_header(request: Request<Body>, next: Next) -> Response {
+    let config = request_config(&request);
+    let is_options = request.method() == Method::OPTIONS;
+    let mut response = next.run(request).await;
+
+    let advertises = is_options
+        || matches!(
+            response.status(),
+            StatusCode::MULTI_STATUS | StatusCode::UNAUTHORIZED | StatusCode::MOVED_PERMANENTLY
+        );
+    if advertises && let Ok(value) = HeaderValue::from_str(&config.dav_header()) {
+        response.headers_mut().insert("DAV", value);
+    }
+    response
+}
+
+/// Run the request with its User-Agent available to [`auth::try_basic_auth`],
+/// which records it as a sync client when the credentials are valid.
+async fn track_user_agent(request: Request<Body>, next: Next) -> Response {
+    let user_agent = request
+        .headers()
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .filter(|ua| !ua.is_empty())
+        .map(str::to_owned);
+    auth::USER_AGENT.scope(user_agent, next.run(request)).await
+}
+
 /// Extract the Authorization header from a request as an owned String.
 fn extract_auth_header(request: &Request<Body>) -> Option<String> {
     request
@@ -156,17 +253,54 @@ async fn auth_or_email_user(
     }
 }
 
-/// Verify that a user has access to a calendar (owns it or has a share).
-/// Returns false if the calendar doesn't exist or the user has no access.
+/// Verify that a user has access to a calendar (owns it or has a share),
+/// returning the calendar row. Returns `None` if the calendar doesn't exist
+/// or the user has no access.
 async fn verify_calendar_access(
     pool: &SqlitePool,
     user: &crate::db::models::User,
     calendar_id: &str,
-) -> bool {
-    let accessible = crate::db::calendars::list_calendars_for_user(pool, &user.id)
+) -> Option<crate::db::models::Calendar> {
+    crate::db::calendars::list_calendars_for_user(pool, &user.id)
         .await
-        .unwrap_or_default();
-    accessible.iter().any(|c| c.id == calendar_id)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|c| c.id == calendar_id)
+}
+
+/// Methods that change a calendar or the objects in it. COPY only writes to
+/// its destination, which `copymove` checks separately.
+const WRITE_METHODS: &[&str] = &["PUT", "DELETE", "PROPPATCH", "MOVE"];
+
+/// Whether the user may change a calendar they can access: the owner and
+/// read-write sharees can, read-only sharees cannot.
+async fn verify_calendar_write(
+    pool: &SqlitePool,
+    user: &crate::db::models::User,
+    calendar_id: &str,
+) -> bool {
+    matches!(
+        crate::db::shares::get_user_permission(pool, calendar_id, &user.id).await,
+        Ok(Some(permission)) if permission.can_write()
+    )
+}
+
+/// Response for a write to a calendar the user can only read.
+fn read_only_denied() -> Response {
+    (StatusCode::FORBIDDEN, "Calendar is shared read-only").into_response()
+}
+
+/// Response for an object request in a calendar the user cannot access.
+/// A PUT into a calendar that does not exist at all gets 404, so clients
+/// know to create the collection first; a calendar that exists but belongs
+/// to someone else (and every other method) gets 403.
+async fn object_access_denied(pool: &SqlitePool, calendar_id: &str, method: &Method) -> Response {
+    if method == Method::PUT
+        && let Ok(None) = crate::db::calendars::get_calendar_by_id(pool, calendar_id).await
+    {
+        return (StatusCode::NOT_FOUND, "Calendar not found").into_response();
+    }
+    (StatusCode::FORBIDDEN, "Access denied").into_response()
 }
 
 /// Handle requests at the server root "/".
@@ -252,7 +386,7 @@ async fn handle_caldav_root(State(pool): State<SqlitePool>, request: Request<Bod
 /// Returns principal info without requiring auth — accountsd and dataaccessd
 /// need this to discover the calendar-home-set.
 async fn handle_principal_discovery(
-    State(_pool): State<SqlitePool>,
+    State(pool): State<SqlitePool>,
     Path(username): Path<String>,
     request: Request<Body>,
 ) -> Response {
@@ -265,6 +399,7 @@ async fn handle_principal_discovery(
     );
     match method.as_str() {
         "OPTIONS" => wellknown::handle_options().await.into_response(),
+        "REPORT" => principal_report(&pool, request).await,
         // Redirect all methods on the principals URL to the calendar home.
         // current-user-principal now points to /caldav/users/{username}/ directly.
         _ => Response::builder()
@@ -296,6 +431,7 @@ async fn handle_caldav_email_discovery(
     let method = request.method().clone();
     let auth_header = extract_auth_header(&request);
     let depth = propfind::get_depth_from_headers(request.headers());
+    let config = request_config(&request);
     let body_bytes = axum::body::to_bytes(request.into_body(), 64 * 1024)
         .await
         .unwrap_or_default();
@@ -333,6 +469,7 @@ async fn handle_caldav_email_discovery(
                                 depth,
                                 &email,
                                 &propfind,
+                                &config,
                             )
                             .await
                         }
@@ -352,6 +489,7 @@ async fn handle_caldav_email_discovery(
                                 depth,
                                 &email,
                                 &propfind,
+                                &config,
                             )
                             .await;
                             // Log response body for debugging
@@ -398,16 +536,26 @@ async fn handle_email_calendar_collection(
         "handle_email_calendar_collection"
     );
     match method_str.as_str() {
-        "OPTIONS" => wellknown::handle_options().await.into_response(),
+        "OPTIONS" => wellknown::options_for(wellknown::COLLECTION_METHODS),
         _ => {
             let auth_header = extract_auth_header(&request);
             match auth_or_email_user(&pool, auth_header.as_deref(), &email).await {
                 Ok(user) => {
                     // Verify calendar ownership (skip for MKCALENDAR)
-                    if method_str != "MKCALENDAR"
-                        && !verify_calendar_access(&pool, &user, &calendar_id).await
+                    let calendar = if method_str == "MKCALENDAR" {
+                        None
+                    } else {
+                        match verify_calendar_access(&pool, &user, &calendar_id).await {
+                            Some(calendar) => Some(calendar),
+                            None => {
+                                return (StatusCode::FORBIDDEN, "Access denied").into_response();
+                            }
+                        }
+                    };
+                    if WRITE_METHODS.contains(&method_str.as_str())
+                        && !verify_calendar_write(&pool, &user, &calendar_id).await
                     {
-                        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+                        return read_only_denied();
                     }
                     let username = user.username.clone();
                     let encoded_email = encode_email_for_path(&email);
@@ -418,6 +566,9 @@ async fn handle_email_calendar_collection(
                     let mut req = request;
                     req.extensions_mut().insert(user);
                     req.extensions_mut().insert(ctx);
+                    if let Some(calendar) = calendar {
+                        req.extensions_mut().insert(calendar);
+                    }
                     match method_str.as_str() {
                         "PROPFIND" => {
                             propfind::handle_calendar(
@@ -451,10 +602,11 @@ async fn handle_email_calendar_collection(
                             delete::handle_delete_calendar(
                                 State(pool),
                                 Path((username, calendar_id)),
+                                req,
                             )
                             .await
                         }
-                        _ => (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed").into_response(),
+                        _ => wellknown::method_not_allowed(wellknown::COLLECTION_METHODS),
                     }
                 }
                 Err(resp) => resp,
@@ -482,14 +634,22 @@ async fn handle_email_object(
         "handle_email_object"
     );
     match request.method().as_str() {
-        "OPTIONS" => wellknown::handle_options().await.into_response(),
+        "OPTIONS" => wellknown::options_for(wellknown::OBJECT_METHODS),
         _ => {
             let auth_header = extract_auth_header(&request);
             match auth_or_email_user(&pool, auth_header.as_deref(), &email).await {
                 Ok(user) => {
                     // Verify calendar ownership
-                    if !verify_calendar_access(&pool, &user, &calendar_id).await {
-                        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+                    if verify_calendar_access(&pool, &user, &calendar_id)
+                        .await
+                        .is_none()
+                    {
+                        return object_access_denied(&pool, &calendar_id, request.method()).await;
+                    }
+                    if WRITE_METHODS.contains(&request.method().as_str())
+                        && !verify_calendar_write(&pool, &user, &calendar_id).await
+                    {
+                        return read_only_denied();
                     }
                     let username = user.username.clone();
                     let encoded_email = encode_email_for_path(&email);
@@ -505,6 +665,10 @@ async fn handle_email_object(
                             get::handle_get(State(pool), Path((username, calendar_id, filename)))
                                 .await
                         }
+                        "HEAD" => {
+                            get::handle_head(State(pool), Path((username, calendar_id, filename)))
+                                .await
+                        }
                         "PUT" => {
                             put::handle_put(
                                 State(pool),
@@ -517,10 +681,21 @@ async fn handle_email_object(
                             delete::handle_delete_object(
                                 State(pool),
                                 Path((username, calendar_id, filename)),
+                                req,
                             )
                             .await
                         }
-                        _ => (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed").into_response(),
+                        method @ ("COPY" | "MOVE") => {
+                            let is_move = method == "MOVE";
+                            copymove::handle_copy_move(
+                                State(pool),
+                                Path((username, calendar_id, filename)),
+                                req,
+                                is_move,
+                            )
+                            .await
+                        }
+                        _ => wellknown::method_not_allowed(wellknown::OBJECT_METHODS),
                     }
                 }
                 Err(resp) => resp,
@@ -565,10 +740,21 @@ async fn handle_fallback_discovery(
             }
             propfind::multistatus_response(builder.build())
         }
+        "REPORT" => principal_report(&pool, request).await,
         _ => (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed").into_response(),
     }
 }
 
+/// REPORT on the principal collection. Unlike discovery PROPFINDs this
+/// searches other users, so credentials are required.
+async fn principal_report(pool: &SqlitePool, request: Request<Body>) -> Response {
+    let auth_header = extract_auth_header(&request);
+    match inline_auth(pool, auth_header.as_deref()).await {
+        Ok(_) => report::handle_principal_report(pool, request).await,
+        Err(resp) => resp,
+    }
+}
+
 /// Dispatch requests at a calendar home based on method.
 /// Uses path-based user resolution as fallback since dataaccessd does not
 /// send credentials to /caldav/users/* URLs.
@@ -612,7 +798,7 @@ async fn handle_calendar_collection(
     request: Request<Body>,
 ) -> Response {
     match request.method().as_str() {
-        "OPTIONS" => wellknown::handle_options().await.into_response(),
+        "OPTIONS" => wellknown::options_for(wellknown::COLLECTION_METHODS),
         _ => {
             let auth_header = extract_auth_header(&request);
             let username = (path.0).0.clone();
@@ -621,20 +807,33 @@ async fn handle_calendar_collection(
                 Ok(user) => {
                     // Verify calendar ownership (skip for MKCALENDAR which creates new calendars)
                     let method_str = request.method().as_str().to_owned();
-                    if method_str != "MKCALENDAR"
-                        && !verify_calendar_access(&state, &user, &calendar_id).await
+                    let calendar = if method_str == "MKCALENDAR" {
+                        None
+                    } else {
+                        match verify_calendar_access(&state, &user, &calendar_id).await {
+                            Some(calendar) => Some(calendar),
+                            None => {
+                                return (StatusCode::FORBIDDEN, "Access denied").into_response();
+                            }
+                        }
+                    };
+                    if WRITE_METHODS.contains(&method_str.as_str())
+                        && !verify_calendar_write(&state, &user, &calendar_id).await
                     {
-                        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+                        return read_only_denied();
                     }
                     let mut req = request;
                     req.extensions_mut().insert(user);
+                    if let Some(calendar) = calendar {
+                        req.extensions_mut().insert(calendar);
+                    }
                     match method_str.as_str() {
                         "PROPFIND" => propfind::handle_calendar(state, path, req).await,
                         "REPORT" => report::handle_report(state, path, req).await,
                         "MKCALENDAR" => mkcalendar::handle_mkcalendar(state, path, req).await,
                         "PROPPATCH" => proppatch::handle_proppatch(state, path, req).await,
-                        "DELETE" => delete::handle_delete_calendar(state, path).await,
-                        _ => (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed").into_response(),
+                        "DELETE" => delete::handle_delete_calendar(state, path, req).await,
+                        _ => wellknown::method_not_allowed(wellknown::COLLECTION_METHODS),
                     }
                 }
                 Err(resp) => resp,
@@ -654,7 +853,7 @@ async fn handle_object(
     request: Request<Body>,
 ) -> Response {
     match request.method().as_str() {
-        "OPTIONS" => wellknown::handle_options().await.into_response(),
+        "OPTIONS" => wellknown::options_for(wellknown::OBJECT_METHODS),
         _ => {
             let auth_header = extract_auth_header(&request);
             let username = (path.0).0.clone();
@@ -662,16 +861,30 @@ async fn handle_object(
             match auth_or_path_user(&state, auth_header.as_deref(), &username).await {
                 Ok(user) => {
                     // Verify calendar ownership
-                    if !verify_calendar_access(&state, &user, &calendar_id).await {
-                        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+                    if verify_calendar_access(&state, &user, &calendar_id)
+                        .await
+                        .is_none()
+                    {
+                        return object_access_denied(&state, &calendar_id, request.method()).await;
+                    }
+                    if WRITE_METHODS.contains(&request.method().as_str())
+                        && !verify_calendar_write(&state, &user, &calendar_id).await
+                    {
+                        return read_only_denied();
                     }
                     let mut req = request;
                     req.extensions_mut().insert(user);
                     match req.method().as_str() {
+                        "GET" if (path.0).2 == "export" => {
+                            get::handle_export(&state, &calendar_id).await
+                        }
                         "GET" => get::handle_get(state, path).await,
+                        "HEAD" => get::handle_head(state, path).await,
                         "PUT" => put::handle_put(state, path, req).await,
-                        "DELETE" => delete::handle_delete_object(state, path).await,
-                        _ => (StatusCode::METHOD_NOT_ALLOWED, "Method not allowed").into_response(),
+                        "DELETE" => delete::handle_delete_object(state, path, req).await,
+                        "COPY" => copymove::handle_copy_move(state, path, req, false).await,
+                        "MOVE" => copymove::handle_copy_move(state, path, req, true).await,
+                        _ => wellknown::method_not_allowed(wellknown::OBJECT_METHODS),
                     }
                 }
                 Err(resp) => resp,
@@ -689,7 +902,7 @@ mod tests {
 
     use crate::db;
     use crate::db::calendars;
-    use crate::db::users;
+    use crate::db::users::{self, PasswordPolicy};
 
     /// Create a test pool with a user and calendar.
     async fn setup() -> (
@@ -698,9 +911,15 @@ mod tests {
         crate::db::models::Calendar,
     ) {
         let pool = db::test_pool().await;
-        let user = users::create_user(&pool, "alice", Some("alice@example.com"), "secret123")
-            .await
-            .unwrap();
+        let user = users::create_user(
+            &pool,
+            "alice",
+            Some("alice@example.com"),
+            "secret123",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
         let cal =
             calendars::create_calendar(&pool, &user.id, "Work", "Work events", "#FF0000", "UTC")
                 .await
@@ -720,7 +939,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_discovery_unauthenticated_known_email_returns_207() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Known email without auth should return 207 (dataaccessd needs this)
         let req = Request::builder()
@@ -737,7 +956,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_discovery_depth1_unauthenticated_returns_calendars() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Depth:1 without auth for known email should return calendar list
         let req = Request::builder()
@@ -755,10 +974,316 @@ mod tests {
         assert!(body_str.contains(&cal.id), "Calendar should be listed");
     }
 
+    #[tokio::test]
+    async fn test_home_depth1_reports_current_ctags() {
+        let (pool, _user, work) = setup().await;
+        let alice = users::get_user_by_username(&pool, "alice")
+            .await
+            .unwrap()
+            .unwrap();
+        let personal = calendars::create_calendar(&pool, &alice.id, "Personal", "", "#0F0", "UTC")
+            .await
+            .unwrap();
+        let bob = users::create_user(&pool, "bob", None, "secret456", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let team = calendars::create_calendar(&pool, &bob.id, "Team", "", "#00F", "UTC")
+            .await
+            .unwrap();
+        crate::db::shares::share_calendar(
+            &pool,
+            &team.id,
+            &alice.id,
+            crate::db::models::Permission::Read,
+        )
+        .await
+        .unwrap();
+        let stale = work.ctag.clone();
+        calendars::bump_ctag(&pool, &work.id).await.unwrap();
+        calendars::bump_ctag(&pool, &team.id).await.unwrap();
+
+        let propfind_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<A:propfind xmlns:A="DAV:" xmlns:C="http://calendarserver.org/ns/">
+  <A:prop><C:getctag/></A:prop>
+</A:propfind>"#;
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri("/caldav/users/alice/")
+            .header("Depth", "1")
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .header("Content-Type", "application/xml")
+            .body(Body::from(propfind_body))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+
+        // Each calendar's response carries that calendar's current ctag
+        for id in [&work.id, &personal.id, &team.id] {
+            let current = calendars::get_calendar_by_id(&pool, id)
+                .await
+                .unwrap()
+                .unwrap()
+                .ctag;
+            let response = body_str
+                .split("response>")
+                .find(|r| r.contains(id.as_str()))
+                .unwrap_or_else(|| panic!("no response for calendar {id}"));
+            assert!(response.contains(&current), "stale ctag for {id}");
+        }
+        assert!(!body_str.contains(&stale));
+    }
+
+    #[tokio::test]
+    async fn test_home_reports_quota_usage() {
+        let (pool, _user, cal) = setup().await;
+        let config = Config {
+            user_quota_bytes: 10_000,
+            ..Config::default()
+        };
+        let quota = |body: &str, name: &str| -> u64 {
+            let open = format!("<D:{name}>");
+            let start = body
+                .find(&open)
+                .unwrap_or_else(|| panic!("no {name} in {body}"));
+            let rest = &body[start + open.len()..];
+            rest[..rest.find('<').unwrap()].parse().unwrap()
+        };
+        let propfind = || {
+            Request::builder()
+                .method(Method::from_bytes(b"PROPFIND").unwrap())
+                .uri("/caldav/users/alice/")
+                .header("Depth", "0")
+                .header("Authorization", basic_auth_header("alice", "secret123"))
+                .header("Content-Type", "application/xml")
+                .body(Body::from(
+                    r#"<?xml version="1.0" encoding="utf-8"?>
+<A:propfind xmlns:A="DAV:">
+  <A:prop><A:quota-used-bytes/><A:quota-available-bytes/></A:prop>
+</A:propfind>"#,
+                ))
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(propfind())
+            .await
+            .unwrap();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body).to_string();
+        assert!(!body.contains("404 Not Found"), "{body}");
+        assert_eq!(quota(&body, "quota-used-bytes"), 0);
+        assert_eq!(quota(&body, "quota-available-bytes"), 10_000);
+
+        let ical = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:sized\r\nSUMMARY:Sized\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "sized",
+            ical,
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: Some("Sized"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(propfind())
+            .await
+            .unwrap();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body).to_string();
+        let used = ical.len() as u64;
+        assert_eq!(quota(&body, "quota-used-bytes"), used);
+        assert_eq!(quota(&body, "quota-available-bytes"), 10_000 - used);
+    }
+
+    #[tokio::test]
+    async fn test_privilege_set_reflects_share_permission() {
+        let (pool, _user, cal) = setup().await;
+        let bob = users::create_user(&pool, "bob", None, "secret456", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        crate::db::shares::share_calendar(
+            &pool,
+            &cal.id,
+            &bob.id,
+            crate::db::models::Permission::Read,
+        )
+        .await
+        .unwrap();
+
+        let propfind_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<A:propfind xmlns:A="DAV:">
+  <A:prop><A:current-user-privilege-set/></A:prop>
+</A:propfind>"#;
+        let privileges = |username: &'static str, password: &'static str| {
+            let req = Request::builder()
+                .method(Method::from_bytes(b"PROPFIND").unwrap())
+                .uri(format!("/caldav/users/{username}/{}/", cal.id))
+                .header("Depth", "0")
+                .header("Authorization", basic_auth_header(username, password))
+                .header("Content-Type", "application/xml")
+                .body(Body::from(propfind_body))
+                .unwrap();
+            let pool = pool.clone();
+            async move {
+                let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+                let body = resp.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8_lossy(&body).to_string()
+            }
+        };
+
+        let owner = privileges("alice", "secret123").await;
+        assert!(owner.contains("<D:read/>"));
+        assert!(owner.contains("<D:write/>"));
+
+        // A read-only sharee is offered read alone
+        let sharee = privileges("bob", "secret456").await;
+        assert!(sharee.contains("<D:read/>"), "{sharee}");
+        assert!(!sharee.contains("<D:write/>"), "{sharee}");
+        assert!(!sharee.contains("<D:write-content/>"), "{sharee}");
+    }
+
+    #[tokio::test]
+    async fn test_read_only_share_blocks_writes() {
+        let (pool, _user, cal) = setup().await;
+        for (name, permission) in [
+            ("bob", crate::db::models::Permission::Read),
+            ("carol", crate::db::models::Permission::ReadWrite),
+        ] {
+            let sharee =
+                users::create_user(&pool, name, None, "secret456", &PasswordPolicy::default())
+                    .await
+                    .unwrap();
+            crate::db::shares::share_calendar(&pool, &cal.id, &sharee.id, permission)
+                .await
+                .unwrap();
+        }
+        let request = |method: &str, username: &str, uid: &str| {
+            Request::builder()
+                .method(method)
+                .uri(format!("/caldav/users/{username}/{}/{uid}.ics", cal.id))
+                .header("Authorization", basic_auth_header(username, "secret456"))
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(format!(
+                    "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nDTSTART:20260302T150000Z\r\nDTEND:20260302T160000Z\r\nSUMMARY:Shared\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+                )))
+                .unwrap()
+        };
+        let send = |req: Request<Body>| {
+            let pool = pool.clone();
+            async move {
+                router(pool, Config::default())
+                    .oneshot(req)
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        // The read-write sharee can create and delete
+        assert_eq!(
+            send(request("PUT", "carol", "from-carol")).await,
+            StatusCode::CREATED
+        );
+
+        // The read-only sharee can read but not change anything
+        assert_eq!(
+            send(request("GET", "bob", "from-carol")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            send(request("PUT", "bob", "from-bob")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            send(request("DELETE", "bob", "from-carol")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "from-bob")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert_eq!(
+            send(request("DELETE", "carol", "from-carol")).await,
+            StatusCode::NO_CONTENT
+        );
+
+        // Deleting the calendar itself stays with the owner
+        let req = Request::builder()
+            .method("DELETE")
+            .uri(format!("/caldav/users/carol/{}/", cal.id))
+            .header("Authorization", basic_auth_header("carol", "secret456"))
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(req).await, StatusCode::FORBIDDEN);
+        assert!(
+            calendars::get_calendar_by_id(&pool, &cal.id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_propfind_records_sync_client() {
+        let (pool, user, _cal) = setup().await;
+        let propfind = |password: &str, user_agent: Option<&str>| {
+            let mut req = Request::builder()
+                .method(Method::from_bytes(b"PROPFIND").unwrap())
+                .uri("/caldav/users/alice/")
+                .header("Depth", "0")
+                .header("Authorization", basic_auth_header("alice", password));
+            if let Some(ua) = user_agent {
+                req = req.header("User-Agent", ua);
+            }
+            req.body(Body::empty()).unwrap()
+        };
+
+        let ua = "DAVx5/4.4.5-ose Android/14";
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(propfind("secret123", Some(ua)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        // Failed logins and requests without a User-Agent are not recorded
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(propfind("wrong", Some("Intruder/1.0")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        router(pool.clone(), Config::default())
+            .oneshot(propfind("secret123", None))
+            .await
+            .unwrap();
+
+        let result =
+            crate::mcp::tools::account::list_sync_clients(&pool, &user.id, &serde_json::json!({}))
+                .await
+                .unwrap();
+        let clients = result["clients"].as_array().unwrap();
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0]["user_agent"], ua);
+        assert!(clients[0]["last_seen"].is_string());
+    }
+
     #[tokio::test]
     async fn test_email_discovery_unknown_email_returns_401() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -774,7 +1299,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_discovery_authenticated_returns_full_data() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -806,7 +1331,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_home_invalid_user_returns_401() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -824,7 +1349,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_access_denied_for_wrong_calendar() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -840,7 +1365,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_access_allowed_for_own_calendar() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -856,7 +1381,7 @@ mod tests {
     #[tokio::test]
     async fn test_object_access_denied_for_wrong_calendar() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("GET")
@@ -873,7 +1398,7 @@ mod tests {
     #[tokio::test]
     async fn test_root_unauthenticated_no_user_leak() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -898,7 +1423,7 @@ mod tests {
     #[tokio::test]
     async fn test_caldav_root_unauthenticated_no_user_leak() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -920,15 +1445,80 @@ mod tests {
         assert!(!body_str.contains(">alice<"), "Should not leak username");
     }
 
+    // --- Ctag polling ---
+
+    /// PROPFIND a calendar collection and return the response body.
+    async fn propfind_calendar(app: &Router, cal_id: &str, depth: &str) -> String {
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(format!("/caldav/users/alice/{cal_id}/"))
+            .header("Depth", depth)
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8_lossy(&body).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_propfind_depth0_reports_current_ctag() {
+        let (pool, _user, cal) = setup().await;
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "polled-uid",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:polled-uid\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: None,
+            },
+        )
+        .await
+        .unwrap();
+        let ctag = calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+
+        let app = router(pool.clone(), Config::default());
+        let body = propfind_calendar(&app, &cal.id, "0").await;
+        assert!(body.contains(&ctag));
+        assert!(
+            !body.contains("polled-uid.ics"),
+            "Depth:0 must not list objects"
+        );
+
+        // A change made outside the router, as MCP tools do, shows up on the
+        // very next poll
+        sqlx::query("UPDATE calendars SET ctag = 'data:,out-of-band' WHERE id = ?")
+            .bind(&cal.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let body = propfind_calendar(&app, &cal.id, "0").await;
+        assert!(body.contains("out-of-band"));
+    }
+
     // --- Cross-user protection ---
 
     #[tokio::test]
     async fn test_cross_user_calendar_access_denied() {
         let pool = db::test_pool().await;
-        let alice = users::create_user(&pool, "alice", None, "pass1")
-            .await
-            .unwrap();
-        let _bob = users::create_user(&pool, "bob", None, "pass2")
+        let alice = users::create_user(
+            &pool,
+            "alice",
+            None,
+            "password1",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let _bob = users::create_user(&pool, "bob", None, "password2", &PasswordPolicy::default())
             .await
             .unwrap();
         let alice_cal =
@@ -936,7 +1526,7 @@ mod tests {
                 .await
                 .unwrap();
 
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Bob trying to access Alice's calendar by manipulating the URL
         let req = Request::builder()
@@ -957,7 +1547,7 @@ mod tests {
     #[tokio::test]
     async fn test_authenticated_wrong_credentials_returns_401() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -979,7 +1569,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_discovery_depth1_returns_email_based_calendar_hrefs() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1011,7 +1601,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_discovery_depth1_returns_email_based_hrefs() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1039,7 +1629,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_calendar_propfind() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/calendar/dav/alice%40example.com/user/{}/", cal.id);
         let req = Request::builder()
@@ -1077,7 +1667,7 @@ mod tests {
             },
         ).await.unwrap();
 
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let report_body = r#"<?xml version="1.0" encoding="utf-8" ?>
 <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
@@ -1131,7 +1721,7 @@ mod tests {
         let ical_data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:put-test@example.com\r\nSUMMARY:Put Test\r\nDTSTART:20260401T090000Z\r\nDTEND:20260401T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR";
 
         // PUT via email path
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
         let put_uri = format!(
             "/calendar/dav/alice%40example.com/user/{}/put-test%40example.com.ics",
             cal.id
@@ -1151,7 +1741,7 @@ mod tests {
         );
 
         // GET via email path
-        let app2 = router(pool);
+        let app2 = router(pool, Config::default());
         let get_uri = format!(
             "/calendar/dav/alice%40example.com/user/{}/put-test%40example.com.ics",
             cal.id
@@ -1193,7 +1783,7 @@ mod tests {
         .await
         .unwrap();
 
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
         let uri = format!(
             "/calendar/dav/alice%40example.com/user/{}/del-test%40example.com.ics",
             cal.id
@@ -1219,7 +1809,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_calendar_unknown_email_returns_401() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/calendar/dav/unknown%40example.com/user/{}/", cal.id);
         let req = Request::builder()
@@ -1236,7 +1826,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_calendar_wrong_calendar_returns_403() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1252,7 +1842,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_object_unknown_email_returns_401() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!(
             "/calendar/dav/unknown%40example.com/user/{}/test.ics",
@@ -1271,7 +1861,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_object_wrong_calendar_returns_403() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("GET")
@@ -1288,7 +1878,7 @@ mod tests {
     #[tokio::test]
     async fn test_username_calendar_propfind_still_works() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1315,7 +1905,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_proppatch_returns_email_based_href() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let proppatch_body = r#"<?xml version="1.0" encoding="utf-8" ?>
 <D:propertyupdate xmlns:D="DAV:" xmlns:A="http://apple.com/ns/ical/">
@@ -1356,19 +1946,151 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_username_proppatch_returns_username_based_href() {
-        let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+    async fn test_proppatch_calendar_order_is_stored() {
+        let (pool, user, cal) = setup().await;
+        let other = calendars::create_calendar(&pool, &user.id, "Alpha", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
 
         let proppatch_body = r#"<?xml version="1.0" encoding="utf-8" ?>
 <D:propertyupdate xmlns:D="DAV:" xmlns:A="http://apple.com/ns/ical/">
   <D:set>
     <D:prop>
-      <D:displayname>Renamed Calendar</D:displayname>
+      <A:calendar-order>-2</A:calendar-order>
     </D:prop>
   </D:set>
 </D:propertyupdate>"#;
-
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPPATCH").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Content-Type", "application/xml")
+            .body(Body::from(proppatch_body))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("<A:calendar-order/>"));
+
+        let propfind_body = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:" xmlns:A="http://apple.com/ns/ical/">
+  <D:prop><A:calendar-order/></D:prop>
+</D:propfind>"#;
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Depth", "0")
+            .header("Content-Type", "application/xml")
+            .body(Body::from(propfind_body))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        assert!(
+            body_str.contains("<A:calendar-order>-2</A:calendar-order>"),
+            "got: {body_str}"
+        );
+
+        // Calendars are listed by order before name
+        let listed = calendars::list_calendars_for_user(&pool, &user.id)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = listed.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec![cal.id.as_str(), other.id.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_proppatch_schedule_calendar_transp_round_trips() {
+        let (pool, _user, cal) = setup().await;
+        let proppatch = |value: &str| {
+            Request::builder()
+                .method(Method::from_bytes(b"PROPPATCH").unwrap())
+                .uri(format!("/caldav/users/alice/{}/", cal.id))
+                .header("Content-Type", "application/xml")
+                .body(Body::from(format!(
+                    r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propertyupdate xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:set>
+    <D:prop>
+      <C:schedule-calendar-transp><C:{value}/></C:schedule-calendar-transp>
+    </D:prop>
+  </D:set>
+</D:propertyupdate>"#
+                )))
+                .unwrap()
+        };
+        let propfind = || {
+            Request::builder()
+                .method(Method::from_bytes(b"PROPFIND").unwrap())
+                .uri(format!("/caldav/users/alice/{}/", cal.id))
+                .header("Depth", "0")
+                .header("Content-Type", "application/xml")
+                .body(Body::from(
+                    r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:schedule-calendar-transp/></D:prop>
+</D:propfind>"#,
+                ))
+                .unwrap()
+        };
+
+        for value in ["transparent", "opaque"] {
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(proppatch(value))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+            let body = resp.into_body().collect().await.unwrap().to_bytes();
+            assert!(String::from_utf8_lossy(&body).contains("<C:schedule-calendar-transp/>"));
+
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(propfind())
+                .await
+                .unwrap();
+            let body = resp.into_body().collect().await.unwrap().to_bytes();
+            let body_str = String::from_utf8_lossy(&body);
+            assert!(
+                body_str.contains(&format!(
+                    "<C:schedule-calendar-transp><C:{value}/></C:schedule-calendar-transp>"
+                )),
+                "got: {body_str}"
+            );
+        }
+
+        // Anything else is refused and leaves the stored value alone
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(proppatch("translucent"))
+            .await
+            .unwrap();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("404 Not Found"));
+        let stored = calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.transp, "opaque");
+    }
+
+    #[tokio::test]
+    async fn test_username_proppatch_returns_username_based_href() {
+        let (pool, _user, cal) = setup().await;
+        let app = router(pool, Config::default());
+
+        let proppatch_body = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propertyupdate xmlns:D="DAV:" xmlns:A="http://apple.com/ns/ical/">
+  <D:set>
+    <D:prop>
+      <D:displayname>Renamed Calendar</D:displayname>
+    </D:prop>
+  </D:set>
+</D:propertyupdate>"#;
+
         let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPPATCH").unwrap())
@@ -1396,7 +2118,7 @@ mod tests {
     #[tokio::test]
     async fn test_propfind_with_specific_props_returns_200_and_404_propstat() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Request specific properties: some we have, some we don't
         let propfind_body = r#"<?xml version="1.0" encoding="utf-8"?>
@@ -1472,7 +2194,7 @@ mod tests {
     #[tokio::test]
     async fn test_email_home_propfind_with_specific_props_filters_correctly() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Apple Calendar-style PROPFIND with non-standard namespace prefixes
         let propfind_body = r#"<?xml version="1.0" encoding="utf-8"?>
@@ -1484,7 +2206,7 @@ mod tests {
     <B:calendar-user-address-set/>
     <C:email-address-set/>
     <A:resource-id/>
-    <A:quota-available-bytes/>
+    <C:checksum-versions/>
   </A:prop>
 </A:propfind>"#;
 
@@ -1532,15 +2254,15 @@ mod tests {
             "Should have 404 propstat"
         );
         assert!(
-            body_str.contains("quota-available-bytes"),
-            "quota-available-bytes should be 404"
+            body_str.contains("checksum-versions"),
+            "checksum-versions should be 404"
         );
     }
 
     #[tokio::test]
     async fn test_propfind_allprop_returns_no_404_propstat() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Empty body = allprop
         let uri = format!("/calendar/dav/alice%40example.com/user/{}/", cal.id);
@@ -1573,7 +2295,7 @@ mod tests {
     #[tokio::test]
     async fn test_well_known_returns_301_redirect() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1592,7 +2314,7 @@ mod tests {
     #[tokio::test]
     async fn test_well_known_options_returns_200_with_dav_headers() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("OPTIONS")
@@ -1606,12 +2328,61 @@ mod tests {
         assert!(dav.contains("calendar-access"));
     }
 
+    #[tokio::test]
+    async fn test_cors_preflight_only_on_discovery_routes() {
+        let (pool, _user, cal) = setup().await;
+        let config = Config {
+            discovery_cors_origins: vec!["https://tools.example.com".to_string()],
+            ..Config::default()
+        };
+        let preflight = |uri: String| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri(uri)
+                .header("Origin", "https://tools.example.com")
+                .header("Access-Control-Request-Method", "PROPFIND")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(preflight("/.well-known/caldav".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()["access-control-allow-origin"],
+            "https://tools.example.com"
+        );
+        let methods = resp.headers()["access-control-allow-methods"]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("PROPFIND"));
+
+        // Authenticated data routes are not opened up
+        let resp = router(pool.clone(), config)
+            .oneshot(preflight(format!(
+                "/caldav/users/alice/{}/event.ics",
+                cal.id
+            )))
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+
+        // Without configured origins, discovery gets no CORS headers either
+        let resp = router(pool, Config::default())
+            .oneshot(preflight("/.well-known/caldav".to_string()))
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+    }
+
     // --- options endpoint ---
 
     #[tokio::test]
     async fn test_caldav_root_options_returns_dav_headers() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("OPTIONS")
@@ -1628,7 +2399,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_collection_options_returns_dav_headers() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
@@ -1646,7 +2417,7 @@ mod tests {
     #[tokio::test]
     async fn test_object_options_returns_dav_headers() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/test.ics", cal.id);
         let req = Request::builder()
@@ -1661,12 +2432,61 @@ mod tests {
         assert!(dav.contains("calendar-access"));
     }
 
+    #[tokio::test]
+    async fn test_dav_header_omits_auto_schedule_when_scheduling_disabled() {
+        let (pool, _user, _cal) = setup().await;
+        let app = router(pool, Config::default());
+
+        let req = Request::builder()
+            .method("OPTIONS")
+            .uri("/caldav/")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let dav = resp.headers().get("DAV").unwrap().to_str().unwrap();
+        assert!(dav.contains("calendar-access"));
+        assert!(!dav.contains("calendar-auto-schedule"));
+
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri("/calendar/dav/alice%40example.com/user/")
+            .header("Depth", "0")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let dav = resp.headers().get("DAV").unwrap().to_str().unwrap();
+        assert!(!dav.contains("calendar-auto-schedule"));
+    }
+
+    #[tokio::test]
+    async fn test_dav_header_advertises_enabled_features() {
+        let (pool, _user, cal) = setup().await;
+        let config = Config {
+            scheduling_enabled: true,
+            no_timezone_enabled: true,
+            ..Config::default()
+        };
+        let app = router(pool, config);
+
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
+        let req = Request::builder()
+            .method("OPTIONS")
+            .uri(&uri)
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let dav = resp.headers().get("DAV").unwrap().to_str().unwrap();
+        assert!(dav.contains("calendar-auto-schedule"));
+        assert!(dav.contains("calendar-no-timezone"));
+    }
+
     // --- caldav root method handling ---
 
     #[tokio::test]
     async fn test_caldav_root_unknown_method_returns_405() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("DELETE")
@@ -1681,7 +2501,7 @@ mod tests {
     #[tokio::test]
     async fn test_caldav_root_propfind_authenticated_returns_principal() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1706,7 +2526,7 @@ mod tests {
     #[tokio::test]
     async fn test_server_root_options_returns_200() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("OPTIONS")
@@ -1721,7 +2541,7 @@ mod tests {
     #[tokio::test]
     async fn test_server_root_unknown_method_redirects() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("GET")
@@ -1736,7 +2556,7 @@ mod tests {
     #[tokio::test]
     async fn test_server_root_propfind_authenticated() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1761,7 +2581,7 @@ mod tests {
     #[tokio::test]
     async fn test_principal_discovery_redirects() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -1778,7 +2598,7 @@ mod tests {
     #[tokio::test]
     async fn test_principal_discovery_options_returns_200() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("OPTIONS")
@@ -1795,7 +2615,7 @@ mod tests {
     #[tokio::test]
     async fn test_mkcalendar_creates_calendar() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
 
         let new_cal_id = "my-new-calendar";
         let uri = format!("/caldav/users/alice/{new_cal_id}/");
@@ -1819,7 +2639,7 @@ mod tests {
     #[tokio::test]
     async fn test_mkcalendar_with_displayname() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
 
         let new_cal_id = "named-calendar";
         let uri = format!("/caldav/users/alice/{new_cal_id}/");
@@ -1852,10 +2672,174 @@ mod tests {
         assert_eq!(cal.color, "#FF0000");
     }
 
+    #[tokio::test]
+    async fn test_mkcalendar_component_set_restricts_puts() {
+        let (pool, _user, _cal) = setup().await;
+
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<C:mkcalendar xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:set>
+    <D:prop>
+      <D:displayname>Events</D:displayname>
+      <C:supported-calendar-component-set>
+        <C:comp name="VEVENT"/>
+      </C:supported-calendar-component-set>
+    </D:prop>
+  </D:set>
+</C:mkcalendar>"#;
+        let req = Request::builder()
+            .method(Method::from_bytes(b"MKCALENDAR").unwrap())
+            .uri("/caldav/users/alice/events-only/")
+            .header("Content-Type", "application/xml")
+            .body(Body::from(body))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let cal = crate::db::calendars::get_calendar_by_id(&pool, "events-only")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cal.component_types(), vec!["VEVENT"]);
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri("/caldav/users/alice/events-only/task.ics")
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .header("Content-Type", "text/calendar")
+            .body(Body::from(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:task\r\nSUMMARY:Task\r\nEND:VTODO\r\nEND:VCALENDAR\r\n",
+            ))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(
+            String::from_utf8_lossy(&body).contains("<C:supported-calendar-component/>"),
+            "got: {}",
+            String::from_utf8_lossy(&body)
+        );
+
+        // A set naming only unsupported components is refused
+        let body = r#"<C:mkcalendar xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:set><D:prop><C:supported-calendar-component-set>
+    <C:comp name="VFREEBUSY"/>
+  </C:supported-calendar-component-set></D:prop></D:set>
+</C:mkcalendar>"#;
+        let req = Request::builder()
+            .method(Method::from_bytes(b"MKCALENDAR").unwrap())
+            .uri("/caldav/users/alice/freebusy-only/")
+            .body(Body::from(body))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(
+            crate::db::calendars::get_calendar_by_id(&pool, "freebusy-only")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mkcalendar_component_set_is_atomic() {
+        let (pool, _user, _cal) = setup().await;
+        let mkcalendar = || {
+            Request::builder()
+                .method(Method::from_bytes(b"MKCALENDAR").unwrap())
+                .uri("/caldav/users/alice/events-only/")
+                .body(Body::from(
+                    r#"<C:mkcalendar xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:set><D:prop><C:supported-calendar-component-set>
+    <C:comp name="VEVENT"/>
+  </C:supported-calendar-component-set></D:prop></D:set>
+</C:mkcalendar>"#,
+                ))
+                .unwrap()
+        };
+
+        // Restricting the component set fails, so the calendar is not created
+        sqlx::query(
+            "CREATE TRIGGER refuse_restrict BEFORE UPDATE OF component_types ON calendars
+             BEGIN SELECT RAISE(ABORT, 'refused'); END",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(mkcalendar())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            crate::db::calendars::get_calendar_by_id(&pool, "events-only")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        sqlx::query("DROP TRIGGER refuse_restrict")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(mkcalendar())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_mkcalendar_rejects_unsafe_calendar_id() {
+        let (pool, _user, _cal) = setup().await;
+
+        for uri in [
+            "/caldav/users/alice/my%20calendar/",
+            "/caldav/users/alice/work%3Fhome/",
+            "/caldav/users/alice/caf%C3%A9/",
+        ] {
+            let req = Request::builder()
+                .method(Method::from_bytes(b"MKCALENDAR").unwrap())
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(req)
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{uri}");
+        }
+        assert!(
+            crate::db::calendars::get_calendar_by_id(&pool, "my calendar")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // UUIDs, as generated for MCP-created calendars, are accepted
+        let id = uuid::Uuid::new_v4().to_string();
+        let req = Request::builder()
+            .method(Method::from_bytes(b"MKCALENDAR").unwrap())
+            .uri(format!("/caldav/users/alice/{id}/"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
     #[tokio::test]
     async fn test_mkcalendar_duplicate_returns_method_not_allowed() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Try to create a calendar that already exists
         let uri = format!("/caldav/users/alice/{}/", cal.id);
@@ -1867,24 +2851,78 @@ mod tests {
 
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(resp.headers().contains_key("allow"));
     }
 
     #[tokio::test]
-    async fn test_mkcalendar_cross_user_returns_forbidden() {
-        let pool = db::test_pool().await;
-        let _alice = users::create_user(&pool, "alice", None, "pass1")
+    async fn test_method_not_allowed_includes_allow_header() {
+        let (pool, _user, cal) = setup().await;
+
+        // GET on a collection
+        let req = Request::builder()
+            .method("GET")
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            resp.headers()["allow"],
+            "OPTIONS, PROPFIND, PROPPATCH, REPORT, MKCALENDAR, DELETE"
+        );
+
+        // PROPFIND on an object
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(format!("/caldav/users/alice/{}/event.ics", cal.id))
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
             .await
             .unwrap();
-        let _bob = users::create_user(&pool, "bob", None, "pass2")
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let allow = resp.headers()["allow"].clone();
+        assert_eq!(allow, "OPTIONS, GET, HEAD, PUT, DELETE, COPY, MOVE");
+
+        // OPTIONS advertises the same set
+        let req = Request::builder()
+            .method("OPTIONS")
+            .uri(format!("/caldav/users/alice/{}/event.ics", cal.id))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["allow"], allow);
+    }
+
+    #[tokio::test]
+    async fn test_mkcalendar_cross_user_returns_forbidden() {
+        let pool = db::test_pool().await;
+        let _alice = users::create_user(
+            &pool,
+            "alice",
+            None,
+            "password1",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let _bob = users::create_user(&pool, "bob", None, "password2", &PasswordPolicy::default())
             .await
             .unwrap();
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         // Alice (resolved via path) tries to create in bob's space — forbidden
         let req = Request::builder()
             .method(Method::from_bytes(b"MKCALENDAR").unwrap())
             .uri("/caldav/users/bob/some-cal/")
-            .header("Authorization", basic_auth_header("alice", "pass1"))
+            .header("Authorization", basic_auth_header("alice", "password1"))
             .body(Body::empty())
             .unwrap();
 
@@ -1914,7 +2952,7 @@ mod tests {
         .await
         .unwrap();
 
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
         let uri = format!("/caldav/users/alice/{}/delete-me%40example.com.ics", cal.id);
         let req = Request::builder()
             .method("DELETE")
@@ -1934,7 +2972,7 @@ mod tests {
     #[tokio::test]
     async fn test_delete_object_not_found_returns_404() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/nonexistent.ics", cal.id);
         let req = Request::builder()
@@ -1950,7 +2988,7 @@ mod tests {
     #[tokio::test]
     async fn test_delete_calendar_returns_no_content() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
 
         let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
@@ -1971,7 +3009,7 @@ mod tests {
     #[tokio::test]
     async fn test_delete_calendar_not_found_returns_404() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method("DELETE")
@@ -1991,9 +3029,53 @@ mod tests {
     // --- PUT ---
 
     #[tokio::test]
-    async fn test_put_creates_event() {
-        let (pool, _user, cal) = setup().await;
-        let app = router(pool.clone());
+    async fn test_put_to_missing_calendar_returns_404() {
+        let (pool, _user, _cal) = setup().await;
+        let ical_data = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:foo\r\nDTSTART:20260301T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        for uri in [
+            "/caldav/users/alice/missing-cal/foo.ics",
+            "/calendar/dav/alice%40example.com/user/missing-cal/foo.ics",
+        ] {
+            let req = Request::builder()
+                .method("PUT")
+                .uri(uri)
+                .header("Authorization", basic_auth_header("alice", "secret123"))
+                .body(Body::from(ical_data))
+                .unwrap();
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(req)
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_to_other_users_calendar_returns_403() {
+        let (pool, _alice, _cal) = setup().await;
+        let bob = users::create_user(&pool, "bob", None, "password2", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let bob_cal = calendars::create_calendar(&pool, &bob.id, "Bob Cal", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let ical_data = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:foo\r\nDTSTART:20260301T090000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/caldav/users/alice/{}/foo.ics", bob_cal.id))
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .body(Body::from(ical_data))
+            .unwrap();
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_put_creates_event() {
+        let (pool, _user, cal) = setup().await;
+        let app = router(pool.clone(), Config::default());
 
         let ical_data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:put-new@test.com\r\nSUMMARY:New Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
 
@@ -2010,6 +3092,339 @@ mod tests {
         assert!(resp.headers().contains_key("etag"));
     }
 
+    #[tokio::test]
+    async fn test_put_indexes_client_events_for_range_queries() {
+        let (pool, _user, cal) = setup().await;
+
+        // As Apple Calendar sends them: folded lines, a TZID-qualified start
+        // with a DURATION, and an all-day event without DTEND
+        let bodies = [
+            (
+                "folded",
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:folded\r\nDTSTART:20260302T090000Z\r\nDTEND:2026\r\n 0302T100000Z\r\nSUMMARY:Planning with a title long enough\r\n  to fold\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            ),
+            (
+                "berlin",
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:berlin\r\nDTSTART;TZID=Europe/Berlin:20260303T090000\r\nDURATION:PT1H\r\nSUMMARY:Berlin\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            ),
+            (
+                "holiday",
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:holiday\r\nDTSTART;VALUE=DATE:20260304\r\nSUMMARY:Holiday\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            ),
+        ];
+        for (uid, body) in bodies {
+            let req = Request::builder()
+                .method("PUT")
+                .uri(format!("/caldav/users/alice/{}/{uid}.ics", cal.id))
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(body))
+                .unwrap();
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(req)
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::CREATED, "{uid}");
+        }
+
+        let stored = crate::db::events::get_object_by_uid(&pool, &cal.id, "folded")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.dtend.as_deref(), Some("20260302T100000Z"));
+        assert_eq!(
+            stored.summary.as_deref(),
+            Some("Planning with a title long enough to fold")
+        );
+
+        let found = |start: &'static str, end: &'static str| {
+            let pool = pool.clone();
+            let cal_id = cal.id.clone();
+            async move {
+                crate::db::events::list_objects_in_range(&pool, &cal_id, start, end)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|o| o.uid)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            found("20260301T000000Z", "20260305T000000Z").await,
+            vec!["folded", "berlin", "holiday"]
+        );
+        // 09:00 in Berlin is 08:00 UTC, so the hour ends at 09:00 UTC
+        assert_eq!(
+            found("20260303T083000Z", "20260303T084500Z").await,
+            vec!["berlin"]
+        );
+        assert!(
+            found("20260303T090000Z", "20260303T100000Z")
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            found("20260304T120000Z", "20260304T130000Z").await,
+            vec!["holiday"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_create_returns_location_of_object() {
+        let (pool, _user, cal) = setup().await;
+        let ical = |uid: &str| {
+            format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nSUMMARY:New\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            )
+        };
+
+        // The Location names the stored object's href
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!(
+                "/caldav/users/alice/{}/located%40test.com.ics",
+                cal.id
+            ))
+            .header("Content-Type", "text/calendar")
+            .body(Body::from(ical("located@test.com")))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(resp.headers().contains_key("etag"));
+        let location = resp.headers()["location"].to_str().unwrap().to_string();
+        assert_eq!(
+            location,
+            format!("/caldav/users/alice/{}/located@test.com.ics", cal.id)
+        );
+
+        // The object can be fetched at that URL
+        let req = Request::builder()
+            .method("GET")
+            .uri(&location)
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Email-based paths get email-based hrefs
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!(
+                "/calendar/dav/alice%40example.com/user/{}/email-located.ics",
+                cal.id
+            ))
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .header("Content-Type", "text/calendar")
+            .body(Body::from(ical("email-located")))
+            .unwrap();
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(
+            resp.headers()["location"],
+            format!(
+                "/calendar/dav/alice%40example.com/user/{}/email-located.ics",
+                cal.id
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_rejects_uid_that_does_not_match_resource_name() {
+        let (pool, _user, cal) = setup().await;
+        let put = |path: String, uid: &str| {
+            Request::builder()
+                .method("PUT")
+                .uri(path)
+                .header("Authorization", basic_auth_header("alice", "secret123"))
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(format!(
+                    "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nSUMMARY:Named\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+                )))
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put(
+                format!("/caldav/users/alice/{}/client-name.ics", cal.id),
+                "other@test.com",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<C:no-uid-conflict>"), "got: {body}");
+        assert!(
+            body.contains(&format!(
+                "<D:href>/caldav/users/alice/{}/other@test.com.ics</D:href>",
+                cal.id
+            )),
+            "got: {body}"
+        );
+        for uid in ["client-name", "other@test.com"] {
+            assert!(
+                crate::db::events::get_object_by_uid(&pool, &cal.id, uid)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+
+        // The email path is held to the same rule
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put(
+                format!(
+                    "/calendar/dav/alice%40example.com/user/{}/client-name.ics",
+                    cal.id
+                ),
+                "other@test.com",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        // A matching name, percent-encoded or not, is stored
+        for path in [
+            format!("/caldav/users/alice/{}/other%40test.com.ics", cal.id),
+            format!("/caldav/users/alice/{}/other@test.com.ics", cal.id),
+        ] {
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(put(path, "other@test.com"))
+                .await
+                .unwrap();
+            assert!(resp.status().is_success(), "got {}", resp.status());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_put_rejects_out_of_range_year() {
+        let (pool, _user, cal) = setup().await;
+        let put = |uid: &str, dtstart: &str| {
+            let body = format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nDTSTART:{dtstart}\r\nSUMMARY:Review\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            );
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/caldav/users/alice/{}/{uid}.ics", cal.id))
+                .header("Authorization", basic_auth_header("alice", "secret123"))
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("typo", "02260301T090000Z"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            resp.headers()["content-type"],
+            "application/xml; charset=utf-8"
+        );
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<D:error"), "got: {body}");
+        assert!(body.contains("<C:min-date-time/>"), "got: {body}");
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("far", "22260301T090000Z"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("<C:max-date-time/>"), "got: {body}");
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("ok", "20260301T090000Z"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_component_blocks_new_objects_only() {
+        let (pool, _user, cal) = setup().await;
+        let todo = |uid: &str, summary: &str| {
+            format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:{uid}\r\nSUMMARY:{summary}\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+            )
+        };
+        let put = |uid: &str, body: String| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/caldav/users/alice/{}/{uid}.ics", cal.id))
+                .header("Authorization", basic_auth_header("alice", "secret123"))
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("task-1", todo("task-1", "Existing")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        calendars::set_component_types(&pool, &cal.id, &["VEVENT"])
+            .await
+            .unwrap();
+
+        // New tasks are refused
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("task-2", todo("task-2", "New")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "task-2")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // The existing task is still readable and can be updated
+        let req = Request::builder()
+            .method("GET")
+            .uri(format!("/caldav/users/alice/{}/task-1.ics", cal.id))
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("SUMMARY:Existing"));
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("task-1", todo("task-1", "Renamed")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        // The advertised component set follows the calendar
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Authorization", basic_auth_header("alice", "secret123"))
+            .header("Depth", "0")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        assert!(body_str.contains("<C:comp name=\"VEVENT\"/>"));
+        assert!(!body_str.contains("<C:comp name=\"VTODO\"/>"));
+    }
+
     #[tokio::test]
     async fn test_put_updates_existing_event() {
         let (pool, _user, cal) = setup().await;
@@ -2026,7 +3441,7 @@ mod tests {
             },
         ).await.unwrap();
 
-        let app = router(pool.clone());
+        let app = router(pool.clone(), Config::default());
 
         let updated_ical = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:update-me@test.com\r\nSUMMARY:Updated\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
 
@@ -2040,6 +3455,8 @@ mod tests {
 
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(resp.headers().contains_key("etag"));
+        assert!(!resp.headers().contains_key("location"));
 
         let obj = crate::db::events::get_object_by_uid(&pool, &cal.id, "update-me@test.com")
             .await
@@ -2064,7 +3481,7 @@ mod tests {
             },
         ).await.unwrap();
 
-        let app = router(pool);
+        let app = router(pool, Config::default());
         let uri = format!("/caldav/users/alice/{}/ifmatch%40test.com.ics", cal.id);
         let req = Request::builder()
             .method("PUT")
@@ -2080,7 +3497,7 @@ mod tests {
     #[tokio::test]
     async fn test_put_with_if_match_on_nonexistent_returns_412() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/nope@test.com.ics", cal.id);
         let req = Request::builder()
@@ -2113,7 +3530,7 @@ mod tests {
         .await
         .unwrap();
 
-        let app = router(pool);
+        let app = router(pool, Config::default());
         let uri = format!("/caldav/users/alice/{}/star%40test.com.ics", cal.id);
         let req = Request::builder()
             .method("PUT")
@@ -2121,95 +3538,1330 @@ mod tests {
             .header("If-Match", "*")
             .body(Body::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:star@test.com\r\nSUMMARY:Updated\r\nDTSTART:20260101T000000Z\r\nDTEND:20260101T010000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"))
             .unwrap();
-
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_put_with_if_none_match_star_on_existing_returns_412() {
+        let (pool, _user, cal) = setup().await;
+        let etag = insert_if_header_event(&pool, &cal.id, "exists@test.com").await;
+
+        let app = router(pool.clone(), Config::default());
+        let uri = format!("/caldav/users/alice/{}/exists%40test.com.ics", cal.id);
+        let req = Request::builder()
+            .method("PUT")
+            .uri(&uri)
+            .header("If-None-Match", "*")
+            .body(Body::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:exists@test.com\r\nSUMMARY:Overwritten\r\nDTSTART:20260101T000000Z\r\nDTEND:20260101T010000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+        let obj = crate::db::events::get_object_by_uid(&pool, &cal.id, "exists@test.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(obj.etag, etag);
+        assert!(!obj.ical_data.contains("Overwritten"));
+    }
+
+    #[tokio::test]
+    async fn test_put_with_if_none_match_star_creates_new() {
+        let (pool, _user, cal) = setup().await;
+
+        let app = router(pool.clone(), Config::default());
+        let uri = format!("/caldav/users/alice/{}/fresh%40test.com.ics", cal.id);
+        let req = Request::builder()
+            .method("PUT")
+            .uri(&uri)
+            .header("If-None-Match", "*")
+            .body(Body::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:fresh@test.com\r\nSUMMARY:Fresh\r\nDTSTART:20260101T000000Z\r\nDTEND:20260101T010000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "fresh@test.com")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    /// Store an event and return its current ETag.
+    async fn insert_if_header_event(pool: &SqlitePool, calendar_id: &str, uid: &str) -> String {
+        let (obj, _) = crate::db::events::upsert_object(
+            pool,
+            calendar_id,
+            uid,
+            &format!("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nEND:VEVENT\r\nEND:VCALENDAR"),
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: None,
+            },
+        )
+        .await
+        .unwrap();
+        obj.etag
+    }
+
+    #[tokio::test]
+    async fn test_put_with_untagged_if_header() {
+        let (pool, _user, cal) = setup().await;
+        let etag = insert_if_header_event(&pool, &cal.id, "if-untagged@test.com").await;
+        let uri = format!("/caldav/users/alice/{}/if-untagged%40test.com.ics", cal.id);
+        let body = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:if-untagged@test.com\r\nSUMMARY:Updated\r\nDTSTART:20260101T000000Z\r\nDTEND:20260101T010000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        let put = |if_header: String| {
+            Request::builder()
+                .method("PUT")
+                .uri(&uri)
+                .header("If", if_header)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // Stale ETag fails
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("([\"stale\"])".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        // Negated current ETag fails
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put(format!("(Not [{etag}])")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        // Negated stale ETag succeeds
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put("(Not [\"stale\"])".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        // The update changed the ETag, so the original no longer matches
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(put(format!("([{etag}])")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        // Malformed headers are rejected
+        let resp = router(pool, Config::default())
+            .oneshot(put("[\"stale\"]".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_delete_with_tagged_if_header() {
+        let (pool, _user, cal) = setup().await;
+        let etag = insert_if_header_event(&pool, &cal.id, "if-tagged@test.com").await;
+        let uri = format!("/caldav/users/alice/{}/if-tagged@test.com.ics", cal.id);
+
+        let delete = |if_header: String| {
+            Request::builder()
+                .method("DELETE")
+                .uri(&uri)
+                .header("If", if_header)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // A tagged list for this resource with a stale ETag fails
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(delete(format!("<http://localhost{uri}> ([\"stale\"])")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        // A tagged list for another resource does not apply
+        let other = format!("/caldav/users/alice/{}/other.ics", cal.id);
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(delete(format!(
+                "<{other}> ([\"stale\"]) <{uri}> ([{etag}])"
+            )))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "if-tagged@test.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_latin1_body_is_stored_as_utf8() {
+        let (pool, _user, cal) = setup().await;
+
+        // "Café Réunion" encoded as ISO-8859-1 (é = 0xE9)
+        let body: &[u8] = b"BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:latin1@test.com\r\nSUMMARY:Caf\xE9 R\xE9union\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        let uri = format!("/caldav/users/alice/{}/latin1%40test.com.ics", cal.id);
+        let req = Request::builder()
+            .method("PUT")
+            .uri(&uri)
+            .header("Content-Type", "text/calendar; charset=ISO-8859-1")
+            .body(Body::from(body))
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let req = Request::builder()
+            .method("GET")
+            .uri(&uri)
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("SUMMARY:Caf\u{e9} R\u{e9}union"));
+
+        let obj = crate::db::events::get_object_by_uid(&pool, &cal.id, "latin1@test.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(obj.summary.as_deref(), Some("Caf\u{e9} R\u{e9}union"));
+    }
+
+    #[tokio::test]
+    async fn test_put_unsupported_charset_returns_415() {
+        let (pool, _user, cal) = setup().await;
+        let app = router(pool, Config::default());
+
+        let uri = format!("/caldav/users/alice/{}/sjis%40test.com.ics", cal.id);
+        let req = Request::builder()
+            .method("PUT")
+            .uri(&uri)
+            .header("Content-Type", "text/calendar; charset=Shift_JIS")
+            .body(Body::from("BEGIN:VCALENDAR\r\nEND:VCALENDAR"))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_put_validates_calendar_structure() {
+        let (pool, _user, cal) = setup().await;
+        let put = |uid: &str, body: &'static str| {
+            Request::builder()
+                .method("PUT")
+                .uri(format!("/caldav/users/alice/{}/{uid}.ics", cal.id))
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        for (uid, body) in [
+            ("garbage", "this is not a calendar"),
+            ("empty", ""),
+            (
+                "no-component",
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n",
+            ),
+            (
+                "unterminated",
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:unterminated\r\nEND:VEVENT\r\n",
+            ),
+        ] {
+            let resp = router(pool.clone(), Config::default())
+                .oneshot(put(uid, body))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{uid}");
+            let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+            assert!(String::from_utf8_lossy(&bytes).contains("<C:valid-calendar-data/>"));
+            assert!(
+                crate::db::events::get_object_by_uid(&pool, &cal.id, uid)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+
+        let resp = router(pool, Config::default())
+            .oneshot(put(
+                "valid",
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:valid\r\nSUMMARY:Valid\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_put_over_max_resource_size_returns_413() {
+        let (pool, _user, cal) = setup().await;
+        let config = Config {
+            max_resource_size: 1024,
+            ..Config::default()
+        };
+
+        let description = "x".repeat(2048);
+        let ical = format!(
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:big\r\nDESCRIPTION:{description}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+        );
+        let req = Request::builder()
+            .method("PUT")
+            .uri(format!("/caldav/users/alice/{}/big.ics", cal.id))
+            .header("Content-Type", "text/calendar")
+            .body(Body::from(ical))
+            .unwrap();
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("<C:max-resource-size/>"));
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "big")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // The limit is advertised on the calendar collection
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Depth", "0")
+            .header("Content-Type", "application/xml")
+            .body(Body::from(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<A:propfind xmlns:A="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <A:prop><C:max-resource-size/></A:prop>
+</A:propfind>"#,
+            ))
+            .unwrap();
+        let resp = router(pool, config).oneshot(req).await.unwrap();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(
+            String::from_utf8_lossy(&body)
+                .contains("<C:max-resource-size>1024</C:max-resource-size>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_identical_content_keeps_etag_in_content_mode() {
+        let (pool, _user, cal) = setup().await;
+        let config = Config {
+            etag_mode: crate::db::events::EtagMode::Content,
+            ..Config::default()
+        };
+
+        let ical_data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:stable@test.com\r\nSUMMARY:Stable\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let uri = format!("/caldav/users/alice/{}/stable%40test.com.ics", cal.id);
+        let put = || {
+            Request::builder()
+                .method("PUT")
+                .uri(&uri)
+                .header("Content-Type", "text/calendar")
+                .body(Body::from(ical_data))
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(put())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let first = resp.headers()["etag"].clone();
+
+        let resp = router(pool.clone(), config).oneshot(put()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(resp.headers()["etag"], first);
+
+        // The default random mode still issues a new ETag
+        let resp = router(pool, Config::default())
+            .oneshot(put())
+            .await
+            .unwrap();
+        assert_ne!(resp.headers()["etag"], first);
+    }
+
+    // --- GET ---
+
+    #[tokio::test]
+    async fn test_get_existing_event() {
+        let (pool, _user, cal) = setup().await;
+
+        crate::db::events::upsert_object(
+            &pool, &cal.id, "get-me@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:get-me@test.com\r\nSUMMARY:Get Me\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: Some("Get Me"),
+            },
+        ).await.unwrap();
+
+        let app = router(pool, Config::default());
+        let uri = format!("/caldav/users/alice/{}/get-me%40test.com.ics", cal.id);
+        let req = Request::builder()
+            .method("GET")
+            .uri(&uri)
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get("content-type")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "text/calendar; charset=utf-8"
+        );
+        assert!(resp.headers().contains_key("etag"));
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("Get Me"));
+    }
+
+    #[tokio::test]
+    async fn test_propfind_depth1_returns_object_dates() {
+        let (pool, _user, cal) = setup().await;
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "dated@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:dated@test.com\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let body = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop><D:getetag/><D:getlastmodified/><D:creationdate/></D:prop>
+</D:propfind>"#;
+        let req = Request::builder()
+            .method(Method::from_bytes(b"PROPFIND").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(Body::from(body))
+            .unwrap();
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+
+        let object = body_str
+            .split("<D:response>")
+            .find(|r| r.contains("dated%40test.com.ics") || r.contains("dated@test.com.ics"))
+            .unwrap_or_else(|| panic!("object missing from listing: {body_str}"));
+        let value = |name: &str| {
+            let open = format!("<D:{name}>");
+            let start = object
+                .find(&open)
+                .unwrap_or_else(|| panic!("no {name}: {object}"));
+            let rest = &object[start + open.len()..];
+            rest[..rest.find('<').unwrap()].to_string()
+        };
+        // RFC 1123, e.g. "Mon, 02 Mar 2026 09:00:00 GMT"
+        let modified = value("getlastmodified");
+        assert!(
+            chrono::DateTime::parse_from_rfc2822(&modified).is_ok() && modified.ends_with(" GMT"),
+            "got: {modified}"
+        );
+        // ISO 8601 / RFC 3339
+        let created = value("creationdate");
+        assert!(
+            chrono::DateTime::parse_from_rfc3339(&created).is_ok(),
+            "got: {created}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_head_returns_headers_without_body() {
+        let (pool, _user, cal) = setup().await;
+        let data = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:head@test.com\r\nSUMMARY:Head\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let (obj, _) = crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "head@test.com",
+            data,
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: Some("Head"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let head = |name: &str| {
+            Request::builder()
+                .method("HEAD")
+                .uri(format!("/caldav/users/alice/{}/{name}", cal.id))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(head("head%40test.com.ics"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["etag"], obj.etag.as_str());
+        assert_eq!(
+            resp.headers()["content-length"],
+            data.len().to_string().as_str()
+        );
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+
+        let resp = router(pool, Config::default())
+            .oneshot(head("missing.ics"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_move_object_to_new_filename() {
+        let (pool, _user, cal) = setup().await;
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "old-name",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:old-name\r\nSUMMARY:Rename me\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: Some("Rename me"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let req = Request::builder()
+            .method(Method::from_bytes(b"MOVE").unwrap())
+            .uri(format!("/caldav/users/alice/{}/old-name.ics", cal.id))
+            .header(
+                "Destination",
+                format!(
+                    "http://localhost/caldav/users/alice/{}/new-name.ics",
+                    cal.id
+                ),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let moved = crate::db::events::get_object_by_uid(&pool, &cal.id, "new-name")
+            .await
+            .unwrap()
+            .expect("object at the new name");
+        assert!(moved.ical_data.contains("UID:new-name"));
+        assert!(moved.ical_data.contains("SUMMARY:Rename me"));
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "old-name")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_object_is_atomic_and_uses_etag_mode() {
+        let (pool, user, cal) = setup().await;
+        let home = calendars::create_calendar(&pool, &user.id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let (source, _) = crate::db::events::upsert_object_with_etag_mode(
+            &pool,
+            &cal.id,
+            "move-me",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:move-me\r\nSUMMARY:Move me\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: Some("Move me"),
+            },
+            crate::db::events::EtagMode::Content,
+        )
+        .await
+        .unwrap();
+        let config = Config {
+            etag_mode: crate::db::events::EtagMode::Content,
+            ..Config::default()
+        };
+        let request = || {
+            Request::builder()
+                .method(Method::from_bytes(b"MOVE").unwrap())
+                .uri(format!("/caldav/users/alice/{}/move-me.ics", cal.id))
+                .header(
+                    "Destination",
+                    format!("/caldav/users/alice/{}/move-me.ics", home.id),
+                )
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // The source delete fails, so the destination write is rolled back
+        sqlx::query(
+            "CREATE TRIGGER refuse_delete BEFORE DELETE ON calendar_objects
+             BEGIN SELECT RAISE(ABORT, 'refused'); END",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &home.id, "move-me")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        sqlx::query("DROP TRIGGER refuse_delete")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let resp = router(pool.clone(), config)
+            .oneshot(request())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        // Same content, so the content-derived ETag carries over
+        assert_eq!(resp.headers()["etag"], source.etag.as_str());
+        assert!(
+            crate::db::events::get_object_by_uid(&pool, &cal.id, "move-me")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_object_into_another_calendar() {
+        let (pool, user, cal) = setup().await;
+        let home = calendars::create_calendar(&pool, &user.id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let data = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:copy-me\r\nSUMMARY:Copy me\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        for calendar_id in [&cal.id, &home.id] {
+            // The destination already holds a stale version of the event
+            let ical_data = if *calendar_id == cal.id {
+                data
+            } else {
+                "stale"
+            };
+            crate::db::events::upsert_object(
+                &pool,
+                calendar_id,
+                "copy-me",
+                ical_data,
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: None,
+                    dtend: None,
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let copy = |overwrite: &str| {
+            Request::builder()
+                .method(Method::from_bytes(b"COPY").unwrap())
+                .uri(format!("/caldav/users/alice/{}/copy-me.ics", cal.id))
+                .header(
+                    "Destination",
+                    format!("/caldav/users/alice/{}/copy-me.ics", home.id),
+                )
+                .header("Overwrite", overwrite)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Overwrite: F refuses to replace the existing object
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(copy("F"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(copy("T"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        for calendar_id in [&cal.id, &home.id] {
+            let obj = crate::db::events::get_object_by_uid(&pool, calendar_id, "copy-me")
+                .await
+                .unwrap()
+                .expect("object in both calendars");
+            assert_eq!(obj.ical_data, data);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_nonexistent_event_returns_404() {
+        let (pool, _user, cal) = setup().await;
+        let app = router(pool, Config::default());
+
+        let uri = format!("/caldav/users/alice/{}/nope.ics", cal.id);
+        let req = Request::builder()
+            .method("GET")
+            .uri(&uri)
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_calendar_export_merges_objects() {
+        let (pool, _user, cal) = setup().await;
+        for uid in ["one@test.com", "two@test.com"] {
+            let data = format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nSUMMARY:Export\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            );
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                uid,
+                &data,
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: None,
+                    dtend: None,
+                    summary: Some("Export"),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let app = router(pool, Config::default());
+        let req = Request::builder()
+            .method("GET")
+            .uri(format!("/caldav/users/alice/{}/export", cal.id))
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "text/calendar; charset=utf-8"
+        );
+        assert_eq!(
+            resp.headers().get("content-disposition").unwrap(),
+            "attachment; filename=\"Work.ics\""
+        );
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        assert_eq!(body.matches("BEGIN:VCALENDAR").count(), 1);
+        assert_eq!(body.matches("END:VCALENDAR").count(), 1);
+        assert_eq!(body.matches("PRODID:").count(), 1);
+        assert!(body.contains("UID:one@test.com"));
+        assert!(body.contains("UID:two@test.com"));
+    }
+
+    // --- REPORT ---
+
+    #[tokio::test]
+    async fn test_report_calendar_query_no_filter() {
+        let (pool, _user, cal) = setup().await;
+
+        crate::db::events::upsert_object(
+            &pool, &cal.id, "query-uid@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:query-uid@test.com\r\nSUMMARY:Query Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260301T090000Z"),
+                dtend: Some("20260301T100000Z"),
+                summary: Some("Query Event"),
+            },
+        ).await.unwrap();
+
+        let app = router(pool, Config::default());
+
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <D:getetag/>
+    <C:calendar-data/>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR"/>
+  </C:filter>
+</C:calendar-query>"#;
+
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(&uri)
+            .body(Body::from(report_body))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        assert!(body_str.contains("query-uid@test.com"));
+    }
+
+    /// Send a REPORT to the calendar collection with an optional Depth header.
+    async fn report_with_depth(
+        pool: &sqlx::SqlitePool,
+        cal_id: &str,
+        depth: Option<&str>,
+        body: &'static str,
+    ) -> (StatusCode, String) {
+        let mut req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(format!("/caldav/users/alice/{cal_id}/"));
+        if let Some(depth) = depth {
+            req = req.header("Depth", depth);
+        }
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(req.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn test_report_calendar_query_depth_scoping() {
+        let (pool, _user, cal) = setup().await;
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "depth-uid@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:depth-uid@test.com\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260301T090000Z"),
+                dtend: Some("20260301T100000Z"),
+                summary: None,
+            },
+        )
+        .await
+        .unwrap();
+        let query = r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/></D:prop>
+  <C:filter><C:comp-filter name="VCALENDAR"/></C:filter>
+</C:calendar-query>"#;
+
+        // Depth:1 covers the collection's members
+        let (status, body) = report_with_depth(&pool, &cal.id, Some("1"), query).await;
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert!(body.contains("depth-uid@test.com"));
+
+        // Depth:0 covers only the collection, which is not a calendar object
+        let (status, body) = report_with_depth(&pool, &cal.id, Some("0"), query).await;
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert!(!body.contains("depth-uid@test.com"));
+
+        let (status, _) = report_with_depth(&pool, &cal.id, Some("2"), query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_report_sync_collection_requires_depth_zero() {
+        let (pool, _user, cal) = setup().await;
+        let sync = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token/>
+  <D:sync-level>1</D:sync-level>
+  <D:prop><D:getetag/></D:prop>
+</D:sync-collection>"#;
+
+        let (status, _) = report_with_depth(&pool, &cal.id, Some("1"), sync).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = report_with_depth(&pool, &cal.id, Some("0"), sync).await;
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert!(body.contains("sync-token"));
+    }
+
+    #[tokio::test]
+    async fn test_report_summary_with_control_char_is_parseable() {
+        let (pool, _user, cal) = setup().await;
+
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "vt-uid@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:vt-uid@test.com\r\nSUMMARY:Pasted\u{0B}Title\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260301T090000Z"),
+                dtend: Some("20260301T100000Z"),
+                summary: Some("Pasted\u{0B}Title"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <C:calendar-data/>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR"/>
+  </C:filter>
+</C:calendar-query>"#;
+
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(&uri)
+            .body(Body::from(report_body))
+            .unwrap();
+
+        let resp = router(pool, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("SUMMARY:PastedTitle"));
+        assert!(!body_str.contains('\u{0B}'));
+
+        let mut reader = quick_xml::Reader::from_str(&body_str);
+        loop {
+            match reader.read_event() {
+                Ok(quick_xml::events::Event::Eof) => break,
+                Ok(_) => {}
+                Err(e) => panic!("multistatus is not well-formed: {e}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_calendar_multiget() {
+        let (pool, _user, cal) = setup().await;
+
+        crate::db::events::upsert_object(
+            &pool, &cal.id, "multiget-uid@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:multiget-uid@test.com\r\nSUMMARY:Multiget Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260301T090000Z"),
+                dtend: Some("20260301T100000Z"),
+                summary: Some("Multiget Event"),
+            },
+        ).await.unwrap();
+
+        let app = router(pool.clone(), Config::default());
+
+        let report_body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <D:getetag/>
+    <C:calendar-data/>
+  </D:prop>
+  <D:href>/caldav/users/alice/{}/multiget-uid%40test.com.ics</D:href>
+</C:calendar-multiget>"#,
+            cal.id
+        );
+
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(&uri)
+            .body(Body::from(report_body))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        assert!(body_str.contains("multiget-uid@test.com"));
+        assert!(body_str.contains("Multiget Event"));
+    }
+
+    #[tokio::test]
+    async fn test_report_sync_collection_initial_sync() {
+        let (pool, _user, cal) = setup().await;
+
+        crate::db::events::upsert_object(
+            &pool, &cal.id, "sync-uid@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:sync-uid@test.com\r\nSUMMARY:Sync Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260301T090000Z"),
+                dtend: Some("20260301T100000Z"),
+                summary: Some("Sync Event"),
+            },
+        ).await.unwrap();
+
+        let app = router(pool, Config::default());
+
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:sync-token/>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+  </D:prop>
+</D:sync-collection>"#;
+
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(&uri)
+            .body(Body::from(report_body))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        assert!(body_str.contains("sync-uid@test.com"));
+        // Sync token must be a valid URI (RFC 6578)
+        assert!(body_str.contains("sync-token"), "Should contain sync-token");
+    }
+
+    #[tokio::test]
+    async fn test_report_sync_collection_with_calendar_data() {
+        let (pool, _user, cal) = setup().await;
+
+        crate::db::events::upsert_object(
+            &pool, &cal.id, "sync-data@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:sync-data@test.com\r\nSUMMARY:Sync Data\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260301T090000Z"),
+                dtend: Some("20260301T100000Z"),
+                summary: Some("Sync Data"),
+            },
+        ).await.unwrap();
+
+        let app = router(pool, Config::default());
+
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:sync-token/>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+    <C:calendar-data/>
+  </D:prop>
+</D:sync-collection>"#;
+
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(&uri)
+            .body(Body::from(report_body))
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        assert!(
+            body_str.contains("Sync Data"),
+            "calendar-data should be included"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report_sync_collection_reports_deletion_as_404() {
+        let (pool, _user, cal) = setup().await;
+
+        for uid in ["kept", "gone"] {
+            let data = format!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            );
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                uid,
+                &data,
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some("20260301T090000Z"),
+                    dtend: Some("20260301T100000Z"),
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let token = crate::db::calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .sync_token;
+        crate::db::events::delete_object(&pool, &cal.id, "gone")
+            .await
+            .unwrap();
+
+        let app = router(pool, Config::default());
+        let report_body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>{token}</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+  </D:prop>
+</D:sync-collection>"#
+        );
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .body(Body::from(report_body))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        let href = format!("/caldav/users/alice/{}/gone.ics", cal.id);
+        assert!(
+            body_str.contains(&format!(
+                "<D:response><D:href>{href}</D:href><D:status>HTTP/1.1 404 Not Found</D:status></D:response>"
+            )),
+            "got: {body_str}"
+        );
+        assert!(!body_str.contains("kept.ics"), "got: {body_str}");
+    }
+
+    #[tokio::test]
+    async fn test_report_sync_collection_reports_pruned_deletion() {
+        let (pool, _user, cal) = setup().await;
+
+        for uid in ["kept", "gone"] {
+            let data = format!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            );
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                uid,
+                &data,
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some("20260301T090000Z"),
+                    dtend: Some("20260301T100000Z"),
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let token = crate::db::calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .sync_token;
+        crate::db::events::delete_object(&pool, &cal.id, "gone")
+            .await
+            .unwrap();
+
+        // Fill the change log to its limit and prune, dropping the rows for
+        // both the client's token and the deletion
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?)
+             INSERT INTO sync_changes (calendar_id, object_uid, change_type, sync_token)
+             SELECT ?, 'kept', 'modified', 'data:,bulk-' || i FROM n",
+        )
+        .bind(crate::db::events::SYNC_CHANGES_RETAINED as i64)
+        .bind(&cal.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        crate::db::events::prune_sync_changes(
+            &pool,
+            &cal.id,
+            crate::db::events::SYNC_CHANGES_RETAINED,
+        )
+        .await
+        .unwrap();
+
+        let app = router(pool, Config::default());
+        let report_body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>{token}</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+  </D:prop>
+</D:sync-collection>"#
+        );
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .body(Body::from(report_body))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8_lossy(&body);
+        let href = format!("/caldav/users/alice/{}/gone.ics", cal.id);
+        assert!(
+            body_str.contains(&format!(
+                "<D:response><D:href>{href}</D:href><D:status>HTTP/1.1 404 Not Found</D:status></D:response>"
+            )),
+            "got: {body_str}"
+        );
+        assert!(body_str.contains("kept.ics"), "got: {body_str}");
+    }
+
+    #[tokio::test]
+    async fn test_report_sync_collection_rejects_unknown_token() {
+        let (pool, _user, cal) = setup().await;
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?)
+             INSERT INTO sync_changes (calendar_id, object_uid, change_type, sync_token)
+             SELECT ?, 'bulk', 'modified', 'data:,bulk-' || i FROM n",
+        )
+        .bind(crate::db::events::SYNC_CHANGES_RETAINED as i64)
+        .bind(&cal.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let app = router(pool, Config::default());
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>data:,not-a-token</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+  </D:prop>
+</D:sync-collection>"#;
+        let req = Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .body(Body::from(report_body))
+            .unwrap();
         let resp = app.oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
-    }
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
 
-    // --- GET ---
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8_lossy(&body).contains("valid-sync-token"));
+    }
 
     #[tokio::test]
-    async fn test_get_existing_event() {
+    async fn test_report_sync_collection_storage_error_returns_500() {
         let (pool, _user, cal) = setup().await;
+        sqlx::query("DROP TABLE sync_changes")
+            .execute(&pool)
+            .await
+            .unwrap();
 
-        crate::db::events::upsert_object(
-            &pool, &cal.id, "get-me@test.com",
-            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:get-me@test.com\r\nSUMMARY:Get Me\r\nEND:VEVENT\r\nEND:VCALENDAR",
-            crate::db::events::ObjectFields {
-                component_type: "VEVENT",
-                dtstart: None,
-                dtend: None,
-                summary: Some("Get Me"),
-            },
-        ).await.unwrap();
-
-        let app = router(pool);
-        let uri = format!("/caldav/users/alice/{}/get-me%40test.com.ics", cal.id);
+        let app = router(pool, Config::default());
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>data:,some-token</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop>
+    <D:getetag/>
+  </D:prop>
+</D:sync-collection>"#;
         let req = Request::builder()
-            .method("GET")
-            .uri(&uri)
-            .body(Body::empty())
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .body(Body::from(report_body))
             .unwrap();
-
         let resp = app.oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::OK);
-        assert_eq!(
-            resp.headers()
-                .get("content-type")
-                .unwrap()
-                .to_str()
-                .unwrap(),
-            "text/calendar; charset=utf-8"
-        );
-        assert!(resp.headers().contains_key("etag"));
-
-        let body = resp.into_body().collect().await.unwrap().to_bytes();
-        assert!(String::from_utf8_lossy(&body).contains("Get Me"));
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
     #[tokio::test]
-    async fn test_get_nonexistent_event_returns_404() {
+    async fn test_report_invalid_body_returns_400() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
-        let uri = format!("/caldav/users/alice/{}/nope.ics", cal.id);
+        let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
-            .method("GET")
+            .method(Method::from_bytes(b"REPORT").unwrap())
             .uri(&uri)
-            .body(Body::empty())
+            .body(Body::from("not valid xml"))
             .unwrap();
 
         let resp = app.oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
-    // --- REPORT ---
-
     #[tokio::test]
-    async fn test_report_calendar_query_no_filter() {
+    async fn test_report_calendar_query_with_time_range() {
         let (pool, _user, cal) = setup().await;
 
         crate::db::events::upsert_object(
-            &pool, &cal.id, "query-uid@test.com",
-            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:query-uid@test.com\r\nSUMMARY:Query Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            &pool, &cal.id, "range-uid@test.com",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:range-uid@test.com\r\nSUMMARY:Range Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
             crate::db::events::ObjectFields {
                 component_type: "VEVENT",
                 dtstart: Some("20260301T090000Z"),
                 dtend: Some("20260301T100000Z"),
-                summary: Some("Query Event"),
+                summary: Some("Range Event"),
             },
         ).await.unwrap();
 
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
 <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
   <D:prop>
     <D:getetag/>
-    <C:calendar-data/>
   </D:prop>
   <C:filter>
-    <C:comp-filter name="VCALENDAR"/>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="20260201T000000Z" end="20260401T000000Z"/>
+      </C:comp-filter>
+    </C:comp-filter>
   </C:filter>
 </C:calendar-query>"#;
 
@@ -2225,37 +4877,62 @@ mod tests {
 
         let body = resp.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8_lossy(&body);
-        assert!(body_str.contains("query-uid@test.com"));
+        assert!(body_str.contains("range-uid@test.com"));
     }
 
     #[tokio::test]
-    async fn test_report_calendar_multiget() {
+    async fn test_report_vtodo_time_range_matches_due() {
         let (pool, _user, cal) = setup().await;
 
+        for (uid, due) in [
+            ("march-task", "20260315T170000Z"),
+            ("april-task", "20260410T170000Z"),
+        ] {
+            let data = format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:{uid}\r\nSUMMARY:Task\r\nDUE:{due}\r\nEND:VTODO\r\nEND:VCALENDAR\r\n"
+            );
+            let fields = crate::ical::parser::extract_fields(&data);
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                uid,
+                &data,
+                crate::db::events::ObjectFields {
+                    component_type: &fields.component_type,
+                    dtstart: fields.dtstart.as_deref(),
+                    dtend: fields.dtend.as_deref(),
+                    summary: fields.summary.as_deref(),
+                },
+            )
+            .await
+            .unwrap();
+        }
         crate::db::events::upsert_object(
-            &pool, &cal.id, "multiget-uid@test.com",
-            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:multiget-uid@test.com\r\nSUMMARY:Multiget Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            &pool, &cal.id, "march-event",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:march-event\r\nDTSTART:20260310T090000Z\r\nDTEND:20260310T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
             crate::db::events::ObjectFields {
                 component_type: "VEVENT",
-                dtstart: Some("20260301T090000Z"),
-                dtend: Some("20260301T100000Z"),
-                summary: Some("Multiget Event"),
+                dtstart: Some("20260310T090000Z"),
+                dtend: Some("20260310T100000Z"),
+                summary: None,
             },
         ).await.unwrap();
 
-        let app = router(pool.clone());
+        let app = router(pool, Config::default());
 
-        let report_body = format!(
-            r#"<?xml version="1.0" encoding="utf-8"?>
-<C:calendar-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
   <D:prop>
     <D:getetag/>
-    <C:calendar-data/>
   </D:prop>
-  <D:href>/caldav/users/alice/{}/multiget-uid%40test.com.ics</D:href>
-</C:calendar-multiget>"#,
-            cal.id
-        );
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VTODO">
+        <C:time-range start="20260301T000000Z" end="20260401T000000Z"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#;
 
         let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
@@ -2269,158 +4946,267 @@ mod tests {
 
         let body = resp.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8_lossy(&body);
-        assert!(body_str.contains("multiget-uid@test.com"));
-        assert!(body_str.contains("Multiget Event"));
+        assert!(body_str.contains("march-task"), "got: {body_str}");
+        assert!(!body_str.contains("april-task"));
+        assert!(!body_str.contains("march-event"));
     }
 
     #[tokio::test]
-    async fn test_report_sync_collection_initial_sync() {
+    async fn test_report_time_range_matches_later_weekly_instances() {
         let (pool, _user, cal) = setup().await;
 
         crate::db::events::upsert_object(
-            &pool, &cal.id, "sync-uid@test.com",
-            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:sync-uid@test.com\r\nSUMMARY:Sync Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            &pool, &cal.id, "weekly@test.com",
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:weekly@test.com\r\nSUMMARY:Weekly\r\nDTSTART:20260302T090000Z\r\nDTEND:20260302T100000Z\r\nRRULE:FREQ=WEEKLY;COUNT=4\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
             crate::db::events::ObjectFields {
                 component_type: "VEVENT",
-                dtstart: Some("20260301T090000Z"),
-                dtend: Some("20260301T100000Z"),
-                summary: Some("Sync Event"),
+                dtstart: Some("20260302T090000Z"),
+                dtend: Some("20260302T100000Z"),
+                summary: Some("Weekly"),
             },
         ).await.unwrap();
 
-        let app = router(pool);
-
-        let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
-<D:sync-collection xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
-  <D:sync-token/>
-  <D:sync-level>1</D:sync-level>
+        let query = |start: &str, end: &str, expand: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
   <D:prop>
     <D:getetag/>
+    <C:calendar-data>{expand}</C:calendar-data>
   </D:prop>
-</D:sync-collection>"#;
-
-        let uri = format!("/caldav/users/alice/{}/", cal.id);
-        let req = Request::builder()
-            .method(Method::from_bytes(b"REPORT").unwrap())
-            .uri(&uri)
-            .body(Body::from(report_body))
-            .unwrap();
-
-        let resp = app.oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
-
-        let body = resp.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8_lossy(&body);
-        assert!(body_str.contains("sync-uid@test.com"));
-        // Sync token must be a valid URI (RFC 6578)
-        assert!(body_str.contains("sync-token"), "Should contain sync-token");
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{start}" end="{end}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#
+            )
+        };
+        let report = |body: String| {
+            let app = router(pool.clone(), Config::default());
+            let uri = format!("/caldav/users/alice/{}/", cal.id);
+            async move {
+                let req = Request::builder()
+                    .method(Method::from_bytes(b"REPORT").unwrap())
+                    .uri(&uri)
+                    .header("Depth", "1")
+                    .body(Body::from(body))
+                    .unwrap();
+                let resp = app.oneshot(req).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+                let body = resp.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8_lossy(&body).to_string()
+            }
+        };
+
+        // The third week holds an instance; the week after the last does not
+        let body = report(query("20260316T000000Z", "20260323T000000Z", "")).await;
+        assert!(body.contains("weekly@test.com"), "got: {body}");
+        let body = report(query("20260330T000000Z", "20260406T000000Z", "")).await;
+        assert!(!body.contains("weekly@test.com"), "got: {body}");
+
+        // CALDAV:expand returns each instance in range with a RECURRENCE-ID
+        let expand = r#"<C:expand start="20260309T000000Z" end="20260330T000000Z"/>"#;
+        let body = report(query("20260309T000000Z", "20260330T000000Z", expand)).await;
+        for week in ["20260309", "20260316", "20260323"] {
+            assert!(
+                body.contains(&format!("RECURRENCE-ID:{week}T090000Z")),
+                "missing {week}: {body}"
+            );
+        }
+        assert!(!body.contains("RECURRENCE-ID:20260302T090000Z"));
+        assert!(!body.contains("RRULE"));
     }
 
     #[tokio::test]
-    async fn test_report_sync_collection_with_calendar_data() {
+    async fn test_report_expand_returns_instances_with_overrides() {
         let (pool, _user, cal) = setup().await;
 
+        let data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+                    BEGIN:VEVENT\r\nUID:sync@test.com\r\nSUMMARY:Sync\r\n\
+                    DTSTART:20260302T090000Z\r\nDTEND:20260302T093000Z\r\n\
+                    RRULE:FREQ=WEEKLY;COUNT=3\r\nEND:VEVENT\r\n\
+                    BEGIN:VEVENT\r\nUID:sync@test.com\r\nSUMMARY:Sync (moved)\r\n\
+                    RECURRENCE-ID:20260309T090000Z\r\n\
+                    DTSTART:20260310T150000Z\r\nDTEND:20260310T153000Z\r\nEND:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
         crate::db::events::upsert_object(
-            &pool, &cal.id, "sync-data@test.com",
-            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:sync-data@test.com\r\nSUMMARY:Sync Data\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            &pool,
+            &cal.id,
+            "sync@test.com",
+            data,
             crate::db::events::ObjectFields {
                 component_type: "VEVENT",
-                dtstart: Some("20260301T090000Z"),
-                dtend: Some("20260301T100000Z"),
-                summary: Some("Sync Data"),
+                dtstart: Some("20260302T090000Z"),
+                dtend: Some("20260302T093000Z"),
+                summary: Some("Sync"),
             },
-        ).await.unwrap();
+        )
+        .await
+        .unwrap();
+
+        let report = |calendar_data: &str| {
+            let body = format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<B:calendar-query xmlns:B="urn:ietf:params:xml:ns:caldav">
+  <A:prop xmlns:A="DAV:">
+    <A:getetag/>
+    <B:calendar-data>{calendar_data}</B:calendar-data>
+  </A:prop>
+  <B:filter>
+    <B:comp-filter name="VCALENDAR">
+      <B:comp-filter name="VEVENT">
+        <B:time-range start="20260301T000000Z" end="20260401T000000Z"/>
+      </B:comp-filter>
+    </B:comp-filter>
+  </B:filter>
+</B:calendar-query>"#
+            );
+            let app = router(pool.clone(), Config::default());
+            let uri = format!("/caldav/users/alice/{}/", cal.id);
+            async move {
+                let req = Request::builder()
+                    .method(Method::from_bytes(b"REPORT").unwrap())
+                    .uri(&uri)
+                    .header("Depth", "1")
+                    .body(Body::from(body))
+                    .unwrap();
+                let resp = app.oneshot(req).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+                let body = resp.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8_lossy(&body).to_string()
+            }
+        };
+
+        let body = report(r#"<B:expand start="20260301T000000Z" end="20260401T000000Z"/>"#).await;
+        assert_eq!(body.matches("BEGIN:VEVENT").count(), 3, "got: {body}");
+        assert!(body.contains("RECURRENCE-ID:20260302T090000Z"));
+        assert!(body.contains("RECURRENCE-ID:20260309T090000Z"));
+        assert!(body.contains("DTSTART:20260310T150000Z"));
+        assert!(body.contains("Sync (moved)"));
+        assert!(body.contains("RECURRENCE-ID:20260316T090000Z"));
+        assert!(!body.contains("RRULE"));
+
+        // Without expand the stored master and override come back as-is
+        let body = report("").await;
+        assert!(body.contains("RRULE:FREQ=WEEKLY;COUNT=3"), "got: {body}");
+        assert!(!body.contains("RECURRENCE-ID:20260302T090000Z"));
+    }
+
+    #[tokio::test]
+    async fn test_report_prop_filter_matches_summary_text() {
+        let (pool, _user, cal) = setup().await;
 
-        let app = router(pool);
+        for (uid, summary) in [("standup", "Daily STANDUP"), ("lunch", "Lunch")] {
+            let data = format!(
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:{uid}\r\nSUMMARY:{summary}\r\nDTSTART:20260302T090000Z\r\nDTEND:20260302T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            );
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                uid,
+                &data,
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some("20260302T090000Z"),
+                    dtend: Some("20260302T100000Z"),
+                    summary: Some(summary),
+                },
+            )
+            .await
+            .unwrap();
+        }
 
+        let app = router(pool, Config::default());
         let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
-<D:sync-collection xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
-  <D:sync-token/>
-  <D:sync-level>1</D:sync-level>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
   <D:prop>
     <D:getetag/>
-    <C:calendar-data/>
   </D:prop>
-</D:sync-collection>"#;
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:prop-filter name="SUMMARY">
+          <C:text-match collation="i;ascii-casemap">standup</C:text-match>
+        </C:prop-filter>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#;
 
-        let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
             .method(Method::from_bytes(b"REPORT").unwrap())
-            .uri(&uri)
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Depth", "1")
             .body(Body::from(report_body))
             .unwrap();
-
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
 
         let body = resp.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8_lossy(&body);
-        assert!(
-            body_str.contains("Sync Data"),
-            "calendar-data should be included"
-        );
+        assert!(body_str.contains("standup.ics"), "got: {body_str}");
+        assert!(!body_str.contains("lunch.ics"), "got: {body_str}");
     }
 
     #[tokio::test]
-    async fn test_report_invalid_body_returns_400() {
-        let (pool, _user, cal) = setup().await;
-        let app = router(pool);
-
-        let uri = format!("/caldav/users/alice/{}/", cal.id);
-        let req = Request::builder()
-            .method(Method::from_bytes(b"REPORT").unwrap())
-            .uri(&uri)
-            .body(Body::from("not valid xml"))
-            .unwrap();
-
-        let resp = app.oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
-    }
-
-    #[tokio::test]
-    async fn test_report_calendar_query_with_time_range() {
+    async fn test_report_calendar_data_returns_selected_props_only() {
         let (pool, _user, cal) = setup().await;
-
+        let data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:planning\r\nDTSTART:20260302T090000Z\r\nDTEND:20260302T100000Z\r\nSUMMARY:Planning\r\nLOCATION:Room 1\r\nDESCRIPTION:Agenda\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
         crate::db::events::upsert_object(
-            &pool, &cal.id, "range-uid@test.com",
-            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:range-uid@test.com\r\nSUMMARY:Range Event\r\nDTSTART:20260301T090000Z\r\nDTEND:20260301T100000Z\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            &pool,
+            &cal.id,
+            "planning",
+            data,
             crate::db::events::ObjectFields {
                 component_type: "VEVENT",
-                dtstart: Some("20260301T090000Z"),
-                dtend: Some("20260301T100000Z"),
-                summary: Some("Range Event"),
+                dtstart: Some("20260302T090000Z"),
+                dtend: Some("20260302T100000Z"),
+                summary: Some("Planning"),
             },
-        ).await.unwrap();
-
-        let app = router(pool);
+        )
+        .await
+        .unwrap();
 
+        let app = router(pool, Config::default());
         let report_body = r#"<?xml version="1.0" encoding="utf-8"?>
 <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
   <D:prop>
     <D:getetag/>
+    <C:calendar-data>
+      <C:comp name="VCALENDAR">
+        <C:prop name="SUMMARY"/>
+      </C:comp>
+    </C:calendar-data>
   </D:prop>
   <C:filter>
-    <C:comp-filter name="VCALENDAR">
-      <C:comp-filter name="VEVENT">
-        <C:time-range start="20260201T000000Z" end="20260401T000000Z"/>
-      </C:comp-filter>
-    </C:comp-filter>
+    <C:comp-filter name="VCALENDAR"/>
   </C:filter>
 </C:calendar-query>"#;
 
-        let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
             .method(Method::from_bytes(b"REPORT").unwrap())
-            .uri(&uri)
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Depth", "1")
             .body(Body::from(report_body))
             .unwrap();
-
         let resp = app.oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
 
         let body = resp.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8_lossy(&body);
-        assert!(body_str.contains("range-uid@test.com"));
+        assert!(body_str.contains("getetag"), "got: {body_str}");
+        assert!(body_str.contains("SUMMARY:Planning"), "got: {body_str}");
+        assert!(body_str.contains("UID:planning"), "got: {body_str}");
+        assert!(
+            body_str.contains("DTSTART:20260302T090000Z"),
+            "got: {body_str}"
+        );
+        assert!(!body_str.contains("DTEND"), "got: {body_str}");
+        assert!(!body_str.contains("LOCATION"), "got: {body_str}");
+        assert!(!body_str.contains("DESCRIPTION"), "got: {body_str}");
     }
 
     // --- calendar home PROPFIND ---
@@ -2428,7 +5214,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_home_depth0_returns_home_props() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -2455,7 +5241,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_home_depth1_lists_calendars() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
@@ -2490,7 +5276,7 @@ mod tests {
             },
         ).await.unwrap();
 
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
@@ -2511,7 +5297,7 @@ mod tests {
     #[tokio::test]
     async fn test_calendar_collection_unknown_method_returns_405() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/", cal.id);
         let req = Request::builder()
@@ -2527,7 +5313,7 @@ mod tests {
     #[tokio::test]
     async fn test_object_unknown_method_returns_405() {
         let (pool, _user, cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let uri = format!("/caldav/users/alice/{}/test.ics", cal.id);
         let req = Request::builder()
@@ -2554,10 +5340,69 @@ mod tests {
 
     // --- fallback discovery ---
 
+    #[tokio::test]
+    async fn test_principal_property_search_finds_user() {
+        let (pool, _user, _cal) = setup().await;
+        users::create_user(&pool, "bob", None, "secret456", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let search = |auth: Option<&str>| {
+            let mut req = Request::builder()
+                .method(Method::from_bytes(b"REPORT").unwrap())
+                .uri("/principals/")
+                .header("Depth", "0")
+                .header("Content-Type", "application/xml");
+            if let Some(auth) = auth {
+                req = req.header("Authorization", auth);
+            }
+            req.body(Body::from(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<D:principal-property-search xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav" test="anyof">
+  <D:property-search>
+    <D:prop><D:displayname/></D:prop>
+    <D:match>alice</D:match>
+  </D:property-search>
+  <D:property-search>
+    <D:prop><C:calendar-user-address-set/></D:prop>
+    <D:match>mailto:alice</D:match>
+  </D:property-search>
+  <D:prop>
+    <D:displayname/>
+    <C:calendar-user-address-set/>
+  </D:prop>
+</D:principal-property-search>"#,
+            ))
+            .unwrap()
+        };
+
+        // Searching other users needs credentials
+        let resp = router(pool.clone(), Config::default())
+            .oneshot(search(None))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let auth = basic_auth_header("bob", "secret456");
+        let resp = router(pool, Config::default())
+            .oneshot(search(Some(&auth)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        assert!(
+            body.contains("<D:href>/caldav/users/alice/</D:href>"),
+            "{body}"
+        );
+        assert!(body.contains("<D:displayname>alice</D:displayname>"));
+        assert!(body.contains("mailto:alice@example.com"));
+        assert!(!body.contains("/caldav/users/bob/"));
+    }
+
     #[tokio::test]
     async fn test_fallback_principals_redirects() {
         let (pool, _user, _cal) = setup().await;
-        let app = router(pool);
+        let app = router(pool, Config::default());
 
         let req = Request::builder()
             .method(Method::from_bytes(b"PROPFIND").unwrap())
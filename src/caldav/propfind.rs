@@ -9,8 +9,9 @@ use super::encode_email_for_path;
 use super::xml::multistatus::MultistatusBuilder;
 use super::xml::parse::{self, PropfindRequest};
 use super::xml::properties;
-use crate::db::models::User;
-use crate::db::{calendars, events};
+use crate::config::Config;
+use crate::db::models::{Calendar, Permission, User};
+use crate::db::{calendars, events, shares};
 
 /// Handle PROPFIND for calendar home: /caldav/users/{username}/
 /// With Depth:1, also lists all calendars.
@@ -20,6 +21,7 @@ pub async fn handle_calendar_home(
     request: Request<Body>,
 ) -> Response {
     let user = request.extensions().get::<User>().unwrap().clone();
+    let config = super::request_config(&request);
     let depth = get_depth(&request);
     let body = axum::body::to_bytes(request.into_body(), 64 * 1024)
         .await
@@ -29,25 +31,36 @@ pub async fn handle_calendar_home(
     let mut builder = MultistatusBuilder::new();
 
     // The calendar home itself
-    let (found, not_found) =
-        properties::filter_props(&propfind, properties::calendar_home_props(&user.username));
+    let quota = user_quota(&pool, &user, config.user_quota_bytes).await;
+    let (found, not_found) = properties::filter_props(
+        &propfind,
+        properties::calendar_home_props(&user.username, quota),
+    );
     builder.add_response(
         &format!("/caldav/users/{}/", user.username),
         found,
         not_found,
     );
 
-    // If Depth:1, list all accessible calendars
+    // If Depth:1, list all accessible calendars. The ctag is a column of the
+    // calendar row and the share permission is joined in, so this one query
+    // yields everything; no per-calendar lookups are needed however many
+    // calendars there are.
     if depth >= 1 {
-        let cals = calendars::list_calendars_for_user(&pool, &user.id)
+        let cals = calendars::list_calendars_with_permission_for_user(&pool, &user.id)
             .await
             .unwrap_or_default();
 
-        for cal in &cals {
+        for (cal, permission) in &cals {
             let href = properties::calendar_href(&user.username, &cal.id);
             let (found, not_found) = properties::filter_props(
                 &propfind,
-                properties::calendar_props(&user.username, cal),
+                properties::calendar_props(
+                    &user.username,
+                    cal,
+                    *permission,
+                    config.max_resource_size,
+                ),
             );
             builder.add_response(&href, found, not_found);
         }
@@ -66,18 +79,25 @@ pub async fn handle_calendar(
 ) -> Response {
     let user = request.extensions().get::<User>().unwrap().clone();
     let href_ctx = request.extensions().get::<HrefContext>().cloned();
+    let config = super::request_config(&request);
+    // The collection handler has already loaded the row to check access
+    let checked = request.extensions().get::<Calendar>().cloned();
     let depth = get_depth(&request);
     let body = axum::body::to_bytes(request.into_body(), 64 * 1024)
         .await
         .unwrap_or_default();
     let propfind = parse::parse_propfind(&body);
 
-    // Find the calendar
-    let calendar = match calendars::get_calendar_by_id(&pool, &calendar_id).await {
-        Ok(Some(cal)) => cal,
-        _ => {
-            return (StatusCode::NOT_FOUND, "Calendar not found").into_response();
-        }
+    // Find the calendar. Depth:0 polls only want the collection's
+    // ctag/sync-token, which the checked row already carries.
+    let calendar = match checked {
+        Some(cal) => cal,
+        None => match calendars::get_calendar_by_id(&pool, &calendar_id).await {
+            Ok(Some(cal)) => cal,
+            _ => {
+                return (StatusCode::NOT_FOUND, "Calendar not found").into_response();
+            }
+        },
     };
 
     let mut builder = MultistatusBuilder::new();
@@ -90,9 +110,15 @@ pub async fn handle_calendar(
 
     // The calendar collection itself
     let href = properties::calendar_href_for_context(&ctx, &calendar.id);
+    let permission = calendar_permission(&pool, &user, &calendar).await;
     let (found, not_found) = properties::filter_props(
         &propfind,
-        properties::calendar_props_for_context(&ctx, &calendar),
+        properties::calendar_props_for_context(
+            &ctx,
+            &calendar,
+            permission,
+            config.max_resource_size,
+        ),
     );
     builder.add_response(&href, found, not_found);
 
@@ -116,6 +142,33 @@ pub async fn handle_calendar(
     multistatus_response(builder.build())
 }
 
+/// The user's permission on a calendar they can access. Owned calendars
+/// need no lookup; a sharee whose share can't be read is treated as
+/// read-only.
+async fn calendar_permission(pool: &SqlitePool, user: &User, calendar: &Calendar) -> Permission {
+    if calendar.owner_id == user.id {
+        return Permission::ReadWrite;
+    }
+    shares::get_user_permission(pool, &calendar.id, &user.id)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(Permission::Read)
+}
+
+/// The user's storage use against `quota_bytes`. A failed lookup reports
+/// nothing used rather than failing the PROPFIND.
+async fn user_quota(pool: &SqlitePool, user: &User, quota_bytes: u64) -> properties::Quota {
+    let used_bytes = events::bytes_used_by_owner(pool, &user.id)
+        .await
+        .map(|used| used.max(0) as u64)
+        .unwrap_or(0);
+    properties::Quota {
+        used_bytes,
+        available_bytes: quota_bytes.saturating_sub(used_bytes),
+    }
+}
+
 /// Extract the Depth header value (0 or 1, default 0).
 fn get_depth<T>(request: &Request<T>) -> u32 {
     get_depth_from_headers(request.headers())
@@ -152,6 +205,7 @@ pub async fn handle_email_home(
     depth: u32,
     email: &str,
     propfind: &PropfindRequest,
+    config: &Config,
 ) -> Response {
     let ctx = HrefContext {
         email: Some(encode_email_for_path(email)),
@@ -161,24 +215,32 @@ pub async fn handle_email_home(
 
     // The email home itself — advertise principal + calendar-home-set pointing
     // back to this same URL, so dataaccessd knows it's already at the right place.
+    let quota = user_quota(&pool, &user, config.user_quota_bytes).await;
     let (found, not_found) = properties::filter_props(
         propfind,
-        properties::email_home_props(&user.username, email, &request_path),
+        properties::email_home_props(&user.username, email, &request_path, quota),
     );
     builder.add_response(&request_path, found, not_found);
 
     // If Depth:1, include all accessible calendars with email-based hrefs
-    // so dataaccessd can access them under the email path.
+    // so dataaccessd can access them under the email path. As for the
+    // username home, the ctags and permissions come from this single
+    // listing query.
     if depth >= 1 {
-        let cals = calendars::list_calendars_for_user(&pool, &user.id)
+        let cals = calendars::list_calendars_with_permission_for_user(&pool, &user.id)
             .await
             .unwrap_or_default();
 
-        for cal in &cals {
+        for (cal, permission) in &cals {
             let href = properties::calendar_href_for_context(&ctx, &cal.id);
             let (found, not_found) = properties::filter_props(
                 propfind,
-                properties::calendar_props_for_context(&ctx, cal),
+                properties::calendar_props_for_context(
+                    &ctx,
+                    cal,
+                    *permission,
+                    config.max_resource_size,
+                ),
             );
             builder.add_response(&href, found, not_found);
         }
@@ -192,7 +254,6 @@ pub fn multistatus_response(xml: Vec<u8>) -> Response {
     Response::builder()
         .status(StatusCode::MULTI_STATUS)
         .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
-        .header("DAV", "1, 2, 3, calendar-access, calendar-schedule")
         .body(Body::from(xml))
         .unwrap()
 }
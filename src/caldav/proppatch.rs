@@ -12,7 +12,8 @@ use crate::db::calendars;
 use crate::db::models::User;
 
 /// Handle PROPPATCH for a calendar collection.
-/// Supports updating displayname, calendar-description, and calendar-color.
+/// Supports updating displayname, calendar-description, calendar-color,
+/// calendar-order and schedule-calendar-transp.
 pub async fn handle_proppatch(
     State(pool): State<SqlitePool>,
     Path((_username, calendar_id)): Path<(String, String)>,
@@ -36,8 +37,14 @@ pub async fn handle_proppatch(
     let name = extract_prop_value(&body_str, "displayname");
     let description = extract_prop_value(&body_str, "calendar-description");
     let color = extract_prop_value(&body_str, "calendar-color");
+    let order = extract_prop_value(&body_str, "calendar-order");
+    let parsed_order = order.as_deref().and_then(|o| o.trim().parse::<i64>().ok());
+    let transp = extract_transp(&body_str);
+    let valid_transp = transp
+        .as_deref()
+        .filter(|t| calendars::TRANSP_VALUES.contains(t));
 
-    match calendars::update_calendar(
+    let mut result = calendars::update_calendar(
         &pool,
         &calendar.id,
         name.as_deref(),
@@ -45,8 +52,19 @@ pub async fn handle_proppatch(
         color.as_deref(),
         None,
     )
-    .await
+    .await;
+    if result.is_ok()
+        && let Some(order) = parsed_order
     {
+        result = calendars::set_calendar_order(&pool, &calendar.id, order).await;
+    }
+    if result.is_ok()
+        && let Some(transp) = valid_transp
+    {
+        result = calendars::set_calendar_transp(&pool, &calendar.id, transp).await;
+    }
+
+    match result {
         Ok(_) => {
             let href = match &href_ctx {
                 Some(ctx) => calendar_href_for_context(ctx, &calendar.id),
@@ -77,8 +95,29 @@ pub async fn handle_proppatch(
                     value: PropContent::Empty,
                 });
             }
+            // A calendar-order that isn't an integer is not stored
+            let mut not_found = Vec::new();
+            match (&order, parsed_order) {
+                (Some(_), Some(_)) => found.push(PropValue {
+                    name: "calendar-order".to_string(),
+                    namespace: APPLE_NS.to_string(),
+                    value: PropContent::Empty,
+                }),
+                (Some(_), None) => not_found.push("A:calendar-order".to_string()),
+                (None, _) => {}
+            }
+            // Likewise a schedule-calendar-transp other than opaque/transparent
+            match (&transp, valid_transp) {
+                (Some(_), Some(_)) => found.push(PropValue {
+                    name: "schedule-calendar-transp".to_string(),
+                    namespace: CALDAV_NS.to_string(),
+                    value: PropContent::Empty,
+                }),
+                (Some(_), None) => not_found.push("C:schedule-calendar-transp".to_string()),
+                (None, _) => {}
+            }
 
-            builder.add_response(&href, found, vec![]);
+            builder.add_response(&href, found, not_found);
 
             Response::builder()
                 .status(StatusCode::MULTI_STATUS)
@@ -130,3 +169,38 @@ fn extract_prop_value(xml: &str, local_name: &str) -> Option<String> {
     }
     None
 }
+
+/// Extract the value of `schedule-calendar-transp` from PROPPATCH XML: the
+/// local name of its child element (`opaque` or `transparent`).
+fn extract_transp(xml: &str) -> Option<String> {
+    use quick_xml::events::Event;
+    use quick_xml::reader::Reader;
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut in_target = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let local = e.local_name();
+                let name = String::from_utf8_lossy(local.as_ref()).to_string();
+                if in_target {
+                    return Some(name);
+                }
+                in_target = name == "schedule-calendar-transp";
+            }
+            Ok(Event::Empty(ref e)) if in_target => {
+                let local = e.local_name();
+                return Some(String::from_utf8_lossy(local.as_ref()).to_string());
+            }
+            Ok(Event::End(_)) if in_target => return Some(String::new()),
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    None
+}
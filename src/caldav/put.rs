@@ -4,17 +4,30 @@ use axum::http::{Request, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use sqlx::SqlitePool;
 
-use crate::db::events;
-use crate::ical::parser;
+use super::HrefContext;
+use super::xml::properties;
+use crate::db::{calendars, events};
+use crate::ical::{datetime, parser};
 
 /// Handle PUT for a calendar object: /caldav/users/{username}/{calendar_id}/{uid}.ics
-/// Creates or updates the event.
+/// Creates or updates the event. A create returns `201 Created` with a
+/// `Location` header naming the object's canonical href; an update returns
+/// `204 No Content`. Both carry the new `ETag`.
 pub async fn handle_put(
     State(pool): State<SqlitePool>,
-    Path((_username, calendar_id, filename)): Path<(String, String, String)>,
+    Path((username, calendar_id, filename)): Path<(String, String, String)>,
     request: Request<Body>,
 ) -> Response {
     let uid_from_url = filename.trim_end_matches(".ics").to_string();
+    let config = super::request_config(&request);
+    let href_ctx = request
+        .extensions()
+        .get::<HrefContext>()
+        .cloned()
+        .unwrap_or(HrefContext {
+            email: None,
+            username,
+        });
 
     // Check If-Match for conditional updates
     let if_match = request
@@ -22,24 +35,63 @@ pub async fn handle_put(
         .get(header::IF_MATCH)
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
+    let create_only = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim() == "*");
+    let headers = request.headers().clone();
+    let request_path = request.uri().path().to_string();
 
-    let body = match axum::body::to_bytes(request.into_body(), 1024 * 1024).await {
-        Ok(b) => b,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, "Request body too large").into_response();
+    let charset = match body_charset(
+        request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok()),
+    ) {
+        Ok(c) => c,
+        Err(name) => {
+            return (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Unsupported charset: {name}"),
+            )
+                .into_response();
         }
     };
 
-    let ical_data = match String::from_utf8(body.to_vec()) {
-        Ok(s) => s,
-        Err(_) => {
-            return (StatusCode::BAD_REQUEST, "Invalid UTF-8 in request body").into_response();
-        }
+    let body = match axum::body::to_bytes(request.into_body(), config.max_resource_size).await {
+        Ok(b) => b,
+        Err(_) => return max_resource_size_error(config.max_resource_size),
+    };
+
+    let ical_data = match charset {
+        Charset::Utf8 => match String::from_utf8(body.to_vec()) {
+            Ok(s) => s,
+            Err(_) => {
+                return (StatusCode::BAD_REQUEST, "Invalid UTF-8 in request body").into_response();
+            }
+        },
+        // Every Latin-1 byte maps directly to the Unicode code point of the same value.
+        Charset::Latin1 => body.iter().map(|&b| b as char).collect(),
     };
 
     // Extract fields from the iCalendar data
     let fields = parser::extract_fields(&ical_data);
-    let uid = fields.uid.as_deref().unwrap_or(&uid_from_url);
+
+    // Objects are stored and addressed by UID, so a body whose UID is not
+    // the resource name would leave the client's href pointing at nothing
+    // (and calendar-multiget asking for it in vain)
+    if let Some(body_uid) = fields.uid.as_deref()
+        && body_uid != uid_from_url
+    {
+        let href = properties::calendar_object_href_for_context(
+            &href_ctx,
+            &calendar_id,
+            &encode_path_segment(body_uid),
+        );
+        return no_uid_conflict_error(&uid_from_url, body_uid, &href);
+    }
+    let uid = uid_from_url.as_str();
 
     // If If-Match is present, verify the current ETag matches
     if let Some(expected_etag) = &if_match
@@ -61,8 +113,56 @@ pub async fn handle_put(
         }
     }
 
+    // If-None-Match: * makes this a create-only PUT (RFC 7232 §3.2), so a
+    // client finishing its initial sync can't overwrite an object it never saw
+    if create_only {
+        match events::get_object_by_uid(&pool, &calendar_id, uid).await {
+            Ok(Some(_)) => {
+                return (StatusCode::PRECONDITION_FAILED, "Object already exists").into_response();
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("Failed to check existing object: {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+            }
+        }
+    }
+
+    // Evaluate WebDAV If header conditions against the current ETag
+    if let Some(response) =
+        super::conditional::check_if_header(&pool, &headers, &request_path, &calendar_id, uid).await
+    {
+        return response;
+    }
+
+    // Bodies that aren't a VCALENDAR around an event, to-do or journal entry
+    // are refused rather than stored where they would later break REPORT
+    if let Err(reason) = parser::validate_calendar(&ical_data) {
+        return valid_calendar_data_error(&reason);
+    }
+
+    // New objects must be of a component type the calendar still accepts;
+    // existing objects of a since-disallowed type may still be updated.
+    if let Some(response) =
+        check_supported_component(&pool, &calendar_id, uid, &fields.component_type).await
+    {
+        return response;
+    }
+
+    // Dates outside MIN_EVENT_YEAR..=MAX_EVENT_YEAR fail the
+    // CALDAV:min-date-time / max-date-time preconditions (RFC 4791 §5.3.2.1)
+    for value in [fields.dtstart.as_deref(), fields.dtend.as_deref()]
+        .into_iter()
+        .flatten()
+    {
+        if let Err(reason) = config.check_event_year(value) {
+            let too_early = datetime::year_of(value).is_some_and(|y| y < config.min_event_year);
+            return date_time_range_error(&reason, too_early);
+        }
+    }
+
     // Upsert the object
-    match events::upsert_object(
+    match events::upsert_object_with_etag_mode(
         &pool,
         &calendar_id,
         uid,
@@ -73,24 +173,221 @@ pub async fn handle_put(
             dtend: fields.dtend.as_deref(),
             summary: fields.summary.as_deref(),
         },
+        config.etag_mode,
     )
     .await
     {
-        Ok((obj, is_new)) => {
-            let status = if is_new {
-                StatusCode::CREATED
-            } else {
-                StatusCode::NO_CONTENT
-            };
+        Ok((obj, true)) => {
+            let href = properties::calendar_object_href_for_context(
+                &href_ctx,
+                &calendar_id,
+                &encode_path_segment(&obj.uid),
+            );
             Response::builder()
-                .status(status)
+                .status(StatusCode::CREATED)
                 .header(header::ETAG, &obj.etag)
+                .header(header::LOCATION, href)
                 .body(Body::empty())
                 .unwrap()
         }
+        Ok((obj, false)) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header(header::ETAG, &obj.etag)
+            .body(Body::empty())
+            .unwrap(),
         Err(e) => {
             tracing::error!("Failed to upsert object: {e}");
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to save event").into_response()
         }
     }
 }
+
+/// Reject creating an object whose component type is not in the calendar's
+/// `supported-calendar-component-set` (RFC 4791 §5.3.2.1). Returns the error
+/// response to send, or `None` if the write may proceed.
+pub(super) async fn check_supported_component(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    uid: &str,
+    component_type: &str,
+) -> Option<Response> {
+    let existing = match events::get_object_by_uid(pool, calendar_id, uid).await {
+        Ok(obj) => obj,
+        Err(e) => {
+            tracing::error!("Failed to check existing object: {e}");
+            return Some((StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response());
+        }
+    };
+    if existing.is_some() {
+        return None;
+    }
+    match calendars::get_calendar_by_id(pool, calendar_id).await {
+        Ok(Some(cal)) if !cal.accepts_component(component_type) => Some(supported_component_error(
+            &format!("Component type {component_type} is not supported by this calendar"),
+        )),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::error!("Failed to load calendar: {e}");
+            Some((StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response())
+        }
+    }
+}
+
+/// 409 with the `CALDAV:no-uid-conflict` precondition (RFC 4791 §5.3.2.1)
+/// for a body whose UID is not the resource name. `href` is where an object
+/// with the body's UID lives.
+fn no_uid_conflict_error(resource_uid: &str, body_uid: &str, href: &str) -> Response {
+    tracing::info!("Rejecting PUT of UID {body_uid} to resource {resource_uid}.ics");
+    (
+        StatusCode::CONFLICT,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <D:error xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+             <C:no-uid-conflict><D:href>{href}</D:href></C:no-uid-conflict></D:error>"
+        ),
+    )
+        .into_response()
+}
+
+/// Percent-encode a UID for use as a path segment in a header. `@` is kept
+/// as-is, matching the hrefs clients use for objects.
+pub(super) fn encode_path_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~@".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Character encodings accepted for PUT bodies. Everything is stored as UTF-8.
+enum Charset {
+    Utf8,
+    Latin1,
+}
+
+/// Determine the body charset from a Content-Type header, defaulting to UTF-8.
+/// Returns the charset name as the error if it is not supported.
+fn body_charset(content_type: Option<&str>) -> Result<Charset, String> {
+    let Some(charset) = content_type.and_then(|ct| {
+        ct.split(';').skip(1).find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim()
+                .eq_ignore_ascii_case("charset")
+                .then(|| value.trim().trim_matches('"').to_string())
+        })
+    }) else {
+        return Ok(Charset::Utf8);
+    };
+
+    match charset.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" | "us-ascii" | "ascii" => Ok(Charset::Utf8),
+        "iso-8859-1" | "iso8859-1" | "latin1" | "l1" => Ok(Charset::Latin1),
+        _ => Err(charset),
+    }
+}
+
+/// 400 with the `CALDAV:valid-calendar-data` precondition (RFC 4791
+/// §5.3.2.1) in a `DAV:error` body. `reason` is logged.
+fn valid_calendar_data_error(reason: &str) -> Response {
+    tracing::info!("Rejecting PUT: {reason}");
+    (
+        StatusCode::BAD_REQUEST,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <D:error xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+         <C:valid-calendar-data/></D:error>",
+    )
+        .into_response()
+}
+
+/// The `413 Payload Too Large` response for a PUT body over
+/// `MAX_RESOURCE_SIZE`, with the `CALDAV:max-resource-size` precondition
+/// (RFC 4791 §5.3.2.1) in a `DAV:error` body.
+fn max_resource_size_error(limit: usize) -> Response {
+    tracing::info!("Rejecting PUT over the {limit}-byte max-resource-size");
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <D:error xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+         <C:max-resource-size/></D:error>",
+    )
+        .into_response()
+}
+
+/// 403 with the `CALDAV:supported-calendar-component` precondition
+/// (RFC 4791 §5.3.2.1) in a `DAV:error` body. `reason` is logged.
+pub fn supported_component_error(reason: &str) -> Response {
+    tracing::info!("{reason}");
+    (
+        StatusCode::FORBIDDEN,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <D:error xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+         <C:supported-calendar-component/></D:error>",
+    )
+        .into_response()
+}
+
+/// The `403 Forbidden` response for a date before `MIN_EVENT_YEAR` or after
+/// `MAX_EVENT_YEAR`, with the `CALDAV:min-date-time` or `CALDAV:max-date-time`
+/// precondition (RFC 4791 §5.3.2.1) in a `DAV:error` body.
+fn date_time_range_error(reason: &str, too_early: bool) -> Response {
+    tracing::info!("Rejecting PUT: {reason}");
+    let precondition = if too_early {
+        "min-date-time"
+    } else {
+        "max-date-time"
+    };
+    (
+        StatusCode::FORBIDDEN,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <D:error xmlns:D=\"DAV:\" xmlns:C=\"urn:ietf:params:xml:ns:caldav\">\
+             <C:{precondition}/></D:error>"
+        ),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_charset() {
+        assert!(matches!(body_charset(None), Ok(Charset::Utf8)));
+        assert!(matches!(
+            body_charset(Some("text/calendar")),
+            Ok(Charset::Utf8)
+        ));
+        assert!(matches!(
+            body_charset(Some("text/calendar; charset=\"ISO-8859-1\"")),
+            Ok(Charset::Latin1)
+        ));
+        assert!(matches!(
+            body_charset(Some("text/calendar; component=VEVENT; CHARSET=utf-8")),
+            Ok(Charset::Utf8)
+        ));
+        assert!(matches!(
+            body_charset(Some("text/calendar; charset=Shift_JIS")),
+            Err(name) if name == "Shift_JIS"
+        ));
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(
+            encode_path_segment("abc-123@example.com"),
+            "abc-123@example.com"
+        );
+        assert_eq!(encode_path_segment("team sync/1"), "team%20sync%2F1");
+        assert_eq!(encode_path_segment("café"), "caf%C3%A9");
+    }
+}
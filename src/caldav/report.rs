@@ -1,11 +1,11 @@
 use axum::body::Body;
 use axum::extract::{Path, State};
-use axum::http::{Request, StatusCode};
+use axum::http::{HeaderMap, Request, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use sqlx::SqlitePool;
 
 /// Percent-decode a URL path segment (e.g. `%40` → `@`).
-fn percent_decode(s: &str) -> String {
+pub(super) fn percent_decode(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     let mut chars = s.chars().peekable();
     while let Some(c) = chars.next() {
@@ -28,8 +28,10 @@ use super::HrefContext;
 use super::propfind::multistatus_response;
 use super::xml::multistatus::MultistatusBuilder;
 use super::xml::{parse, properties};
-use crate::db::models::User;
-use crate::db::{calendars, events};
+use crate::db::models::{CalendarObject, User};
+use crate::db::{calendars, events, users};
+use crate::ical::parser::{self, CompSelection, Component};
+use crate::ical::{datetime, recurrence};
 
 /// Handle REPORT for a calendar collection: /caldav/users/{username}/{calendar_id}/
 /// or /calendar/dav/{email}/user/{calendar_id}/
@@ -40,6 +42,14 @@ pub async fn handle_report(
 ) -> Response {
     let user = request.extensions().get::<User>().unwrap().clone();
     let href_ctx = request.extensions().get::<HrefContext>().cloned();
+
+    let depth = match report_depth(request.headers()) {
+        Ok(depth) => depth,
+        Err(value) => {
+            return (StatusCode::BAD_REQUEST, format!("Invalid Depth: {value}")).into_response();
+        }
+    };
+
     let body = axum::body::to_bytes(request.into_body(), 256 * 1024)
         .await
         .unwrap_or_default();
@@ -59,19 +69,57 @@ pub async fn handle_report(
     };
 
     let resp = match report {
+        // RFC 4791 §7.9: the Depth header is ignored for calendar-multiget.
         parse::ReportRequest::CalendarMultiget {
             ref props,
             ref hrefs,
+            ref data_selection,
         } => {
             tracing::info!(calendar_id = %calendar_id, hrefs = ?hrefs, "REPORT: calendar-multiget");
-            handle_multiget(&pool, &ctx, &calendar_id, props, hrefs).await
+            handle_multiget(
+                &pool,
+                &ctx,
+                &calendar_id,
+                props,
+                hrefs,
+                data_selection.as_ref(),
+            )
+            .await
+        }
+        // Depth:0 scopes the query to the collection itself, which is not a
+        // calendar object, so nothing matches. A missing Depth is treated as
+        // 1 because many clients omit it on collection queries.
+        parse::ReportRequest::CalendarQuery { .. } if depth == Some(Depth::Zero) => {
+            tracing::info!(calendar_id = %calendar_id, "REPORT: calendar-query at Depth:0");
+            multistatus_response(MultistatusBuilder::new().build())
         }
         parse::ReportRequest::CalendarQuery {
             ref props,
+            ref component,
             ref time_range,
+            ref expand,
+            ref prop_filters,
+            ref data_selection,
         } => {
-            tracing::info!(calendar_id = %calendar_id, time_range = ?time_range, "REPORT: calendar-query");
-            handle_query(&pool, &ctx, &calendar_id, props, time_range.as_ref()).await
+            tracing::info!(calendar_id = %calendar_id, component = ?component, time_range = ?time_range, "REPORT: calendar-query");
+            handle_query(
+                &pool,
+                &ctx,
+                &calendar_id,
+                props,
+                component.as_deref(),
+                time_range.as_ref(),
+                expand.as_ref(),
+                prop_filters,
+                data_selection.as_ref(),
+            )
+            .await
+        }
+        // RFC 6578 §3.2: sync-collection is only defined for Depth:0 (the
+        // default when the header is absent); scope comes from sync-level.
+        parse::ReportRequest::SyncCollection { .. } if depth.is_some_and(|d| d != Depth::Zero) => {
+            tracing::warn!(calendar_id = %calendar_id, depth = ?depth, "REPORT: sync-collection with non-zero Depth");
+            (StatusCode::BAD_REQUEST, "sync-collection requires Depth: 0").into_response()
         }
         parse::ReportRequest::SyncCollection {
             ref props,
@@ -80,6 +128,11 @@ pub async fn handle_report(
             tracing::info!(calendar_id = %calendar_id, sync_token = %sync_token, "REPORT: sync-collection");
             handle_sync(&pool, &ctx, &calendar_id, props, sync_token).await
         }
+        parse::ReportRequest::PrincipalPropertySearch { .. } => (
+            StatusCode::FORBIDDEN,
+            "principal-property-search is only supported on the principal collection",
+        )
+            .into_response(),
     };
 
     let (parts, resp_body) = resp.into_parts();
@@ -95,6 +148,145 @@ pub async fn handle_report(
     Response::from_parts(parts, Body::from(resp_bytes))
 }
 
+/// The value of a REPORT's `Depth` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Depth {
+    Zero,
+    One,
+    Infinity,
+}
+
+/// Read the `Depth` header. `Ok(None)` when absent; `Err` carries an
+/// unrecognised value so the caller can reject the request.
+fn report_depth(headers: &HeaderMap) -> Result<Option<Depth>, String> {
+    let Some(value) = headers.get("Depth") else {
+        return Ok(None);
+    };
+    match value.to_str().map(str::trim) {
+        Ok("0") => Ok(Some(Depth::Zero)),
+        Ok("1") => Ok(Some(Depth::One)),
+        Ok(v) if v.eq_ignore_ascii_case("infinity") => Ok(Some(Depth::Infinity)),
+        Ok(v) => Err(v.to_string()),
+        Err(_) => Err("(non-ASCII)".to_string()),
+    }
+}
+
+/// Most principals a principal-property-search REPORT returns.
+const PRINCIPAL_SEARCH_LIMIT: i64 = 50;
+
+/// Handle REPORT on the principal collection (/principals/ and
+/// /caldav/principals/{username}/). Only principal-property-search
+/// (RFC 3744 §9.4) is supported there; scheduling clients use it to
+/// resolve attendees by name or email.
+pub async fn handle_principal_report(pool: &SqlitePool, request: Request<Body>) -> Response {
+    let body = axum::body::to_bytes(request.into_body(), 64 * 1024)
+        .await
+        .unwrap_or_default();
+    match parse::parse_report(&body) {
+        Some(parse::ReportRequest::PrincipalPropertySearch {
+            props,
+            searches,
+            any_of,
+        }) => handle_principal_search(pool, &props, &searches, any_of).await,
+        Some(_) => (
+            StatusCode::FORBIDDEN,
+            "Only principal-property-search is supported on principals",
+        )
+            .into_response(),
+        None => (StatusCode::BAD_REQUEST, "Invalid REPORT body").into_response(),
+    }
+}
+
+/// Find the users matching a principal-property-search. Searches on
+/// `displayname` (or the CalendarServer `first-name`/`last-name`) match the
+/// username; searches on `calendar-user-address-set` or `email-address-set`
+/// match the email address. All searches must match unless `any_of`.
+async fn handle_principal_search(
+    pool: &SqlitePool,
+    props: &[parse::PropRequest],
+    searches: &[parse::PropertySearch],
+    any_of: bool,
+) -> Response {
+    let mut matches: Option<Vec<User>> = None;
+    for search in searches {
+        let by_name = search.props.iter().any(|p| {
+            matches!(
+                p.local_name.as_str(),
+                "displayname" | "first-name" | "last-name"
+            )
+        });
+        let by_email = search.props.iter().any(|p| {
+            matches!(
+                p.local_name.as_str(),
+                "calendar-user-address-set" | "email-address-set"
+            )
+        });
+        let text = search.match_text.trim();
+        let text = text.strip_prefix("mailto:").unwrap_or(text).to_lowercase();
+        // An empty match would list every user
+        if text.is_empty() {
+            matches = Some(if any_of {
+                matches.unwrap_or_default()
+            } else {
+                Vec::new()
+            });
+            continue;
+        }
+
+        let found: Vec<User> = match users::search_users(pool, &text, PRINCIPAL_SEARCH_LIMIT).await
+        {
+            Ok(users) => users
+                .into_iter()
+                .filter(|u| {
+                    (by_name && u.username.to_lowercase().contains(&text))
+                        || (by_email
+                            && u.email
+                                .as_deref()
+                                .is_some_and(|e| e.to_lowercase().contains(&text)))
+                })
+                .collect(),
+            Err(e) => {
+                tracing::error!("Failed to search principals: {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+            }
+        };
+        matches = Some(match matches {
+            None => found,
+            Some(mut all) if any_of => {
+                for user in found {
+                    if !all.iter().any(|u| u.id == user.id) {
+                        all.push(user);
+                    }
+                }
+                all
+            }
+            Some(all) => all
+                .into_iter()
+                .filter(|u| found.iter().any(|f| f.id == u.id))
+                .collect(),
+        });
+    }
+
+    let mut matches = matches.unwrap_or_default();
+    matches.sort_by(|a, b| a.username.cmp(&b.username));
+    let request = if props.is_empty() {
+        parse::PropfindRequest::AllProp
+    } else {
+        parse::PropfindRequest::Props(props.to_vec())
+    };
+    let mut builder = MultistatusBuilder::new();
+    for user in &matches {
+        let (found, not_found) =
+            properties::filter_props(&request, properties::principal_search_props(user));
+        builder.add_response(
+            &format!("/caldav/users/{}/", user.username),
+            found,
+            not_found,
+        );
+    }
+    multistatus_response(builder.build())
+}
+
 /// Handle calendar-multiget REPORT: fetch specific events by href.
 async fn handle_multiget(
     pool: &SqlitePool,
@@ -102,6 +294,7 @@ async fn handle_multiget(
     calendar_id: &str,
     _props: &[parse::PropRequest],
     hrefs: &[String],
+    data_selection: Option<&CompSelection>,
 ) -> Response {
     let mut builder = MultistatusBuilder::new();
 
@@ -122,9 +315,15 @@ async fn handle_multiget(
 
     for obj in &objects {
         let href = properties::calendar_object_href_for_context(ctx, calendar_id, &obj.uid);
+        let selected = data_selection.and_then(|s| select_data(obj, s));
         builder.add_response(
             &href,
-            properties::calendar_object_props(&ctx.username, calendar_id, obj, true),
+            properties::calendar_object_props(
+                &ctx.username,
+                calendar_id,
+                selected.as_ref().unwrap_or(obj),
+                true,
+            ),
             vec![],
         );
     }
@@ -132,30 +331,77 @@ async fn handle_multiget(
     multistatus_response(builder.build())
 }
 
-/// Handle calendar-query REPORT: fetch events matching a filter (time-range).
+/// Handle calendar-query REPORT: fetch objects matching a filter (component
+/// type and time-range). A time-range on VTODO matches on DUE as well as
+/// DTSTART (RFC 4791 §9.9); a recurring event matches if any of its
+/// instances falls in the range. Property filters are applied to the
+/// object's main component. With `expand`, recurring events are returned as
+/// one VEVENT per instance, each with a RECURRENCE-ID. A calendar-data
+/// comp/prop selection is applied last.
+#[allow(clippy::too_many_arguments)]
 async fn handle_query(
     pool: &SqlitePool,
     ctx: &HrefContext,
     calendar_id: &str,
     _props: &[parse::PropRequest],
+    component: Option<&str>,
     time_range: Option<&(String, String)>,
+    expand: Option<&(String, String)>,
+    prop_filters: &[parse::PropFilter],
+    data_selection: Option<&CompSelection>,
 ) -> Response {
     let mut builder = MultistatusBuilder::new();
 
-    let objects = match time_range {
-        Some((start, end)) => events::list_objects_in_range(pool, calendar_id, start, end)
-            .await
-            .unwrap_or_default(),
-        None => events::list_objects(pool, calendar_id)
+    let objects = match (component, time_range) {
+        (Some("VTODO"), Some((start, end))) => {
+            events::list_todos_in_range(pool, calendar_id, start, end)
+                .await
+                .unwrap_or_default()
+        }
+        (_, Some((start, end))) => {
+            match events::list_objects_in_range_with_recurring(pool, calendar_id, start, end).await
+            {
+                Ok((mut in_range, recurring)) => {
+                    add_recurring_matches(&mut in_range, recurring, start, end);
+                    in_range
+                }
+                Err(_) => vec![],
+            }
+        }
+        (_, None) => events::list_objects(pool, calendar_id)
             .await
             .unwrap_or_default(),
     };
 
-    for obj in &objects {
+    let expand_range = expand.and_then(|(start, end)| {
+        Some((
+            datetime::parse_ical_datetime(start, None)?,
+            datetime::parse_ical_datetime(end, None)?,
+        ))
+    });
+
+    let matching = objects
+        .iter()
+        .filter(|obj| component.is_none_or(|c| obj.component_type == c))
+        .filter(|obj| prop_filters.iter().all(|f| matches_prop_filter(obj, f)));
+    for obj in matching {
         let href = properties::calendar_object_href_for_context(ctx, calendar_id, &obj.uid);
+        let expanded = expand_range.and_then(|(start, end)| {
+            let calendar = parser::parse_calendar(&obj.ical_data).filter(is_recurring)?;
+            let mut expanded = obj.clone();
+            expanded.ical_data = recurrence::expand_instances(&calendar, start, end).to_ical();
+            Some(expanded)
+        });
+        let obj = expanded.as_ref().unwrap_or(obj);
+        let selected = data_selection.and_then(|s| select_data(obj, s));
         builder.add_response(
             &href,
-            properties::calendar_object_props(&ctx.username, calendar_id, obj, true),
+            properties::calendar_object_props(
+                &ctx.username,
+                calendar_id,
+                selected.as_ref().unwrap_or(obj),
+                true,
+            ),
             vec![],
         );
     }
@@ -163,6 +409,102 @@ async fn handle_query(
     multistatus_response(builder.build())
 }
 
+/// A copy of `obj` whose calendar data holds only the selected components
+/// and properties (RFC 4791 §9.6.1).
+fn select_data(obj: &CalendarObject, selection: &CompSelection) -> Option<CalendarObject> {
+    let calendar = parser::parse_calendar(&obj.ical_data)?;
+    let mut selected = obj.clone();
+    selected.ical_data = parser::select_properties(&calendar, selection).to_ical();
+    Some(selected)
+}
+
+/// Add the recurring events among `candidates` with an instance in
+/// `[start, end)`. Objects are matched on their stored DTSTART/DTEND, which
+/// for a recurring event covers only the first instance.
+fn add_recurring_matches(
+    matched: &mut Vec<CalendarObject>,
+    candidates: Vec<CalendarObject>,
+    start: &str,
+    end: &str,
+) {
+    let (Some(range_start), Some(range_end)) = (
+        datetime::parse_ical_datetime(start, None),
+        datetime::parse_ical_datetime(end, None),
+    ) else {
+        return;
+    };
+    for obj in candidates {
+        let Some(calendar) = parser::parse_calendar(&obj.ical_data).filter(is_recurring) else {
+            continue;
+        };
+        if !recurrence::instances_in_range(&calendar, range_start, range_end).is_empty() {
+            matched.push(obj);
+        }
+    }
+}
+
+/// Whether an object satisfies a prop-filter. Text matches are
+/// case-insensitive substring matches (the default `i;ascii-casemap`
+/// collation). SUMMARY is read from the stored column; other properties from
+/// the object's first component of its type.
+fn matches_prop_filter(obj: &CalendarObject, filter: &parse::PropFilter) -> bool {
+    let values: Vec<String> = if filter.name == "SUMMARY" {
+        obj.summary.iter().cloned().collect()
+    } else {
+        parser::parse_calendar(&obj.ical_data)
+            .and_then(|cal| {
+                cal.components
+                    .into_iter()
+                    .find(|c| c.name == obj.component_type)
+            })
+            .map(|c| {
+                c.properties_named(&filter.name)
+                    .map(|p| p.value.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    if filter.is_not_defined {
+        return values.is_empty();
+    }
+    let Some(text) = &filter.text_match else {
+        return !values.is_empty();
+    };
+    let text = text.to_lowercase();
+    let found = values.iter().any(|v| v.to_lowercase().contains(&text));
+    !values.is_empty() && found != filter.negate
+}
+
+/// Whether a calendar object holds a VEVENT with an RRULE or RDATE.
+fn is_recurring(calendar: &Component) -> bool {
+    calendar.components.iter().any(|c| {
+        c.name == "VEVENT" && (c.property("RRULE").is_some() || c.property("RDATE").is_some())
+    })
+}
+
+/// 500 for a sync-collection REPORT that could not read the change log or
+/// objects. Answering with a partial result and the current token would make
+/// the client skip the changes it missed.
+fn sync_failed(error: crate::error::AppError) -> Response {
+    tracing::error!("REPORT: sync-collection failed: {error}");
+    (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+}
+
+/// 403 with the `DAV:valid-sync-token` precondition (RFC 6578 §3.2), telling
+/// the client to drop its token and sync from scratch. Used when a token is
+/// too old for its deletions to be recovered.
+fn valid_sync_token_error(calendar_id: &str) -> Response {
+    tracing::info!(calendar_id = %calendar_id, "REPORT: sync-collection token is no longer valid");
+    (
+        StatusCode::FORBIDDEN,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <D:error xmlns:D=\"DAV:\"><D:valid-sync-token/></D:error>",
+    )
+        .into_response()
+}
+
 /// Handle sync-collection REPORT (RFC 6578): return changes since a sync token.
 async fn handle_sync(
     pool: &SqlitePool,
@@ -183,11 +525,36 @@ async fn handle_sync(
 
     let mut builder = MultistatusBuilder::new();
 
-    if sync_token.is_empty() {
-        // Initial sync: return all objects
-        let objects = events::list_objects(pool, calendar_id)
-            .await
-            .unwrap_or_default();
+    // An empty token asks for every object. Otherwise return the changes
+    // since the token; if its change rows have been pruned, fall back to
+    // every object plus the deletions its tombstones still record.
+    let mut changes = Vec::new();
+    let mut deleted = Vec::new();
+    let list_all = if sync_token.is_empty() {
+        true
+    } else {
+        match events::get_sync_changes_since(pool, calendar_id, sync_token).await {
+            Ok(Some(since)) => {
+                changes = since;
+                false
+            }
+            Ok(None) => match events::deleted_uids_since(pool, calendar_id, sync_token).await {
+                Ok(Some(uids)) => {
+                    deleted = uids;
+                    true
+                }
+                Ok(None) => return valid_sync_token_error(calendar_id),
+                Err(e) => return sync_failed(e),
+            },
+            Err(e) => return sync_failed(e),
+        }
+    };
+
+    if list_all {
+        let objects = match events::list_objects(pool, calendar_id).await {
+            Ok(objects) => objects,
+            Err(e) => return sync_failed(e),
+        };
 
         for obj in &objects {
             let href = properties::calendar_object_href_for_context(ctx, calendar_id, &obj.uid);
@@ -197,35 +564,39 @@ async fn handle_sync(
                 vec![],
             );
         }
-    } else {
-        // Delta sync: return changes since the given token
-        let changes = events::get_sync_changes_since(pool, calendar_id, sync_token)
-            .await
-            .unwrap_or_default();
+    }
 
-        for change in &changes {
-            let href =
-                properties::calendar_object_href_for_context(ctx, calendar_id, &change.object_uid);
+    // RFC 6578 §3.5.2: a removed member is a response with a bare 404 status
+    // and no propstat
+    for uid in &deleted {
+        let href = properties::calendar_object_href_for_context(ctx, calendar_id, uid);
+        builder.add_status_response(&href, "HTTP/1.1 404 Not Found");
+    }
 
-            if change.change_type == "deleted" {
-                // For deletions, return a 404 status for that href
-                builder.add_response(&href, vec![], vec![]);
-            } else {
-                // For created/modified, return the current object
-                if let Ok(Some(obj)) =
-                    events::get_object_by_uid(pool, calendar_id, &change.object_uid).await
-                {
-                    builder.add_response(
-                        &href,
-                        properties::calendar_object_props(
-                            &ctx.username,
-                            calendar_id,
-                            &obj,
-                            include_data,
-                        ),
-                        vec![],
-                    );
-                }
+    for change in &changes {
+        let href =
+            properties::calendar_object_href_for_context(ctx, calendar_id, &change.object_uid);
+
+        if change.change_type == "deleted" {
+            builder.add_status_response(&href, "HTTP/1.1 404 Not Found");
+        } else {
+            // For created/modified, return the current object
+            let current =
+                match events::get_object_by_uid(pool, calendar_id, &change.object_uid).await {
+                    Ok(current) => current,
+                    Err(e) => return sync_failed(e),
+                };
+            if let Some(obj) = current {
+                builder.add_response(
+                    &href,
+                    properties::calendar_object_props(
+                        &ctx.username,
+                        calendar_id,
+                        &obj,
+                        include_data,
+                    ),
+                    vec![],
+                );
             }
         }
     }
@@ -3,7 +3,8 @@ use axum::response::{IntoResponse, Response};
 
 /// Handle any method on /.well-known/caldav
 /// Apple Calendar hits this first to discover the CalDAV service root.
-/// OPTIONS returns DAV headers; everything else returns 301 redirect to /caldav/.
+/// OPTIONS returns 200; everything else returns 301 redirect to /caldav/.
+/// The `DAV` header on both is added by [`super::dav_header`].
 ///
 /// Note: Apple Calendar's accountsd process does discovery without auth.
 /// It expects a redirect here, then authenticates at the destination.
@@ -19,7 +20,6 @@ pub async fn handle_well_known(request: axum::extract::Request) -> Response {
     if request.method().as_str() == "OPTIONS" {
         return Response::builder()
             .status(StatusCode::OK)
-            .header("DAV", "1, 2, 3, calendar-access, calendar-schedule")
             .header(
                 "Allow",
                 "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, PROPPATCH, REPORT, MKCALENDAR",
@@ -33,22 +33,41 @@ pub async fn handle_well_known(request: axum::extract::Request) -> Response {
     Response::builder()
         .status(StatusCode::MOVED_PERMANENTLY)
         .header(header::LOCATION, "/caldav/")
-        .header("DAV", "1, 2, 3, calendar-access, calendar-schedule")
         .body(axum::body::Body::empty())
         .unwrap()
 }
 
 /// Handle OPTIONS requests at any CalDAV path.
-/// Returns DAV compliance headers that Apple Calendar requires.
+/// The DAV compliance header Apple Calendar requires is added by
+/// [`super::dav_header`] from the configured features.
 pub async fn handle_options() -> impl IntoResponse {
     (
         StatusCode::OK,
-        [
-            ("DAV", "1, 2, 3, calendar-access, calendar-schedule"),
-            (
-                "Allow",
-                "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, PROPPATCH, REPORT, MKCALENDAR",
-            ),
-        ],
+        [(
+            "Allow",
+            "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, PROPPATCH, REPORT, MKCALENDAR",
+        )],
+    )
+}
+
+/// Methods supported on a calendar collection.
+pub const COLLECTION_METHODS: &str = "OPTIONS, PROPFIND, PROPPATCH, REPORT, MKCALENDAR, DELETE";
+
+/// Methods supported on a calendar object.
+pub const OBJECT_METHODS: &str = "OPTIONS, GET, HEAD, PUT, DELETE, COPY, MOVE";
+
+/// OPTIONS response for a resource that supports exactly `allow`.
+pub fn options_for(allow: &'static str) -> Response {
+    (StatusCode::OK, [(header::ALLOW, allow)]).into_response()
+}
+
+/// 405 response naming the methods the resource does support, as
+/// RFC 9110 §15.5.6 requires.
+pub fn method_not_allowed(allow: &'static str) -> Response {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(header::ALLOW, allow)],
+        "Method not allowed",
     )
+        .into_response()
 }
@@ -1,5 +1,6 @@
 use quick_xml::Writer;
 use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use std::borrow::Cow;
 use std::io::{Cursor, Write};
 
 /// Builder for WebDAV multistatus XML responses.
@@ -59,7 +60,7 @@ impl MultistatusBuilder {
             .write_event(Event::Start(BytesStart::new("D:href")))
             .unwrap();
         self.writer
-            .write_event(Event::Text(BytesText::new(href)))
+            .write_event(Event::Text(BytesText::new(&xml_safe(href))))
             .unwrap();
         self.writer
             .write_event(Event::End(BytesEnd::new("D:href")))
@@ -84,7 +85,7 @@ impl MultistatusBuilder {
                             .write_event(Event::Start(BytesStart::new(&prefixed)))
                             .unwrap();
                         self.writer
-                            .write_event(Event::Text(BytesText::new(text)))
+                            .write_event(Event::Text(BytesText::new(&xml_safe(text))))
                             .unwrap();
                         self.writer
                             .write_event(Event::End(BytesEnd::new(&prefixed)))
@@ -167,6 +168,36 @@ impl MultistatusBuilder {
             .unwrap();
     }
 
+    /// Add a response entry that carries only a status, e.g. the
+    /// `HTTP/1.1 404 Not Found` that sync-collection reports for a deleted
+    /// member (RFC 6578 §3.5.2).
+    pub fn add_status_response(&mut self, href: &str, status: &str) {
+        self.writer
+            .write_event(Event::Start(BytesStart::new("D:response")))
+            .unwrap();
+        self.writer
+            .write_event(Event::Start(BytesStart::new("D:href")))
+            .unwrap();
+        self.writer
+            .write_event(Event::Text(BytesText::new(&xml_safe(href))))
+            .unwrap();
+        self.writer
+            .write_event(Event::End(BytesEnd::new("D:href")))
+            .unwrap();
+        self.writer
+            .write_event(Event::Start(BytesStart::new("D:status")))
+            .unwrap();
+        self.writer
+            .write_event(Event::Text(BytesText::new(status)))
+            .unwrap();
+        self.writer
+            .write_event(Event::End(BytesEnd::new("D:status")))
+            .unwrap();
+        self.writer
+            .write_event(Event::End(BytesEnd::new("D:response")))
+            .unwrap();
+    }
+
     /// Add a sync-token element (used in sync-collection response).
     pub fn add_sync_token(&mut self, token: &str) {
         self.writer
@@ -191,6 +222,22 @@ impl MultistatusBuilder {
     }
 }
 
+/// Strip characters that are not allowed in XML 1.0 documents (control
+/// characters other than tab, LF and CR, plus U+FFFE/U+FFFF). Stored event
+/// data can contain them, e.g. a vertical tab pasted into a summary, and a
+/// single one would make the whole multistatus unparseable for clients.
+fn xml_safe(text: &str) -> Cow<'_, str> {
+    fn allowed(c: char) -> bool {
+        matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}')
+            || c >= '\u{10000}'
+    }
+    if text.chars().all(allowed) {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(text.chars().filter(|&c| allowed(c)).collect())
+    }
+}
+
 /// Map a namespace URI + local name to a prefixed element name.
 fn prefix_name(namespace: &str, local_name: &str) -> String {
     match namespace {
@@ -242,6 +289,42 @@ mod tests {
         assert!(xml.contains("HTTP/1.1 404 Not Found"));
     }
 
+    #[test]
+    fn test_status_response_has_no_propstat() {
+        let mut builder = MultistatusBuilder::new();
+        builder.add_status_response("/caldav/users/alice/cal/gone.ics", "HTTP/1.1 404 Not Found");
+        let xml = String::from_utf8(builder.build()).unwrap();
+        assert!(xml.contains(
+            "<D:response><D:href>/caldav/users/alice/cal/gone.ics</D:href>\
+             <D:status>HTTP/1.1 404 Not Found</D:status></D:response>"
+        ));
+        assert!(!xml.contains("D:propstat"));
+    }
+
+    #[test]
+    fn test_xml_safe_strips_invalid_chars() {
+        assert!(matches!(xml_safe("Plain\ttext\r\n"), Cow::Borrowed(_)));
+        assert_eq!(xml_safe("Bad\u{0B}tab\u{0}\u{FFFF}"), "Badtab");
+        assert_eq!(xml_safe("Caf\u{e9} \u{1F4C5}"), "Caf\u{e9} \u{1F4C5}");
+    }
+
+    #[test]
+    fn test_text_prop_with_control_char_is_well_formed() {
+        let mut builder = MultistatusBuilder::new();
+        builder.add_response(
+            "/caldav/",
+            vec![PropValue {
+                name: "calendar-data".to_string(),
+                namespace: super::super::CALDAV_NS.to_string(),
+                value: PropContent::Text("SUMMARY:Stand\u{0B}up".to_string()),
+            }],
+            vec![],
+        );
+        let xml = String::from_utf8(builder.build()).unwrap();
+        assert!(xml.contains("<C:calendar-data>SUMMARY:Standup</C:calendar-data>"));
+        assert!(!xml.contains('\u{0B}'));
+    }
+
     #[test]
     fn test_prefix_name_mapping() {
         assert_eq!(
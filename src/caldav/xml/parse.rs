@@ -1,7 +1,9 @@
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::reader::Reader;
 use std::collections::HashMap;
 
+use crate::ical::parser::CompSelection;
+
 /// Parsed PROPFIND request body.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -144,21 +146,60 @@ pub fn parse_propfind(body: &[u8]) -> PropfindRequest {
     }
 }
 
+/// A `CALDAV:prop-filter` from a calendar-query (RFC 4791 §9.7.2).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropFilter {
+    /// Property name, upper-cased, e.g. `SUMMARY`.
+    pub name: String,
+    /// Substring the property value must contain, from `text-match`.
+    pub text_match: Option<String>,
+    /// `negate-condition="yes"` on the text-match: the value must not contain it.
+    pub negate: bool,
+    /// `is-not-defined`: the property must be absent.
+    pub is_not_defined: bool,
+}
+
 /// Parsed REPORT request body.
 #[derive(Debug, Clone)]
 pub enum ReportRequest {
     CalendarMultiget {
         props: Vec<PropRequest>,
         hrefs: Vec<String>,
+        /// `comp`/`prop` sub-selection from the requested calendar-data.
+        data_selection: Option<CompSelection>,
     },
     CalendarQuery {
         props: Vec<PropRequest>,
+        /// Component named by the comp-filter directly below VCALENDAR,
+        /// e.g. `VEVENT` or `VTODO`.
+        component: Option<String>,
         time_range: Option<(String, String)>,
+        /// `CALDAV:expand` range from the requested calendar-data, if any.
+        expand: Option<(String, String)>,
+        /// Property filters, all of which an object must match.
+        prop_filters: Vec<PropFilter>,
+        /// `comp`/`prop` sub-selection from the requested calendar-data.
+        data_selection: Option<CompSelection>,
     },
     SyncCollection {
         props: Vec<PropRequest>,
         sync_token: String,
     },
+    PrincipalPropertySearch {
+        props: Vec<PropRequest>,
+        searches: Vec<PropertySearch>,
+        /// `test="anyof"`: a principal matching any search is returned.
+        /// Otherwise (the RFC 3744 default) it must match all of them.
+        any_of: bool,
+    },
+}
+
+/// One `DAV:property-search` of a principal-property-search REPORT
+/// (RFC 3744 §9.4): the properties to look in and the text to look for.
+#[derive(Debug, Clone, Default)]
+pub struct PropertySearch {
+    pub props: Vec<PropRequest>,
+    pub match_text: String,
 }
 
 /// Parse a REPORT request body.
@@ -179,12 +220,26 @@ pub fn parse_report(body: &[u8]) -> Option<ReportRequest> {
     let mut hrefs = Vec::new();
     let mut time_start = String::new();
     let mut time_end = String::new();
+    let mut component: Option<String> = None;
+    let mut expand: Option<(String, String)> = None;
+    let mut prop_filters = Vec::new();
+    let mut prop_filter: Option<PropFilter> = None;
+    let mut in_text_match = false;
+    let mut in_calendar_data = false;
+    let mut comp_stack: Vec<CompSelection> = Vec::new();
+    let mut data_selection: Option<CompSelection> = None;
     let mut sync_token = String::new();
     let mut in_sync_token = false;
     let mut in_href = false;
+    let mut searches = Vec::new();
+    let mut search: Option<PropertySearch> = None;
+    let mut in_match = false;
+    let mut any_of = false;
 
     loop {
-        match reader.read_event_into(&mut buf) {
+        let event = reader.read_event_into(&mut buf);
+        let is_empty = matches!(event, Ok(Event::Empty(_)));
+        match event {
             Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
                 ns_ctx.register_from_event(e);
                 let local = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
@@ -193,8 +248,54 @@ pub fn parse_report(body: &[u8]) -> Option<ReportRequest> {
                     "calendar-multiget" => report_type = Some("multiget".to_string()),
                     "calendar-query" => report_type = Some("query".to_string()),
                     "sync-collection" => report_type = Some("sync".to_string()),
+                    "principal-property-search" => {
+                        report_type = Some("principal-search".to_string());
+                        any_of = e.attributes().flatten().any(|attr| {
+                            attr.key.as_ref() == b"test"
+                                && attr.value.eq_ignore_ascii_case(b"anyof")
+                        });
+                    }
+                    "property-search" => search = Some(PropertySearch::default()),
+                    "match" if search.is_some() => in_match = !is_empty,
+                    "comp" if in_calendar_data => {
+                        let comp = CompSelection {
+                            name: name_attr(e).to_uppercase(),
+                            ..Default::default()
+                        };
+                        if is_empty {
+                            close_comp(comp, &mut comp_stack, &mut data_selection);
+                        } else {
+                            comp_stack.push(comp);
+                        }
+                    }
+                    "prop" if in_calendar_data => {
+                        if let Some(comp) = comp_stack.last_mut() {
+                            comp.props.push(name_attr(e).to_uppercase());
+                        }
+                    }
+                    "allprop" if in_calendar_data => {
+                        if let Some(comp) = comp_stack.last_mut() {
+                            comp.all_props = true;
+                        }
+                    }
+                    "allcomp" if in_calendar_data => {
+                        if let Some(comp) = comp_stack.last_mut() {
+                            comp.all_comps = true;
+                        }
+                    }
                     "prop" => in_prop = true,
-                    "filter" | "comp-filter" => _in_filter = true,
+                    "filter" => _in_filter = true,
+                    "comp-filter" => {
+                        _in_filter = true;
+                        for attr in e.attributes().flatten() {
+                            if attr.key.as_ref() == b"name" {
+                                let name = String::from_utf8_lossy(&attr.value).to_uppercase();
+                                if name != "VCALENDAR" && component.is_none() {
+                                    component = Some(name);
+                                }
+                            }
+                        }
+                    }
                     "href" => in_href = true,
                     "sync-token" => in_sync_token = true,
                     "time-range" => {
@@ -208,8 +309,60 @@ pub fn parse_report(body: &[u8]) -> Option<ReportRequest> {
                             }
                         }
                     }
+                    "prop-filter" => {
+                        let mut filter = PropFilter::default();
+                        for attr in e.attributes().flatten() {
+                            if attr.key.as_ref() == b"name" {
+                                filter.name = String::from_utf8_lossy(&attr.value).to_uppercase();
+                            }
+                        }
+                        // <prop-filter name="X"/> only asks that X is defined
+                        if is_empty {
+                            prop_filters.push(filter);
+                        } else {
+                            prop_filter = Some(filter);
+                        }
+                    }
+                    "text-match" => {
+                        if let Some(filter) = prop_filter.as_mut() {
+                            filter.negate = e.attributes().flatten().any(|attr| {
+                                attr.key.as_ref() == b"negate-condition"
+                                    && attr.value.eq_ignore_ascii_case(b"yes")
+                            });
+                            filter.text_match = Some(String::new());
+                            in_text_match = !is_empty;
+                        }
+                    }
+                    "is-not-defined" => {
+                        if let Some(filter) = prop_filter.as_mut() {
+                            filter.is_not_defined = true;
+                        }
+                    }
+                    "expand" if in_prop => {
+                        let (mut start, mut end) = (String::new(), String::new());
+                        for attr in e.attributes().flatten() {
+                            let val = String::from_utf8_lossy(&attr.value).to_string();
+                            match attr.key.as_ref() {
+                                b"start" => start = val,
+                                b"end" => end = val,
+                                _ => {}
+                            }
+                        }
+                        if !start.is_empty() && !end.is_empty() {
+                            expand = Some((start, end));
+                        }
+                    }
+                    _ if in_prop && search.is_some() => {
+                        if let Some(search) = search.as_mut() {
+                            search.props.push(PropRequest {
+                                namespace: ns_ctx.resolve(e),
+                                local_name: local,
+                            });
+                        }
+                    }
                     _ if in_prop => {
                         let ns = ns_ctx.resolve(e);
+                        in_calendar_data = local == "calendar-data" && !is_empty;
                         props.push(PropRequest {
                             namespace: ns,
                             local_name: local,
@@ -221,16 +374,31 @@ pub fn parse_report(body: &[u8]) -> Option<ReportRequest> {
             Ok(Event::End(ref e)) => {
                 let local = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
                 match local.as_str() {
+                    "comp" if in_calendar_data => {
+                        if let Some(comp) = comp_stack.pop() {
+                            close_comp(comp, &mut comp_stack, &mut data_selection);
+                        }
+                    }
+                    "prop" if in_calendar_data => {}
+                    "calendar-data" => in_calendar_data = false,
                     "prop" => in_prop = false,
                     "filter" | "comp-filter" => _in_filter = false,
                     "href" => in_href = false,
                     "sync-token" => in_sync_token = false,
+                    "text-match" => in_text_match = false,
+                    "prop-filter" => prop_filters.extend(prop_filter.take()),
+                    "property-search" => searches.extend(search.take()),
+                    "match" => in_match = false,
                     _ => {}
                 }
             }
             Ok(Event::Text(ref e)) => {
                 let text = e.unescape().unwrap_or_default().to_string();
-                if in_href {
+                if in_text_match && let Some(filter) = prop_filter.as_mut() {
+                    filter.text_match = Some(text);
+                } else if in_match && let Some(search) = search.as_mut() {
+                    search.match_text = text;
+                } else if in_href {
                     hrefs.push(text);
                 } else if in_sync_token {
                     sync_token = text;
@@ -244,20 +412,57 @@ pub fn parse_report(body: &[u8]) -> Option<ReportRequest> {
     }
 
     match report_type.as_deref() {
-        Some("multiget") => Some(ReportRequest::CalendarMultiget { props, hrefs }),
+        Some("multiget") => Some(ReportRequest::CalendarMultiget {
+            props,
+            hrefs,
+            data_selection,
+        }),
         Some("query") => {
             let time_range = if !time_start.is_empty() && !time_end.is_empty() {
                 Some((time_start, time_end))
             } else {
                 None
             };
-            Some(ReportRequest::CalendarQuery { props, time_range })
+            Some(ReportRequest::CalendarQuery {
+                props,
+                component,
+                time_range,
+                expand,
+                prop_filters,
+                data_selection,
+            })
         }
         Some("sync") => Some(ReportRequest::SyncCollection { props, sync_token }),
+        Some("principal-search") => Some(ReportRequest::PrincipalPropertySearch {
+            props,
+            searches,
+            any_of,
+        }),
         _ => None,
     }
 }
 
+/// The `name` attribute of a calendar-data `comp` or `prop` element.
+fn name_attr(e: &BytesStart) -> String {
+    e.attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == b"name")
+        .map(|attr| String::from_utf8_lossy(&attr.value).to_string())
+        .unwrap_or_default()
+}
+
+/// Attach a finished `comp` to its parent, or make it the selection root.
+fn close_comp(
+    comp: CompSelection,
+    stack: &mut [CompSelection],
+    selection: &mut Option<CompSelection>,
+) {
+    match stack.last_mut() {
+        Some(parent) => parent.comps.push(comp),
+        None => *selection = Some(comp),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -313,7 +518,7 @@ mod tests {
         </C:calendar-multiget>"#;
         let result = parse_report(xml).unwrap();
         match result {
-            ReportRequest::CalendarMultiget { props, hrefs } => {
+            ReportRequest::CalendarMultiget { props, hrefs, .. } => {
                 assert_eq!(props.len(), 2);
                 assert_eq!(hrefs.len(), 2);
                 assert!(hrefs[0].contains("event1.ics"));
@@ -340,8 +545,16 @@ mod tests {
         </C:calendar-query>"#;
         let result = parse_report(xml).unwrap();
         match result {
-            ReportRequest::CalendarQuery { props, time_range } => {
+            ReportRequest::CalendarQuery {
+                props,
+                component,
+                time_range,
+                expand,
+                ..
+            } => {
                 assert_eq!(props.len(), 2);
+                assert_eq!(component.as_deref(), Some("VEVENT"));
+                assert!(expand.is_none());
                 let (start, end) = time_range.unwrap();
                 assert_eq!(start, "20260301T000000Z");
                 assert_eq!(end, "20260401T000000Z");
@@ -350,6 +563,167 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_calendar_query_with_expand() {
+        // As sent by Apple Calendar
+        let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+        <B:calendar-query xmlns:B="urn:ietf:params:xml:ns:caldav">
+          <A:prop xmlns:A="DAV:">
+            <A:getetag/>
+            <B:calendar-data>
+              <B:expand start="20260309T000000Z" end="20260330T000000Z"/>
+            </B:calendar-data>
+          </A:prop>
+          <B:filter>
+            <B:comp-filter name="VCALENDAR">
+              <B:comp-filter name="VEVENT">
+                <B:time-range start="20260301T000000Z" end="20260401T000000Z"/>
+              </B:comp-filter>
+            </B:comp-filter>
+          </B:filter>
+        </B:calendar-query>"#;
+        match parse_report(xml).unwrap() {
+            ReportRequest::CalendarQuery {
+                props,
+                time_range,
+                expand,
+                ..
+            } => {
+                // expand qualifies calendar-data; it is not a property itself
+                let names: Vec<&str> = props.iter().map(|p| p.local_name.as_str()).collect();
+                assert_eq!(names, ["getetag", "calendar-data"]);
+                assert_eq!(
+                    time_range,
+                    Some((
+                        "20260301T000000Z".to_string(),
+                        "20260401T000000Z".to_string()
+                    ))
+                );
+                assert_eq!(
+                    expand,
+                    Some((
+                        "20260309T000000Z".to_string(),
+                        "20260330T000000Z".to_string()
+                    ))
+                );
+            }
+            _ => panic!("Expected CalendarQuery"),
+        }
+    }
+
+    #[test]
+    fn test_parse_calendar_data_comp_selection() {
+        let xml = br#"<?xml version="1.0" encoding="utf-8"?>
+        <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+            <D:prop>
+                <C:calendar-data>
+                    <C:comp name="VCALENDAR">
+                        <C:allprop/>
+                        <C:comp name="VEVENT">
+                            <C:prop name="SUMMARY"/>
+                            <C:prop name="location"></C:prop>
+                        </C:comp>
+                        <C:comp name="VTIMEZONE"/>
+                    </C:comp>
+                </C:calendar-data>
+                <D:getetag/>
+            </D:prop>
+            <C:filter>
+                <C:comp-filter name="VCALENDAR"/>
+            </C:filter>
+        </C:calendar-query>"#;
+        match parse_report(xml).unwrap() {
+            ReportRequest::CalendarQuery {
+                props,
+                data_selection,
+                ..
+            } => {
+                // comp and prop qualify calendar-data; they are not properties
+                let names: Vec<&str> = props.iter().map(|p| p.local_name.as_str()).collect();
+                assert_eq!(names, ["calendar-data", "getetag"]);
+                assert_eq!(
+                    data_selection,
+                    Some(CompSelection {
+                        name: "VCALENDAR".to_string(),
+                        all_props: true,
+                        comps: vec![
+                            CompSelection {
+                                name: "VEVENT".to_string(),
+                                props: vec!["SUMMARY".to_string(), "LOCATION".to_string()],
+                                ..Default::default()
+                            },
+                            CompSelection {
+                                name: "VTIMEZONE".to_string(),
+                                ..Default::default()
+                            },
+                        ],
+                        ..Default::default()
+                    })
+                );
+            }
+            _ => panic!("Expected CalendarQuery"),
+        }
+    }
+
+    #[test]
+    fn test_parse_calendar_query_with_prop_filters() {
+        let xml = br#"<?xml version="1.0" encoding="utf-8"?>
+        <C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+            <D:prop><D:getetag/></D:prop>
+            <C:filter>
+                <C:comp-filter name="VCALENDAR">
+                    <C:comp-filter name="VEVENT">
+                        <C:prop-filter name="summary">
+                            <C:text-match collation="i;ascii-casemap">standup</C:text-match>
+                        </C:prop-filter>
+                        <C:prop-filter name="LOCATION">
+                            <C:text-match negate-condition="yes">Berlin</C:text-match>
+                        </C:prop-filter>
+                        <C:prop-filter name="URL">
+                            <C:is-not-defined/>
+                        </C:prop-filter>
+                        <C:prop-filter name="DESCRIPTION"/>
+                    </C:comp-filter>
+                </C:comp-filter>
+            </C:filter>
+        </C:calendar-query>"#;
+        match parse_report(xml).unwrap() {
+            ReportRequest::CalendarQuery {
+                props,
+                prop_filters,
+                ..
+            } => {
+                assert_eq!(props.len(), 1);
+                assert_eq!(
+                    prop_filters,
+                    [
+                        PropFilter {
+                            name: "SUMMARY".to_string(),
+                            text_match: Some("standup".to_string()),
+                            ..Default::default()
+                        },
+                        PropFilter {
+                            name: "LOCATION".to_string(),
+                            text_match: Some("Berlin".to_string()),
+                            negate: true,
+                            ..Default::default()
+                        },
+                        PropFilter {
+                            name: "URL".to_string(),
+                            is_not_defined: true,
+                            ..Default::default()
+                        },
+                        PropFilter {
+                            name: "DESCRIPTION".to_string(),
+                            ..Default::default()
+                        },
+                    ]
+                );
+            }
+            _ => panic!("Expected CalendarQuery"),
+        }
+    }
+
     #[test]
     fn test_parse_sync_collection() {
         let xml = br#"<?xml version="1.0" encoding="utf-8"?>
@@ -369,6 +743,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_principal_property_search() {
+        let xml = br#"<?xml version="1.0" encoding="utf-8"?>
+        <D:principal-property-search xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav" test="anyof">
+            <D:property-search>
+                <D:prop><D:displayname/></D:prop>
+                <D:match>ali</D:match>
+            </D:property-search>
+            <D:property-search>
+                <D:prop><C:calendar-user-address-set/></D:prop>
+                <D:match match-type="starts-with">alice@</D:match>
+            </D:property-search>
+            <D:prop>
+                <D:displayname/>
+                <C:calendar-user-address-set/>
+            </D:prop>
+        </D:principal-property-search>"#;
+        match parse_report(xml).unwrap() {
+            ReportRequest::PrincipalPropertySearch {
+                props,
+                searches,
+                any_of,
+            } => {
+                assert!(any_of);
+                assert_eq!(props.len(), 2);
+                assert_eq!(searches.len(), 2);
+                assert_eq!(searches[0].props[0].local_name, "displayname");
+                assert_eq!(searches[0].match_text, "ali");
+                assert_eq!(
+                    searches[1].props[0].namespace,
+                    "urn:ietf:params:xml:ns:caldav"
+                );
+                assert_eq!(searches[1].match_text, "alice@");
+            }
+            _ => panic!("Expected PrincipalPropertySearch"),
+        }
+    }
+
     /// Apple Calendar uses non-standard namespace prefixes (A=DAV, B=CalDAV, etc.).
     /// Our parser must resolve namespaces from xmlns declarations, not prefix guessing.
     #[test]
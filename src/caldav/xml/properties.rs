@@ -2,7 +2,7 @@ use super::multistatus::{PropContent, PropValue};
 use super::{APPLE_NS, CALDAV_NS, CS_NS, DAV_NS};
 use crate::caldav::HrefContext;
 use crate::caldav::xml::parse::PropfindRequest;
-use crate::db::models::{Calendar, CalendarObject};
+use crate::db::models::{Calendar, CalendarObject, Permission, User};
 
 /// Ensure a sync token is a valid URI (RFC 6578 requirement).
 /// Old tokens without a URI scheme get wrapped with `data:,` prefix.
@@ -126,9 +126,59 @@ pub fn root_props_unauthenticated() -> Vec<PropValue> {
     ]
 }
 
-/// Build the standard set of properties for a calendar-home-set resource.
-pub fn calendar_home_props(username: &str) -> Vec<PropValue> {
+/// Storage used by a user and what remains of their quota, in bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+}
+
+/// The RFC 4331 `quota-used-bytes` and `quota-available-bytes` properties.
+fn quota_props(quota: Quota) -> [PropValue; 2] {
+    [
+        PropValue {
+            name: "quota-used-bytes".to_string(),
+            namespace: DAV_NS.to_string(),
+            value: PropContent::Text(quota.used_bytes.to_string()),
+        },
+        PropValue {
+            name: "quota-available-bytes".to_string(),
+            namespace: DAV_NS.to_string(),
+            value: PropContent::Text(quota.available_bytes.to_string()),
+        },
+    ]
+}
+
+/// Build the properties returned for a user found by a
+/// principal-property-search REPORT.
+pub fn principal_search_props(user: &User) -> Vec<PropValue> {
+    let home = format!("/caldav/users/{}/", user.username);
+    let address = match &user.email {
+        Some(email) => format!("mailto:{email}"),
+        None => home.clone(),
+    };
     vec![
+        PropValue {
+            name: "displayname".to_string(),
+            namespace: DAV_NS.to_string(),
+            value: PropContent::Text(user.username.clone()),
+        },
+        PropValue {
+            name: "calendar-user-address-set".to_string(),
+            namespace: CALDAV_NS.to_string(),
+            value: PropContent::Xml(format!("<D:href>{address}</D:href>")),
+        },
+        PropValue {
+            name: "calendar-home-set".to_string(),
+            namespace: CALDAV_NS.to_string(),
+            value: PropContent::Xml(format!("<D:href>{home}</D:href>")),
+        },
+    ]
+}
+
+/// Build the standard set of properties for a calendar-home-set resource.
+pub fn calendar_home_props(username: &str, quota: Quota) -> Vec<PropValue> {
+    let mut props = vec![
         PropValue {
             name: "resourcetype".to_string(),
             namespace: DAV_NS.to_string(),
@@ -144,7 +194,9 @@ pub fn calendar_home_props(username: &str) -> Vec<PropValue> {
             namespace: DAV_NS.to_string(),
             value: PropContent::Xml(format!("<D:href>/caldav/users/{username}/</D:href>")),
         },
-    ]
+    ];
+    props.extend(quota_props(quota));
+    props
 }
 
 /// Build properties for the Apple-proprietary email home URL
@@ -154,8 +206,13 @@ pub fn calendar_home_props(username: &str) -> Vec<PropValue> {
 /// We set `current-user-principal`, `principal-URL`, and `calendar-home-set`
 /// all to point back to `request_path` (the URL the client is already at),
 /// so it never needs to follow a redirect to find the calendar list.
-pub fn email_home_props(username: &str, email: &str, request_path: &str) -> Vec<PropValue> {
-    vec![
+pub fn email_home_props(
+    username: &str,
+    email: &str,
+    request_path: &str,
+    quota: Quota,
+) -> Vec<PropValue> {
+    let mut props = vec![
         PropValue {
             name: "resourcetype".to_string(),
             namespace: DAV_NS.to_string(),
@@ -258,11 +315,18 @@ pub fn email_home_props(username: &str, email: &str, request_path: &str) -> Vec<
             namespace: DAV_NS.to_string(),
             value: PropContent::Xml(format!("<D:href>{request_path}</D:href>")),
         },
-    ]
+    ];
+    props.extend(quota_props(quota));
+    props
 }
 
 /// Build the properties for a calendar collection.
-pub fn calendar_props(username: &str, calendar: &Calendar) -> Vec<PropValue> {
+pub fn calendar_props(
+    username: &str,
+    calendar: &Calendar,
+    permission: Permission,
+    max_resource_size: usize,
+) -> Vec<PropValue> {
     vec![
         PropValue {
             name: "resourcetype".to_string(),
@@ -284,6 +348,16 @@ pub fn calendar_props(username: &str, calendar: &Calendar) -> Vec<PropValue> {
             namespace: APPLE_NS.to_string(),
             value: PropContent::Text(calendar.color.clone()),
         },
+        PropValue {
+            name: "calendar-order".to_string(),
+            namespace: APPLE_NS.to_string(),
+            value: PropContent::Text(calendar.calendar_order.to_string()),
+        },
+        PropValue {
+            name: "schedule-calendar-transp".to_string(),
+            namespace: CALDAV_NS.to_string(),
+            value: PropContent::Xml(schedule_calendar_transp(calendar)),
+        },
         PropValue {
             name: "calendar-timezone".to_string(),
             namespace: CALDAV_NS.to_string(),
@@ -292,9 +366,12 @@ pub fn calendar_props(username: &str, calendar: &Calendar) -> Vec<PropValue> {
         PropValue {
             name: "supported-calendar-component-set".to_string(),
             namespace: CALDAV_NS.to_string(),
-            value: PropContent::Xml(
-                "<C:comp name=\"VEVENT\"/><C:comp name=\"VTODO\"/>".to_string(),
-            ),
+            value: PropContent::Xml(supported_component_set(calendar)),
+        },
+        PropValue {
+            name: "max-resource-size".to_string(),
+            namespace: CALDAV_NS.to_string(),
+            value: PropContent::Text(max_resource_size.to_string()),
         },
         PropValue {
             name: "getctag".to_string(),
@@ -316,12 +393,7 @@ pub fn calendar_props(username: &str, calendar: &Calendar) -> Vec<PropValue> {
         PropValue {
             name: "current-user-privilege-set".to_string(),
             namespace: DAV_NS.to_string(),
-            value: PropContent::Xml(
-                "<D:privilege><D:read/></D:privilege>\
-                 <D:privilege><D:write/></D:privilege>\
-                 <D:privilege><D:write-content/></D:privilege>"
-                    .to_string(),
-            ),
+            value: PropContent::Xml(privilege_set(permission)),
         },
         PropValue {
             name: "owner".to_string(),
@@ -343,6 +415,38 @@ pub fn calendar_props(username: &str, calendar: &Calendar) -> Vec<PropValue> {
     ]
 }
 
+/// The `schedule-calendar-transp` value for a calendar (RFC 6638 §9.1).
+fn schedule_calendar_transp(calendar: &Calendar) -> String {
+    if calendar.is_transparent() {
+        "<C:transparent/>".to_string()
+    } else {
+        "<C:opaque/>".to_string()
+    }
+}
+
+/// The `supported-calendar-component-set` value for a calendar.
+fn supported_component_set(calendar: &Calendar) -> String {
+    calendar
+        .component_types()
+        .iter()
+        .map(|c| format!("<C:comp name=\"{c}\"/>"))
+        .collect()
+}
+
+/// The `current-user-privilege-set` value for a user holding `permission`
+/// on a calendar. Read-only sharees are offered `read` alone so clients
+/// don't attempt writes that would be refused.
+fn privilege_set(permission: Permission) -> String {
+    if permission.can_write() {
+        "<D:privilege><D:read/></D:privilege>\
+         <D:privilege><D:write/></D:privilege>\
+         <D:privilege><D:write-content/></D:privilege>"
+            .to_string()
+    } else {
+        "<D:privilege><D:read/></D:privilege>".to_string()
+    }
+}
+
 /// Build properties for a calendar object (event/todo).
 pub fn calendar_object_props(
     _username: &str,
@@ -361,6 +465,28 @@ pub fn calendar_object_props(
             namespace: DAV_NS.to_string(),
             value: PropContent::Text("text/calendar; charset=utf-8".to_string()),
         },
+        PropValue {
+            name: "getlastmodified".to_string(),
+            namespace: DAV_NS.to_string(),
+            value: PropContent::Text(
+                object
+                    .updated_at
+                    .and_utc()
+                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                    .to_string(),
+            ),
+        },
+        PropValue {
+            name: "creationdate".to_string(),
+            namespace: DAV_NS.to_string(),
+            value: PropContent::Text(
+                object
+                    .created_at
+                    .and_utc()
+                    .format("%Y-%m-%dT%H:%M:%SZ")
+                    .to_string(),
+            ),
+        },
     ];
 
     if include_data {
@@ -402,7 +528,12 @@ pub fn calendar_object_href_for_context(ctx: &HrefContext, calendar_id: &str, ui
 }
 
 /// Build the properties for a calendar collection with context-aware hrefs.
-pub fn calendar_props_for_context(ctx: &HrefContext, calendar: &Calendar) -> Vec<PropValue> {
+pub fn calendar_props_for_context(
+    ctx: &HrefContext,
+    calendar: &Calendar,
+    permission: Permission,
+    max_resource_size: usize,
+) -> Vec<PropValue> {
     let principal_href = match &ctx.email {
         Some(email) => format!("/calendar/dav/{email}/user/"),
         None => format!("/caldav/users/{}/", ctx.username),
@@ -431,7 +562,7 @@ pub fn calendar_props_for_context(ctx: &HrefContext, calendar: &Calendar) -> Vec
         PropValue {
             name: "calendar-order".to_string(),
             namespace: APPLE_NS.to_string(),
-            value: PropContent::Text("1".to_string()),
+            value: PropContent::Text(calendar.calendar_order.to_string()),
         },
         PropValue {
             name: "calendar-timezone".to_string(),
@@ -441,9 +572,12 @@ pub fn calendar_props_for_context(ctx: &HrefContext, calendar: &Calendar) -> Vec
         PropValue {
             name: "supported-calendar-component-set".to_string(),
             namespace: CALDAV_NS.to_string(),
-            value: PropContent::Xml(
-                "<C:comp name=\"VEVENT\"/><C:comp name=\"VTODO\"/>".to_string(),
-            ),
+            value: PropContent::Xml(supported_component_set(calendar)),
+        },
+        PropValue {
+            name: "max-resource-size".to_string(),
+            namespace: CALDAV_NS.to_string(),
+            value: PropContent::Text(max_resource_size.to_string()),
         },
         PropValue {
             name: "getctag".to_string(),
@@ -463,12 +597,7 @@ pub fn calendar_props_for_context(ctx: &HrefContext, calendar: &Calendar) -> Vec
         PropValue {
             name: "current-user-privilege-set".to_string(),
             namespace: DAV_NS.to_string(),
-            value: PropContent::Xml(
-                "<D:privilege><D:read/></D:privilege>\
-                 <D:privilege><D:write/></D:privilege>\
-                 <D:privilege><D:write-content/></D:privilege>"
-                    .to_string(),
-            ),
+            value: PropContent::Xml(privilege_set(permission)),
         },
         PropValue {
             name: "owner".to_string(),
@@ -489,7 +618,7 @@ pub fn calendar_props_for_context(ctx: &HrefContext, calendar: &Calendar) -> Vec
         PropValue {
             name: "schedule-calendar-transp".to_string(),
             namespace: CALDAV_NS.to_string(),
-            value: PropContent::Xml("<C:opaque/>".to_string()),
+            value: PropContent::Xml(schedule_calendar_transp(calendar)),
         },
         PropValue {
             name: "schedule-default-calendar-URL".to_string(),
@@ -1,19 +1,96 @@
 use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::db::events::EtagMode;
+use crate::db::users::PasswordPolicy;
+use crate::ical::datetime;
+use crate::outbound::OutboundLimits;
+use crate::reminders::ReminderSink;
+
+/// Accepted values for `MCP_TOOL_MODE`.
+pub const TOOL_MODES: &[&str] = &["full", "medium", "simple"];
+
+/// A configuration value that could not be used.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{0}")]
+    Invalid(String),
+}
 
 /// Application configuration loaded from environment variables.
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Address both listeners bind to; `0.0.0.0` accepts connections on
+    /// every interface.
+    pub bind_address: IpAddr,
     pub caldav_port: u16,
     pub mcp_port: u16,
     pub database_url: String,
-    /// MCP tool mode: "full" (12 tools) or "simple" (4 tools for local LLMs).
+    /// Most SQLite connections the pool keeps open at once.
+    pub db_max_connections: u32,
+    /// MCP tool mode: "full" (41 tools), "medium" (38 tools, everything but
+    /// sharing) or "simple" (3 tools for local LLMs).
     pub tool_mode: String,
+    /// Minimum number of characters required for user passwords.
+    pub password_min_length: usize,
+    /// Require passwords to mix lowercase, uppercase and digits.
+    pub password_require_complexity: bool,
+    /// IANA timezones the MCP event tools accept. Empty means any valid zone.
+    pub allowed_timezones: Vec<String>,
+    /// How ETags are assigned to objects written over CalDAV.
+    pub etag_mode: EtagMode,
+    /// Advertise `calendar-auto-schedule` (RFC 6638) in the DAV header.
+    pub scheduling_enabled: bool,
+    /// Advertise `calendar-no-timezone` (RFC 7809) in the DAV header.
+    pub no_timezone_enabled: bool,
+    /// Create users on first MCP use of a signed provisioning token.
+    pub auto_provision: bool,
+    /// HMAC-SHA256 secret that provisioning tokens must be signed with.
+    pub auto_provision_secret: Option<String>,
+    /// Refuse MCP requests that did not arrive over HTTPS.
+    pub require_tls_for_mcp: bool,
+    /// Believe a reverse proxy's `X-Forwarded-Proto` header. Only safe when
+    /// the listeners are reachable through the proxy alone.
+    pub trust_proxy: bool,
+    /// PEM certificate chain to serve HTTPS with. Set together with
+    /// `tls_key_path`; when both are unset the listeners speak plain HTTP.
+    pub tls_cert_path: Option<String>,
+    /// PEM private key for `tls_cert_path`.
+    pub tls_key_path: Option<String>,
+    /// Origins allowed to make CORS requests to the discovery routes
+    /// (`/.well-known/caldav` and `/`). `*` allows any origin; empty disables CORS.
+    pub discovery_cors_origins: Vec<String>,
+    /// Reject `tools/call` arguments that the tool's schema does not declare.
+    pub strict_tool_args: bool,
+    /// Run the background task that delivers VALARM reminders.
+    pub reminders_enabled: bool,
+    /// Where reminders are delivered: the log, or a webhook URL.
+    pub reminder_sink: ReminderSink,
+    /// Seconds between reminder scans; each scan looks this far ahead.
+    pub reminder_interval_secs: u64,
+    /// Seconds an outbound request (e.g. a reminder webhook) may take in total.
+    pub outbound_timeout_secs: u64,
+    /// Largest response body accepted from an outbound request, in bytes.
+    pub outbound_max_bytes: usize,
+    /// Earliest year accepted for event start and end dates.
+    pub min_event_year: i32,
+    /// Latest year accepted for event start and end dates.
+    pub max_event_year: i32,
+    /// Storage each user may use for calendar data, in bytes. Advertised to
+    /// CalDAV clients as `quota-available-bytes`.
+    pub user_quota_bytes: u64,
+    /// Largest calendar object a PUT may store, in bytes. Advertised to
+    /// CalDAV clients as `max-resource-size`.
+    pub max_resource_size: usize,
 }
 
 impl Config {
     /// Load configuration from environment variables with sensible defaults.
-    pub fn from_env() -> Result<Self, env::VarError> {
+    pub fn from_env() -> Result<Self, ConfigError> {
         Ok(Self {
+            bind_address: parse_bind_address(
+                &env::var("BIND_ADDRESS").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            )?,
             caldav_port: env::var("CALDAV_PORT")
                 .unwrap_or_else(|_| "5232".to_string())
                 .parse()
@@ -24,9 +101,185 @@ impl Config {
                 .expect("MCP_PORT must be a valid port number"),
             database_url: env::var("DATABASE_URL")
                 .unwrap_or_else(|_| "sqlite:data/caldav.db?mode=rwc".to_string()),
-            tool_mode: env::var("MCP_TOOL_MODE").unwrap_or_else(|_| "full".to_string()),
+            db_max_connections: env::var("DB_MAX_CONNECTIONS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+                .expect("DB_MAX_CONNECTIONS must be a positive integer"),
+            tool_mode: Some(env::var("MCP_TOOL_MODE").unwrap_or_else(|_| "full".to_string()))
+                .filter(|mode| TOOL_MODES.contains(&mode.as_str()))
+                .expect("MCP_TOOL_MODE must be 'full', 'medium' or 'simple'"),
+            password_min_length: env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .expect("PASSWORD_MIN_LENGTH must be a non-negative integer"),
+            password_require_complexity: env_flag("PASSWORD_REQUIRE_COMPLEXITY"),
+            allowed_timezones: env::var("ALLOWED_TIMEZONES")
+                .map(|v| parse_list(&v))
+                .unwrap_or_default(),
+            etag_mode: env::var("ETAG_MODE")
+                .unwrap_or_else(|_| "random".to_string())
+                .parse()
+                .expect("ETAG_MODE must be 'random' or 'content'"),
+            scheduling_enabled: env_flag("CALDAV_SCHEDULING"),
+            no_timezone_enabled: env_flag("CALDAV_NO_TIMEZONE"),
+            auto_provision: env_flag("AUTO_PROVISION"),
+            auto_provision_secret: env::var("AUTO_PROVISION_SECRET")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            require_tls_for_mcp: env_flag("REQUIRE_TLS_FOR_MCP"),
+            trust_proxy: env_flag("TRUST_PROXY"),
+            tls_cert_path: env::var("TLS_CERT_PATH").ok().filter(|s| !s.is_empty()),
+            tls_key_path: env::var("TLS_KEY_PATH").ok().filter(|s| !s.is_empty()),
+            discovery_cors_origins: env::var("DISCOVERY_CORS_ORIGINS")
+                .map(|v| parse_list(&v))
+                .unwrap_or_default(),
+            strict_tool_args: env_flag("STRICT_TOOL_ARGS"),
+            reminders_enabled: env_flag("REMINDERS_ENABLED"),
+            reminder_sink: env::var("REMINDER_SINK")
+                .unwrap_or_else(|_| "log".to_string())
+                .parse()
+                .expect("REMINDER_SINK must be 'log' or an http:// or https:// URL"),
+            reminder_interval_secs: env::var("REMINDER_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("REMINDER_INTERVAL_SECS must be a positive integer"),
+            outbound_timeout_secs: env::var("OUTBOUND_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .expect("OUTBOUND_TIMEOUT_SECS must be a positive integer"),
+            outbound_max_bytes: env::var("OUTBOUND_MAX_BYTES")
+                .unwrap_or_else(|_| "1048576".to_string())
+                .parse()
+                .expect("OUTBOUND_MAX_BYTES must be a positive integer"),
+            min_event_year: env::var("MIN_EVENT_YEAR")
+                .unwrap_or_else(|_| "1970".to_string())
+                .parse()
+                .expect("MIN_EVENT_YEAR must be an integer"),
+            max_event_year: env::var("MAX_EVENT_YEAR")
+                .unwrap_or_else(|_| "2200".to_string())
+                .parse()
+                .expect("MAX_EVENT_YEAR must be an integer"),
+            user_quota_bytes: env::var("USER_QUOTA_BYTES")
+                .unwrap_or_else(|_| "104857600".to_string())
+                .parse()
+                .expect("USER_QUOTA_BYTES must be a non-negative integer"),
+            max_resource_size: env::var("MAX_RESOURCE_SIZE")
+                .unwrap_or_else(|_| "1048576".to_string())
+                .parse()
+                .expect("MAX_RESOURCE_SIZE must be a positive integer"),
         })
     }
+
+    /// The value of the `DAV` compliance header for the enabled features.
+    pub fn dav_header(&self) -> String {
+        let mut classes = vec!["1", "2", "3", "calendar-access"];
+        if self.scheduling_enabled {
+            classes.push("calendar-auto-schedule");
+        }
+        if self.no_timezone_enabled {
+            classes.push("calendar-no-timezone");
+        }
+        classes.join(", ")
+    }
+
+    /// Reject a date or date-time whose year is outside
+    /// `MIN_EVENT_YEAR..=MAX_EVENT_YEAR`, which usually means a mistyped year
+    /// such as `0226` or `22026`.
+    pub fn check_event_year(&self, value: &str) -> Result<(), String> {
+        match datetime::year_of(value) {
+            Some(year) if year < self.min_event_year || year > self.max_event_year => Err(format!(
+                "Year {year} in '{value}' is outside the allowed range {}-{}",
+                self.min_event_year, self.max_event_year
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The timeout and size limit applied to outbound HTTP requests.
+    pub fn outbound_limits(&self) -> OutboundLimits {
+        OutboundLimits {
+            timeout: std::time::Duration::from_secs(self.outbound_timeout_secs.max(1)),
+            max_response_bytes: self.outbound_max_bytes,
+        }
+    }
+
+    /// The password policy applied when creating users or resetting passwords.
+    pub fn password_policy(&self) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: self.password_min_length,
+            require_complexity: self.password_require_complexity,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            caldav_port: 5232,
+            mcp_port: 5233,
+            database_url: "sqlite:data/caldav.db?mode=rwc".to_string(),
+            db_max_connections: 5,
+            tool_mode: "full".to_string(),
+            password_min_length: 8,
+            password_require_complexity: false,
+            allowed_timezones: Vec::new(),
+            etag_mode: EtagMode::Random,
+            scheduling_enabled: false,
+            no_timezone_enabled: false,
+            auto_provision: false,
+            auto_provision_secret: None,
+            require_tls_for_mcp: false,
+            trust_proxy: false,
+            tls_cert_path: None,
+            tls_key_path: None,
+            discovery_cors_origins: Vec::new(),
+            strict_tool_args: false,
+            reminders_enabled: false,
+            reminder_sink: ReminderSink::Log,
+            reminder_interval_secs: 60,
+            outbound_timeout_secs: 10,
+            outbound_max_bytes: 1024 * 1024,
+            min_event_year: 1970,
+            max_event_year: 2200,
+            user_quota_bytes: 100 * 1024 * 1024,
+            max_resource_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Split a comma-separated value into trimmed, non-empty entries.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse `BIND_ADDRESS`: an IPv4 or IPv6 address, the latter optionally in
+/// brackets.
+fn parse_bind_address(value: &str) -> Result<IpAddr, ConfigError> {
+    let value = value.trim();
+    let bare = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    bare.parse().map_err(|_| {
+        ConfigError::Invalid(format!(
+            "BIND_ADDRESS must be an IP address such as 0.0.0.0, 127.0.0.1 or ::1, got '{value}'"
+        ))
+    })
+}
+
+/// Read a boolean flag from the environment ("1", "true", "yes" are truthy).
+fn env_flag(name: &str) -> bool {
+    env::var(name)
+        .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
@@ -43,7 +296,7 @@ mod tests {
         assert!(config.mcp_port > 0);
         assert!(!config.database_url.is_empty());
         assert!(
-            config.tool_mode == "full" || config.tool_mode == "simple",
+            TOOL_MODES.contains(&config.tool_mode.as_str()),
             "tool_mode should default to 'full'"
         );
     }
@@ -56,4 +309,72 @@ mod tests {
         let config = Config::from_env().unwrap();
         assert_eq!(config.tool_mode, "full");
     }
+
+    #[test]
+    fn test_password_policy_from_config() {
+        let mut config = Config::from_env().unwrap();
+        config.password_min_length = 12;
+        config.password_require_complexity = true;
+        let policy = config.password_policy();
+        assert_eq!(policy.min_length, 12);
+        assert!(policy.require_complexity);
+    }
+
+    #[test]
+    fn test_dav_header_reflects_features() {
+        let config = Config::default();
+        assert_eq!(config.dav_header(), "1, 2, 3, calendar-access");
+
+        let config = Config {
+            scheduling_enabled: true,
+            no_timezone_enabled: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            config.dav_header(),
+            "1, 2, 3, calendar-access, calendar-auto-schedule, calendar-no-timezone"
+        );
+    }
+
+    #[test]
+    fn test_check_event_year_bounds() {
+        let config = Config::default();
+        assert!(config.check_event_year("20260301T090000Z").is_ok());
+        assert!(config.check_event_year("21990301").is_ok());
+        let err = config.check_event_year("02260301T090000").unwrap_err();
+        assert!(err.contains("Year 226"), "got: {err}");
+        assert!(config.check_event_year("220260301T090000").is_err());
+    }
+
+    #[test]
+    fn test_parse_list_trims_and_skips_empty() {
+        assert_eq!(
+            parse_list(" America/New_York, Europe/London,,UTC "),
+            vec!["America/New_York", "Europe/London", "UTC"]
+        );
+        assert!(parse_list("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_bind_address() {
+        assert_eq!(
+            parse_bind_address("127.0.0.1"),
+            Ok(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_bind_address("::1"),
+            Ok(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_bind_address("[::]"),
+            Ok(IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED))
+        );
+        let err = parse_bind_address("localhost").unwrap_err().to_string();
+        assert!(err.contains("BIND_ADDRESS"), "got: {err}");
+        assert!(err.contains("'localhost'"), "got: {err}");
+        assert_eq!(
+            Config::default().bind_address,
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        );
+    }
 }
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+use super::models::CalendarObject;
+use crate::error::AppResult;
+
+/// Objects flagged as containing a VALARM, read through
+/// `idx_calendar_objects_has_alarm`.
+const OBJECTS_WITH_ALARMS_SQL: &str = "SELECT * FROM calendar_objects WHERE has_alarm = 1";
+
+/// Every object whose iCalendar data contains at least one VALARM.
+pub async fn objects_with_alarms(pool: &SqlitePool) -> AppResult<Vec<CalendarObject>> {
+    let objs = sqlx::query_as::<_, CalendarObject>(OBJECTS_WITH_ALARMS_SQL)
+        .fetch_all(pool)
+        .await?;
+    Ok(objs)
+}
+
+/// Whether an alarm trigger has already been delivered.
+pub async fn is_fired(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    object_uid: &str,
+    alarm_key: &str,
+    trigger_at: DateTime<Utc>,
+) -> AppResult<bool> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT 1 FROM fired_alarms
+         WHERE calendar_id = ? AND object_uid = ? AND alarm_key = ? AND trigger_at = ?",
+    )
+    .bind(calendar_id)
+    .bind(object_uid)
+    .bind(alarm_key)
+    .bind(trigger_at.to_rfc3339())
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
+/// Record an alarm trigger as delivered. Returns `false` if it already was.
+pub async fn mark_fired(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    object_uid: &str,
+    alarm_key: &str,
+    trigger_at: DateTime<Utc>,
+) -> AppResult<bool> {
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO fired_alarms (calendar_id, object_uid, alarm_key, trigger_at)
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(calendar_id)
+    .bind(object_uid)
+    .bind(alarm_key)
+    .bind(trigger_at.to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget delivered triggers from before `before`. Returns how many were
+/// removed.
+pub async fn prune_fired(pool: &SqlitePool, before: DateTime<Utc>) -> AppResult<u64> {
+    let result = sqlx::query("DELETE FROM fired_alarms WHERE trigger_at < ?")
+        .bind(before.to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[tokio::test]
+    async fn test_objects_with_alarms_uses_index() {
+        let pool = db::test_pool().await;
+        let plan: Vec<(i64, i64, i64, String)> =
+            sqlx::query_as(&format!("EXPLAIN QUERY PLAN {OBJECTS_WITH_ALARMS_SQL}"))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        let detail = plan
+            .into_iter()
+            .map(|(_, _, _, detail)| detail)
+            .collect::<Vec<_>>()
+            .join("; ");
+        assert!(
+            detail.contains("idx_calendar_objects_has_alarm"),
+            "got: {detail}"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use sqlx::SqlitePool;
+
+use super::models::AvailabilityWindow;
+use crate::error::AppResult;
+
+/// A user's working-hours template.
+#[derive(Debug, Clone)]
+pub struct Availability {
+    pub timezone: String,
+    pub windows: Vec<AvailabilityWindow>,
+}
+
+/// Replace a user's working-hours template. An empty window list removes it.
+pub async fn set_availability(
+    pool: &SqlitePool,
+    user_id: &str,
+    timezone: &str,
+    windows: &[AvailabilityWindow],
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM availability_windows WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM user_availability WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if !windows.is_empty() {
+        sqlx::query("INSERT INTO user_availability (user_id, timezone) VALUES (?, ?)")
+            .bind(user_id)
+            .bind(timezone)
+            .execute(&mut *tx)
+            .await?;
+
+        for window in windows {
+            sqlx::query(
+                "INSERT INTO availability_windows (user_id, weekday, start_time, end_time)
+                 VALUES (?, ?, ?, ?)",
+            )
+            .bind(user_id)
+            .bind(window.weekday)
+            .bind(&window.start_time)
+            .bind(&window.end_time)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Get a user's working-hours template, if one has been set.
+pub async fn get_availability(pool: &SqlitePool, user_id: &str) -> AppResult<Option<Availability>> {
+    let timezone: Option<(String,)> =
+        sqlx::query_as("SELECT timezone FROM user_availability WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    let Some((timezone,)) = timezone else {
+        return Ok(None);
+    };
+
+    let windows = sqlx::query_as::<_, AvailabilityWindow>(
+        "SELECT weekday, start_time, end_time FROM availability_windows
+         WHERE user_id = ? ORDER BY weekday, start_time",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(Availability { timezone, windows }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::users::{self, PasswordPolicy};
+
+    fn window(weekday: i64, start: &str, end: &str) -> AvailabilityWindow {
+        AvailabilityWindow {
+            weekday,
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_get_and_clear_availability() {
+        let pool = db::test_pool().await;
+        let user = users::create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        assert!(get_availability(&pool, &user.id).await.unwrap().is_none());
+
+        let windows = vec![window(0, "09:00", "12:00"), window(0, "13:00", "17:00")];
+        set_availability(&pool, &user.id, "Europe/London", &windows)
+            .await
+            .unwrap();
+        let availability = get_availability(&pool, &user.id).await.unwrap().unwrap();
+        assert_eq!(availability.timezone, "Europe/London");
+        assert_eq!(availability.windows, windows);
+
+        // Setting again replaces the previous template
+        set_availability(&pool, &user.id, "UTC", &[window(4, "10:00", "14:00")])
+            .await
+            .unwrap();
+        let availability = get_availability(&pool, &user.id).await.unwrap().unwrap();
+        assert_eq!(availability.timezone, "UTC");
+        assert_eq!(availability.windows, vec![window(4, "10:00", "14:00")]);
+
+        set_availability(&pool, &user.id, "UTC", &[]).await.unwrap();
+        assert!(get_availability(&pool, &user.id).await.unwrap().is_none());
+    }
+}
@@ -1,7 +1,9 @@
-use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
 use uuid::Uuid;
 
-use super::models::Calendar;
+use super::models::{Calendar, Permission};
 use crate::error::{AppError, AppResult};
 
 /// Generate a new sync token as a valid URI (RFC 6578 requires sync-tokens be URIs).
@@ -19,12 +21,14 @@ pub async fn create_calendar(
     timezone: &str,
 ) -> AppResult<Calendar> {
     let id = Uuid::now_v7().to_string();
-    create_calendar_with_id(pool, &id, owner_id, name, description, color, timezone).await
+    let mut conn = pool.acquire().await?;
+    create_calendar_in(&mut conn, &id, owner_id, name, description, color, timezone).await
 }
 
-/// Create a new calendar with a specific ID. Returns the created calendar.
-pub async fn create_calendar_with_id(
-    pool: &SqlitePool,
+/// Create a new calendar with a specific ID on an open connection, so the
+/// insert can be part of a caller's transaction. Returns the created calendar.
+pub async fn create_calendar_in(
+    conn: &mut SqliteConnection,
     id: &str,
     owner_id: &str,
     name: &str,
@@ -46,19 +50,22 @@ pub async fn create_calendar_with_id(
     .bind(timezone)
     .bind(&sync_token)
     .bind(&sync_token)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
-    get_calendar_by_id(pool, id)
+    get_calendar_by_id(&mut *conn, id)
         .await?
         .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Calendar created but not found")))
 }
 
 /// Get a calendar by its ID.
-pub async fn get_calendar_by_id(pool: &SqlitePool, id: &str) -> AppResult<Option<Calendar>> {
+pub async fn get_calendar_by_id(
+    executor: impl SqliteExecutor<'_>,
+    id: &str,
+) -> AppResult<Option<Calendar>> {
     let cal = sqlx::query_as::<_, Calendar>("SELECT * FROM calendars WHERE id = ?")
         .bind(id)
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
     Ok(cal)
 }
@@ -85,7 +92,7 @@ pub async fn list_calendars_for_user(pool: &SqlitePool, user_id: &str) -> AppRes
          SELECT c.* FROM calendars c
          INNER JOIN calendar_shares cs ON cs.calendar_id = c.id
          WHERE cs.user_id = ?
-         ORDER BY name",
+         ORDER BY calendar_order, name",
     )
     .bind(user_id)
     .bind(user_id)
@@ -94,6 +101,69 @@ pub async fn list_calendars_for_user(pool: &SqlitePool, user_id: &str) -> AppRes
     Ok(cals)
 }
 
+/// A calendar row joined with the user's share of it, if any.
+#[derive(sqlx::FromRow)]
+struct CalendarAccess {
+    #[sqlx(flatten)]
+    calendar: Calendar,
+    share_permission: Option<String>,
+}
+
+/// Like [`list_calendars_for_user`], paired with the user's permission on
+/// each calendar, in a single query. Owned calendars are read-write; a share
+/// with an unknown permission is treated as read-only.
+pub async fn list_calendars_with_permission_for_user(
+    pool: &SqlitePool,
+    user_id: &str,
+) -> AppResult<Vec<(Calendar, Permission)>> {
+    let rows = sqlx::query_as::<_, CalendarAccess>(
+        "SELECT c.*, cs.permission AS share_permission FROM calendars c
+         LEFT JOIN calendar_shares cs ON cs.calendar_id = c.id AND cs.user_id = ?
+         WHERE c.owner_id = ? OR cs.user_id IS NOT NULL
+         ORDER BY c.calendar_order, c.name",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let permission = if row.calendar.owner_id == user_id {
+                Permission::ReadWrite
+            } else {
+                row.share_permission
+                    .as_deref()
+                    .and_then(Permission::from_str_value)
+                    .unwrap_or(Permission::Read)
+            };
+            (row.calendar, permission)
+        })
+        .collect())
+}
+
+/// Count the objects in every calendar accessible to a user with a single
+/// grouped query. Calendars without objects are absent from the result.
+pub async fn object_counts_for_user(
+    pool: &SqlitePool,
+    user_id: &str,
+) -> AppResult<HashMap<String, i64>> {
+    let counts = sqlx::query_as::<_, (String, i64)>(
+        "SELECT calendar_id, COUNT(*) FROM calendar_objects
+         WHERE calendar_id IN (
+             SELECT id FROM calendars WHERE owner_id = ?
+             UNION
+             SELECT calendar_id FROM calendar_shares WHERE user_id = ?
+         )
+         GROUP BY calendar_id",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(counts.into_iter().collect())
+}
+
 /// Update a calendar's properties. Returns the updated calendar.
 pub async fn update_calendar(
     pool: &SqlitePool,
@@ -129,21 +199,118 @@ pub async fn update_calendar(
         .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Calendar updated but not found")))
 }
 
-/// Delete a calendar and all its objects (cascade).
+/// Set a calendar's sort position (`calendar-order`). Returns the updated
+/// calendar.
+pub async fn set_calendar_order(pool: &SqlitePool, id: &str, order: i64) -> AppResult<Calendar> {
+    let result = sqlx::query(
+        "UPDATE calendars SET calendar_order = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(order)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Calendar {id} not found")));
+    }
+    get_calendar_by_id(pool, id)
+        .await?
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Calendar updated but not found")))
+}
+
+/// Values of a calendar's `transp` column.
+pub const TRANSP_VALUES: &[&str] = &["opaque", "transparent"];
+
+/// Set whether a calendar's events count as busy time
+/// (`schedule-calendar-transp`). `transp` must be one of [`TRANSP_VALUES`].
+/// Returns the updated calendar.
+pub async fn set_calendar_transp(pool: &SqlitePool, id: &str, transp: &str) -> AppResult<Calendar> {
+    let result =
+        sqlx::query("UPDATE calendars SET transp = ?, updated_at = datetime('now') WHERE id = ?")
+            .bind(transp)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Calendar {id} not found")));
+    }
+    get_calendar_by_id(pool, id)
+        .await?
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Calendar updated but not found")))
+}
+
+/// Component types a calendar may be restricted to.
+pub const COMPONENT_TYPES: &[&str] = &["VEVENT", "VTODO", "VJOURNAL"];
+
+/// Replace the component types a calendar accepts for new objects.
+/// Existing objects are left untouched. Returns the updated calendar.
+pub async fn set_component_types(
+    pool: &SqlitePool,
+    id: &str,
+    component_types: &[&str],
+) -> AppResult<Calendar> {
+    let mut conn = pool.acquire().await?;
+    set_component_types_in(&mut conn, id, component_types).await
+}
+
+/// [`set_component_types`] on an open connection, so the update can be part
+/// of a caller's transaction.
+pub async fn set_component_types_in(
+    conn: &mut SqliteConnection,
+    id: &str,
+    component_types: &[&str],
+) -> AppResult<Calendar> {
+    let result = sqlx::query(
+        "UPDATE calendars SET component_types = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(component_types.join(","))
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("Calendar {id} not found")));
+    }
+    get_calendar_by_id(&mut *conn, id)
+        .await?
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Calendar updated but not found")))
+}
+
+/// Tables whose rows belong to a calendar, emptied before the calendar row.
+pub(super) const CALENDAR_DEPENDENTS: &[&str] = &[
+    "calendar_objects",
+    "calendar_shares",
+    "sync_changes",
+    "deleted_objects",
+    "fired_alarms",
+];
+
+/// Delete a calendar and every row that belongs to it, in one transaction.
+/// The schema also cascades these deletes, but a connection opened without
+/// `PRAGMA foreign_keys` would otherwise leave orphans behind.
 pub async fn delete_calendar(pool: &SqlitePool, id: &str) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    for table in CALENDAR_DEPENDENTS {
+        sqlx::query(&format!("DELETE FROM {table} WHERE calendar_id = ?"))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
     let result = sqlx::query("DELETE FROM calendars WHERE id = ?")
         .bind(id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound(format!("Calendar {id} not found")));
     }
+    tx.commit().await?;
     Ok(())
 }
 
 /// Bump the ctag and sync_token for a calendar (called after any object mutation).
-pub async fn bump_ctag(pool: &SqlitePool, calendar_id: &str) -> AppResult<String> {
+pub async fn bump_ctag(executor: impl SqliteExecutor<'_>, calendar_id: &str) -> AppResult<String> {
     let new_token = new_sync_token();
 
     sqlx::query(
@@ -153,7 +320,7 @@ pub async fn bump_ctag(pool: &SqlitePool, calendar_id: &str) -> AppResult<String
     .bind(&new_token)
     .bind(&new_token)
     .bind(calendar_id)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(new_token)
@@ -163,11 +330,11 @@ pub async fn bump_ctag(pool: &SqlitePool, calendar_id: &str) -> AppResult<String
 mod tests {
     use super::*;
     use crate::db;
-    use crate::db::users;
+    use crate::db::users::{self, PasswordPolicy};
 
     async fn setup() -> (SqlitePool, String) {
         let pool = db::test_pool().await;
-        let user = users::create_user(&pool, "alice", None, "pass")
+        let user = users::create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
             .await
             .unwrap();
         (pool, user.id)
@@ -209,6 +376,45 @@ mod tests {
         assert_eq!(cals[1].name, "Work");
     }
 
+    #[tokio::test]
+    async fn test_list_calendars_with_permission_for_user() {
+        let (pool, alice_id) = setup().await;
+        let bob = users::create_user(&pool, "bob", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let own = create_calendar(&pool, &alice_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let read = create_calendar(&pool, &bob.id, "Family", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let write = create_calendar(&pool, &bob.id, "Team", "", "#0000FF", "UTC")
+            .await
+            .unwrap();
+        create_calendar(&pool, &bob.id, "Private", "", "#000000", "UTC")
+            .await
+            .unwrap();
+        db::shares::share_calendar(&pool, &read.id, &alice_id, Permission::Read)
+            .await
+            .unwrap();
+        db::shares::share_calendar(&pool, &write.id, &alice_id, Permission::ReadWrite)
+            .await
+            .unwrap();
+
+        let cals = list_calendars_with_permission_for_user(&pool, &alice_id)
+            .await
+            .unwrap();
+        let found: Vec<_> = cals.iter().map(|(c, p)| (c.id.as_str(), *p)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (read.id.as_str(), Permission::Read),
+                (write.id.as_str(), Permission::ReadWrite),
+                (own.id.as_str(), Permission::ReadWrite),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_update_calendar() {
         let (pool, user_id) = setup().await;
@@ -240,6 +446,53 @@ mod tests {
         assert!(fetched.is_none());
     }
 
+    #[tokio::test]
+    async fn test_delete_calendar_removes_dependent_rows() {
+        use crate::db::events::{self, ObjectFields};
+        use crate::db::models::Permission;
+
+        let (pool, user_id) = setup().await;
+        let bob = users::create_user(&pool, "bob", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let cal = create_calendar(&pool, &user_id, "Temp", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let fields = || ObjectFields {
+            component_type: "VEVENT",
+            dtstart: Some("20260301T090000Z"),
+            dtend: Some("20260301T100000Z"),
+            summary: Some("Meeting"),
+        };
+        let ical = "BEGIN:VCALENDAR\r\nEND:VCALENDAR";
+        events::upsert_object(&pool, &cal.id, "keep", ical, fields())
+            .await
+            .unwrap();
+        events::upsert_object(&pool, &cal.id, "gone", ical, fields())
+            .await
+            .unwrap();
+        events::delete_object(&pool, &cal.id, "gone").await.unwrap();
+        crate::db::shares::share_calendar(&pool, &cal.id, &bob.id, Permission::Read)
+            .await
+            .unwrap();
+        crate::db::alarms::mark_fired(&pool, &cal.id, "keep", "alarm-1", chrono::Utc::now())
+            .await
+            .unwrap();
+
+        delete_calendar(&pool, &cal.id).await.unwrap();
+
+        for table in CALENDAR_DEPENDENTS {
+            let count: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {table} WHERE calendar_id = ?"
+            ))
+            .bind(&cal.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(count, 0, "{table} still has rows for the calendar");
+        }
+    }
+
     #[tokio::test]
     async fn test_delete_nonexistent_calendar() {
         let (pool, _) = setup().await;
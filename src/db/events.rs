@@ -1,8 +1,13 @@
-use sqlx::SqlitePool;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
 use uuid::Uuid;
 
 use super::models::{CalendarObject, SyncChange};
 use crate::error::{AppError, AppResult};
+use crate::ical::{datetime, parser};
 
 /// Extracted iCalendar fields stored alongside the raw `ical_data`.
 pub struct ObjectFields<'a> {
@@ -12,18 +17,104 @@ pub struct ObjectFields<'a> {
     pub summary: Option<&'a str>,
 }
 
+/// How ETags are assigned when objects are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EtagMode {
+    /// A fresh random UUID on every write.
+    #[default]
+    Random,
+    /// A hash of the normalized iCalendar data, so re-writing identical
+    /// content keeps the same ETag.
+    Content,
+}
+
+impl FromStr for EtagMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "random" => Ok(Self::Random),
+            "content" => Ok(Self::Content),
+            _ => Err(format!("unknown etag mode: {s}")),
+        }
+    }
+}
+
+impl EtagMode {
+    /// The ETag to assign to `ical_data` under this mode.
+    fn etag_for(self, ical_data: &str) -> String {
+        match self {
+            Self::Random => new_etag(),
+            Self::Content => content_etag(ical_data),
+        }
+    }
+}
+
 /// Generate a new ETag value.
 fn new_etag() -> String {
     format!("\"{}\"", Uuid::new_v4())
 }
 
-/// Create or update a calendar object. Returns the object and whether it was created (vs updated).
+/// ETag derived from the iCalendar data. Line endings and blank lines are
+/// normalized first so the same content sent with LF or CRLF hashes equally.
+fn content_etag(ical_data: &str) -> String {
+    let mut hasher = Sha256::new();
+    for line in ical_data
+        .lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.is_empty())
+    {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\r\n");
+    }
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("\"{hex}\"")
+}
+
+/// [`upsert_object_with_etag_mode`] with random ETags, for tests. Server code
+/// passes the configured `ETAG_MODE`.
+#[cfg(test)]
 pub async fn upsert_object(
     pool: &SqlitePool,
     calendar_id: &str,
     uid: &str,
     ical_data: &str,
     fields: ObjectFields<'_>,
+) -> AppResult<(CalendarObject, bool)> {
+    upsert_object_with_etag_mode(pool, calendar_id, uid, ical_data, fields, EtagMode::Random).await
+}
+
+/// Create or update a calendar object, assigning the ETag according to
+/// `etag_mode`. Returns the object and whether it was created (vs updated).
+/// When the computed ETag equals the stored one the content is unchanged, so
+/// the write (and the ctag/sync-token bump) is skipped.
+pub async fn upsert_object_with_etag_mode(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    uid: &str,
+    ical_data: &str,
+    fields: ObjectFields<'_>,
+    etag_mode: EtagMode,
+) -> AppResult<(CalendarObject, bool)> {
+    let mut tx = pool.begin().await?;
+    let result = upsert_object_in(&mut tx, calendar_id, uid, ical_data, fields, etag_mode).await?;
+    tx.commit().await?;
+    Ok(result)
+}
+
+/// [`upsert_object_with_etag_mode`] on an open connection, so the write can
+/// be part of a caller's transaction.
+pub async fn upsert_object_in(
+    conn: &mut SqliteConnection,
+    calendar_id: &str,
+    uid: &str,
+    ical_data: &str,
+    fields: ObjectFields<'_>,
+    etag_mode: EtagMode,
 ) -> AppResult<(CalendarObject, bool)> {
     let ObjectFields {
         component_type,
@@ -31,11 +122,15 @@ pub async fn upsert_object(
         dtend,
         summary,
     } = fields;
-    let existing = get_object_by_uid(pool, calendar_id, uid).await?;
+    let existing = get_object_by_uid(&mut *conn, calendar_id, uid).await?;
     let is_new = existing.is_none();
 
-    let etag = new_etag();
-    let new_sync_token = format!("data:,sync-{}", Uuid::now_v7());
+    let etag = etag_mode.etag_for(ical_data);
+    if let Some(existing) = existing
+        && existing.etag == etag
+    {
+        return Ok((existing, false));
+    }
 
     if is_new {
         let id = Uuid::now_v7().to_string();
@@ -53,11 +148,8 @@ pub async fn upsert_object(
         .bind(dtstart)
         .bind(dtend)
         .bind(summary)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
-
-        // Log sync change
-        log_sync_change(pool, calendar_id, uid, "created", &new_sync_token).await?;
     } else {
         sqlx::query(
             "UPDATE calendar_objects SET etag = ?, ical_data = ?, component_type = ?,
@@ -72,26 +164,39 @@ pub async fn upsert_object(
         .bind(summary)
         .bind(calendar_id)
         .bind(uid)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
-
-        // Log sync change
-        log_sync_change(pool, calendar_id, uid, "modified", &new_sync_token).await?;
     }
 
-    // Bump the calendar's ctag and sync_token
-    super::calendars::bump_ctag(pool, calendar_id).await?;
+    // Bump the calendar's ctag and sync_token, and log the change under the
+    // new token so a client presenting it can sync from here
+    let sync_token = super::calendars::bump_ctag(&mut *conn, calendar_id).await?;
+    let change_type = if is_new { "created" } else { "modified" };
+    log_sync_change(conn, calendar_id, uid, change_type, &sync_token).await?;
 
-    let obj = get_object_by_uid(pool, calendar_id, uid)
+    let obj = get_object_by_uid(&mut *conn, calendar_id, uid)
         .await?
         .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Object upserted but not found")))?;
 
     Ok((obj, is_new))
 }
 
+/// Total size in bytes of the calendar data stored in calendars the user
+/// owns. Objects in calendars shared with the user count against the owner.
+pub async fn bytes_used_by_owner(pool: &SqlitePool, owner_id: &str) -> AppResult<i64> {
+    let (used,): (i64,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(LENGTH(CAST(ical_data AS BLOB))), 0) FROM calendar_objects
+         WHERE calendar_id IN (SELECT id FROM calendars WHERE owner_id = ?)",
+    )
+    .bind(owner_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(used)
+}
+
 /// Get a calendar object by its UID within a calendar.
 pub async fn get_object_by_uid(
-    pool: &SqlitePool,
+    executor: impl SqliteExecutor<'_>,
     calendar_id: &str,
     uid: &str,
 ) -> AppResult<Option<CalendarObject>> {
@@ -100,7 +205,7 @@ pub async fn get_object_by_uid(
     )
     .bind(calendar_id)
     .bind(uid)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
     .await?;
     Ok(obj)
 }
@@ -116,28 +221,254 @@ pub async fn list_objects(pool: &SqlitePool, calendar_id: &str) -> AppResult<Vec
     Ok(objs)
 }
 
+/// List one page of a calendar's objects, ordered by start time (then UID,
+/// so pages are stable), along with the total number of objects. With a
+/// `component_type`, only objects of that type are listed and counted.
+pub async fn list_objects_paged(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    component_type: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> AppResult<(Vec<CalendarObject>, i64)> {
+    let objs = sqlx::query_as::<_, CalendarObject>(
+        "SELECT * FROM calendar_objects
+         WHERE calendar_id = ? AND (? IS NULL OR component_type = ?)
+         ORDER BY dtstart, uid LIMIT ? OFFSET ?",
+    )
+    .bind(calendar_id)
+    .bind(component_type)
+    .bind(component_type)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    let (total,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM calendar_objects
+         WHERE calendar_id = ? AND (? IS NULL OR component_type = ?)",
+    )
+    .bind(calendar_id)
+    .bind(component_type)
+    .bind(component_type)
+    .fetch_one(pool)
+    .await?;
+    Ok((objs, total))
+}
+
+/// List `(uid, etag)` pairs for every object in a calendar, ordered by UID.
+/// Only the two columns are read, so no iCalendar bodies are loaded.
+pub async fn list_etags(pool: &SqlitePool, calendar_id: &str) -> AppResult<Vec<(String, String)>> {
+    let etags = sqlx::query_as::<_, (String, String)>(
+        "SELECT uid, etag FROM calendar_objects WHERE calendar_id = ? ORDER BY uid",
+    )
+    .bind(calendar_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(etags)
+}
+
+/// Candidate objects for a time range, by the indexed DTSTART/DTEND columns.
+/// Bind the calendar ID and the bounds from [`range_prefilter_bounds`].
+const RANGE_CANDIDATES_SQL: &str = "SELECT * FROM calendar_objects
+     WHERE calendar_id = ? AND dtstart < ? AND dtend > ?";
+
+/// [`RANGE_CANDIDATES_SQL`] plus every object flagged as recurring, whose
+/// stored span covers only its first instance. Bind the calendar ID again
+/// after the bounds.
+const RANGE_CANDIDATES_WITH_RECURRING_SQL: &str = "SELECT * FROM calendar_objects
+     WHERE calendar_id = ? AND dtstart < ? AND dtend > ?
+     UNION
+     SELECT * FROM calendar_objects WHERE calendar_id = ? AND recurring = 1";
+
+/// Bounds for comparing stored DTSTART/DTEND text against a range in SQL:
+/// `(upper, lower)` such that an object can only overlap the range if its
+/// start is below `upper` and its end above `lower`. Stored values all begin
+/// with their `YYYYMMDD` date but may be local times, so the bounds are whole
+/// dates widened by a day to cover any UTC offset.
+fn range_prefilter_bounds(
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+) -> (String, String) {
+    let day = chrono::Duration::days(1);
+    let upper = (range_end + day + day).format("%Y%m%d").to_string();
+    let lower = (range_start - day).format("%Y%m%d").to_string();
+    (upper, lower)
+}
+
+/// Parse the start and end of a requested time range.
+fn parse_range(start: &str, end: &str) -> AppResult<(DateTime<Utc>, DateTime<Utc>)> {
+    let range_start = datetime::parse_ical_datetime(start, None)
+        .ok_or_else(|| AppError::BadRequest(format!("invalid range start: {start}")))?;
+    let range_end = datetime::parse_ical_datetime(end, None)
+        .ok_or_else(|| AppError::BadRequest(format!("invalid range end: {end}")))?;
+    Ok((range_start, range_end))
+}
+
+/// Split candidate objects into those whose stored span overlaps the range,
+/// ordered by start, and the rest.
+fn partition_by_span(
+    objs: Vec<CalendarObject>,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+) -> (Vec<CalendarObject>, Vec<CalendarObject>) {
+    let mut in_range = Vec::new();
+    let mut rest = Vec::new();
+    for obj in objs {
+        match object_span(&obj) {
+            Some((obj_start, obj_end)) if obj_start < range_end && obj_end > range_start => {
+                in_range.push((obj_start, obj))
+            }
+            _ => rest.push(obj),
+        }
+    }
+    in_range.sort_by_key(|(obj_start, _)| *obj_start);
+    (in_range.into_iter().map(|(_, obj)| obj).collect(), rest)
+}
+
 /// List calendar objects within a time range.
+///
+/// Stored DTSTART/DTEND values may be UTC, local with a TZID, floating or
+/// dates, so SQL only narrows the rows down by date; the candidates are then
+/// compared as instants.
 pub async fn list_objects_in_range(
     pool: &SqlitePool,
     calendar_id: &str,
     start: &str,
     end: &str,
 ) -> AppResult<Vec<CalendarObject>> {
+    let (range_start, range_end) = parse_range(start, end)?;
+    let (upper, lower) = range_prefilter_bounds(range_start, range_end);
+
+    let objs = sqlx::query_as::<_, CalendarObject>(RANGE_CANDIDATES_SQL)
+        .bind(calendar_id)
+        .bind(&upper)
+        .bind(&lower)
+        .fetch_all(pool)
+        .await?;
+    Ok(partition_by_span(objs, range_start, range_end).0)
+}
+
+/// Like [`list_objects_in_range`], but in the same query also fetch the
+/// recurring objects whose first instance lies outside the range. Returns
+/// the objects in range and, separately, the remaining recurring candidates
+/// for the caller to expand.
+pub async fn list_objects_in_range_with_recurring(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    start: &str,
+    end: &str,
+) -> AppResult<(Vec<CalendarObject>, Vec<CalendarObject>)> {
+    let (range_start, range_end) = parse_range(start, end)?;
+    let (upper, lower) = range_prefilter_bounds(range_start, range_end);
+
+    let objs = sqlx::query_as::<_, CalendarObject>(RANGE_CANDIDATES_WITH_RECURRING_SQL)
+        .bind(calendar_id)
+        .bind(&upper)
+        .bind(&lower)
+        .bind(calendar_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(partition_by_span(objs, range_start, range_end))
+}
+
+/// List VTODOs that overlap a time range, by the rules of RFC 4791 §9.9.
+/// A VTODO's `DUE` is indexed in the `dtend` column, so tasks that have only
+/// a due date (no `DTSTART`) are matched on it; tasks with neither match
+/// every range.
+pub async fn list_todos_in_range(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    start: &str,
+    end: &str,
+) -> AppResult<Vec<CalendarObject>> {
+    let (range_start, range_end) = parse_range(start, end)?;
+    let (upper, lower) = range_prefilter_bounds(range_start, range_end);
+
+    // A task matches only if its earlier time is before the range ends and
+    // its later time after the range starts. Tasks with neither always match.
     let objs = sqlx::query_as::<_, CalendarObject>(
         "SELECT * FROM calendar_objects
-         WHERE calendar_id = ?
-           AND dtstart IS NOT NULL
-           AND dtend IS NOT NULL
-           AND dtstart < ?
-           AND dtend > ?
-         ORDER BY dtstart",
+         WHERE calendar_id = ? AND component_type = 'VTODO'
+           AND (COALESCE(dtstart, dtend) IS NULL
+                OR (MIN(COALESCE(dtstart, dtend), COALESCE(dtend, dtstart)) < ?
+                    AND MAX(COALESCE(dtstart, dtend), COALESCE(dtend, dtstart)) > ?))
+         ORDER BY COALESCE(dtstart, dtend)",
     )
     .bind(calendar_id)
-    .bind(end)
-    .bind(start)
+    .bind(&upper)
+    .bind(&lower)
     .fetch_all(pool)
     .await?;
-    Ok(objs)
+
+    Ok(objs
+        .into_iter()
+        .filter(|obj| {
+            let (dtstart, due) = todo_times(obj);
+            match (dtstart, due) {
+                (Some(s), Some(d)) => {
+                    (range_start <= s || range_start < d) && (range_end > s || range_end >= d)
+                }
+                (Some(s), None) => range_start <= s && range_end > s,
+                (None, Some(d)) => range_start < d && range_end >= d,
+                (None, None) => true,
+            }
+        })
+        .collect())
+}
+
+/// `DTSTART` and `DUE` of a stored VTODO as UTC instants. Either may be
+/// missing; a value that cannot be parsed counts as missing.
+fn todo_times(obj: &CalendarObject) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let component = parser::parse_calendar(&obj.ical_data).and_then(|cal| {
+        cal.components
+            .into_iter()
+            .find(|c| c.name == "VTODO" && c.property("RECURRENCE-ID").is_none())
+    });
+    let tzid = |name: &str| -> Option<String> {
+        component
+            .as_ref()?
+            .property(name)?
+            .param("TZID")
+            .map(str::to_string)
+    };
+    let dtstart = obj
+        .dtstart
+        .as_deref()
+        .and_then(|v| datetime::parse_ical_datetime(v, tzid("DTSTART").as_deref()));
+    let due = obj
+        .dtend
+        .as_deref()
+        .and_then(|v| datetime::parse_ical_datetime(v, tzid("DUE").as_deref()));
+    (dtstart, due)
+}
+
+/// Start and end of a stored object as UTC instants, honouring the TZID
+/// parameters in its iCalendar data.
+pub fn object_span(obj: &CalendarObject) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let component = parser::parse_calendar(&obj.ical_data).and_then(|cal| {
+        cal.components
+            .into_iter()
+            .find(|c| c.name == obj.component_type && c.property("RECURRENCE-ID").is_none())
+    });
+    let tzid = |names: &[&str]| -> Option<String> {
+        let c = component.as_ref()?;
+        let prop = names.iter().find_map(|name| c.property(name))?;
+        prop.param("TZID").map(str::to_string)
+    };
+    let start_tzid = tzid(&["DTSTART"]);
+    // An end computed from DURATION is written in DTSTART's form, so it is
+    // read in DTSTART's zone
+    let has_end = component
+        .as_ref()
+        .is_some_and(|c| c.property("DTEND").is_some() || c.property("DUE").is_some());
+    let end_tzid = if has_end {
+        tzid(&["DTEND", "DUE"])
+    } else {
+        start_tzid.clone()
+    };
+    let start = datetime::parse_ical_datetime(obj.dtstart.as_deref()?, start_tzid.as_deref())?;
+    let end = datetime::parse_ical_datetime(obj.dtend.as_deref()?, end_tzid.as_deref())?;
+    Some((start, end))
 }
 
 /// Get multiple calendar objects by their UIDs.
@@ -166,12 +497,73 @@ pub async fn get_objects_by_uids(
     Ok(objs)
 }
 
-/// Delete a calendar object by UID. Returns the deleted object's ETag.
+/// Objects in any of `calendar_ids` whose summary or iCalendar text contains
+/// `query`, ignoring ASCII case, ordered by start. The iCalendar match is a
+/// coarse filter; callers check the parsed properties they care about.
+pub async fn search_objects(
+    pool: &SqlitePool,
+    calendar_ids: &[String],
+    query: &str,
+) -> AppResult<Vec<CalendarObject>> {
+    if calendar_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let placeholders: Vec<&str> = calendar_ids.iter().map(|_| "?").collect();
+    let sql = format!(
+        "SELECT * FROM calendar_objects WHERE calendar_id IN ({})
+         AND (summary LIKE ? ESCAPE '\\' OR ical_data LIKE ? ESCAPE '\\')
+         ORDER BY dtstart, uid",
+        placeholders.join(", ")
+    );
+    let pattern = super::like_pattern(query);
+
+    let mut q = sqlx::query_as::<_, CalendarObject>(&sql);
+    for id in calendar_ids {
+        q = q.bind(id);
+    }
+    let objs = q.bind(&pattern).bind(&pattern).fetch_all(pool).await?;
+    Ok(objs)
+}
+
+/// Delete a calendar object by UID, keeping a tombstone for `restore_object`.
+/// Returns `NotFound` if there is no such object.
 pub async fn delete_object(pool: &SqlitePool, calendar_id: &str, uid: &str) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+    delete_object_in(&mut tx, calendar_id, uid).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// [`delete_object`] on an open connection, so the delete can be part of a
+/// caller's transaction.
+pub async fn delete_object_in(
+    conn: &mut SqliteConnection,
+    calendar_id: &str,
+    uid: &str,
+) -> AppResult<()> {
+    // Keep a tombstone so the delete can be undone with `restore_object`,
+    // and drop tombstones that have aged out of the retention window.
+    sqlx::query(&format!(
+        "DELETE FROM deleted_objects WHERE deleted_at < datetime('now', '-{TOMBSTONE_RETENTION_DAYS} days')"
+    ))
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO deleted_objects
+         (id, calendar_id, uid, etag, ical_data, component_type, dtstart, dtend, summary, created_at)
+         SELECT id, calendar_id, uid, etag, ical_data, component_type, dtstart, dtend, summary, created_at
+         FROM calendar_objects WHERE calendar_id = ? AND uid = ?",
+    )
+    .bind(calendar_id)
+    .bind(uid)
+    .execute(&mut *conn)
+    .await?;
+
     let result = sqlx::query("DELETE FROM calendar_objects WHERE calendar_id = ? AND uid = ?")
         .bind(calendar_id)
         .bind(uid)
-        .execute(pool)
+        .execute(&mut *conn)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -180,17 +572,125 @@ pub async fn delete_object(pool: &SqlitePool, calendar_id: &str, uid: &str) -> A
         )));
     }
 
-    let new_sync_token = format!("data:,sync-{}", Uuid::now_v7());
-    log_sync_change(pool, calendar_id, uid, "deleted", &new_sync_token).await?;
-    super::calendars::bump_ctag(pool, calendar_id).await?;
+    let sync_token = super::calendars::bump_ctag(&mut *conn, calendar_id).await?;
+    log_sync_change(conn, calendar_id, uid, "deleted", &sync_token).await?;
+    sqlx::query(
+        "UPDATE deleted_objects SET deleted_sync_token = ? WHERE calendar_id = ? AND uid = ?",
+    )
+    .bind(&sync_token)
+    .bind(calendar_id)
+    .bind(uid)
+    .execute(&mut *conn)
+    .await?;
 
     Ok(())
 }
 
-/// Log a sync change for the sync-collection REPORT.
-async fn log_sync_change(
+/// How long deleted objects can be restored for.
+pub const TOMBSTONE_RETENTION_DAYS: i64 = 30;
+
+/// UIDs deleted from a calendar after `since_token` and not since re-created,
+/// read from the tombstones. Used when the token's change rows have been
+/// pruned, so a client syncing from it still hears about deletions.
+///
+/// Returns `None` when the token isn't one of ours or is older than the
+/// tombstone retention window, since deletions before the oldest surviving
+/// tombstone can't be recovered.
+pub async fn deleted_uids_since(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    since_token: &str,
+) -> AppResult<Option<Vec<String>>> {
+    let Some(issued) = sync_token_time(since_token) else {
+        return Ok(None);
+    };
+    if issued < Utc::now() - chrono::Duration::days(TOMBSTONE_RETENTION_DAYS) {
+        return Ok(None);
+    }
+
+    // Sync tokens embed a UUID v7, so they sort in the order they were issued
+    let uids = sqlx::query_scalar(&format!(
+        "SELECT uid FROM deleted_objects
+         WHERE calendar_id = ? AND deleted_sync_token > ?
+           AND deleted_at >= datetime('now', '-{TOMBSTONE_RETENTION_DAYS} days')
+           AND uid NOT IN (SELECT uid FROM calendar_objects WHERE calendar_id = ?)
+         ORDER BY deleted_sync_token"
+    ))
+    .bind(calendar_id)
+    .bind(since_token)
+    .bind(calendar_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(Some(uids))
+}
+
+/// When a `data:,sync-{uuid}` token was issued, from its UUID v7 timestamp.
+fn sync_token_time(token: &str) -> Option<DateTime<Utc>> {
+    let uuid = Uuid::parse_str(token.strip_prefix("data:,sync-")?).ok()?;
+    let (secs, nanos) = uuid.get_timestamp()?.to_unix();
+    DateTime::from_timestamp(secs as i64, nanos)
+}
+
+/// Restore an object deleted within the retention window. The object comes
+/// back with its original ETag and is logged as created so syncing clients
+/// re-add it. All of it happens in one transaction, so a failure never
+/// leaves the object both live and tombstoned, or live without a sync row.
+pub async fn restore_object(
     pool: &SqlitePool,
     calendar_id: &str,
+    uid: &str,
+) -> AppResult<CalendarObject> {
+    let mut tx = pool.begin().await?;
+    if get_object_by_uid(&mut *tx, calendar_id, uid)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict(format!(
+            "Object with UID '{uid}' already exists in calendar"
+        )));
+    }
+
+    let restored = sqlx::query(&format!(
+        "INSERT INTO calendar_objects
+         (id, calendar_id, uid, etag, ical_data, component_type, dtstart, dtend, summary, created_at)
+         SELECT id, calendar_id, uid, etag, ical_data, component_type, dtstart, dtend, summary, created_at
+         FROM deleted_objects WHERE calendar_id = ? AND uid = ?
+         AND deleted_at >= datetime('now', '-{TOMBSTONE_RETENTION_DAYS} days')"
+    ))
+    .bind(calendar_id)
+    .bind(uid)
+    .execute(&mut *tx)
+    .await?;
+    if restored.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!(
+            "No recently deleted object with UID '{uid}' in calendar"
+        )));
+    }
+    sqlx::query("DELETE FROM deleted_objects WHERE calendar_id = ? AND uid = ?")
+        .bind(calendar_id)
+        .bind(uid)
+        .execute(&mut *tx)
+        .await?;
+
+    let sync_token = super::calendars::bump_ctag(&mut *tx, calendar_id).await?;
+    log_sync_change(&mut tx, calendar_id, uid, "created", &sync_token).await?;
+
+    let object = get_object_by_uid(&mut *tx, calendar_id, uid)
+        .await?
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Object restored but not found")))?;
+    tx.commit().await?;
+    Ok(object)
+}
+
+/// How many change rows each calendar keeps for delta sync. Older rows are
+/// pruned as new changes are logged.
+pub const SYNC_CHANGES_RETAINED: usize = 1000;
+
+/// Log a sync change for the sync-collection REPORT, pruning the calendar's
+/// change log back to `SYNC_CHANGES_RETAINED` rows.
+async fn log_sync_change(
+    conn: &mut SqliteConnection,
+    calendar_id: &str,
     object_uid: &str,
     change_type: &str,
     sync_token: &str,
@@ -203,47 +703,125 @@ async fn log_sync_change(
     .bind(object_uid)
     .bind(change_type)
     .bind(sync_token)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
+    prune_sync_changes(&mut *conn, calendar_id, SYNC_CHANGES_RETAINED).await?;
     Ok(())
 }
 
+/// Delete all but the newest `keep_latest` change rows for a calendar.
+/// Returns the number of rows removed.
+///
+/// A client whose token belonged to a pruned row can no longer sync
+/// incrementally: `get_sync_changes_since` reports its token as invalid and
+/// the client gets every object, with deletions recovered from tombstones.
+/// Keeping more rows makes that rarer for clients that stay offline through
+/// many changes, at the cost of a larger table.
+pub async fn prune_sync_changes(
+    executor: impl SqliteExecutor<'_>,
+    calendar_id: &str,
+    keep_latest: usize,
+) -> AppResult<u64> {
+    let result = sqlx::query(
+        "DELETE FROM sync_changes WHERE calendar_id = ? AND id <= (
+             SELECT id FROM sync_changes WHERE calendar_id = ?
+             ORDER BY id DESC LIMIT 1 OFFSET ?
+         )",
+    )
+    .bind(calendar_id)
+    .bind(calendar_id)
+    .bind(keep_latest as i64)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// The change row a sync token was logged with. Bind the calendar ID and
+/// the token.
+const SYNC_ANCHOR_SQL: &str =
+    "SELECT id FROM sync_changes WHERE calendar_id = ? AND sync_token = ? LIMIT 1";
+
+/// A calendar's changes after a change row, in order. Bind the calendar ID
+/// and the row ID.
+const SYNC_CHANGES_AFTER_SQL: &str =
+    "SELECT * FROM sync_changes WHERE calendar_id = ? AND id > ? ORDER BY id";
+
 /// Get sync changes after a given sync token for a calendar.
+///
+/// Returns `None` when the token can't be used as a base: it isn't in the
+/// change log and the log may have been pruned, so changes made after it
+/// could be missing. The caller should list every object instead, along with
+/// the deletions from `deleted_uids_since`.
 pub async fn get_sync_changes_since(
     pool: &SqlitePool,
     calendar_id: &str,
     since_token: &str,
-) -> AppResult<Vec<SyncChange>> {
+) -> AppResult<Option<Vec<SyncChange>>> {
     // Find the ID of the sync change record with this token
-    let anchor: Option<(i64,)> = sqlx::query_as(
-        "SELECT id FROM sync_changes WHERE calendar_id = ? AND sync_token = ? LIMIT 1",
-    )
-    .bind(calendar_id)
-    .bind(since_token)
-    .fetch_optional(pool)
-    .await?;
+    let anchor: Option<(i64,)> = sqlx::query_as(SYNC_ANCHOR_SQL)
+        .bind(calendar_id)
+        .bind(since_token)
+        .fetch_optional(pool)
+        .await?;
 
     let changes = match anchor {
         Some((anchor_id,)) => {
-            sqlx::query_as::<_, SyncChange>(
-                "SELECT * FROM sync_changes WHERE calendar_id = ? AND id > ? ORDER BY id",
-            )
-            .bind(calendar_id)
-            .bind(anchor_id)
-            .fetch_all(pool)
-            .await?
+            sqlx::query_as::<_, SyncChange>(SYNC_CHANGES_AFTER_SQL)
+                .bind(calendar_id)
+                .bind(anchor_id)
+                .fetch_all(pool)
+                .await?
         }
         None => {
-            // If token not found, return all changes (full sync)
-            sqlx::query_as::<_, SyncChange>(
+            // An unknown token predates the change log (e.g. the token a
+            // calendar is created with). While the log is shorter than the
+            // retention limit nothing has been pruned, so it holds every
+            // change since then.
+            let changes = sqlx::query_as::<_, SyncChange>(
                 "SELECT * FROM sync_changes WHERE calendar_id = ? ORDER BY id",
             )
             .bind(calendar_id)
             .fetch_all(pool)
-            .await?
+            .await?;
+            if changes.len() >= SYNC_CHANGES_RETAINED {
+                return Ok(None);
+            }
+            changes
         }
     };
 
+    Ok(Some(changes))
+}
+
+/// Sync changes for a calendar recorded in `[since, until)`, oldest first,
+/// at most `limit` of them. Either bound may be omitted.
+pub async fn list_sync_changes(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    limit: i64,
+) -> AppResult<Vec<SyncChange>> {
+    // created_at is stored as SQLite's `datetime('now')` text, in UTC
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let since = since.map(format);
+    let until = until.map(format);
+    let changes = sqlx::query_as::<_, SyncChange>(
+        "SELECT * FROM sync_changes
+         WHERE calendar_id = ?
+           AND (? IS NULL OR created_at >= ?)
+           AND (? IS NULL OR created_at < ?)
+         ORDER BY id
+         LIMIT ?",
+    )
+    .bind(calendar_id)
+    .bind(&since)
+    .bind(&since)
+    .bind(&until)
+    .bind(&until)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
     Ok(changes)
 }
 
@@ -251,11 +829,12 @@ pub async fn get_sync_changes_since(
 mod tests {
     use super::*;
     use crate::db;
-    use crate::db::{calendars, users};
+    use crate::db::calendars;
+    use crate::db::users::{self, PasswordPolicy};
 
     async fn setup() -> (SqlitePool, String, String) {
         let pool = db::test_pool().await;
-        let user = users::create_user(&pool, "alice", None, "pass")
+        let user = users::create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
             .await
             .unwrap();
         let cal = calendars::create_calendar(&pool, &user.id, "Work", "", "#FF0000", "UTC")
@@ -289,6 +868,166 @@ mod tests {
         assert!(obj.etag.starts_with('"'));
     }
 
+    #[tokio::test]
+    async fn test_search_objects_matches_summary_and_text() {
+        let (pool, user_id, cal_id) = setup().await;
+        let other = calendars::create_calendar(&pool, &user_id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let event = |summary: &'static str| ObjectFields {
+            component_type: "VEVENT",
+            dtstart: Some("20260301T090000Z"),
+            dtend: Some("20260301T100000Z"),
+            summary: Some(summary),
+        };
+        upsert_object(
+            &pool,
+            &cal_id,
+            "a",
+            "BEGIN:VCALENDAR\r\nEND:VCALENDAR",
+            event("Dentist"),
+        )
+        .await
+        .unwrap();
+        upsert_object(
+            &pool,
+            &other.id,
+            "b",
+            "BEGIN:VCALENDAR\r\nLOCATION:Dentist office\r\nEND:VCALENDAR",
+            event("Checkup"),
+        )
+        .await
+        .unwrap();
+        upsert_object(
+            &pool,
+            &other.id,
+            "c",
+            "BEGIN:VCALENDAR\r\nEND:VCALENDAR",
+            event("Lunch"),
+        )
+        .await
+        .unwrap();
+
+        let ids = vec![cal_id.clone(), other.id.clone()];
+        let found = search_objects(&pool, &ids, "dentist").await.unwrap();
+        let uids: Vec<&str> = found.iter().map(|o| o.uid.as_str()).collect();
+        assert_eq!(uids, vec!["a", "b"]);
+
+        // Wildcards are literal and other calendars are not searched
+        assert!(search_objects(&pool, &ids, "%").await.unwrap().is_empty());
+        let found = search_objects(&pool, &[cal_id], "dentist").await.unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_range_and_sync_queries_use_indexes() {
+        let (pool, _, _) = setup().await;
+        // EXPLAIN the statements the queries run, with every parameter bound
+        let plan = |sql: &'static str| {
+            let pool = pool.clone();
+            async move {
+                let explain = format!("EXPLAIN QUERY PLAN {sql}");
+                let mut query = sqlx::query_as::<_, (i64, i64, i64, String)>(&explain);
+                for _ in 0..sql.matches('?').count() {
+                    query = query.bind("x");
+                }
+                query
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(_, _, _, detail)| detail)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            }
+        };
+
+        let range = plan(RANGE_CANDIDATES_SQL).await;
+        assert!(
+            range.contains("idx_calendar_objects_calendar_range (calendar_id=? AND dtstart<?)"),
+            "got: {range}"
+        );
+
+        let range = plan(RANGE_CANDIDATES_WITH_RECURRING_SQL).await;
+        assert!(
+            range.contains("idx_calendar_objects_calendar_range (calendar_id=? AND dtstart<?)"),
+            "got: {range}"
+        );
+        assert!(
+            range.contains("idx_calendar_objects_calendar_recurring"),
+            "got: {range}"
+        );
+
+        let anchor = plan(SYNC_ANCHOR_SQL).await;
+        assert!(
+            anchor.contains("idx_sync_changes_calendar_id_token"),
+            "got: {anchor}"
+        );
+
+        let sync = plan(SYNC_CHANGES_AFTER_SQL).await;
+        assert!(
+            sync.contains("idx_sync_changes_calendar_id_id"),
+            "got: {sync}"
+        );
+    }
+
+    #[test]
+    fn test_content_etag_ignores_line_endings() {
+        let crlf = content_etag("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n");
+        assert_eq!(crlf, content_etag("BEGIN:VCALENDAR\nEND:VCALENDAR"));
+        assert_ne!(
+            crlf,
+            content_etag("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR")
+        );
+        assert!(crlf.starts_with('"') && crlf.ends_with('"'));
+    }
+
+    #[tokio::test]
+    async fn test_content_etag_mode_skips_identical_write() {
+        let (pool, _, cal_id) = setup().await;
+        let fields = || ObjectFields {
+            component_type: "VEVENT",
+            dtstart: None,
+            dtend: None,
+            summary: None,
+        };
+
+        let (first, _) = upsert_object_with_etag_mode(
+            &pool,
+            &cal_id,
+            "same@example.com",
+            "BEGIN:VCALENDAR\r\nEND:VCALENDAR",
+            fields(),
+            EtagMode::Content,
+        )
+        .await
+        .unwrap();
+        let ctag = calendars::get_calendar_by_id(&pool, &cal_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+
+        let (second, is_new) = upsert_object_with_etag_mode(
+            &pool,
+            &cal_id,
+            "same@example.com",
+            "BEGIN:VCALENDAR\r\nEND:VCALENDAR",
+            fields(),
+            EtagMode::Content,
+        )
+        .await
+        .unwrap();
+        assert!(!is_new);
+        assert_eq!(second.etag, first.etag);
+        let unchanged = calendars::get_calendar_by_id(&pool, &cal_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+        assert_eq!(unchanged, ctag);
+    }
+
     #[tokio::test]
     async fn test_update_object() {
         let (pool, _, cal_id) = setup().await;
@@ -407,6 +1146,166 @@ mod tests {
         assert_eq!(objs[0].summary.as_deref(), Some("March"));
     }
 
+    #[tokio::test]
+    async fn test_list_objects_in_range_compares_instants() {
+        let (pool, _, cal_id) = setup().await;
+
+        // 08:00-09:00 in Tokyo is 23:00-00:00Z the previous day
+        let tokyo = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:tokyo\r\n\
+                     DTSTART;TZID=Asia/Tokyo:20260301T080000\r\n\
+                     DTEND;TZID=Asia/Tokyo:20260301T090000\r\n\
+                     END:VEVENT\r\nEND:VCALENDAR\r\n";
+        // 20:00-21:00 in New York on March 31 is April 1 in UTC
+        let new_york = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:new-york\r\n\
+                        DTSTART;TZID=America/New_York:20260331T200000\r\n\
+                        DTEND;TZID=America/New_York:20260331T210000\r\n\
+                        END:VEVENT\r\nEND:VCALENDAR\r\n";
+        for (uid, data, dtstart, dtend) in [
+            ("tokyo", tokyo, "20260301T080000", "20260301T090000"),
+            ("new-york", new_york, "20260331T200000", "20260331T210000"),
+            ("all-day", "data", "20260310", "20260311"),
+            (
+                "millis",
+                "data",
+                "20260315T090000.000Z",
+                "20260315T100000.000Z",
+            ),
+        ] {
+            upsert_object(
+                &pool,
+                &cal_id,
+                uid,
+                data,
+                ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some(dtstart),
+                    dtend: Some(dtend),
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let objs = list_objects_in_range(&pool, &cal_id, "20260301T000000Z", "20260401T000000Z")
+            .await
+            .unwrap();
+        let uids: Vec<_> = objs.iter().map(|o| o.uid.as_str()).collect();
+        assert_eq!(uids, ["all-day", "millis"]);
+
+        let objs = list_objects_in_range(&pool, &cal_id, "20260228T000000Z", "20260301T000000Z")
+            .await
+            .unwrap();
+        assert_eq!(objs.len(), 1);
+        assert_eq!(objs[0].uid, "tokyo");
+
+        assert!(
+            list_objects_in_range(&pool, &cal_id, "soon", "20260401T000000Z")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_in_range_with_recurring() {
+        let (pool, _, cal_id) = setup().await;
+
+        let weekly = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:weekly\r\n\
+                      DTSTART:20260105T090000Z\r\nDTEND:20260105T100000Z\r\n\
+                      RRULE:FREQ=WEEKLY\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        for (uid, data, dtstart, dtend) in [
+            ("weekly", weekly, "20260105T090000Z", "20260105T100000Z"),
+            ("inside", "data", "20260310T090000Z", "20260310T100000Z"),
+            ("before", "data", "20260105T090000Z", "20260105T100000Z"),
+        ] {
+            upsert_object(
+                &pool,
+                &cal_id,
+                uid,
+                data,
+                ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some(dtstart),
+                    dtend: Some(dtend),
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let (in_range, recurring) = list_objects_in_range_with_recurring(
+            &pool,
+            &cal_id,
+            "20260301T000000Z",
+            "20260401T000000Z",
+        )
+        .await
+        .unwrap();
+        let uids: Vec<_> = in_range.iter().map(|o| o.uid.as_str()).collect();
+        assert_eq!(uids, ["inside"]);
+        // The recurring event's first instance is out of range, so it is
+        // returned for expansion; the plain out-of-range event is not
+        let uids: Vec<_> = recurring.iter().map(|o| o.uid.as_str()).collect();
+        assert_eq!(uids, ["weekly"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_todos_in_range_matches_due() {
+        let (pool, _, cal_id) = setup().await;
+
+        for (uid, dtstart, due) in [
+            ("due-march", None, Some("20260315T170000Z")),
+            ("due-april", None, Some("20260410T170000Z")),
+            ("starts-march", Some("20260320T090000Z"), None),
+            (
+                "spans-march",
+                Some("20260201T090000Z"),
+                Some("20260501T090000Z"),
+            ),
+            ("undated", None, None),
+        ] {
+            upsert_object(
+                &pool,
+                &cal_id,
+                uid,
+                "data",
+                ObjectFields {
+                    component_type: "VTODO",
+                    dtstart,
+                    dtend: due,
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        upsert_object(
+            &pool,
+            &cal_id,
+            "event",
+            "data",
+            ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260310T090000Z"),
+                dtend: Some("20260310T100000Z"),
+                summary: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let objs = list_todos_in_range(&pool, &cal_id, "20260301T000000Z", "20260401T000000Z")
+            .await
+            .unwrap();
+        let mut uids: Vec<_> = objs.iter().map(|o| o.uid.as_str()).collect();
+        uids.sort();
+        assert_eq!(
+            uids,
+            ["due-march", "spans-march", "starts-march", "undated"]
+        );
+    }
+
     #[tokio::test]
     async fn test_delete_object() {
         let (pool, _, cal_id) = setup().await;
@@ -434,6 +1333,59 @@ mod tests {
         assert!(obj.is_none());
     }
 
+    #[tokio::test]
+    async fn test_restore_object_within_retention_window() {
+        let (pool, _, cal_id) = setup().await;
+        let fields = || ObjectFields {
+            component_type: "VEVENT",
+            dtstart: None,
+            dtend: None,
+            summary: Some("Oops"),
+        };
+        let (original, _) = upsert_object(&pool, &cal_id, "e1@ex.com", "data", fields())
+            .await
+            .unwrap();
+        delete_object(&pool, &cal_id, "e1@ex.com").await.unwrap();
+
+        let restored = restore_object(&pool, &cal_id, "e1@ex.com").await.unwrap();
+        assert_eq!(restored.etag, original.etag);
+        assert_eq!(restored.summary.as_deref(), Some("Oops"));
+        // The tombstone is consumed
+        let again = restore_object(&pool, &cal_id, "e1@ex.com").await;
+        assert!(matches!(again, Err(AppError::Conflict(_))));
+
+        // Tombstones older than the retention window cannot be restored
+        delete_object(&pool, &cal_id, "e1@ex.com").await.unwrap();
+        sqlx::query("UPDATE deleted_objects SET deleted_at = datetime('now', '-31 days')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let expired = restore_object(&pool, &cal_id, "e1@ex.com").await;
+        assert!(matches!(expired, Err(AppError::NotFound(_))));
+
+        // A restore that fails part-way leaves the object tombstoned, not live
+        sqlx::query("UPDATE deleted_objects SET deleted_at = datetime('now')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DROP TABLE sync_changes")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(restore_object(&pool, &cal_id, "e1@ex.com").await.is_err());
+        assert!(
+            get_object_by_uid(&pool, &cal_id, "e1@ex.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let (tombstones,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM deleted_objects")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tombstones, 1);
+    }
+
     #[tokio::test]
     async fn test_delete_nonexistent_object() {
         let (pool, _, cal_id) = setup().await;
@@ -538,12 +1490,162 @@ mod tests {
         // Get changes since initial token
         let changes = get_sync_changes_since(&pool, &cal_id, &initial_token)
             .await
+            .unwrap()
             .unwrap();
         assert_eq!(changes.len(), 2);
         assert_eq!(changes[0].change_type, "created");
         assert_eq!(changes[1].change_type, "created");
     }
 
+    #[tokio::test]
+    async fn test_sync_changes_since_calendar_token() {
+        let (pool, _, cal_id) = setup().await;
+        let fields = || ObjectFields {
+            component_type: "VEVENT",
+            dtstart: None,
+            dtend: None,
+            summary: None,
+        };
+
+        upsert_object(&pool, &cal_id, "e1@ex.com", "d1", fields())
+            .await
+            .unwrap();
+        let token = calendars::get_calendar_by_id(&pool, &cal_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .sync_token;
+        upsert_object(&pool, &cal_id, "e2@ex.com", "d2", fields())
+            .await
+            .unwrap();
+
+        // The calendar's token anchors the delta: only later changes return
+        let changes = get_sync_changes_since(&pool, &cal_id, &token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].object_uid, "e2@ex.com");
+    }
+
+    #[tokio::test]
+    async fn test_object_writes_roll_back_with_transaction() {
+        let (pool, user_id, cal_id) = setup().await;
+        let other = calendars::create_calendar(&pool, &user_id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let fields = || ObjectFields {
+            component_type: "VEVENT",
+            dtstart: None,
+            dtend: None,
+            summary: None,
+        };
+        upsert_object(&pool, &cal_id, "moving", "data", fields())
+            .await
+            .unwrap();
+        let ctag = calendars::get_calendar_by_id(&pool, &cal_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+
+        // Dropped without committing: neither calendar changes
+        let mut tx = pool.begin().await.unwrap();
+        upsert_object_in(
+            &mut tx,
+            &other.id,
+            "moving",
+            "data",
+            fields(),
+            EtagMode::Random,
+        )
+        .await
+        .unwrap();
+        delete_object_in(&mut tx, &cal_id, "moving").await.unwrap();
+        drop(tx);
+
+        assert!(
+            get_object_by_uid(&pool, &cal_id, "moving")
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            get_object_by_uid(&pool, &other.id, "moving")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let after = calendars::get_calendar_by_id(&pool, &cal_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(after.ctag, ctag);
+    }
+
+    #[tokio::test]
+    async fn test_prune_sync_changes() {
+        let (pool, _, cal_id) = setup().await;
+        for i in 0..5 {
+            upsert_object(
+                &pool,
+                &cal_id,
+                &format!("e{i}@ex.com"),
+                "data",
+                ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: None,
+                    dtend: None,
+                    summary: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let all = get_sync_changes_since(&pool, &cal_id, "")
+            .await
+            .unwrap()
+            .unwrap();
+        let pruned_token = all[1].sync_token.clone();
+        let kept_token = all[2].sync_token.clone();
+
+        assert_eq!(prune_sync_changes(&pool, &cal_id, 3).await.unwrap(), 2);
+        assert_eq!(prune_sync_changes(&pool, &cal_id, 3).await.unwrap(), 0);
+        let remaining: Vec<String> = get_sync_changes_since(&pool, &cal_id, "")
+            .await
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|c| c.object_uid)
+            .collect();
+        assert_eq!(remaining, ["e2@ex.com", "e3@ex.com", "e4@ex.com"]);
+
+        // A retained token still anchors a delta sync
+        let changes = get_sync_changes_since(&pool, &cal_id, &kept_token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+
+        // Once the log is at the retention limit, a pruned token is invalid
+        sqlx::query(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?)
+             INSERT INTO sync_changes (calendar_id, object_uid, change_type, sync_token)
+             SELECT ?, 'bulk', 'modified', 'data:,bulk-' || i FROM n",
+        )
+        .bind(SYNC_CHANGES_RETAINED as i64)
+        .bind(&cal_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(
+            get_sync_changes_since(&pool, &cal_id, &pruned_token)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[tokio::test]
     async fn test_upsert_bumps_ctag() {
         let (pool, _, cal_id) = setup().await;
@@ -1,23 +1,36 @@
+pub mod alarms;
+pub mod availability;
 pub mod calendars;
 pub mod events;
 pub mod models;
 pub mod shares;
+pub mod sync_clients;
 pub mod tokens;
 pub mod users;
 
 use sqlx::SqlitePool;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use std::str::FromStr;
-
-/// Initialize the database connection pool and run migrations.
-pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
+use std::time::Duration;
+
+/// How long a connection waits for another writer's lock before failing
+/// with `database is locked`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Initialize the database connection pool and run migrations. The pool
+/// opens at most `max_connections` connections.
+pub async fn init_pool(
+    database_url: &str,
+    max_connections: u32,
+) -> Result<SqlitePool, sqlx::Error> {
     let options = SqliteConnectOptions::from_str(database_url)?
         .create_if_missing(true)
         .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT)
         .foreign_keys(true);
 
     let pool = SqlitePoolOptions::new()
-        .max_connections(5)
+        .max_connections(max_connections)
         .connect_with(options)
         .await?;
 
@@ -26,13 +39,30 @@ pub async fn init_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     Ok(pool)
 }
 
+/// SQL migrations, applied in order on every start. Each one must be
+/// idempotent (`CREATE ... IF NOT EXISTS`). SQLite has no
+/// `ADD COLUMN IF NOT EXISTS`, so a duplicate-column error is ignored instead.
+const MIGRATIONS: &[&str] = &[
+    include_str!("../../migrations/001_initial.sql"),
+    include_str!("../../migrations/002_availability.sql"),
+    include_str!("../../migrations/003_component_types.sql"),
+    include_str!("../../migrations/004_deleted_objects.sql"),
+    include_str!("../../migrations/005_fired_alarms.sql"),
+    include_str!("../../migrations/006_sync_clients.sql"),
+    include_str!("../../migrations/007_calendar_order.sql"),
+    include_str!("../../migrations/008_calendar_transp.sql"),
+    include_str!("../../migrations/009_token_scope.sql"),
+    include_str!("../../migrations/010_range_indexes.sql"),
+    include_str!("../../migrations/011_tombstone_sync_token.sql"),
+    include_str!("../../migrations/012_recurring_flag.sql"),
+    include_str!("../../migrations/013_alarm_flag.sql"),
+];
+
 /// Run SQL migrations from the migrations directory.
 async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    let sql = include_str!("../../migrations/001_initial.sql");
-
     // sqlx::query().execute() only runs the first statement.
     // Split on semicolons and execute each statement individually.
-    for statement in sql.split(';') {
+    for statement in MIGRATIONS.iter().flat_map(|sql| sql.split(';')) {
         let trimmed = statement.trim();
         // Skip empty segments. Don't skip comments — they may precede
         // SQL in the same segment, and SQLite handles `--` comments fine.
@@ -47,12 +77,32 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         if !has_sql {
             continue;
         }
-        sqlx::query(trimmed).execute(pool).await?;
+        match sqlx::query(trimmed).execute(pool).await {
+            Ok(_) => {}
+            Err(e) if is_duplicate_column(&e) => {}
+            Err(e) => return Err(e),
+        }
     }
 
     Ok(())
 }
 
+/// Whether an error is SQLite rejecting an `ADD COLUMN` that already ran.
+fn is_duplicate_column(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Database(db) if db.message().contains("duplicate column name"))
+}
+
+/// A `LIKE` pattern matching any value that contains `text`, for use with
+/// `ESCAPE '\'`. Wildcards in `text` match literally.
+pub fn like_pattern(text: &str) -> String {
+    format!(
+        "%{}%",
+        text.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
+
 /// Create an in-memory pool for testing.
 #[cfg(test)]
 pub async fn test_pool() -> SqlitePool {
@@ -83,7 +133,7 @@ mod tests {
     async fn test_init_pool_with_memory_url_succeeds() {
         // Use an in-memory DB via init_pool (exercises WAL mode attempt, which
         // is silently ignored for :memory: and still produces a working pool).
-        let pool = init_pool("sqlite::memory:")
+        let pool = init_pool("sqlite::memory:", 5)
             .await
             .expect("init_pool should succeed");
 
@@ -97,7 +147,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_init_pool_memory_url_enables_foreign_keys() {
-        let pool = init_pool("sqlite::memory:")
+        let pool = init_pool("sqlite::memory:", 5)
             .await
             .expect("init_pool should succeed");
 
@@ -110,6 +160,47 @@ mod tests {
         let _ = row;
     }
 
+    #[tokio::test]
+    async fn test_init_pool_honors_max_connections() {
+        let path = std::env::temp_dir().join(format!("pool-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite:{}?mode=rwc", path.display());
+        let pool = init_pool(&url, 3).await.expect("init_pool should succeed");
+        assert_eq!(pool.options().get_max_connections(), 3);
+
+        // More concurrent writers than connections queue for one and succeed
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    sqlx::query(
+                        "INSERT INTO users (id, username, password_hash) VALUES (?, ?, 'x')",
+                    )
+                    .bind(format!("id-{i}"))
+                    .bind(format!("user-{i}"))
+                    .execute(&pool)
+                    .await
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle
+                .await
+                .unwrap()
+                .expect("concurrent insert should succeed");
+        }
+        assert!(pool.size() <= 3);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 8);
+
+        pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
     #[tokio::test]
     async fn test_test_pool_has_all_tables() {
         let pool = test_pool().await;
@@ -121,6 +212,11 @@ mod tests {
             "calendar_shares",
             "sync_changes",
             "mcp_tokens",
+            "user_availability",
+            "availability_windows",
+            "deleted_objects",
+            "fired_alarms",
+            "sync_clients",
         ] {
             let query = format!("SELECT COUNT(*) FROM {table}");
             let row: (i64,) = sqlx::query_as(&query)
@@ -24,6 +24,36 @@ pub struct Calendar {
     pub sync_token: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// Comma-separated component types accepted for new objects, e.g. `VEVENT,VTODO`.
+    pub component_types: String,
+    /// Sort position set by clients through `calendar-order`; lower sorts first.
+    pub calendar_order: i64,
+    /// `opaque` (the default) or `transparent`, set by clients through
+    /// `schedule-calendar-transp`. Transparent calendars are never busy.
+    pub transp: String,
+}
+
+impl Calendar {
+    /// Whether the calendar's events are left out of free-busy time.
+    pub fn is_transparent(&self) -> bool {
+        self.transp == "transparent"
+    }
+
+    /// The component types this calendar accepts for new objects.
+    pub fn component_types(&self) -> Vec<&str> {
+        self.component_types
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .collect()
+    }
+
+    /// Whether a new object of `component` may be stored in this calendar.
+    pub fn accepts_component(&self, component: &str) -> bool {
+        self.component_types()
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(component))
+    }
 }
 
 /// A calendar object (VEVENT, VTODO, etc.) stored as raw iCalendar data.
@@ -72,6 +102,34 @@ pub struct McpToken {
     pub name: String,
     pub created_at: NaiveDateTime,
     pub expires_at: Option<NaiveDateTime>,
+    /// What the token may do: `"read"` or `"read-write"`.
+    pub scope: String,
+}
+
+impl McpToken {
+    /// The token's scope as a permission. An unrecognised scope is read-only.
+    pub fn permission(&self) -> Permission {
+        Permission::from_str_value(&self.scope).unwrap_or(Permission::Read)
+    }
+}
+
+/// A CalDAV client (identified by its User-Agent) that has made
+/// authenticated requests as a user.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SyncClient {
+    pub user_id: String,
+    pub user_agent: String,
+    pub first_seen: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+}
+
+/// A working-hours window on one day of the week, in the owner's
+/// availability timezone. Times are `HH:MM`; weekday 0 is Monday.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AvailabilityWindow {
+    pub weekday: i64,
+    pub start_time: String,
+    pub end_time: String,
 }
 
 /// Permission level for calendar sharing.
@@ -100,7 +158,6 @@ impl Permission {
     }
 
     /// Whether this permission allows write operations.
-    #[allow(dead_code)]
     pub fn can_write(&self) -> bool {
         matches!(self, Permission::ReadWrite)
     }
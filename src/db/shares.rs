@@ -95,7 +95,6 @@ pub async fn list_shared_calendars(
 }
 
 /// Check what permission a user has on a calendar (owner = ReadWrite, shared, or None).
-#[allow(dead_code)]
 pub async fn get_user_permission(
     pool: &SqlitePool,
     calendar_id: &str,
@@ -129,14 +128,16 @@ pub async fn get_user_permission(
 mod tests {
     use super::*;
     use crate::db;
-    use crate::db::{calendars, users};
+    use crate::db::calendars;
+    use crate::db::users::{self, PasswordPolicy};
 
     async fn setup() -> (SqlitePool, String, String, String) {
         let pool = db::test_pool().await;
-        let alice = users::create_user(&pool, "alice", None, "pass")
-            .await
-            .unwrap();
-        let bob = users::create_user(&pool, "bob", None, "pass")
+        let alice =
+            users::create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
+                .await
+                .unwrap();
+        let bob = users::create_user(&pool, "bob", None, "password", &PasswordPolicy::default())
             .await
             .unwrap();
         let cal = calendars::create_calendar(&pool, &alice.id, "Work", "", "#FF0000", "UTC")
@@ -214,6 +215,22 @@ mod tests {
         assert_eq!(perm, Some(Permission::Read));
     }
 
+    #[tokio::test]
+    async fn test_permission_distinguishes_read_only_share() {
+        let (pool, alice_id, bob_id, cal_id) = setup().await;
+
+        let owner = get_user_permission(&pool, &cal_id, &alice_id)
+            .await
+            .unwrap();
+        assert!(owner.is_some_and(|p| p.can_write()));
+        share_calendar(&pool, &cal_id, &bob_id, Permission::Read)
+            .await
+            .unwrap();
+        let sharee = get_user_permission(&pool, &cal_id, &bob_id).await.unwrap();
+        assert_eq!(sharee, Some(Permission::Read));
+        assert!(!sharee.unwrap().can_write());
+    }
+
     #[tokio::test]
     async fn test_no_permission() {
         let (pool, _, bob_id, cal_id) = setup().await;
@@ -0,0 +1,38 @@
+use sqlx::SqlitePool;
+
+use super::models::SyncClient;
+use crate::error::AppResult;
+
+/// Longest User-Agent stored; anything beyond is truncated.
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Record that `user_agent` made an authenticated request as `user_id`.
+/// `last_seen` is refreshed at most once a minute to keep busy clients from
+/// writing on every request.
+pub async fn record_client(pool: &SqlitePool, user_id: &str, user_agent: &str) -> AppResult<()> {
+    let user_agent = match user_agent.char_indices().nth(MAX_USER_AGENT_LEN) {
+        Some((end, _)) => &user_agent[..end],
+        None => user_agent,
+    };
+    sqlx::query(
+        "INSERT INTO sync_clients (user_id, user_agent) VALUES (?, ?)
+         ON CONFLICT (user_id, user_agent) DO UPDATE SET last_seen = datetime('now')
+         WHERE last_seen < datetime('now', '-60 seconds')",
+    )
+    .bind(user_id)
+    .bind(user_agent)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// List a user's clients, most recently seen first.
+pub async fn list_clients(pool: &SqlitePool, user_id: &str) -> AppResult<Vec<SyncClient>> {
+    let clients = sqlx::query_as::<_, SyncClient>(
+        "SELECT * FROM sync_clients WHERE user_id = ? ORDER BY last_seen DESC, user_agent",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(clients)
+}
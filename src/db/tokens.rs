@@ -3,31 +3,40 @@ use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 use base64::Engine;
+use chrono::NaiveDateTime;
 use rand::RngCore;
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use super::models::McpToken;
+use super::models::{McpToken, Permission};
 use crate::error::{AppError, AppResult};
 
-/// Create a new MCP token for a user. Returns the raw token (only shown once)
-/// and the stored record.
+/// Create a new MCP token for a user with the given scope, valid until
+/// `expires_at` (UTC) or forever when `None`. Returns the raw token (only
+/// shown once) and the stored record.
 pub async fn create_token(
     pool: &SqlitePool,
     user_id: &str,
     name: &str,
+    scope: Permission,
+    expires_at: Option<NaiveDateTime>,
 ) -> AppResult<(String, McpToken)> {
     let id = Uuid::now_v7().to_string();
     let raw_token = generate_raw_token();
     let token_hash = hash_token(&raw_token)?;
 
-    sqlx::query("INSERT INTO mcp_tokens (id, user_id, token_hash, name) VALUES (?, ?, ?, ?)")
-        .bind(&id)
-        .bind(user_id)
-        .bind(&token_hash)
-        .bind(name)
-        .execute(pool)
-        .await?;
+    sqlx::query(
+        "INSERT INTO mcp_tokens (id, user_id, token_hash, name, scope, expires_at) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(&token_hash)
+    .bind(name)
+    .bind(scope.as_str())
+    .bind(expires_at.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()))
+    .execute(pool)
+    .await?;
 
     let record = sqlx::query_as::<_, McpToken>("SELECT * FROM mcp_tokens WHERE id = ?")
         .bind(&id)
@@ -37,8 +46,12 @@ pub async fn create_token(
     Ok((raw_token, record))
 }
 
-/// Validate a raw token and return the associated user ID if valid.
-pub async fn validate_token(pool: &SqlitePool, raw_token: &str) -> AppResult<Option<String>> {
+/// Validate a raw token and return the associated user ID and the token's
+/// scope if valid.
+pub async fn validate_token(
+    pool: &SqlitePool,
+    raw_token: &str,
+) -> AppResult<Option<(String, Permission)>> {
     let tokens = sqlx::query_as::<_, McpToken>(
         "SELECT * FROM mcp_tokens WHERE expires_at IS NULL OR expires_at > datetime('now')",
     )
@@ -47,13 +60,39 @@ pub async fn validate_token(pool: &SqlitePool, raw_token: &str) -> AppResult<Opt
 
     for token in tokens {
         if verify_token(raw_token, &token.token_hash)? {
-            return Ok(Some(token.user_id));
+            let scope = token.permission();
+            return Ok(Some((token.user_id, scope)));
         }
     }
 
     Ok(None)
 }
 
+/// Replace a token's secret, keeping its ID, name and expiry. The old secret
+/// stops working immediately. Returns the new raw token (only shown once) and
+/// the updated record.
+pub async fn rotate_token(pool: &SqlitePool, token_id: &str) -> AppResult<(String, McpToken)> {
+    let raw_token = generate_raw_token();
+    let token_hash = hash_token(&raw_token)?;
+
+    let result = sqlx::query("UPDATE mcp_tokens SET token_hash = ? WHERE id = ?")
+        .bind(&token_hash)
+        .bind(token_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Token not found".to_string()));
+    }
+
+    let record = sqlx::query_as::<_, McpToken>("SELECT * FROM mcp_tokens WHERE id = ?")
+        .bind(token_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok((raw_token, record))
+}
+
 /// Delete an MCP token by ID.
 pub async fn delete_token(pool: &SqlitePool, token_id: &str) -> AppResult<()> {
     let result = sqlx::query("DELETE FROM mcp_tokens WHERE id = ?")
@@ -111,11 +150,11 @@ fn verify_token(token: &str, hash: &str) -> AppResult<bool> {
 mod tests {
     use super::*;
     use crate::db;
-    use crate::db::users;
+    use crate::db::users::{self, PasswordPolicy};
 
     async fn setup() -> (SqlitePool, String) {
         let pool = db::test_pool().await;
-        let user = users::create_user(&pool, "alice", None, "pass")
+        let user = users::create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
             .await
             .unwrap();
         (pool, user.id)
@@ -125,7 +164,10 @@ mod tests {
     async fn test_create_and_validate_token() {
         let (pool, user_id) = setup().await;
 
-        let (raw_token, record) = create_token(&pool, &user_id, "test-token").await.unwrap();
+        let (raw_token, record) =
+            create_token(&pool, &user_id, "test-token", Permission::ReadWrite, None)
+                .await
+                .unwrap();
 
         assert!(raw_token.starts_with("mcp_"));
         assert_eq!(record.name, "test-token");
@@ -133,14 +175,61 @@ mod tests {
 
         // Validate the token
         let validated_user = validate_token(&pool, &raw_token).await.unwrap();
-        assert_eq!(validated_user, Some(user_id));
+        assert_eq!(validated_user, Some((user_id, Permission::ReadWrite)));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_token_scope() {
+        let (pool, user_id) = setup().await;
+
+        let (raw_token, record) = create_token(&pool, &user_id, "viewer", Permission::Read, None)
+            .await
+            .unwrap();
+        assert_eq!(record.scope, "read");
+
+        let validated = validate_token(&pool, &raw_token).await.unwrap();
+        assert_eq!(validated, Some((user_id, Permission::Read)));
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_invalid() {
+        let (pool, user_id) = setup().await;
+        let now = chrono::Utc::now().naive_utc();
+
+        let (expired, _) = create_token(
+            &pool,
+            &user_id,
+            "old",
+            Permission::ReadWrite,
+            Some(now - chrono::Duration::hours(1)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(validate_token(&pool, &expired).await.unwrap(), None);
+
+        let (current, record) = create_token(
+            &pool,
+            &user_id,
+            "new",
+            Permission::ReadWrite,
+            Some(now + chrono::Duration::days(30)),
+        )
+        .await
+        .unwrap();
+        assert!(record.expires_at.is_some());
+        assert_eq!(
+            validate_token(&pool, &current).await.unwrap(),
+            Some((user_id, Permission::ReadWrite))
+        );
     }
 
     #[tokio::test]
     async fn test_invalid_token() {
         let (pool, user_id) = setup().await;
 
-        create_token(&pool, &user_id, "test").await.unwrap();
+        create_token(&pool, &user_id, "test", Permission::ReadWrite, None)
+            .await
+            .unwrap();
 
         let result = validate_token(&pool, "mcp_invalid_token").await.unwrap();
         assert_eq!(result, None);
@@ -150,7 +239,10 @@ mod tests {
     async fn test_delete_token() {
         let (pool, user_id) = setup().await;
 
-        let (raw_token, record) = create_token(&pool, &user_id, "test").await.unwrap();
+        let (raw_token, record) =
+            create_token(&pool, &user_id, "test", Permission::ReadWrite, None)
+                .await
+                .unwrap();
         delete_token(&pool, &record.id).await.unwrap();
 
         let result = validate_token(&pool, &raw_token).await.unwrap();
@@ -161,12 +253,40 @@ mod tests {
     async fn test_list_tokens() {
         let (pool, user_id) = setup().await;
 
-        create_token(&pool, &user_id, "token-1").await.unwrap();
-        create_token(&pool, &user_id, "token-2").await.unwrap();
+        create_token(&pool, &user_id, "token-1", Permission::ReadWrite, None)
+            .await
+            .unwrap();
+        create_token(&pool, &user_id, "token-2", Permission::ReadWrite, None)
+            .await
+            .unwrap();
 
         let tokens = list_tokens_for_user(&pool, &user_id).await.unwrap();
         assert_eq!(tokens.len(), 2);
         assert_eq!(tokens[0].name, "token-1");
         assert_eq!(tokens[1].name, "token-2");
     }
+
+    #[tokio::test]
+    async fn test_rotate_token_keeps_identity() {
+        let (pool, user_id) = setup().await;
+
+        let (old_token, record) =
+            create_token(&pool, &user_id, "laptop", Permission::ReadWrite, None)
+                .await
+                .unwrap();
+        let (new_token, rotated) = rotate_token(&pool, &record.id).await.unwrap();
+
+        assert_ne!(new_token, old_token);
+        assert_eq!(rotated.id, record.id);
+        assert_eq!(rotated.name, "laptop");
+        assert_eq!(rotated.created_at, record.created_at);
+
+        assert_eq!(validate_token(&pool, &old_token).await.unwrap(), None);
+        assert_eq!(
+            validate_token(&pool, &new_token).await.unwrap(),
+            Some((user_id, Permission::ReadWrite))
+        );
+
+        assert!(rotate_token(&pool, "nonexistent").await.is_err());
+    }
 }
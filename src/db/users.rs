@@ -8,13 +8,58 @@ use uuid::Uuid;
 use super::models::User;
 use crate::error::{AppError, AppResult};
 
+/// Password strength requirements enforced when setting a password.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// Minimum number of characters.
+    pub min_length: usize,
+    /// Require at least one lowercase letter, one uppercase letter and one digit.
+    pub require_complexity: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_complexity: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Check a candidate password, returning a BadRequest describing the
+    /// first unmet requirement.
+    pub fn validate(&self, password: &str) -> AppResult<()> {
+        if password.chars().count() < self.min_length {
+            return Err(AppError::BadRequest(format!(
+                "Password must be at least {} characters long",
+                self.min_length
+            )));
+        }
+        if self.require_complexity {
+            let has_lower = password.chars().any(|c| c.is_lowercase());
+            let has_upper = password.chars().any(|c| c.is_uppercase());
+            let has_digit = password.chars().any(|c| c.is_ascii_digit());
+            if !(has_lower && has_upper && has_digit) {
+                return Err(AppError::BadRequest(
+                    "Password must contain lowercase and uppercase letters and a digit".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Create a new user with a hashed password. Returns the created user.
+/// The password must satisfy `policy`.
 pub async fn create_user(
     pool: &SqlitePool,
     username: &str,
     email: Option<&str>,
     password: &str,
+    policy: &PasswordPolicy,
 ) -> AppResult<User> {
+    policy.validate(password)?;
     let id = Uuid::now_v7().to_string();
     let password_hash = hash_password(password)?;
 
@@ -65,12 +110,31 @@ pub async fn get_user_by_email(pool: &SqlitePool, email: &str) -> AppResult<Opti
     Ok(user)
 }
 
+/// Find users whose username or email contains `text`, ignoring ASCII
+/// case, ordered by username. At most `limit` users are returned.
+pub async fn search_users(pool: &SqlitePool, text: &str, limit: i64) -> AppResult<Vec<User>> {
+    let pattern = super::like_pattern(text);
+    let users = sqlx::query_as::<_, User>(
+        "SELECT * FROM users
+         WHERE username LIKE ?1 ESCAPE '\\' OR email LIKE ?1 ESCAPE '\\'
+         ORDER BY username LIMIT ?2",
+    )
+    .bind(pattern)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(users)
+}
+
 /// Reset a user's password by hashing the new password and updating the DB.
+/// The new password must satisfy `policy`.
 pub async fn reset_password(
     pool: &SqlitePool,
     username: &str,
     new_password: &str,
+    policy: &PasswordPolicy,
 ) -> AppResult<()> {
+    policy.validate(new_password)?;
     let hash = hash_password(new_password)?;
     let rows = sqlx::query("UPDATE users SET password_hash = ? WHERE username = ?")
         .bind(&hash)
@@ -87,6 +151,50 @@ pub async fn reset_password(
     }
 }
 
+/// Tables whose rows belong to a user, emptied before the user row.
+const USER_DEPENDENTS: &[&str] = &[
+    "calendar_shares",
+    "mcp_tokens",
+    "availability_windows",
+    "user_availability",
+    "sync_clients",
+];
+
+/// Delete a user along with the calendars they own, everything in those
+/// calendars, and their shares, tokens, working hours and sync clients, in
+/// one transaction.
+pub async fn delete_user(pool: &SqlitePool, username: &str) -> AppResult<()> {
+    let user = get_user_by_username(pool, username)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("User '{username}' not found")))?;
+
+    let mut tx = pool.begin().await?;
+    for table in super::calendars::CALENDAR_DEPENDENTS {
+        sqlx::query(&format!(
+            "DELETE FROM {table} WHERE calendar_id IN (SELECT id FROM calendars WHERE owner_id = ?)"
+        ))
+        .bind(&user.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM calendars WHERE owner_id = ?")
+        .bind(&user.id)
+        .execute(&mut *tx)
+        .await?;
+    for table in USER_DEPENDENTS {
+        sqlx::query(&format!("DELETE FROM {table} WHERE user_id = ?"))
+            .bind(&user.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(&user.id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
 /// Verify a password against a user's stored hash. Returns the user if valid.
 /// Accepts either username or email as the login identifier.
 pub async fn verify_user(
@@ -138,9 +246,15 @@ mod tests {
     async fn test_create_and_get_user() {
         let pool = db::test_pool().await;
 
-        let user = create_user(&pool, "alice", Some("alice@example.com"), "password123")
-            .await
-            .unwrap();
+        let user = create_user(
+            &pool,
+            "alice",
+            Some("alice@example.com"),
+            "password123",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
 
         assert_eq!(user.username, "alice");
         assert_eq!(user.email.as_deref(), Some("alice@example.com"));
@@ -154,8 +268,23 @@ mod tests {
     async fn test_duplicate_username_fails() {
         let pool = db::test_pool().await;
 
-        create_user(&pool, "alice", None, "pass1").await.unwrap();
-        let result = create_user(&pool, "alice", None, "pass2").await;
+        create_user(
+            &pool,
+            "alice",
+            None,
+            "password1",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let result = create_user(
+            &pool,
+            "alice",
+            None,
+            "password2",
+            &PasswordPolicy::default(),
+        )
+        .await;
 
         assert!(matches!(result, Err(AppError::Conflict(_))));
     }
@@ -164,9 +293,15 @@ mod tests {
     async fn test_verify_correct_password() {
         let pool = db::test_pool().await;
 
-        create_user(&pool, "alice", None, "secret123")
-            .await
-            .unwrap();
+        create_user(
+            &pool,
+            "alice",
+            None,
+            "secret123",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
         let user = verify_user(&pool, "alice", "secret123").await.unwrap();
 
         assert!(user.is_some());
@@ -177,9 +312,15 @@ mod tests {
     async fn test_verify_wrong_password() {
         let pool = db::test_pool().await;
 
-        create_user(&pool, "alice", None, "secret123")
-            .await
-            .unwrap();
+        create_user(
+            &pool,
+            "alice",
+            None,
+            "secret123",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
         let user = verify_user(&pool, "alice", "wrong").await.unwrap();
 
         assert!(user.is_none());
@@ -197,9 +338,172 @@ mod tests {
     async fn test_get_user_by_id() {
         let pool = db::test_pool().await;
 
-        let created = create_user(&pool, "alice", None, "pass").await.unwrap();
+        let created = create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
         let fetched = get_user_by_id(&pool, &created.id).await.unwrap().unwrap();
 
         assert_eq!(fetched.username, "alice");
     }
+
+    #[tokio::test]
+    async fn test_short_password_rejected() {
+        let pool = db::test_pool().await;
+
+        let result = create_user(&pool, "alice", None, "abc", &PasswordPolicy::default()).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert!(
+            get_user_by_username(&pool, "alice")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compliant_password_accepted() {
+        let pool = db::test_pool().await;
+        let policy = PasswordPolicy {
+            min_length: 10,
+            require_complexity: true,
+        };
+
+        let weak = create_user(&pool, "alice", None, "alllowercase1", &policy).await;
+        assert!(matches!(weak, Err(AppError::BadRequest(_))));
+
+        let user = create_user(&pool, "alice", None, "Correct-Horse-9", &policy)
+            .await
+            .unwrap();
+        assert_eq!(user.username, "alice");
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_enforces_policy() {
+        let pool = db::test_pool().await;
+        let policy = PasswordPolicy::default();
+        create_user(&pool, "alice", None, "password", &policy)
+            .await
+            .unwrap();
+
+        let result = reset_password(&pool, "alice", "short", &policy).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert!(
+            verify_user(&pool, "alice", "password")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        reset_password(&pool, "alice", "new-password", &policy)
+            .await
+            .unwrap();
+        assert!(
+            verify_user(&pool, "alice", "new-password")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_removes_owned_rows() {
+        use crate::db::events::{self, ObjectFields};
+        use crate::db::models::{AvailabilityWindow, Permission};
+        use crate::db::{availability, calendars, shares, sync_clients, tokens};
+
+        let pool = db::test_pool().await;
+        let policy = PasswordPolicy::default();
+        let alice = create_user(&pool, "alice", None, "password", &policy)
+            .await
+            .unwrap();
+        let bob = create_user(&pool, "bob", None, "password", &policy)
+            .await
+            .unwrap();
+        let cal = calendars::create_calendar(&pool, &alice.id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let bobs = calendars::create_calendar(&pool, &bob.id, "Bob's", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let fields = || ObjectFields {
+            component_type: "VEVENT",
+            dtstart: Some("20260301T090000Z"),
+            dtend: Some("20260301T100000Z"),
+            summary: Some("Meeting"),
+        };
+        let ical = "BEGIN:VCALENDAR\r\nEND:VCALENDAR";
+        events::upsert_object(&pool, &cal.id, "keep", ical, fields())
+            .await
+            .unwrap();
+        events::upsert_object(&pool, &cal.id, "gone", ical, fields())
+            .await
+            .unwrap();
+        events::delete_object(&pool, &cal.id, "gone").await.unwrap();
+        shares::share_calendar(&pool, &cal.id, &bob.id, Permission::Read)
+            .await
+            .unwrap();
+        shares::share_calendar(&pool, &bobs.id, &alice.id, Permission::Read)
+            .await
+            .unwrap();
+        tokens::create_token(&pool, &alice.id, "laptop", Permission::ReadWrite, None)
+            .await
+            .unwrap();
+        let window = AvailabilityWindow {
+            weekday: 0,
+            start_time: "09:00".to_string(),
+            end_time: "17:00".to_string(),
+        };
+        availability::set_availability(&pool, &alice.id, "UTC", &[window])
+            .await
+            .unwrap();
+        sync_clients::record_client(&pool, &alice.id, "DAVx5")
+            .await
+            .unwrap();
+
+        delete_user(&pool, "alice").await.unwrap();
+
+        assert!(get_user_by_id(&pool, &alice.id).await.unwrap().is_none());
+        assert!(
+            calendars::get_calendar_by_id(&pool, &cal.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        for table in ["calendar_objects", "sync_changes", "deleted_objects"] {
+            let count: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {table} WHERE calendar_id = ?"
+            ))
+            .bind(&cal.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(count, 0, "{table} still has rows for alice's calendar");
+        }
+        for table in USER_DEPENDENTS {
+            let count: i64 =
+                sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table} WHERE user_id = ?"))
+                    .bind(&alice.id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(count, 0, "{table} still has rows for alice");
+        }
+        let shares_left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM calendar_shares")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(shares_left, 0);
+
+        // Other users are untouched
+        assert!(get_user_by_id(&pool, &bob.id).await.unwrap().is_some());
+        assert!(
+            calendars::get_calendar_by_id(&pool, &bobs.id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let missing = delete_user(&pool, "alice").await;
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+    }
 }
@@ -1,12 +1,26 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
 use uuid::Uuid;
 
+use super::datetime::IcalTime;
+use super::parser::{Component, Property, parse_calendar};
+
 /// Build a minimal VCALENDAR wrapping a VEVENT.
 ///
 /// If `timezone` is `Some("America/Los_Angeles")` (or any IANA tz name), the
-/// DTSTART/DTEND lines are emitted as `DTSTART;TZID=…` and a minimal
+/// DTSTART/DTEND lines are emitted as `DTSTART;TZID=…` and a matching
 /// VTIMEZONE component is included.  When `timezone` is `None` the values are
 /// written verbatim (caller is responsible for supplying a UTC `Z`-suffixed
 /// value or any other valid iCal datetime string).
+///
+/// A `dtstart` that is a plain date (`20260301`) makes an all-day event:
+/// both values are written with `VALUE=DATE` and `timezone` is ignored, since
+/// dates have no time to anchor. `dtend` is then the exclusive end date.
+///
+/// `geo` is a `(latitude, longitude)` pair written as a `GEO` property, and
+/// `url` (e.g. a video-call link) is written as a `URL` property. `rrule` is
+/// an RRULE value such as `FREQ=WEEKLY;BYDAY=MO`, making the event recurring.
+#[allow(clippy::too_many_arguments)]
 pub fn build_vevent(
     uid: &str,
     summary: &str,
@@ -14,6 +28,9 @@ pub fn build_vevent(
     dtend: &str,
     description: Option<&str>,
     location: Option<&str>,
+    geo: Option<(f64, f64)>,
+    url: Option<&str>,
+    rrule: Option<&str>,
     timezone: Option<&str>,
 ) -> String {
     let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
@@ -24,85 +41,272 @@ pub fn build_vevent(
         "PRODID:-//CalDAV Server//EN".to_string(),
     ];
 
+    let all_day = IcalTime::parse(dtstart).is_some_and(|t| t.date_only);
+    let timezone = timezone.filter(|_| !all_day);
     if let Some(tz) = timezone {
-        // Minimal VTIMEZONE — enough for Apple Calendar / RFC 5545 compliance.
-        // Using RRULE-based definitions so the component stays compact while
-        // correctly representing DST transitions for common US timezones.
-        lines.push("BEGIN:VTIMEZONE".to_string());
-        lines.push(format!("TZID:{tz}"));
-        lines.push("BEGIN:STANDARD".to_string());
-        lines.push("DTSTART:19671029T020000".to_string());
-        lines.push("RRULE:FREQ=YEARLY;BYDAY=1SU;BYMONTH=11".to_string());
-        lines.push(vtimezone_std_offset(tz));
-        lines.push(vtimezone_dst_offset(tz));
-        lines.push("END:STANDARD".to_string());
-        lines.push("BEGIN:DAYLIGHT".to_string());
-        lines.push("DTSTART:20070311T020000".to_string());
-        lines.push("RRULE:FREQ=YEARLY;BYDAY=2SU;BYMONTH=3".to_string());
-        lines.push(vtimezone_dst_offset(tz));
-        lines.push(vtimezone_std_offset(tz));
-        lines.push("END:DAYLIGHT".to_string());
-        lines.push("END:VTIMEZONE".to_string());
+        lines.extend(build_vtimezone(tz));
     }
 
     lines.push("BEGIN:VEVENT".to_string());
     lines.push(format!("UID:{uid}"));
     lines.push(format!("DTSTAMP:{now}"));
 
-    if let Some(tz) = timezone {
+    if all_day {
+        lines.push(format!("DTSTART;VALUE=DATE:{dtstart}"));
+        lines.push(format!("DTEND;VALUE=DATE:{dtend}"));
+    } else if let Some(tz) = timezone {
         lines.push(format!("DTSTART;TZID={tz}:{dtstart}"));
         lines.push(format!("DTEND;TZID={tz}:{dtend}"));
     } else {
         lines.push(format!("DTSTART:{dtstart}"));
         lines.push(format!("DTEND:{dtend}"));
     }
+    if let Some(rrule) = rrule {
+        lines.push(format!("RRULE:{rrule}"));
+    }
 
-    lines.push(format!("SUMMARY:{summary}"));
+    lines.push(format!("SUMMARY:{}", escape_text(summary)));
 
     if let Some(desc) = description {
-        lines.push(format!("DESCRIPTION:{desc}"));
+        lines.push(format!("DESCRIPTION:{}", escape_text(desc)));
     }
     if let Some(loc) = location {
-        lines.push(format!("LOCATION:{loc}"));
+        lines.push(format!("LOCATION:{}", escape_text(loc)));
+    }
+    if let Some((lat, lon)) = geo {
+        lines.push(format!("GEO:{lat};{lon}"));
+    }
+    if let Some(url) = url {
+        lines.push(format!("URL:{url}"));
     }
 
     lines.push("END:VEVENT".to_string());
     lines.push("END:VCALENDAR".to_string());
 
-    lines.join("\r\n") + "\r\n"
+    let folded: Vec<String> = lines.iter().map(|line| fold_line(line)).collect();
+    folded.join("\r\n") + "\r\n"
 }
 
-/// Returns the TZOFFSETFROM line for the standard (winter) period of a timezone.
-fn vtimezone_std_offset(tz: &str) -> String {
-    let offset = match tz {
-        "America/Los_Angeles" | "America/Vancouver" => "-0800",
-        "America/Denver" | "America/Phoenix" => "-0700",
-        "America/Chicago" => "-0600",
-        "America/New_York" | "America/Toronto" => "-0500",
-        "Europe/London" => "+0000",
-        "Europe/Paris" | "Europe/Berlin" | "Europe/Rome" => "+0100",
-        "Asia/Tokyo" => "+0900",
-        "Australia/Sydney" => "+1100",
-        _ => "+0000",
-    };
-    format!("TZOFFSETFROM:{offset}")
+/// Escape a TEXT value for an iCalendar content line (RFC 5545 §3.3.11).
+/// [`super::parser::unescape_text`] reverses it.
+pub fn escape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
 }
 
-/// Returns the TZOFFSETTO line for the daylight-saving (summer) period of a timezone.
-fn vtimezone_dst_offset(tz: &str) -> String {
-    let offset = match tz {
-        "America/Los_Angeles" | "America/Vancouver" => "-0700",
-        "America/Denver" => "-0600",
-        "America/Chicago" => "-0500",
-        "America/New_York" | "America/Toronto" => "-0400",
-        "Europe/London" => "+0100",
-        "Europe/Paris" | "Europe/Berlin" | "Europe/Rome" => "+0200",
-        // Tokyo and Phoenix don't observe DST — use the same offset
-        "Asia/Tokyo" | "America/Phoenix" => "+0900",
-        "Australia/Sydney" => "+1100",
-        _ => "+0000",
+/// Fold a content line at 75 octets (RFC 5545 §3.1): each continuation
+/// starts with CRLF and a space. Breaks fall between characters, so a UTF-8
+/// sequence is never split.
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 25);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+/// Build a minimal VCALENDAR wrapping a VTODO.
+///
+/// `due` is written verbatim as `DUE`, or with `VALUE=DATE` when it is a
+/// plain date. `status` is a VTODO status such as `NEEDS-ACTION` or
+/// `COMPLETED`, and `percent_complete` (0-100) becomes `PERCENT-COMPLETE`.
+pub fn build_vtodo(
+    uid: &str,
+    summary: &str,
+    due: Option<&str>,
+    status: Option<&str>,
+    percent_complete: Option<u8>,
+) -> String {
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//CalDAV Server//EN".to_string(),
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{uid}"),
+        format!("DTSTAMP:{now}"),
+    ];
+
+    if let Some(due) = due {
+        if IcalTime::parse(due).is_some_and(|t| t.date_only) {
+            lines.push(format!("DUE;VALUE=DATE:{due}"));
+        } else {
+            lines.push(format!("DUE:{due}"));
+        }
+    }
+
+    lines.push(format!("SUMMARY:{}", escape_text(summary)));
+
+    if let Some(status) = status {
+        lines.push(format!("STATUS:{status}"));
+    }
+    if let Some(percent) = percent_complete {
+        lines.push(format!("PERCENT-COMPLETE:{percent}"));
+    }
+
+    lines.push("END:VTODO".to_string());
+    lines.push("END:VCALENDAR".to_string());
+
+    let folded: Vec<String> = lines.iter().map(|line| fold_line(line)).collect();
+    folded.join("\r\n") + "\r\n"
+}
+
+/// Build the lines of a VTIMEZONE component for `tz` from the tz database.
+///
+/// Observances are derived from the zone's offset changes in the current
+/// year and written as yearly RRULEs, so each zone gets its own offsets and
+/// transition dates (southern-hemisphere DAYLIGHT falls in October, not
+/// March). A zone without DST gets a single STANDARD observance, and a TZID
+/// unknown to the tz database falls back to UTC.
+pub fn build_vtimezone(tz: &str) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{tz}")];
+    let zone = tz.parse::<Tz>().ok();
+    let transitions = zone
+        .map(|z| yearly_transitions(z, Utc::now().year()))
+        .unwrap_or_default();
+
+    if transitions.is_empty() {
+        let offset = zone.map(|z| z.offset_from_utc_datetime(&Utc::now().naive_utc()));
+        let seconds = offset.map(|o| o.fix().local_minus_utc()).unwrap_or(0);
+        lines.push("BEGIN:STANDARD".to_string());
+        lines.push("DTSTART:19700101T000000".to_string());
+        lines.push(format!("TZOFFSETFROM:{}", format_offset(seconds)));
+        lines.push(format!("TZOFFSETTO:{}", format_offset(seconds)));
+        if let Some(offset) = offset {
+            lines.push(format!("TZNAME:{offset}"));
+        }
+        lines.push("END:STANDARD".to_string());
+    }
+
+    for t in &transitions {
+        let kind = if t.to > t.from {
+            "DAYLIGHT"
+        } else {
+            "STANDARD"
+        };
+        let date = t.local.date();
+        let last = date + Duration::days(7);
+        let week = if last.month() != date.month() {
+            -1
+        } else {
+            date.day0() as i32 / 7 + 1
+        };
+        let first = nth_weekday(1970, date.month(), date.weekday(), week)
+            .unwrap_or(date)
+            .and_time(t.local.time());
+        lines.push(format!("BEGIN:{kind}"));
+        lines.push(format!("DTSTART:{}", first.format("%Y%m%dT%H%M%S")));
+        lines.push(format!(
+            "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={week}{}",
+            date.month(),
+            weekday_code(date.weekday())
+        ));
+        lines.push(format!("TZOFFSETFROM:{}", format_offset(t.from)));
+        lines.push(format!("TZOFFSETTO:{}", format_offset(t.to)));
+        lines.push(format!("TZNAME:{}", t.name));
+        lines.push(format!("END:{kind}"));
+    }
+
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+/// A change of UTC offset within a zone.
+struct Transition {
+    /// Wall-clock time at which the change happens, in the old offset.
+    local: NaiveDateTime,
+    /// Offset before the change, in seconds east of UTC.
+    from: i32,
+    /// Offset after the change, in seconds east of UTC.
+    to: i32,
+    /// Abbreviation in effect after the change, e.g. `AEDT`.
+    name: String,
+}
+
+/// The offset changes of `zone` during `year`, found by comparing daily
+/// offsets and narrowing each change down to the second.
+fn yearly_transitions(zone: Tz, year: i32) -> Vec<Transition> {
+    let offset = |t: NaiveDateTime| zone.offset_from_utc_datetime(&t).fix().local_minus_utc();
+    let Some(mut day) = NaiveDate::from_ymd_opt(year, 1, 1).and_then(|d| d.and_hms_opt(0, 0, 0))
+    else {
+        return Vec::new();
     };
-    format!("TZOFFSETTO:{offset}")
+
+    let mut transitions = Vec::new();
+    while day.year() == year {
+        let next = day + Duration::days(1);
+        if offset(day) != offset(next) {
+            let (mut lo, mut hi) = (day, next);
+            while hi - lo > Duration::seconds(1) {
+                let mid = lo + (hi - lo) / 2;
+                if offset(mid) == offset(lo) {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            let from = offset(lo);
+            transitions.push(Transition {
+                local: hi + Duration::seconds(from.into()),
+                from,
+                to: offset(hi),
+                name: zone.offset_from_utc_datetime(&hi).to_string(),
+            });
+        }
+        day = next;
+    }
+    transitions
+}
+
+/// The `n`th `weekday` of a month, counting from the end when `n` is -1.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: i32) -> Option<NaiveDate> {
+    if n > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8);
+    }
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .or_else(|| NaiveDate::from_weekday_of_month_opt(year, month, weekday, 4))
+}
+
+/// The two-letter RRULE code for a weekday.
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// Format a UTC offset in seconds as `+HHMM` (or `+HHMMSS` when needed).
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let abs = seconds.unsigned_abs();
+    let (hours, minutes, secs) = (abs / 3600, abs / 60 % 60, abs % 60);
+    if secs == 0 {
+        format!("{sign}{hours:02}{minutes:02}")
+    } else {
+        format!("{sign}{hours:02}{minutes:02}{secs:02}")
+    }
 }
 
 /// Generate a new unique event UID.
@@ -110,6 +314,76 @@ pub fn generate_uid() -> String {
     format!("{}@caldav-server", Uuid::new_v4())
 }
 
+/// Merge stored calendar objects into a single VCALENDAR for export.
+///
+/// The result has one VERSION/PRODID header (plus `X-WR-CALNAME` when `name`
+/// is given), every VTIMEZONE once per TZID, then every other component of
+/// each object in order. Data without a VCALENDAR is skipped, and lines are
+/// re-folded at 75 octets since parsing unfolds them.
+pub fn merge_calendars<'a>(
+    objects: impl IntoIterator<Item = &'a str>,
+    name: Option<&str>,
+) -> String {
+    let mut merged = Component::new("VCALENDAR");
+    merged.properties.push(Property::new("VERSION", "2.0"));
+    merged
+        .properties
+        .push(Property::new("PRODID", "-//CalDAV Server//EN"));
+    if let Some(name) = name {
+        merged
+            .properties
+            .push(Property::new("X-WR-CALNAME", &escape_text(name)));
+    }
+
+    let mut timezones: Vec<Component> = Vec::new();
+    let mut components = Vec::new();
+    for data in objects {
+        let Some(calendar) = parse_calendar(data) else {
+            continue;
+        };
+        for child in calendar.components {
+            if child.name != "VTIMEZONE" {
+                components.push(child);
+                continue;
+            }
+            let tzid = child.property_value("TZID");
+            if !timezones.iter().any(|tz| tz.property_value("TZID") == tzid) {
+                timezones.push(child);
+            }
+        }
+    }
+    merged.components = timezones;
+    merged.components.extend(components);
+
+    merged
+        .to_ical()
+        .split_terminator("\r\n")
+        .map(|line| fold_line(line) + "\r\n")
+        .collect()
+}
+
+/// File name for a calendar export: the display name with characters that
+/// are unsafe in file names replaced by `_`, plus `.ics`.
+pub fn export_filename(calendar_name: &str) -> String {
+    let stem: String = calendar_name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let stem = stem.trim_matches('.');
+    if stem.is_empty() {
+        "calendar.ics".to_string()
+    } else {
+        format!("{stem}.ics")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,6 +398,9 @@ mod tests {
             Some("A description"),
             Some("Room 101"),
             None,
+            None,
+            None,
+            None,
         );
 
         assert!(ical.contains("BEGIN:VCALENDAR"));
@@ -137,6 +414,62 @@ mod tests {
         assert!(!ical.contains("VTIMEZONE"));
     }
 
+    #[test]
+    fn test_build_vevent_with_geo() {
+        let ical = build_vevent(
+            "geo-uid@example.com",
+            "Site visit",
+            "20260301T090000Z",
+            "20260301T100000Z",
+            None,
+            Some("Ferry Building"),
+            Some((37.7955, -122.3937)),
+            None,
+            None,
+            None,
+        );
+
+        assert!(ical.contains("GEO:37.7955;-122.3937"));
+    }
+
+    #[test]
+    fn test_build_vevent_with_url() {
+        let ical = build_vevent(
+            "url-uid@example.com",
+            "Standup",
+            "20260301T090000Z",
+            "20260301T091500Z",
+            None,
+            None,
+            None,
+            Some("https://meet.example.com/abc"),
+            None,
+            None,
+        );
+
+        assert!(ical.contains("URL:https://meet.example.com/abc\r\n"));
+    }
+
+    #[test]
+    fn test_build_vevent_with_rrule() {
+        let ical = build_vevent(
+            "rrule-uid@example.com",
+            "Standup",
+            "20260302T090000",
+            "20260302T091500",
+            None,
+            None,
+            None,
+            None,
+            Some("FREQ=WEEKLY;BYDAY=MO"),
+            Some("America/New_York"),
+        );
+
+        assert!(ical.contains(
+            "DTEND;TZID=America/New_York:20260302T091500\r\nRRULE:FREQ=WEEKLY;BYDAY=MO\r\n"
+        ));
+    }
+
     #[test]
     fn test_build_vevent_minimal() {
         let ical = build_vevent(
@@ -147,6 +480,9 @@ mod tests {
             None,
             None,
             None,
+            None,
+            None,
+            None,
         );
 
         assert!(ical.contains("UID:min-uid@example.com"));
@@ -163,6 +499,9 @@ mod tests {
             "20260301T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("America/Los_Angeles"),
         );
 
@@ -173,6 +512,36 @@ mod tests {
         assert!(!ical.contains("DTSTART:20260301")); // should not appear without TZID
     }
 
+    #[test]
+    fn test_build_vtodo() {
+        let ical = build_vtodo(
+            "todo-uid@example.com",
+            "File taxes",
+            Some("20260415T170000Z"),
+            Some("IN-PROCESS"),
+            Some(40),
+        );
+
+        assert!(ical.contains("BEGIN:VTODO\r\n"));
+        assert!(ical.contains("UID:todo-uid@example.com"));
+        assert!(ical.contains("DUE:20260415T170000Z\r\n"));
+        assert!(ical.contains("SUMMARY:File taxes"));
+        assert!(ical.contains("STATUS:IN-PROCESS"));
+        assert!(ical.contains("PERCENT-COMPLETE:40"));
+        assert!(!ical.contains("VEVENT"));
+        assert!(!ical.contains("DTSTART"));
+
+        let fields = crate::ical::parser::extract_fields(&ical);
+        assert_eq!(fields.component_type, "VTODO");
+        assert_eq!(fields.dtend.as_deref(), Some("20260415T170000Z"));
+
+        let minimal = build_vtodo("t", "Someday", None, None, None);
+        assert!(!minimal.contains("DUE"));
+        assert!(!minimal.contains("STATUS:"));
+        let dated = build_vtodo("t", "Trip", Some("20260501"), None, None);
+        assert!(dated.contains("DUE;VALUE=DATE:20260501\r\n"));
+    }
+
     #[test]
     fn test_generate_uid() {
         let uid = generate_uid();
@@ -196,6 +565,9 @@ mod tests {
             "20260301T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("America/New_York"),
         );
         assert!(ical.contains("TZID:America/New_York"));
@@ -212,6 +584,9 @@ mod tests {
             "20260301T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("America/Chicago"),
         );
         assert!(ical.contains("TZID:America/Chicago"));
@@ -228,6 +603,9 @@ mod tests {
             "20260601T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("Europe/London"),
         );
         assert!(ical.contains("TZID:Europe/London"));
@@ -244,6 +622,9 @@ mod tests {
             "20260601T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("Europe/Paris"),
         );
         assert!(ical.contains("TZID:Europe/Paris"));
@@ -260,6 +641,9 @@ mod tests {
             "20260601T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("Asia/Tokyo"),
         );
         assert!(ical.contains("TZID:Asia/Tokyo"));
@@ -275,6 +659,9 @@ mod tests {
             "20260601T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("Pacific/Fake"),
         );
         assert!(ical.contains("TZID:Pacific/Fake"));
@@ -291,6 +678,9 @@ mod tests {
             "20260301T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("America/Denver"),
         );
         assert!(ical.contains("TZID:America/Denver"));
@@ -307,6 +697,9 @@ mod tests {
             "20260601T100000",
             None,
             None,
+            None,
+            None,
+            None,
             Some("America/Phoenix"),
         );
         assert!(ical.contains("TZID:America/Phoenix"));
@@ -314,6 +707,159 @@ mod tests {
         assert!(ical.contains("TZOFFSETFROM:-0700"));
     }
 
+    #[test]
+    fn test_vtimezone_sydney_has_southern_hemisphere_dst() {
+        let vtz = build_vtimezone("Australia/Sydney").join("\r\n");
+        assert!(
+            vtz.contains(
+                "BEGIN:DAYLIGHT\r\nDTSTART:19701004T020000\r\n\
+                 RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=1SU\r\n\
+                 TZOFFSETFROM:+1000\r\nTZOFFSETTO:+1100\r\nTZNAME:AEDT"
+            ),
+            "got: {vtz}"
+        );
+        assert!(
+            vtz.contains(
+                "BEGIN:STANDARD\r\nDTSTART:19700405T030000\r\n\
+                 RRULE:FREQ=YEARLY;BYMONTH=4;BYDAY=1SU\r\n\
+                 TZOFFSETFROM:+1100\r\nTZOFFSETTO:+1000\r\nTZNAME:AEST"
+            ),
+            "got: {vtz}"
+        );
+        assert!(!vtz.contains("BYMONTH=3"), "got: {vtz}");
+    }
+
+    #[test]
+    fn test_vtimezone_auckland_uses_its_own_rules() {
+        let vtz = build_vtimezone("Pacific/Auckland").join("\r\n");
+        assert!(
+            vtz.contains(
+                "BEGIN:DAYLIGHT\r\nDTSTART:19700927T020000\r\n\
+                 RRULE:FREQ=YEARLY;BYMONTH=9;BYDAY=-1SU\r\n\
+                 TZOFFSETFROM:+1200\r\nTZOFFSETTO:+1300"
+            ),
+            "got: {vtz}"
+        );
+        assert!(
+            vtz.contains(
+                "BEGIN:STANDARD\r\nDTSTART:19700405T030000\r\n\
+                 RRULE:FREQ=YEARLY;BYMONTH=4;BYDAY=1SU\r\n\
+                 TZOFFSETFROM:+1300\r\nTZOFFSETTO:+1200"
+            ),
+            "got: {vtz}"
+        );
+        assert!(!vtz.contains("BYMONTH=11"), "got: {vtz}");
+    }
+
+    #[test]
+    fn test_format_offset() {
+        assert_eq!(format_offset(0), "+0000");
+        assert_eq!(format_offset(-5 * 3600), "-0500");
+        assert_eq!(format_offset(5 * 3600 + 45 * 60), "+0545");
+        assert_eq!(format_offset(-(3600 + 30)), "-010030");
+    }
+
+    #[test]
+    fn test_build_vevent_all_day_uses_value_date() {
+        let ical = build_vevent(
+            "all-day@example.com",
+            "Holiday",
+            "20260301",
+            "20260302",
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("America/New_York"),
+        );
+        assert!(ical.contains("DTSTART;VALUE=DATE:20260301\r\n"));
+        assert!(ical.contains("DTEND;VALUE=DATE:20260302\r\n"));
+        assert!(!ical.contains("VTIMEZONE"));
+        assert!(!ical.contains("TZID"));
+    }
+
+    #[test]
+    fn test_build_vevent_folds_long_lines() {
+        let description: String = "Agenda: budget review, hiring plan, café offsite. "
+            .repeat(6)
+            .chars()
+            .take(299)
+            .collect();
+        let ical = build_vevent(
+            "fold@example.com",
+            "Planning",
+            "20260301T090000Z",
+            "20260301T100000Z",
+            Some(&description),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        for line in ical.split("\r\n") {
+            assert!(line.len() <= 75, "line of {} octets: {line}", line.len());
+        }
+        assert!(ical.contains("\r\n "));
+        let unfolded = ical.replace("\r\n ", "");
+        let escaped = escape_text(&description);
+        assert!(unfolded.contains(&format!("DESCRIPTION:{escaped}\r\n")));
+        let calendar = super::super::parser::parse_calendar(&ical).unwrap();
+        assert_eq!(
+            calendar.components[0].property_value("DESCRIPTION"),
+            Some(escaped.as_str())
+        );
+    }
+
+    #[test]
+    fn test_build_vevent_escapes_text_values() {
+        let description = "Agenda:\n1. Budget; hiring\n2. C:\\shared\\plans";
+        let ical = build_vevent(
+            "escape@example.com",
+            "Meeting, with, commas",
+            "20260301T090000Z",
+            "20260301T100000Z",
+            Some(description),
+            Some("Room 1; Building B"),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(ical.contains("SUMMARY:Meeting\\, with\\, commas\r\n"));
+        assert!(
+            ical.contains(
+                "DESCRIPTION:Agenda:\\n1. Budget\\; hiring\\n2. C:\\\\shared\\\\plans\r\n"
+            )
+        );
+        assert!(ical.contains("LOCATION:Room 1\\; Building B\r\n"));
+
+        let fields = super::super::parser::extract_fields(&ical);
+        assert_eq!(fields.summary.as_deref(), Some("Meeting, with, commas"));
+        let calendar = super::super::parser::parse_calendar(&ical).unwrap();
+        let event = &calendar.components[0];
+        let text = |name| {
+            event
+                .property_value(name)
+                .map(super::super::parser::unescape_text)
+        };
+        assert_eq!(text("DESCRIPTION").as_deref(), Some(description));
+        assert_eq!(text("LOCATION").as_deref(), Some("Room 1; Building B"));
+    }
+
+    #[test]
+    fn test_fold_line_keeps_multibyte_characters_whole() {
+        let line = format!("SUMMARY:{}", "é".repeat(80));
+        let folded = fold_line(&line);
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), line);
+        assert_eq!(fold_line("SUMMARY:Short"), "SUMMARY:Short");
+    }
+
     #[test]
     fn test_vevent_output_ends_with_crlf() {
         let ical = build_vevent(
@@ -324,7 +870,55 @@ mod tests {
             None,
             None,
             None,
+            None,
+            None,
+            None,
         );
         assert!(ical.ends_with("\r\n"), "iCal output must end with CRLF");
     }
+
+    #[test]
+    fn test_merge_calendars_single_header_and_shared_timezones() {
+        let first = build_vevent(
+            "a@test.com",
+            "A",
+            "20260301T090000",
+            "20260301T100000",
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("Europe/Berlin"),
+        );
+        let second = build_vevent(
+            "b@test.com",
+            "B",
+            "20260302T090000",
+            "20260302T100000",
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some("Europe/Berlin"),
+        );
+        let merged = merge_calendars([first.as_str(), second.as_str(), "garbage"], Some("Work"));
+
+        assert_eq!(merged.matches("BEGIN:VCALENDAR").count(), 1);
+        assert_eq!(merged.matches("END:VCALENDAR").count(), 1);
+        assert_eq!(merged.matches("VERSION:2.0").count(), 1);
+        assert_eq!(merged.matches("BEGIN:VTIMEZONE").count(), 1);
+        assert_eq!(merged.matches("BEGIN:VEVENT").count(), 2);
+        assert!(merged.contains("UID:a@test.com"));
+        assert!(merged.contains("UID:b@test.com"));
+        assert!(merged.contains("X-WR-CALNAME:Work"));
+    }
+
+    #[test]
+    fn test_export_filename() {
+        assert_eq!(export_filename("Work"), "Work.ics");
+        assert_eq!(export_filename("Team/Ops: Q1"), "Team_Ops_ Q1.ics");
+        assert_eq!(export_filename("  "), "calendar.ics");
+    }
 }
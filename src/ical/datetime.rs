@@ -0,0 +1,291 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// A DATE or DATE-TIME value as written in iCalendar data
+/// (e.g. `20260301`, `20260301T090000`, `20260301T090000Z`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IcalTime {
+    /// Wall-clock value: UTC when `utc` is set, otherwise local or floating.
+    pub value: NaiveDateTime,
+    /// The value was a DATE (all-day) rather than a DATE-TIME.
+    pub date_only: bool,
+    /// The value carried a trailing `Z`.
+    pub utc: bool,
+}
+
+impl IcalTime {
+    /// Parse an iCalendar DATE or DATE-TIME value. The extended form
+    /// (`2026-03-01T09:00:00Z`) and fractional seconds, which some producers
+    /// emit, are accepted and normalized to basic form.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (value, utc) = match value.strip_suffix('Z') {
+            Some(naive) => (naive, true),
+            None => (value, false),
+        };
+        let value = match value.split_once('.') {
+            Some((whole, fraction)) if fraction.chars().all(|c| c.is_ascii_digit()) => whole,
+            _ => value,
+        };
+        let value: String = value.chars().filter(|c| !matches!(c, '-' | ':')).collect();
+        if utc {
+            return NaiveDateTime::parse_from_str(&value, "%Y%m%dT%H%M%S")
+                .ok()
+                .map(|value| Self {
+                    value,
+                    date_only: false,
+                    utc: true,
+                });
+        }
+        if value.len() == 8 {
+            return NaiveDate::parse_from_str(&value, "%Y%m%d")
+                .ok()
+                .map(|date| Self {
+                    value: date.and_time(NaiveTime::MIN),
+                    date_only: true,
+                    utc: false,
+                });
+        }
+        NaiveDateTime::parse_from_str(&value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|value| Self {
+                value,
+                date_only: false,
+                utc: false,
+            })
+    }
+
+    /// The same kind of value (DATE/DATE-TIME, UTC or not) at a different time.
+    pub fn with_value(self, value: NaiveDateTime) -> Self {
+        Self { value, ..self }
+    }
+
+    /// Wall-clock time of this value in `target`. `tzid` is the value's TZID
+    /// parameter, if any. Dates and floating times (or an unknown TZID) have no
+    /// fixed instant and are returned unchanged.
+    pub fn in_timezone(&self, tzid: Option<&str>, target: Tz) -> NaiveDateTime {
+        if self.date_only {
+            return self.value;
+        }
+        if self.utc {
+            return Utc
+                .from_utc_datetime(&self.value)
+                .with_timezone(&target)
+                .naive_local();
+        }
+        tzid.and_then(|id| id.parse::<Tz>().ok())
+            .and_then(|source| source.from_local_datetime(&self.value).earliest())
+            .map(|dt| dt.with_timezone(&target).naive_local())
+            .unwrap_or(self.value)
+    }
+
+    /// This value as a UTC instant. Floating times and dates are taken as UTC.
+    pub fn to_utc(self, tzid: Option<&str>) -> DateTime<Utc> {
+        self.in_timezone(tzid, chrono_tz::UTC).and_utc()
+    }
+
+    /// Format back to basic iCalendar form.
+    pub fn format(&self) -> String {
+        if self.date_only {
+            self.value.format("%Y%m%d").to_string()
+        } else if self.utc {
+            self.value.format("%Y%m%dT%H%M%SZ").to_string()
+        } else {
+            self.value.format("%Y%m%dT%H%M%S").to_string()
+        }
+    }
+}
+
+/// Parse a calendar date supplied by a client: `2026-03-09`, `20260309`, or
+/// a date-time in either form (the time part is ignored).
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    let date_part = value.split('T').next().unwrap_or(value);
+    NaiveDate::parse_from_str(date_part, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date_part, "%Y%m%d"))
+        .ok()
+}
+
+/// The year of a date or date-time as written, without requiring it to be a
+/// valid date: `02260301T090000` gives 226 and `220260301` gives 22026, so
+/// mistyped years can be reported rather than just failing to parse.
+pub fn year_of(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let date_part = value.split('T').next().unwrap_or(value);
+    let year = match date_part.split_once('-') {
+        Some((year, _)) => year,
+        None => date_part.get(..date_part.len().checked_sub(4)?)?,
+    };
+    if year.is_empty() || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    year.parse().ok()
+}
+
+/// Parse a DATE or DATE-TIME as a UTC instant, so that values written in
+/// different forms compare correctly. `tzid` is the value's TZID parameter,
+/// applied to local times. Accepts everything [`IcalTime::parse`] does plus
+/// RFC 3339 offsets (`2026-03-01T09:00:00-05:00`). Floating times, dates and
+/// unknown TZIDs are taken as UTC.
+pub fn parse_ical_datetime(value: &str, tzid: Option<&str>) -> Option<DateTime<Utc>> {
+    if let Some(time) = IcalTime::parse(value) {
+        return Some(time.to_utc(tzid));
+    }
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Parse an RFC 5545 DURATION value such as `PT1H30M`, `P1D` or `-PT15M`.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, rest) = match value.as_bytes().first()? {
+        b'-' => (true, &value[1..]),
+        b'+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let rest = rest.strip_prefix('P')?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    let mut any_unit = false;
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if number.is_empty() => in_time = true,
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                any_unit = true;
+                total += match (c, in_time) {
+                    ('W', false) => Duration::try_weeks(n)?,
+                    ('D', false) => Duration::try_days(n)?,
+                    ('H', true) => Duration::try_hours(n)?,
+                    ('M', true) => Duration::try_minutes(n)?,
+                    ('S', true) => Duration::try_seconds(n)?,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() || !any_unit {
+        return None;
+    }
+    Some(if negative { -total } else { total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_utc_datetime() {
+        let t = IcalTime::parse("20260301T090000Z").unwrap();
+        assert!(t.utc);
+        assert!(!t.date_only);
+        assert_eq!(t.format(), "20260301T090000Z");
+    }
+
+    #[test]
+    fn test_parse_local_datetime_and_date() {
+        let local = IcalTime::parse("20260301T090000").unwrap();
+        assert!(!local.utc);
+        assert_eq!(local.format(), "20260301T090000");
+
+        let date = IcalTime::parse("20260301").unwrap();
+        assert!(date.date_only);
+        assert_eq!(date.format(), "20260301");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(IcalTime::parse("not-a-date").is_none());
+        assert!(IcalTime::parse("20261301T090000Z").is_none());
+    }
+
+    #[test]
+    fn test_in_timezone() {
+        let ny: Tz = "America/New_York".parse().unwrap();
+        let expect = |s| NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S").unwrap();
+
+        let utc = IcalTime::parse("20260302T030000Z").unwrap();
+        assert_eq!(utc.in_timezone(None, ny), expect("20260301T220000"));
+
+        let berlin = IcalTime::parse("20260302T090000").unwrap();
+        assert_eq!(
+            berlin.in_timezone(Some("Europe/Berlin"), ny),
+            expect("20260302T030000")
+        );
+
+        // Floating times and dates stay as written
+        assert_eq!(berlin.in_timezone(None, ny), expect("20260302T090000"));
+        let date = IcalTime::parse("20260302").unwrap();
+        assert_eq!(date.in_timezone(None, ny), expect("20260302T000000"));
+    }
+
+    #[test]
+    fn test_parse_ical_datetime_formats() {
+        let expected = Utc.with_ymd_and_hms(2026, 3, 1, 14, 0, 0).unwrap();
+        let parse = |value| parse_ical_datetime(value, None);
+        assert_eq!(parse("20260301T140000Z"), Some(expected));
+        assert_eq!(parse("2026-03-01T14:00:00Z"), Some(expected));
+        assert_eq!(parse("2026-03-01T09:00:00-05:00"), Some(expected));
+        assert_eq!(parse("2026-03-01T14:00:00"), Some(expected));
+        assert_eq!(parse("20260301T140000.250Z"), Some(expected));
+        assert_eq!(parse("2026-03-01T14:00:00.000Z"), Some(expected));
+        assert_eq!(parse("tomorrow"), None);
+
+        // Local times are resolved in their TZID; dates and floating times are UTC
+        assert_eq!(
+            parse_ical_datetime("20260301T090000", Some("America/New_York")),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_ical_datetime("20260301T230000", Some("Asia/Tokyo")),
+            Some(expected)
+        );
+        assert_eq!(
+            parse("20260301"),
+            Some(Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_ical_datetime("20260301T140000Z", Some("Asia/Tokyo")),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1D"), Some(Duration::days(1)));
+        assert_eq!(parse_duration("P2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_duration("P1DT12H"), Some(Duration::hours(36)));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("PT5"), None);
+        assert_eq!(parse_duration("P1H"), None);
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        let expected = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(parse_date("2026-03-09"), Some(expected));
+        assert_eq!(parse_date("20260309"), Some(expected));
+        assert_eq!(parse_date("20260309T100000Z"), Some(expected));
+        assert_eq!(parse_date("2026-03-09T10:00:00Z"), Some(expected));
+        assert_eq!(parse_date("next monday"), None);
+    }
+
+    #[test]
+    fn test_year_of_malformed_years() {
+        assert_eq!(year_of("20260301T090000Z"), Some(2026));
+        assert_eq!(year_of("20260301"), Some(2026));
+        assert_eq!(year_of("2026-03-01T09:00:00Z"), Some(2026));
+        assert_eq!(year_of("02260301T090000"), Some(226));
+        assert_eq!(year_of("220260301T090000"), Some(22026));
+        assert_eq!(year_of("0301"), None);
+        assert_eq!(year_of("tomorrow"), None);
+    }
+}
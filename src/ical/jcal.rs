@@ -0,0 +1,217 @@
+//! Conversion from jCal (RFC 7265), the JSON form of iCalendar, into
+//! [`Component`] trees that serialize to ordinary iCalendar text.
+//!
+//! A component is `[name, [properties...], [components...]]` and a property
+//! is `[name, {params}, type, value, ...]`. Names are lowercase in jCal and
+//! uppercased here; values are converted from their JSON form (e.g.
+//! `"2026-03-02T15:00:00Z"`) to the iCalendar form (`20260302T150000Z`).
+
+use serde_json::{Map, Value};
+
+use super::builder::escape_text;
+use super::parser::{Component, Property};
+
+/// Convert one jCal component (and its children) into a [`Component`].
+pub fn parse_component(value: &Value) -> Result<Component, String> {
+    let parts = value
+        .as_array()
+        .filter(|a| a.len() == 3)
+        .ok_or("a jCal component must be [name, properties, components]")?;
+    let name = parts[0].as_str().ok_or("component name must be a string")?;
+    let properties = parts[1]
+        .as_array()
+        .ok_or_else(|| format!("properties of {name} must be an array"))?;
+    let components = parts[2]
+        .as_array()
+        .ok_or_else(|| format!("subcomponents of {name} must be an array"))?;
+
+    let mut component = Component::new(&name.to_ascii_uppercase());
+    for prop in properties {
+        component.properties.push(parse_property(prop)?);
+    }
+    for child in components {
+        component.components.push(parse_component(child)?);
+    }
+    Ok(component)
+}
+
+/// Convert one jCal property into a [`Property`].
+fn parse_property(value: &Value) -> Result<Property, String> {
+    let parts = value
+        .as_array()
+        .filter(|a| a.len() >= 4)
+        .ok_or("a jCal property must be [name, params, type, value, ...]")?;
+    let name = parts[0]
+        .as_str()
+        .ok_or("property name must be a string")?
+        .to_ascii_uppercase();
+    let params = parts[1]
+        .as_object()
+        .ok_or_else(|| format!("parameters of {name} must be an object"))?;
+    let value_type = parts[2]
+        .as_str()
+        .ok_or_else(|| format!("value type of {name} must be a string"))?
+        .to_ascii_lowercase();
+
+    let values = parts[3..]
+        .iter()
+        .map(|v| convert_value(v, &value_type))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{name}: {e}"))?;
+
+    let mut prop = Property::new(&name, &values.join(","));
+    prop.params = convert_params(params);
+    // Types other than the property's default must be named, e.g.
+    // `DTSTART;VALUE=DATE`. DATE and PERIOD are the ones clients rely on.
+    if matches!(value_type.as_str(), "date" | "period") && prop.param("VALUE").is_none() {
+        prop.params
+            .push(("VALUE".to_string(), value_type.to_ascii_uppercase()));
+    }
+    Ok(prop)
+}
+
+/// Convert a parameter object, joining multi-valued parameters with commas.
+fn convert_params(params: &Map<String, Value>) -> Vec<(String, String)> {
+    params
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Array(items) => items
+                    .iter()
+                    .map(scalar_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                other => scalar_string(other),
+            };
+            (key.to_ascii_uppercase(), value)
+        })
+        .collect()
+}
+
+/// Convert a single property value of the given jCal type.
+fn convert_value(value: &Value, value_type: &str) -> Result<String, String> {
+    match (value_type, value) {
+        ("recur", Value::Object(parts)) => Ok(convert_recur(parts)),
+        ("period", Value::Array(bounds)) if bounds.len() == 2 => {
+            let start = convert_temporal(&scalar_string(&bounds[0]));
+            let end = convert_temporal(&scalar_string(&bounds[1]));
+            Ok(format!("{start}/{end}"))
+        }
+        // Structured values such as GEO or REQUEST-STATUS
+        (_, Value::Array(fields)) => Ok(fields
+            .iter()
+            .map(|f| convert_value(f, value_type))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(";")),
+        (_, Value::Object(_)) => Err(format!("unexpected object for {value_type} value")),
+        ("date" | "date-time" | "time", v) => Ok(convert_temporal(&scalar_string(v))),
+        ("period", v) => Ok(scalar_string(v)
+            .split('/')
+            .map(convert_temporal)
+            .collect::<Vec<_>>()
+            .join("/")),
+        ("utc-offset", v) => Ok(scalar_string(v).replace(':', "")),
+        ("text", v) => Ok(escape_text(&scalar_string(v))),
+        ("boolean", Value::Bool(b)) => Ok(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        (_, v) => Ok(scalar_string(v)),
+    }
+}
+
+/// Convert a RECUR object, e.g. `{"freq": "WEEKLY", "byday": ["MO", "WE"]}`.
+/// `FREQ` is written first, as some clients expect.
+fn convert_recur(parts: &Map<String, Value>) -> String {
+    let mut parts: Vec<_> = parts.iter().collect();
+    parts.sort_by_key(|(key, _)| !key.eq_ignore_ascii_case("freq"));
+    parts
+        .into_iter()
+        .map(|(key, value)| {
+            let key = key.to_ascii_uppercase();
+            let value = match value {
+                Value::Array(items) => items
+                    .iter()
+                    .map(scalar_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                other if key == "UNTIL" => convert_temporal(&scalar_string(other)),
+                other => scalar_string(other),
+            };
+            format!("{key}={value}")
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// `2026-03-02T15:00:00Z` → `20260302T150000Z`, `2026-03-02` → `20260302`,
+/// `15:00:00` → `150000`.
+fn convert_temporal(value: &str) -> String {
+    value.chars().filter(|c| *c != '-' && *c != ':').collect()
+}
+
+/// The string form of a JSON scalar.
+fn scalar_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_vevent() {
+        let jcal = json!(["vcalendar", [["version", {}, "text", "2.0"]], [
+            ["vevent", [
+                ["uid", {}, "text", "jcal-1"],
+                ["dtstart", {"tzid": "America/New_York"}, "date-time", "2026-03-02T09:00:00"],
+                ["dtend", {}, "date-time", "2026-03-02T15:00:00Z"],
+                ["summary", {}, "text", "Plan, review; ship"],
+                ["categories", {}, "text", "work", "planning"],
+                ["geo", {}, "float", [37.5, -122.25]],
+                ["rrule", {}, "recur", {"freq": "WEEKLY", "byday": ["MO", "WE"], "until": "2026-04-01T00:00:00Z"}]
+            ], [
+                ["valarm", [["trigger", {}, "duration", "-PT15M"]], []]
+            ]]
+        ]]);
+        let cal = parse_component(&jcal).unwrap();
+        assert_eq!(cal.name, "VCALENDAR");
+        assert_eq!(cal.property_value("VERSION"), Some("2.0"));
+
+        let event = &cal.components[0];
+        assert_eq!(event.name, "VEVENT");
+        let dtstart = event.property("DTSTART").unwrap();
+        assert_eq!(dtstart.value, "20260302T090000");
+        assert_eq!(dtstart.param("TZID"), Some("America/New_York"));
+        assert_eq!(event.property_value("DTEND"), Some("20260302T150000Z"));
+        assert_eq!(
+            event.property_value("SUMMARY"),
+            Some("Plan\\, review\\; ship")
+        );
+        assert_eq!(event.property_value("CATEGORIES"), Some("work,planning"));
+        assert_eq!(event.property_value("GEO"), Some("37.5;-122.25"));
+        let rrule = event.property_value("RRULE").unwrap();
+        assert!(rrule.starts_with("FREQ=WEEKLY;"));
+        assert!(rrule.contains("BYDAY=MO,WE"));
+        assert!(rrule.contains("UNTIL=20260401T000000Z"));
+        assert_eq!(event.components[0].name, "VALARM");
+    }
+
+    #[test]
+    fn test_date_values_are_typed() {
+        let prop = parse_property(&json!(["dtstart", {}, "date", "2026-03-02"])).unwrap();
+        assert_eq!(prop.to_line(), "DTSTART;VALUE=DATE:20260302");
+        let prop = parse_property(&json!(["tzoffsetfrom", {}, "utc-offset", "-05:00"])).unwrap();
+        assert_eq!(prop.value, "-0500");
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert!(parse_component(&json!("vcalendar")).is_err());
+        assert!(parse_component(&json!(["vevent", [], {}])).is_err());
+        assert!(parse_component(&json!(["vevent", [["uid", {}, "text"]], []])).is_err());
+        assert!(parse_property(&json!(["summary", [], "text", "x"])).is_err());
+    }
+}
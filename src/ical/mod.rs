@@ -1,2 +1,5 @@
 pub mod builder;
+pub mod datetime;
+pub mod jcal;
 pub mod parser;
+pub mod recurrence;
@@ -1,3 +1,5 @@
+use super::datetime::{self, IcalTime};
+
 /// Extracted fields from iCalendar data.
 #[derive(Debug, Clone, Default)]
 pub struct IcalFields {
@@ -18,6 +20,7 @@ pub fn extract_fields(ical_data: &str) -> IcalFields {
     };
 
     let mut in_component = false;
+    let mut duration = None;
 
     for line in unfold_lines(ical_data) {
         let line = line.trim();
@@ -48,10 +51,36 @@ pub fn extract_fields(ical_data: &str) -> IcalFields {
                 fields.dtend = Some(value);
             }
         } else if let Some(value) = extract_property(line, "SUMMARY") {
-            fields.summary = Some(value);
+            fields.summary = Some(unescape_text(&value));
+        } else if let Some(value) = extract_property(line, "DURATION") {
+            duration = Some(value);
         }
     }
 
+    // An event given as DTSTART plus DURATION is indexed with the end it
+    // implies, written in the same form as DTSTART, so range queries see it.
+    if fields.dtend.is_none()
+        && let Some(duration) = duration.as_deref().and_then(datetime::parse_duration)
+        && let Some(start) = fields.dtstart.as_deref().and_then(IcalTime::parse)
+    {
+        fields.dtend = Some(start.with_value(start.value + duration).format());
+    }
+
+    // RFC 5545 §3.6.1: an all-day event without DTEND or DURATION lasts one
+    // day, so index its end as the following date.
+    if fields.component_type == "VEVENT" && fields.dtend.is_none() && duration.is_none() {
+        fields.dtend = fields
+            .dtstart
+            .as_deref()
+            .and_then(IcalTime::parse)
+            .filter(|t| t.date_only)
+            .map(|t| {
+                (t.value.date() + chrono::Duration::days(1))
+                    .format("%Y%m%d")
+                    .to_string()
+            });
+    }
+
     fields
 }
 
@@ -71,6 +100,30 @@ fn extract_property(line: &str, name: &str) -> Option<String> {
     None
 }
 
+/// Undo TEXT escaping (RFC 5545 §3.3.11): `\n` or `\N` becomes a newline
+/// and `\,`, `\;` and `\\` the character itself. Other backslashes are
+/// kept as written.
+pub fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(escaped @ (',' | ';' | '\\')) => out.push(escaped),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
 /// Unfold iCalendar line continuations (lines starting with space or tab).
 fn unfold_lines(data: &str) -> Vec<String> {
     let mut result = Vec::new();
@@ -97,6 +150,323 @@ fn unfold_lines(data: &str) -> Vec<String> {
     result
 }
 
+/// Parse a `GEO` value (`latitude;longitude`) into a coordinate pair.
+/// Returns `None` if either part is not a number or is out of range.
+pub fn parse_geo(value: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = value.split_once(';')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// A single iCalendar content line: `NAME;PARAM=VALUE:value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    /// Create a property with no parameters.
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            params: Vec::new(),
+            value: value.to_string(),
+        }
+    }
+
+    /// Look up a parameter value by name (case-insensitive).
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Serialize back to a single (unfolded) content line.
+    pub fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            line.push(';');
+            line.push_str(key);
+            line.push('=');
+            if value.contains([':', ';', ',']) {
+                line.push('"');
+                line.push_str(value);
+                line.push('"');
+            } else {
+                line.push_str(value);
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+}
+
+/// Parse a single unfolded content line into a `Property`.
+/// Parameter values may be double-quoted to contain `:`, `;` or `,`.
+pub fn parse_property(line: &str) -> Option<Property> {
+    let name_end = line.find([';', ':'])?;
+    let name = line[..name_end].to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    let mut rest = &line[name_end..];
+    while let Some(after) = rest.strip_prefix(';') {
+        let eq = after.find('=')?;
+        let key = after[..eq].to_ascii_uppercase();
+        let after_eq = &after[eq + 1..];
+        let (value, remainder) = if let Some(quoted) = after_eq.strip_prefix('"') {
+            let close = quoted.find('"')?;
+            (&quoted[..close], &quoted[close + 1..])
+        } else {
+            let end = after_eq.find([';', ':'])?;
+            (&after_eq[..end], &after_eq[end..])
+        };
+        params.push((key, value.to_string()));
+        rest = remainder;
+    }
+
+    let value = rest.strip_prefix(':')?;
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// A BEGIN/END block such as VCALENDAR, VEVENT or VALARM, with its
+/// properties and nested components kept in their original order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    /// Create an empty component.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// First property with the given name.
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    /// Value of the first property with the given name.
+    pub fn property_value(&self, name: &str) -> Option<&str> {
+        self.property(name).map(|p| p.value.as_str())
+    }
+
+    /// All properties with the given name (e.g. repeated EXDATE lines).
+    pub fn properties_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    /// Replace every property named `prop.name` with `prop`, keeping the
+    /// position of the first one; appends if the property was absent.
+    pub fn set_property(&mut self, prop: Property) {
+        let Some(idx) = self.properties.iter().position(|p| p.name == prop.name) else {
+            self.properties.push(prop);
+            return;
+        };
+        let name = prop.name.clone();
+        self.properties[idx] = prop;
+        let mut seen = false;
+        self.properties.retain(|p| {
+            if p.name != name {
+                return true;
+            }
+            let first = !seen;
+            seen = true;
+            first
+        });
+    }
+
+    /// Remove every property with the given name.
+    pub fn remove_property(&mut self, name: &str) {
+        self.properties.retain(|p| p.name != name);
+    }
+
+    /// Serialize the component and its children as CRLF-terminated lines.
+    pub fn to_ical(&self) -> String {
+        let mut lines = Vec::new();
+        self.write_lines(&mut lines);
+        lines.join("\r\n") + "\r\n"
+    }
+
+    fn write_lines(&self, lines: &mut Vec<String>) {
+        lines.push(format!("BEGIN:{}", self.name));
+        for prop in &self.properties {
+            lines.push(prop.to_line());
+        }
+        for child in &self.components {
+            child.write_lines(lines);
+        }
+        lines.push(format!("END:{}", self.name));
+    }
+}
+
+/// Parse raw iCalendar data into its top-level components (normally a
+/// single VCALENDAR). Lines outside any component and unbalanced END lines
+/// are ignored; unterminated components are closed at end of input.
+pub fn parse_components(ical_data: &str) -> Vec<Component> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Component> = Vec::new();
+
+    for line in unfold_lines(ical_data) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let Some(prop) = parse_property(line) else {
+            continue;
+        };
+        match prop.name.as_str() {
+            "BEGIN" => stack.push(Component::new(&prop.value.to_ascii_uppercase())),
+            "END" => {
+                if stack
+                    .last()
+                    .is_some_and(|c| c.name.eq_ignore_ascii_case(&prop.value))
+                {
+                    let done = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => parent.components.push(done),
+                        None => roots.push(done),
+                    }
+                }
+            }
+            _ => {
+                if let Some(current) = stack.last_mut() {
+                    current.properties.push(prop);
+                }
+            }
+        }
+    }
+
+    while let Some(done) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.components.push(done),
+            None => roots.push(done),
+        }
+    }
+
+    roots
+}
+
+/// Parse raw iCalendar data and return the first VCALENDAR component.
+pub fn parse_calendar(ical_data: &str) -> Option<Component> {
+    parse_components(ical_data)
+        .into_iter()
+        .find(|c| c.name == "VCALENDAR")
+}
+
+/// Components a calendar object resource may be built around.
+const OBJECT_COMPONENTS: &[&str] = &["VEVENT", "VTODO", "VJOURNAL"];
+
+/// Check that `ical_data` is a single VCALENDAR, opened on its first line
+/// and closed on its last, holding at least one VEVENT, VTODO or VJOURNAL.
+/// The error describes what is wrong.
+pub fn validate_calendar(ical_data: &str) -> Result<(), String> {
+    let lines = unfold_lines(ical_data);
+    let mut lines = lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty());
+    let Some(first) = lines.next() else {
+        return Err("Calendar data is empty".to_string());
+    };
+    if !first.eq_ignore_ascii_case("BEGIN:VCALENDAR") {
+        return Err("Calendar data must start with BEGIN:VCALENDAR".to_string());
+    }
+    if !lines
+        .next_back()
+        .is_some_and(|l| l.eq_ignore_ascii_case("END:VCALENDAR"))
+    {
+        return Err("Calendar data must end with END:VCALENDAR".to_string());
+    }
+
+    let calendar = parse_calendar(ical_data).unwrap_or_else(|| Component::new("VCALENDAR"));
+    if !calendar
+        .components
+        .iter()
+        .any(|c| OBJECT_COMPONENTS.contains(&c.name.as_str()))
+    {
+        return Err("Calendar data has no VEVENT, VTODO or VJOURNAL".to_string());
+    }
+    Ok(())
+}
+
+/// A `CALDAV:comp` element from a calendar-data request (RFC 4791 §9.6.1):
+/// which properties and sub-components of `name` the client wants back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompSelection {
+    /// Component name, upper-cased, e.g. `VCALENDAR` or `VEVENT`.
+    pub name: String,
+    /// `allprop`: keep every property.
+    pub all_props: bool,
+    /// Requested property names, upper-cased.
+    pub props: Vec<String>,
+    /// `allcomp`: keep every sub-component unchanged.
+    pub all_comps: bool,
+    /// Requested sub-components.
+    pub comps: Vec<CompSelection>,
+}
+
+/// Properties kept even when not requested, so the result still identifies
+/// the object and places it in time.
+const ALWAYS_SELECTED: &[&str] = &["VERSION", "PRODID", "UID", "DTSTART", "RECURRENCE-ID"];
+
+/// Strip the properties and sub-components `selection` does not ask for.
+///
+/// VTIMEZONE components are always kept whole since DTSTART may refer to
+/// them. A selection that names no sub-components (and no `allcomp`) applies
+/// its own property list to every sub-component, so the short form
+/// `<comp name="VCALENDAR"><prop name="SUMMARY"/></comp>` selects SUMMARY
+/// from the events inside; sub-components left with no properties (such as
+/// a VALARM) are dropped.
+pub fn select_properties(component: &Component, selection: &CompSelection) -> Component {
+    let mut out = Component::new(&component.name);
+    out.properties = component
+        .properties
+        .iter()
+        .filter(|p| {
+            selection.all_props
+                || ALWAYS_SELECTED.contains(&p.name.as_str())
+                || selection
+                    .props
+                    .iter()
+                    .any(|n| n.eq_ignore_ascii_case(&p.name))
+        })
+        .cloned()
+        .collect();
+
+    for child in &component.components {
+        if selection.all_comps || child.name == "VTIMEZONE" {
+            out.components.push(child.clone());
+        } else if let Some(sub) = selection
+            .comps
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(&child.name))
+        {
+            out.components.push(select_properties(child, sub));
+        } else if selection.comps.is_empty() {
+            let selected = select_properties(child, selection);
+            if !selected.properties.is_empty() {
+                out.components.push(selected);
+            }
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,6 +508,47 @@ mod tests {
         assert_eq!(fields.summary.as_deref(), Some("Lunch Break"));
     }
 
+    #[test]
+    fn test_extract_folded_event_with_duration() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                     BEGIN:VEVENT\r\n\
+                     UID:folded@exa\r\n mple.com\r\n\
+                     DTSTART;TZID=Europe/Berlin:2026\r\n 0301T090000\r\n\
+                     DURATION:PT1H30M\r\n\
+                     SUMMARY:A long title that a client\r\n  folded\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR";
+
+        let fields = extract_fields(ical);
+        assert_eq!(fields.uid.as_deref(), Some("folded@example.com"));
+        assert_eq!(fields.dtstart.as_deref(), Some("20260301T090000"));
+        assert_eq!(fields.dtend.as_deref(), Some("20260301T103000"));
+        assert_eq!(
+            fields.summary.as_deref(),
+            Some("A long title that a client folded")
+        );
+
+        let all_day = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:trip\r\n\
+                       DTSTART;VALUE=DATE:20260301\r\nDURATION:P3D\r\n\
+                       END:VEVENT\r\nEND:VCALENDAR";
+        assert_eq!(extract_fields(all_day).dtend.as_deref(), Some("20260304"));
+    }
+
+    #[test]
+    fn test_extract_all_day_event_defaults_end_to_next_day() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                     BEGIN:VEVENT\r\n\
+                     UID:holiday@example.com\r\n\
+                     DTSTART;VALUE=DATE:20260228\r\n\
+                     SUMMARY:Holiday\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR";
+
+        let fields = extract_fields(ical);
+        assert_eq!(fields.dtstart.as_deref(), Some("20260228"));
+        assert_eq!(fields.dtend.as_deref(), Some("20260301"));
+    }
+
     #[test]
     fn test_extract_vtodo() {
         let ical = "BEGIN:VCALENDAR\r\n\
@@ -154,6 +565,20 @@ mod tests {
         assert_eq!(fields.component_type, "VTODO");
     }
 
+    #[test]
+    fn test_unescape_text() {
+        assert_eq!(
+            unescape_text("Meeting\\, with\\, commas"),
+            "Meeting, with, commas"
+        );
+        assert_eq!(
+            unescape_text("Line one\\nLine two\\NEnd"),
+            "Line one\nLine two\nEnd"
+        );
+        assert_eq!(unescape_text("a\\;b\\\\n"), "a;b\\n");
+        assert_eq!(unescape_text("odd\\x trailing\\"), "odd\\x trailing\\");
+    }
+
     #[test]
     fn test_unfold_lines() {
         let data = "SUMMARY:This is a long\r\n summary that wraps\r\n";
@@ -180,4 +605,159 @@ mod tests {
         let fields = extract_fields(ical);
         assert_eq!(fields.uid.as_deref(), Some("cal-level-uid@example.com"));
     }
+
+    #[test]
+    fn test_parse_property_with_quoted_params() {
+        let prop =
+            parse_property("ATTENDEE;CN=\"Doe; Jane\";ROLE=CHAIR:mailto:jane@example.com").unwrap();
+        assert_eq!(prop.name, "ATTENDEE");
+        assert_eq!(prop.param("cn"), Some("Doe; Jane"));
+        assert_eq!(prop.param("ROLE"), Some("CHAIR"));
+        assert_eq!(prop.value, "mailto:jane@example.com");
+    }
+
+    #[test]
+    fn test_parse_components_nested() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                    VERSION:2.0\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:a@example.com\r\n\
+                    DTSTART;TZID=Europe/Paris:20260301T090000\r\n\
+                    BEGIN:VALARM\r\n\
+                    TRIGGER:-PT15M\r\n\
+                    END:VALARM\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+
+        let cal = parse_calendar(ical).unwrap();
+        assert_eq!(cal.property_value("VERSION"), Some("2.0"));
+        let event = &cal.components[0];
+        assert_eq!(event.name, "VEVENT");
+        assert_eq!(
+            event.property("DTSTART").unwrap().param("TZID"),
+            Some("Europe/Paris")
+        );
+        assert_eq!(event.components[0].name, "VALARM");
+        assert_eq!(
+            event.components[0].property_value("TRIGGER"),
+            Some("-PT15M")
+        );
+    }
+
+    #[test]
+    fn test_property_to_line_quotes_params() {
+        let prop =
+            parse_property("ATTENDEE;CN=\"Doe; Jane\";ROLE=CHAIR:mailto:jane@example.com").unwrap();
+        assert_eq!(
+            prop.to_line(),
+            "ATTENDEE;CN=\"Doe; Jane\";ROLE=CHAIR:mailto:jane@example.com"
+        );
+    }
+
+    #[test]
+    fn test_parse_geo() {
+        assert_eq!(
+            parse_geo("37.386013;-122.082932"),
+            Some((37.386013, -122.082932))
+        );
+        assert_eq!(
+            parse_geo(" -33.8688 ; 151.2093 "),
+            Some((-33.8688, 151.2093))
+        );
+        assert_eq!(parse_geo("91;0"), None);
+        assert_eq!(parse_geo("0;-180.5"), None);
+        assert_eq!(parse_geo("NaN;0"), None);
+        assert_eq!(parse_geo("37.3"), None);
+    }
+
+    #[test]
+    fn test_component_round_trip_and_set_property() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:a@example.com\r\n\
+                    LOCATION:Room 1\r\n\
+                    DESCRIPTION:Agenda\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+
+        let mut cal = parse_calendar(ical).unwrap();
+        assert_eq!(cal.to_ical(), ical);
+
+        let event = &mut cal.components[0];
+        event.set_property(Property::new("LOCATION", "Room 2"));
+        event.set_property(Property::new("SUMMARY", "Planning"));
+        event.remove_property("DESCRIPTION");
+        let out = cal.to_ical();
+        assert!(
+            out.contains("UID:a@example.com\r\nLOCATION:Room 2\r\nSUMMARY:Planning\r\nEND:VEVENT")
+        );
+        assert!(!out.contains("Room 1"));
+        assert!(!out.contains("DESCRIPTION"));
+    }
+
+    #[test]
+    fn test_select_properties_keeps_requested_and_mandatory() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                    VERSION:2.0\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:a@example.com\r\n\
+                    DTSTART:20260301T090000Z\r\n\
+                    SUMMARY:Planning\r\n\
+                    LOCATION:Room 1\r\n\
+                    BEGIN:VALARM\r\n\
+                    ACTION:DISPLAY\r\n\
+                    END:VALARM\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+        let cal = parse_calendar(ical).unwrap();
+
+        let short = CompSelection {
+            name: "VCALENDAR".to_string(),
+            props: vec!["SUMMARY".to_string()],
+            ..Default::default()
+        };
+        let out = select_properties(&cal, &short).to_ical();
+        assert!(out.contains("UID:a@example.com\r\nDTSTART:20260301T090000Z\r\nSUMMARY:Planning"));
+        assert!(out.contains("VERSION:2.0"));
+        assert!(!out.contains("LOCATION"));
+        assert!(!out.contains("VALARM"));
+
+        let nested = CompSelection {
+            name: "VCALENDAR".to_string(),
+            all_props: true,
+            comps: vec![CompSelection {
+                name: "VEVENT".to_string(),
+                props: vec!["LOCATION".to_string()],
+                all_comps: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let out = select_properties(&cal, &nested).to_ical();
+        assert!(out.contains("LOCATION:Room 1"));
+        assert!(out.contains("ACTION:DISPLAY"));
+        assert!(!out.contains("SUMMARY"));
+    }
+
+    #[test]
+    fn test_validate_calendar() {
+        let valid = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VTODO\r\nUID:t\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        assert!(validate_calendar(valid).is_ok());
+
+        assert!(validate_calendar("").unwrap_err().contains("empty"));
+        assert!(validate_calendar("  \r\n").unwrap_err().contains("empty"));
+        assert!(
+            validate_calendar("hello world")
+                .unwrap_err()
+                .contains("BEGIN:VCALENDAR")
+        );
+        let unterminated = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:u\r\nEND:VEVENT\r\n";
+        assert!(
+            validate_calendar(unterminated)
+                .unwrap_err()
+                .contains("END:VCALENDAR")
+        );
+        let empty = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n";
+        assert!(validate_calendar(empty).unwrap_err().contains("no VEVENT"));
+    }
 }
@@ -0,0 +1,813 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+
+use super::datetime::{self, IcalTime};
+use super::parser::{Component, Property};
+
+/// Upper bound on the number of periods (days, weeks, months, years) walked
+/// while expanding a rule, so a rule that never matches can't loop forever.
+const MAX_PERIODS: i64 = 50_000;
+
+/// RRULE frequency. Sub-daily frequencies are not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A parsed RRULE (RFC 5545 §3.3.10), limited to the parts used by common
+/// calendar clients: FREQ, INTERVAL, COUNT, UNTIL, BYDAY, BYMONTHDAY, BYMONTH.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<IcalTime>,
+    /// Weekdays with an optional ordinal (`2MO` = second Monday, `-1FR` = last Friday).
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+}
+
+impl RecurrenceRule {
+    /// Parse an RRULE value such as `FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10`.
+    /// Returns `None` for malformed or unsupported rules.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut freq = None;
+        let mut rule = Self {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in value.trim().split(';').filter(|p| !p.is_empty()) {
+            let (key, val) = part.split_once('=')?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match val.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    })
+                }
+                "INTERVAL" => rule.interval = val.parse::<u32>().ok()?.max(1),
+                "COUNT" => rule.count = Some(val.parse().ok()?),
+                "UNTIL" => rule.until = Some(IcalTime::parse(val)?),
+                "BYDAY" => {
+                    for day in val.split(',') {
+                        rule.by_day.push(parse_by_day(day)?);
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in val.split(',') {
+                        rule.by_month_day.push(day.parse().ok()?);
+                    }
+                }
+                "BYMONTH" => {
+                    for month in val.split(',') {
+                        rule.by_month.push(month.parse().ok()?);
+                    }
+                }
+                // WKST and other parts don't change the instances we generate.
+                _ => {}
+            }
+        }
+
+        rule.freq = freq?;
+        Some(rule)
+    }
+
+    /// Expand the rule from `dtstart`, returning every instance start up to
+    /// and including `limit`. `dtstart` itself is the first instance when it
+    /// matches the rule, as RFC 5545 requires. `tzid` is DTSTART's TZID, used
+    /// to compare local instances against a UTC UNTIL.
+    pub fn expand(
+        &self,
+        dtstart: NaiveDateTime,
+        tzid: Option<&str>,
+        limit: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let mut out = Vec::new();
+        let mut emitted = 0u32;
+
+        for period in 0..MAX_PERIODS {
+            let Some(period_start) = self.period_start(dtstart.date(), period) else {
+                break;
+            };
+            if period_start > limit.date() {
+                break;
+            }
+            for date in self.period_dates(dtstart.date(), period_start) {
+                let candidate = date.and_time(dtstart.time());
+                if candidate < dtstart {
+                    continue;
+                }
+                if let Some(until) = &self.until
+                    && !until_allows(until, candidate, tzid)
+                {
+                    return out;
+                }
+                if self.count.is_some_and(|count| emitted >= count) || candidate > limit {
+                    return out;
+                }
+                emitted += 1;
+                out.push(candidate);
+            }
+        }
+
+        out
+    }
+
+    /// First day of the `period`-th interval after the one containing `start`.
+    fn period_start(&self, start: NaiveDate, period: i64) -> Option<NaiveDate> {
+        let step = period.checked_mul(self.interval as i64)?;
+        match self.freq {
+            Frequency::Daily => start.checked_add_signed(Duration::try_days(step)?),
+            Frequency::Weekly => {
+                let monday = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+                monday.checked_add_signed(Duration::try_weeks(step)?)
+            }
+            Frequency::Monthly => {
+                let months = start.month0() as i64 + step;
+                let year = start.year() as i64 + months.div_euclid(12);
+                let month = months.rem_euclid(12) as u32 + 1;
+                NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, month, 1)
+            }
+            Frequency::Yearly => {
+                NaiveDate::from_ymd_opt(i32::try_from(start.year() as i64 + step).ok()?, 1, 1)
+            }
+        }
+    }
+
+    /// Candidate dates within one period, in chronological order.
+    fn period_dates(&self, dtstart: NaiveDate, period_start: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = match self.freq {
+            Frequency::Daily => {
+                let day = period_start;
+                let day_ok = self.by_day.is_empty()
+                    || self.by_day.iter().any(|(_, wd)| *wd == day.weekday());
+                let month_day_ok = self.by_month_day.is_empty()
+                    || self
+                        .by_month_day
+                        .iter()
+                        .any(|d| resolve_month_day(day.year(), day.month(), *d) == Some(day));
+                if day_ok && month_day_ok {
+                    vec![day]
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Weekly => {
+                if self.by_day.is_empty() {
+                    vec![
+                        period_start
+                            + Duration::days(dtstart.weekday().num_days_from_monday() as i64),
+                    ]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|(_, wd)| {
+                            period_start + Duration::days(wd.num_days_from_monday() as i64)
+                        })
+                        .collect()
+                }
+            }
+            Frequency::Monthly => {
+                self.month_dates(period_start.year(), period_start.month(), dtstart.day())
+            }
+            Frequency::Yearly => {
+                let months = if self.by_month.is_empty() {
+                    vec![dtstart.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                months
+                    .into_iter()
+                    .flat_map(|m| self.month_dates(period_start.year(), m, dtstart.day()))
+                    .collect()
+            }
+        };
+
+        if !self.by_month.is_empty() {
+            dates.retain(|d| self.by_month.contains(&d.month()));
+        }
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    /// Dates in a month selected by BYMONTHDAY / BYDAY, or `default_day`
+    /// (the DTSTART day) when neither is given.
+    fn month_dates(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        if !self.by_month_day.is_empty() {
+            return self
+                .by_month_day
+                .iter()
+                .filter_map(|d| resolve_month_day(year, month, *d))
+                .filter(|date| {
+                    self.by_day.is_empty()
+                        || self.by_day.iter().any(|(_, wd)| *wd == date.weekday())
+                })
+                .collect();
+        }
+
+        if !self.by_day.is_empty() {
+            let mut dates = Vec::new();
+            for (ordinal, weekday) in &self.by_day {
+                let matching: Vec<NaiveDate> = (1..=days_in_month(year, month))
+                    .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+                    .filter(|date| date.weekday() == *weekday)
+                    .collect();
+                match ordinal {
+                    Some(n) if *n > 0 => dates.extend(matching.get(*n as usize - 1)),
+                    Some(n) if *n < 0 => {
+                        let from_end = n.unsigned_abs() as usize;
+                        if from_end <= matching.len() {
+                            dates.push(matching[matching.len() - from_end]);
+                        }
+                    }
+                    _ => dates.extend(matching),
+                }
+            }
+            return dates;
+        }
+
+        NaiveDate::from_ymd_opt(year, month, default_day)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// All instance start times of a master VEVENT/VTODO up to `limit`, in the
+/// DTSTART's own frame (UTC or local wall-clock). Applies RRULE, RDATE and
+/// EXDATE; a component without RRULE or RDATE yields just its DTSTART.
+pub fn occurrences(component: &Component, limit: NaiveDateTime) -> Vec<NaiveDateTime> {
+    let Some(dtstart_prop) = component.property("DTSTART") else {
+        return Vec::new();
+    };
+    let Some(dtstart) = IcalTime::parse(&dtstart_prop.value) else {
+        return Vec::new();
+    };
+    // A UTC DTSTART carries no TZID, and a TZID on a DATE is meaningless
+    let tzid = dtstart_prop
+        .param("TZID")
+        .filter(|_| !dtstart.utc && !dtstart.date_only);
+
+    let mut instances = match component
+        .property_value("RRULE")
+        .and_then(RecurrenceRule::parse)
+    {
+        Some(rule) => rule.expand(dtstart.value, tzid, limit),
+        None if dtstart.value <= limit => vec![dtstart.value],
+        None => Vec::new(),
+    };
+
+    for rdate in component.properties_named("RDATE") {
+        instances.extend(
+            rdate
+                .value
+                .split(',')
+                .filter_map(IcalTime::parse)
+                .map(|t| t.value)
+                .filter(|t| *t <= limit),
+        );
+    }
+
+    let exdates: Vec<IcalTime> = component
+        .properties_named("EXDATE")
+        .flat_map(|p| p.value.split(',').filter_map(IcalTime::parse))
+        .collect();
+    instances.retain(|instance| {
+        !exdates.iter().any(|ex| {
+            if ex.date_only {
+                ex.value.date() == instance.date()
+            } else {
+                ex.value == *instance
+            }
+        })
+    });
+
+    instances.sort();
+    instances.dedup();
+    instances
+}
+
+/// Parse one BYDAY entry such as `MO`, `2TU` or `-1FR`.
+fn parse_by_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let value = value.trim();
+    if value.len() < 2 || !value.is_ascii() {
+        return None;
+    }
+    let (ordinal, code) = value.split_at(value.len() - 2);
+    let weekday = match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let ordinal = if ordinal.is_empty() {
+        None
+    } else {
+        Some(ordinal.trim_start_matches('+').parse().ok()?)
+    };
+    Some((ordinal, weekday))
+}
+
+/// Whether `candidate` falls on or before the rule's UNTIL bound. With a
+/// TZID, `candidate` is local time there while UNTIL must be UTC
+/// (RFC 5545 §3.3.10), so both are compared as instants.
+fn until_allows(until: &IcalTime, candidate: NaiveDateTime, tzid: Option<&str>) -> bool {
+    if until.date_only {
+        return candidate.date() <= until.value.date();
+    }
+    if until.utc && tzid.is_some() {
+        let local = IcalTime {
+            value: candidate,
+            date_only: false,
+            utc: false,
+        };
+        return local.to_utc(tzid) <= until.to_utc(None);
+    }
+    candidate <= until.value
+}
+
+/// Resolve a BYMONTHDAY value (negative counts from the end of the month).
+fn resolve_month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    let len = days_in_month(year, month) as i32;
+    let day = if day < 0 { len + day + 1 } else { day };
+    if !(1..=len).contains(&day) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year, month, day as u32)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    match (
+        NaiveDate::from_ymd_opt(year, month, 1),
+        NaiveDate::from_ymd_opt(next_year, next_month, 1),
+    ) {
+        (Some(first), Some(next)) => (next - first).num_days() as u32,
+        _ => 0,
+    }
+}
+
+/// One concrete occurrence of an event as a UTC interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instance {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Instance {
+    /// Whether the instance overlaps `[range_start, range_end)`. A zero-length
+    /// instance overlaps if it starts inside the range (RFC 4791 §9.9).
+    pub fn overlaps(&self, range_start: DateTime<Utc>, range_end: DateTime<Utc>) -> bool {
+        self.start < range_end
+            && (self.end > range_start || (self.start == self.end && self.start >= range_start))
+    }
+}
+
+/// Expand every VEVENT in a VCALENDAR into the UTC intervals that overlap
+/// `[range_start, range_end)`. Recurring events are expanded with
+/// [`occurrences`]; an override (a VEVENT with RECURRENCE-ID) replaces the
+/// master instance it identifies. Floating times are treated as UTC.
+pub fn instances_in_range(
+    calendar: &Component,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+) -> Vec<Instance> {
+    let events: Vec<&Component> = calendar
+        .components
+        .iter()
+        .filter(|c| c.name == "VEVENT")
+        .collect();
+    let overridden: Vec<NaiveDateTime> = events
+        .iter()
+        .filter_map(|e| e.property_value("RECURRENCE-ID"))
+        .filter_map(IcalTime::parse)
+        .map(|t| t.value)
+        .collect();
+
+    let overlaps = |i: &Instance| i.overlaps(range_start, range_end);
+
+    let mut instances = Vec::new();
+    for event in events {
+        let Some(dtstart_prop) = event.property("DTSTART") else {
+            continue;
+        };
+        let Some(dtstart) = IcalTime::parse(&dtstart_prop.value) else {
+            continue;
+        };
+        let tzid = dtstart_prop.param("TZID");
+        let duration = event_duration(event, dtstart, tzid);
+
+        if event.property("RECURRENCE-ID").is_some() {
+            let start = dtstart.to_utc(tzid);
+            instances.push(Instance {
+                start,
+                end: start + duration,
+            });
+            continue;
+        }
+
+        // Occurrences are in DTSTART's wall-clock time; pad the limit so that
+        // zones ahead of UTC don't cut off the last day of the range.
+        let limit = range_end.naive_utc() + Duration::days(2);
+        for occurrence in occurrences(event, limit) {
+            if overridden.contains(&occurrence) {
+                continue;
+            }
+            let start = dtstart.with_value(occurrence).to_utc(tzid);
+            instances.push(Instance {
+                start,
+                end: start + duration,
+            });
+        }
+    }
+
+    instances.retain(overlaps);
+    instances.sort_by_key(|i| i.start);
+    instances
+}
+
+/// Rewrite a VCALENDAR the way CALDAV:expand asks (RFC 4791 §9.6.5): one
+/// VEVENT per instance overlapping `[range_start, range_end)`, each with a
+/// RECURRENCE-ID and with DTSTART, DTEND and RECURRENCE-ID in UTC. RRULE,
+/// RDATE, EXDATE and DURATION are dropped, overrides replace the instances
+/// they identify, and VTIMEZONEs are removed since nothing refers to them.
+pub fn expand_instances(
+    calendar: &Component,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+) -> Component {
+    let events: Vec<&Component> = calendar
+        .components
+        .iter()
+        .filter(|c| c.name == "VEVENT")
+        .collect();
+    let overridden: Vec<NaiveDateTime> = events
+        .iter()
+        .filter_map(|e| e.property_value("RECURRENCE-ID"))
+        .filter_map(IcalTime::parse)
+        .map(|t| t.value)
+        .collect();
+    let limit = range_end.naive_utc() + Duration::days(2);
+
+    let mut instances: Vec<(DateTime<Utc>, Component)> = Vec::new();
+    for event in &events {
+        let Some(dtstart_prop) = event.property("DTSTART") else {
+            continue;
+        };
+        let Some(dtstart) = IcalTime::parse(&dtstart_prop.value) else {
+            continue;
+        };
+        let tzid = dtstart_prop.param("TZID");
+        let duration = event_duration(event, dtstart, tzid);
+
+        // (RECURRENCE-ID, start) of each instance this VEVENT contributes
+        let starts: Vec<(DateTime<Utc>, DateTime<Utc>)> = match event.property("RECURRENCE-ID") {
+            Some(rid) => IcalTime::parse(&rid.value)
+                .map(|r| (r.to_utc(rid.param("TZID")), dtstart.to_utc(tzid)))
+                .into_iter()
+                .collect(),
+            None => occurrences(event, limit)
+                .into_iter()
+                .filter(|o| !overridden.contains(o))
+                .map(|o| {
+                    let start = dtstart.with_value(o).to_utc(tzid);
+                    (start, start)
+                })
+                .collect(),
+        };
+
+        for (recurrence_id, start) in starts {
+            let end = start + duration;
+            if !(Instance { start, end }).overlaps(range_start, range_end) {
+                continue;
+            }
+            let mut instance = (*event).clone();
+            instance.properties.retain(|p| {
+                !matches!(
+                    p.name.as_str(),
+                    "RRULE"
+                        | "RDATE"
+                        | "EXDATE"
+                        | "DTSTART"
+                        | "DTEND"
+                        | "DURATION"
+                        | "RECURRENCE-ID"
+                )
+            });
+            instance.properties.push(utc_property(
+                "RECURRENCE-ID",
+                recurrence_id,
+                dtstart.date_only,
+            ));
+            instance
+                .properties
+                .push(utc_property("DTSTART", start, dtstart.date_only));
+            instance
+                .properties
+                .push(utc_property("DTEND", end, dtstart.date_only));
+            instances.push((start, instance));
+        }
+    }
+    instances.sort_by_key(|(start, _)| *start);
+
+    let mut expanded = Component::new(&calendar.name);
+    expanded.properties = calendar.properties.clone();
+    expanded.components = instances.into_iter().map(|(_, c)| c).collect();
+    expanded.components.extend(
+        calendar
+            .components
+            .iter()
+            .filter(|c| c.name != "VEVENT" && c.name != "VTIMEZONE")
+            .cloned(),
+    );
+    expanded
+}
+
+/// A DATE (for all-day events) or UTC DATE-TIME property.
+fn utc_property(name: &str, value: DateTime<Utc>, date_only: bool) -> Property {
+    if date_only {
+        let mut prop = Property::new(name, &value.format("%Y%m%d").to_string());
+        prop.params.push(("VALUE".to_string(), "DATE".to_string()));
+        prop
+    } else {
+        Property::new(name, &value.format("%Y%m%dT%H%M%SZ").to_string())
+    }
+}
+
+/// Length of an event: DTEND - DTSTART, else DURATION, else one day for
+/// all-day events and zero otherwise.
+pub fn event_duration(event: &Component, dtstart: IcalTime, tzid: Option<&str>) -> Duration {
+    if let Some(dtend_prop) = event.property("DTEND")
+        && let Some(dtend) = IcalTime::parse(&dtend_prop.value)
+    {
+        return dtend.to_utc(dtend_prop.param("TZID")) - dtstart.to_utc(tzid);
+    }
+    if let Some(duration) = event
+        .property_value("DURATION")
+        .and_then(datetime::parse_duration)
+    {
+        return duration;
+    }
+    if dtstart.date_only {
+        Duration::days(1)
+    } else {
+        Duration::zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical::parser;
+
+    fn dt(s: &str) -> NaiveDateTime {
+        IcalTime::parse(s).unwrap().value
+    }
+
+    fn fmt(instances: &[NaiveDateTime]) -> Vec<String> {
+        instances
+            .iter()
+            .map(|i| i.format("%Y%m%dT%H%M%S").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = RecurrenceRule::parse("FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR;COUNT=6").unwrap();
+        assert_eq!(rule.freq, Frequency::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(6));
+        assert_eq!(rule.by_day, vec![(Some(-1), Weekday::Fri)]);
+    }
+
+    #[test]
+    fn test_parse_rejects_unsupported() {
+        assert!(RecurrenceRule::parse("FREQ=HOURLY").is_none());
+        assert!(RecurrenceRule::parse("INTERVAL=2").is_none());
+        assert!(RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=XX").is_none());
+    }
+
+    #[test]
+    fn test_daily_count() {
+        let rule = RecurrenceRule::parse("FREQ=DAILY;COUNT=3").unwrap();
+        let out = rule.expand(dt("20260301T090000Z"), None, dt("20270101T000000Z"));
+        assert_eq!(
+            fmt(&out),
+            vec!["20260301T090000", "20260302T090000", "20260303T090000"]
+        );
+    }
+
+    #[test]
+    fn test_weekly_byday_until() {
+        // Monday 2026-03-02; Mondays and Wednesdays until the 11th
+        let rule = RecurrenceRule::parse("FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20260311T235959Z").unwrap();
+        let out = rule.expand(dt("20260302T090000Z"), None, dt("20270101T000000Z"));
+        assert_eq!(
+            fmt(&out),
+            vec![
+                "20260302T090000",
+                "20260304T090000",
+                "20260309T090000",
+                "20260311T090000"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monthly_last_friday() {
+        let rule = RecurrenceRule::parse("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3").unwrap();
+        let out = rule.expand(dt("20260130T120000"), None, dt("20270101T000000"));
+        assert_eq!(
+            fmt(&out),
+            vec!["20260130T120000", "20260227T120000", "20260327T120000"]
+        );
+    }
+
+    #[test]
+    fn test_monthly_skips_short_months() {
+        let rule = RecurrenceRule::parse("FREQ=MONTHLY;COUNT=3").unwrap();
+        let out = rule.expand(dt("20260131T090000"), None, dt("20270101T000000"));
+        assert_eq!(
+            fmt(&out),
+            vec!["20260131T090000", "20260331T090000", "20260531T090000"]
+        );
+    }
+
+    #[test]
+    fn test_yearly_stops_at_limit() {
+        let rule = RecurrenceRule::parse("FREQ=YEARLY").unwrap();
+        let out = rule.expand(dt("20240229"), None, dt("20330101"));
+        // Feb 29 only exists in leap years
+        assert_eq!(
+            fmt(&out),
+            vec!["20240229T000000", "20280229T000000", "20320229T000000"]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_apply_exdate_and_rdate() {
+        let ical = "BEGIN:VCALENDAR\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:standup@example.com\r\n\
+                    DTSTART:20260302T090000Z\r\n\
+                    RRULE:FREQ=DAILY;COUNT=3\r\n\
+                    EXDATE:20260303T090000Z\r\n\
+                    RDATE:20260310T090000Z\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+        let cal = parser::parse_calendar(ical).unwrap();
+        let out = occurrences(&cal.components[0], dt("20261231T000000Z"));
+        assert_eq!(
+            fmt(&out),
+            vec!["20260302T090000", "20260304T090000", "20260310T090000"]
+        );
+    }
+
+    #[test]
+    fn test_instances_in_range_with_override_and_tzid() {
+        let data = "BEGIN:VCALENDAR\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:standup\r\n\
+                    DTSTART;TZID=America/New_York:20260302T090000\r\n\
+                    DTEND;TZID=America/New_York:20260302T093000\r\n\
+                    RRULE:FREQ=DAILY;COUNT=3\r\n\
+                    END:VEVENT\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:standup\r\n\
+                    RECURRENCE-ID;TZID=America/New_York:20260303T090000\r\n\
+                    DTSTART;TZID=America/New_York:20260303T110000\r\n\
+                    DURATION:PT1H\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+        let calendar = parser::parse_calendar(data).unwrap();
+        let utc = |s: &str| IcalTime::parse(s).unwrap().value.and_utc();
+
+        let instances =
+            instances_in_range(&calendar, utc("20260301T000000Z"), utc("20260310T000000Z"));
+        let spans: Vec<_> = instances
+            .iter()
+            .map(|i| {
+                (
+                    i.start.format("%d %H:%M").to_string(),
+                    (i.end - i.start).num_minutes(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("02 14:00".to_string(), 30),
+                ("03 16:00".to_string(), 60),
+                ("04 14:00".to_string(), 30),
+            ]
+        );
+
+        // Only instances overlapping the range are returned
+        let instances =
+            instances_in_range(&calendar, utc("20260303T150000Z"), utc("20260303T163000Z"));
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].start, utc("20260303T160000Z"));
+    }
+
+    #[test]
+    fn test_expand_instances_weekly_with_override() {
+        let data = "BEGIN:VCALENDAR\r\n\
+                    VERSION:2.0\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:review\r\n\
+                    DTSTART;TZID=Europe/Berlin:20260302T100000\r\n\
+                    DTEND;TZID=Europe/Berlin:20260302T110000\r\n\
+                    RRULE:FREQ=WEEKLY;COUNT=4\r\n\
+                    END:VEVENT\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:review\r\n\
+                    RECURRENCE-ID;TZID=Europe/Berlin:20260316T100000\r\n\
+                    DTSTART;TZID=Europe/Berlin:20260316T140000\r\n\
+                    DTEND;TZID=Europe/Berlin:20260316T150000\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+        let calendar = parser::parse_calendar(data).unwrap();
+        let utc = |s: &str| IcalTime::parse(s).unwrap().value.and_utc();
+
+        let expanded =
+            expand_instances(&calendar, utc("20260309T000000Z"), utc("20260401T000000Z"));
+        let instances: Vec<_> = expanded
+            .components
+            .iter()
+            .map(|c| {
+                (
+                    c.property_value("RECURRENCE-ID").unwrap(),
+                    c.property_value("DTSTART").unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            instances,
+            [
+                ("20260309T090000Z", "20260309T090000Z"),
+                ("20260316T090000Z", "20260316T130000Z"),
+                ("20260323T090000Z", "20260323T090000Z"),
+            ]
+        );
+        assert!(
+            expanded
+                .components
+                .iter()
+                .all(|c| c.property("RRULE").is_none())
+        );
+    }
+
+    #[test]
+    fn test_tzid_until_compared_against_utc_window() {
+        // 09:00 in Tokyo is 00:00Z the same day. UNTIL is 05:00Z on the 4th, so
+        // the 4th's instance (00:00Z) is included even though its local
+        // wall-clock time (09:00) is later than UNTIL's digits.
+        let data = "BEGIN:VCALENDAR\r\n\
+                    BEGIN:VEVENT\r\n\
+                    UID:tokyo\r\n\
+                    DTSTART;TZID=Asia/Tokyo:20260302T090000\r\n\
+                    DTEND;TZID=Asia/Tokyo:20260302T100000\r\n\
+                    RRULE:FREQ=DAILY;UNTIL=20260304T050000Z\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+        let calendar = parser::parse_calendar(data).unwrap();
+        let utc = |s: &str| IcalTime::parse(s).unwrap().value.and_utc();
+
+        let instances =
+            instances_in_range(&calendar, utc("20260301T000000Z"), utc("20260310T000000Z"));
+        let starts: Vec<_> = instances.iter().map(|i| i.start).collect();
+        assert_eq!(
+            starts,
+            [
+                utc("20260302T000000Z"),
+                utc("20260303T000000Z"),
+                utc("20260304T000000Z"),
+            ]
+        );
+
+        // A window after UNTIL has no instances
+        let instances =
+            instances_in_range(&calendar, utc("20260304T050000Z"), utc("20260310T000000Z"));
+        assert!(instances.is_empty());
+    }
+}
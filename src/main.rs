@@ -4,10 +4,14 @@ mod db;
 mod error;
 mod ical;
 mod mcp;
+mod outbound;
+mod reminders;
+mod tls;
 
 use std::net::SocketAddr;
 
 use clap::{Parser, Subcommand};
+use serde_json::json;
 use tokio::net::TcpListener;
 use tracing_subscriber::EnvFilter;
 
@@ -16,6 +20,10 @@ use tracing_subscriber::EnvFilter;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Print list commands' output as a JSON array instead of a table
+    #[arg(long, global = true)]
+    json: bool,
 }
 
 #[derive(Subcommand)]
@@ -44,6 +52,12 @@ enum Commands {
         /// A descriptive name for this token
         #[arg(short, long)]
         name: String,
+        /// Only allow tools that read calendar data
+        #[arg(long)]
+        read_only: bool,
+        /// Expire the token this many days from now (default: never)
+        #[arg(long)]
+        expires_in_days: Option<u32>,
     },
 
     /// List all users
@@ -56,6 +70,13 @@ enum Commands {
         username: String,
     },
 
+    /// List the CalDAV clients that have synced as a user
+    ListSyncClients {
+        /// Username
+        #[arg(short, long)]
+        username: String,
+    },
+
     /// Delete an MCP token by ID
     DeleteToken {
         /// Token ID to delete
@@ -63,6 +84,49 @@ enum Commands {
         id: String,
     },
 
+    /// Replace an MCP token's secret, keeping its ID and name
+    RotateToken {
+        /// Token ID to rotate
+        #[arg(short, long)]
+        id: String,
+    },
+
+    /// Import an .ics file into a user's calendar
+    ImportIcs {
+        /// Username of the calendar owner
+        #[arg(short, long)]
+        username: String,
+        /// Calendar to import into (default: a new calendar named after the file)
+        #[arg(short, long)]
+        calendar_id: Option<String>,
+        /// Path to the .ics file
+        #[arg(short, long)]
+        file: std::path::PathBuf,
+    },
+
+    /// Export a calendar to an .ics file
+    ExportIcs {
+        /// Username of the calendar owner
+        #[arg(short, long)]
+        username: String,
+        /// Calendar to export
+        #[arg(short, long)]
+        calendar_id: String,
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        out: Option<std::path::PathBuf>,
+    },
+
+    /// Delete a user with their calendars, events, shares and tokens
+    DeleteUser {
+        /// Username
+        #[arg(short, long)]
+        username: String,
+        /// Confirm the deletion; nothing is removed without it
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Reset a user's password
     ResetPassword {
         /// Username
@@ -91,10 +155,28 @@ async fn main() -> anyhow::Result<()> {
             password,
             email,
         } => cmd_create_user(&username, &password, email.as_deref()).await,
-        Commands::CreateToken { username, name } => cmd_create_token(&username, &name).await,
-        Commands::ListUsers => cmd_list_users().await,
-        Commands::ListTokens { username } => cmd_list_tokens(&username).await,
+        Commands::CreateToken {
+            username,
+            name,
+            read_only,
+            expires_in_days,
+        } => cmd_create_token(&username, &name, read_only, expires_in_days).await,
+        Commands::ListUsers => cmd_list_users(cli.json).await,
+        Commands::ListTokens { username } => cmd_list_tokens(&username, cli.json).await,
+        Commands::ListSyncClients { username } => cmd_list_sync_clients(&username, cli.json).await,
         Commands::DeleteToken { id } => cmd_delete_token(&id).await,
+        Commands::RotateToken { id } => cmd_rotate_token(&id).await,
+        Commands::ImportIcs {
+            username,
+            calendar_id,
+            file,
+        } => cmd_import_ics(&username, calendar_id.as_deref(), &file).await,
+        Commands::ExportIcs {
+            username,
+            calendar_id,
+            out,
+        } => cmd_export_ics(&username, &calendar_id, out.as_deref()).await,
+        Commands::DeleteUser { username, force } => cmd_delete_user(&username, force).await,
         Commands::ResetPassword { username, password } => {
             cmd_reset_password(&username, &password).await
         }
@@ -115,24 +197,41 @@ async fn run_server() -> anyhow::Result<()> {
         "Starting CalDAV server"
     );
 
-    let pool = db::init_pool(&config.database_url).await?;
+    let pool = db::init_pool(&config.database_url, config.db_max_connections).await?;
     tracing::info!("Database initialized");
 
-    let caldav_app = caldav::router(pool.clone());
+    if config.reminders_enabled {
+        tracing::info!(
+            sink = ?config.reminder_sink,
+            interval_secs = config.reminder_interval_secs,
+            "Reminder dispatcher enabled"
+        );
+        reminders::Dispatcher::new(
+            pool.clone(),
+            config.reminder_sink.clone(),
+            config.outbound_limits(),
+        )
+        .spawn(config.reminder_interval_secs);
+    }
+
+    let caldav_app = caldav::router(pool.clone(), config.clone());
     tracing::info!(tool_mode = %config.tool_mode, "MCP tool mode");
-    let mcp_app = mcp::router(pool.clone(), config.tool_mode.clone());
+    let mcp_app = mcp::router(pool.clone(), config.clone());
+
+    let tls = tls::load(&config).await?;
+    let scheme = if tls.is_some() { "https" } else { "http" };
 
-    let caldav_addr = SocketAddr::from(([0, 0, 0, 0], config.caldav_port));
+    let caldav_addr = SocketAddr::new(config.bind_address, config.caldav_port);
     let caldav_listener = TcpListener::bind(caldav_addr).await?;
-    tracing::info!(%caldav_addr, "CalDAV server listening");
+    tracing::info!(%caldav_addr, scheme, "CalDAV server listening");
 
-    let mcp_addr = SocketAddr::from(([0, 0, 0, 0], config.mcp_port));
+    let mcp_addr = SocketAddr::new(config.bind_address, config.mcp_port);
     let mcp_listener = TcpListener::bind(mcp_addr).await?;
-    tracing::info!(%mcp_addr, "MCP server listening");
+    tracing::info!(%mcp_addr, scheme, "MCP server listening");
 
     tokio::try_join!(
-        axum::serve(caldav_listener, caldav_app).into_future(),
-        axum::serve(mcp_listener, mcp_app).into_future(),
+        tls::serve(caldav_listener, caldav_app, tls.clone()),
+        tls::serve(mcp_listener, mcp_app, tls),
     )?;
 
     Ok(())
@@ -141,7 +240,7 @@ async fn run_server() -> anyhow::Result<()> {
 /// Helper: init a DB pool from env for CLI commands.
 async fn cli_pool() -> anyhow::Result<sqlx::SqlitePool> {
     let config = config::Config::from_env()?;
-    Ok(db::init_pool(&config.database_url).await?)
+    Ok(db::init_pool(&config.database_url, config.db_max_connections).await?)
 }
 
 /// Create a new user.
@@ -150,8 +249,10 @@ async fn cmd_create_user(
     password: &str,
     email: Option<&str>,
 ) -> anyhow::Result<()> {
+    let config = config::Config::from_env()?;
     let pool = cli_pool().await?;
-    let user = db::users::create_user(&pool, username, email, password).await?;
+    let user =
+        db::users::create_user(&pool, username, email, password, &config.password_policy()).await?;
     println!("User created:");
     println!("  ID:       {}", user.id);
     println!("  Username: {}", user.username);
@@ -162,16 +263,33 @@ async fn cmd_create_user(
 }
 
 /// Create an MCP token for a user.
-async fn cmd_create_token(username: &str, name: &str) -> anyhow::Result<()> {
+async fn cmd_create_token(
+    username: &str,
+    name: &str,
+    read_only: bool,
+    expires_in_days: Option<u32>,
+) -> anyhow::Result<()> {
     let pool = cli_pool().await?;
     let user = db::users::get_user_by_username(&pool, username)
         .await?
         .ok_or_else(|| anyhow::anyhow!("User '{username}' not found"))?;
 
-    let (raw_token, record) = db::tokens::create_token(&pool, &user.id, name).await?;
+    let scope = if read_only {
+        db::models::Permission::Read
+    } else {
+        db::models::Permission::ReadWrite
+    };
+    let expires_at = expires_in_days
+        .map(|days| chrono::Utc::now().naive_utc() + chrono::Duration::days(i64::from(days)));
+    let (raw_token, record) =
+        db::tokens::create_token(&pool, &user.id, name, scope, expires_at).await?;
     println!("MCP token created:");
     println!("  ID:    {}", record.id);
     println!("  Name:  {}", record.name);
+    println!("  Scope: {}", record.scope);
+    if let Some(expires_at) = record.expires_at {
+        println!("  Expires: {expires_at}");
+    }
     println!("  Token: {raw_token}");
     println!();
     println!("Save this token — it cannot be retrieved again.");
@@ -179,47 +297,134 @@ async fn cmd_create_token(username: &str, name: &str) -> anyhow::Result<()> {
 }
 
 /// List all users.
-async fn cmd_list_users() -> anyhow::Result<()> {
+async fn cmd_list_users(json: bool) -> anyhow::Result<()> {
     let pool = cli_pool().await?;
     let users = sqlx::query_as::<_, db::models::User>("SELECT * FROM users ORDER BY username")
         .fetch_all(&pool)
         .await?;
+    print!("{}", format_users(&users, json));
+    Ok(())
+}
 
+/// `list-users` output: a table, or a JSON array with `--json`. Password
+/// hashes are never included.
+fn format_users(users: &[db::models::User], json: bool) -> String {
+    if json {
+        let rows: Vec<_> = users
+            .iter()
+            .map(|u| {
+                json!({
+                    "id": u.id,
+                    "username": u.username,
+                    "email": u.email,
+                    "created_at": u.created_at.to_string(),
+                })
+            })
+            .collect();
+        return format!("{}\n", serde_json::Value::from(rows));
+    }
     if users.is_empty() {
-        println!("No users found.");
-        return Ok(());
+        return "No users found.\n".to_string();
     }
 
-    println!("{:<38} {:<20} Email", "ID", "Username");
-    println!("{}", "-".repeat(70));
-    for u in &users {
-        println!(
-            "{:<38} {:<20} {}",
+    let mut out = format!("{:<38} {:<20} Email\n", "ID", "Username");
+    out.push_str(&format!("{}\n", "-".repeat(70)));
+    for u in users {
+        out.push_str(&format!(
+            "{:<38} {:<20} {}\n",
             u.id,
             u.username,
             u.email.as_deref().unwrap_or("-")
-        );
+        ));
     }
-    Ok(())
+    out
 }
 
 /// List MCP tokens for a user.
-async fn cmd_list_tokens(username: &str) -> anyhow::Result<()> {
+async fn cmd_list_tokens(username: &str, json: bool) -> anyhow::Result<()> {
     let pool = cli_pool().await?;
     let user = db::users::get_user_by_username(&pool, username)
         .await?
         .ok_or_else(|| anyhow::anyhow!("User '{username}' not found"))?;
 
     let tokens = db::tokens::list_tokens_for_user(&pool, &user.id).await?;
+    print!("{}", format_tokens(username, &tokens, json));
+    Ok(())
+}
+
+/// `list-tokens` output: a table, or a JSON array with `--json`. Token
+/// hashes are never included.
+fn format_tokens(username: &str, tokens: &[db::models::McpToken], json: bool) -> String {
+    if json {
+        let rows: Vec<_> = tokens
+            .iter()
+            .map(|t| {
+                json!({
+                    "id": t.id,
+                    "name": t.name,
+                    "scope": t.scope,
+                    "created_at": t.created_at.to_string(),
+                    "expires_at": t.expires_at.map(|e| e.to_string()),
+                })
+            })
+            .collect();
+        return format!("{}\n", serde_json::Value::from(rows));
+    }
     if tokens.is_empty() {
-        println!("No tokens found for user '{username}'.");
+        return format!("No tokens found for user '{username}'.\n");
+    }
+
+    let mut out = format!(
+        "{:<38} {:<20} {:<10} {:<20} Expires\n",
+        "ID", "Name", "Scope", "Created"
+    );
+    out.push_str(&format!("{}\n", "-".repeat(100)));
+    for t in tokens {
+        out.push_str(&format!(
+            "{:<38} {:<20} {:<10} {:<20} {}\n",
+            t.id,
+            t.name,
+            t.scope,
+            t.created_at.to_string(),
+            t.expires_at
+                .map(|e| e.to_string())
+                .unwrap_or_else(|| "never".to_string())
+        ));
+    }
+    out
+}
+
+/// List the CalDAV clients that have synced as a user.
+async fn cmd_list_sync_clients(username: &str, json: bool) -> anyhow::Result<()> {
+    let pool = cli_pool().await?;
+    let user = db::users::get_user_by_username(&pool, username)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("User '{username}' not found"))?;
+
+    let clients = db::sync_clients::list_clients(&pool, &user.id).await?;
+    if json {
+        let rows: Vec<_> = clients
+            .iter()
+            .map(|c| {
+                json!({
+                    "user_agent": c.user_agent,
+                    "first_seen": c.first_seen.to_string(),
+                    "last_seen": c.last_seen.to_string(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::from(rows));
+        return Ok(());
+    }
+    if clients.is_empty() {
+        println!("No sync clients found for user '{username}'.");
         return Ok(());
     }
 
-    println!("{:<38} {:<20} Created", "ID", "Name");
+    println!("{:<20} {:<20} User-Agent", "Last seen", "First seen");
     println!("{}", "-".repeat(70));
-    for t in &tokens {
-        println!("{:<38} {:<20} {}", t.id, t.name, t.created_at);
+    for c in &clients {
+        println!("{:<20} {:<20} {}", c.last_seen, c.first_seen, c.user_agent);
     }
     Ok(())
 }
@@ -232,10 +437,158 @@ async fn cmd_delete_token(token_id: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Replace an MCP token's secret, keeping its ID and name.
+async fn cmd_rotate_token(token_id: &str) -> anyhow::Result<()> {
+    let pool = cli_pool().await?;
+    let (raw_token, record) = db::tokens::rotate_token(&pool, token_id).await?;
+    println!("MCP token rotated:");
+    println!("  ID:    {}", record.id);
+    println!("  Name:  {}", record.name);
+    println!("  Token: {raw_token}");
+    println!();
+    println!(
+        "The previous secret no longer works. Save this token — it cannot be retrieved again."
+    );
+    Ok(())
+}
+
+/// Import an .ics file into a user's calendar.
+async fn cmd_import_ics(
+    username: &str,
+    calendar_id: Option<&str>,
+    file: &std::path::Path,
+) -> anyhow::Result<()> {
+    let config = config::Config::from_env()?;
+    let pool = cli_pool().await?;
+    let user = db::users::get_user_by_username(&pool, username)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("User '{username}' not found"))?;
+    let result = mcp::tools::import::import_file(&pool, &user.id, calendar_id, file, &config)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    println!("Imported {}:", file.display());
+    println!(
+        "  Calendar: {}",
+        result["calendar_id"].as_str().unwrap_or_default()
+    );
+    println!("  Created:  {}", result["created"]);
+    println!("  Updated:  {}", result["updated"]);
+    println!("  Failed:   {}", result["failed"]);
+    for error in result["errors"].as_array().into_iter().flatten() {
+        println!(
+            "    {}: {}",
+            error["uid"].as_str().unwrap_or_default(),
+            error["error"].as_str().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Export a calendar to a file, or to stdout when no file is given.
+async fn cmd_export_ics(
+    username: &str,
+    calendar_id: &str,
+    out: Option<&std::path::Path>,
+) -> anyhow::Result<()> {
+    let pool = cli_pool().await?;
+    let user = db::users::get_user_by_username(&pool, username)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("User '{username}' not found"))?;
+    let result = mcp::tools::calendars::export_owned_calendar(&pool, &user.id, calendar_id, out)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    match out {
+        Some(path) => println!(
+            "Exported {} objects to {}.",
+            result["count"],
+            path.display()
+        ),
+        None => print!("{}", result["ics"].as_str().unwrap_or_default()),
+    }
+    Ok(())
+}
+
+/// Delete a user and everything they own. Requires `--force`.
+async fn cmd_delete_user(username: &str, force: bool) -> anyhow::Result<()> {
+    if !force {
+        anyhow::bail!(
+            "Deleting '{username}' removes their calendars, events, shares and tokens. \
+             Re-run with --force to confirm."
+        );
+    }
+    let pool = cli_pool().await?;
+    db::users::delete_user(&pool, username).await?;
+    println!("User '{username}' deleted.");
+    Ok(())
+}
+
 /// Reset a user's password.
 async fn cmd_reset_password(username: &str, password: &str) -> anyhow::Result<()> {
+    let config = config::Config::from_env()?;
     let pool = cli_pool().await?;
-    db::users::reset_password(&pool, username, password).await?;
+    db::users::reset_password(&pool, username, password, &config.password_policy()).await?;
     println!("Password updated for user '{username}'.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_output_as_json() {
+        let pool = db::test_pool().await;
+        let policy = db::users::PasswordPolicy::default();
+        let alice = db::users::create_user(
+            &pool,
+            "alice",
+            Some("alice@example.com"),
+            "password",
+            &policy,
+        )
+        .await
+        .unwrap();
+        db::users::create_user(&pool, "bob", None, "password", &policy)
+            .await
+            .unwrap();
+        let users = sqlx::query_as::<_, db::models::User>("SELECT * FROM users ORDER BY username")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&format_users(&users, true)).unwrap();
+        let rows = parsed.as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["id"], alice.id);
+        assert_eq!(rows[0]["username"], "alice");
+        assert_eq!(rows[0]["email"], "alice@example.com");
+        assert!(rows[1]["email"].is_null());
+        assert!(rows[0].get("password_hash").is_none());
+
+        db::tokens::create_token(
+            &pool,
+            &alice.id,
+            "laptop",
+            db::models::Permission::Read,
+            None,
+        )
+        .await
+        .unwrap();
+        let tokens = db::tokens::list_tokens_for_user(&pool, &alice.id)
+            .await
+            .unwrap();
+        let parsed: serde_json::Value =
+            serde_json::from_str(&format_tokens("alice", &tokens, true)).unwrap();
+        let rows = parsed.as_array().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["id"], tokens[0].id);
+        assert_eq!(rows[0]["name"], "laptop");
+        assert_eq!(rows[0]["scope"], "read");
+        assert!(rows[0]["expires_at"].is_null());
+        assert!(rows[0].get("token_hash").is_none());
+
+        // An empty list is still valid JSON
+        assert_eq!(format_tokens("bob", &[], true), "[]\n");
+        assert!(format_tokens("bob", &[], false).starts_with("No tokens found"));
+    }
+}
@@ -4,17 +4,35 @@ use axum::{
     middleware::Next,
     response::Response,
 };
-use sqlx::SqlitePool;
 
+use super::provision;
+use super::transport::McpState;
+use crate::db::models::Permission;
 use crate::db::tokens;
+use crate::tls::ServedOverTls;
 
 /// Middleware to require Bearer token authentication for MCP requests.
-/// On success, inserts the user_id into request extensions.
+/// On success, inserts the user_id and the token's scope into request
+/// extensions.
+///
+/// With `AUTO_PROVISION` enabled, a token that is not a stored MCP token may
+/// instead be a provisioning JWT signed with `AUTO_PROVISION_SECRET`; its
+/// `username` claim names the user, who is created if absent.
 pub async fn require_bearer_auth(
-    State(pool): State<SqlitePool>,
+    State(state): State<McpState>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, Response> {
+    // Check before looking at the token so it is never validated over plain HTTP
+    if state.config.require_tls_for_mcp && !is_https(&request, state.config.trust_proxy) {
+        return Err(Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(axum::body::Body::from(
+                "HTTPS required: bearer tokens are not accepted over plain HTTP",
+            ))
+            .unwrap());
+    }
+
     let auth_header = request
         .headers()
         .get(header::AUTHORIZATION)
@@ -25,21 +43,68 @@ pub async fn require_bearer_auth(
         .strip_prefix("Bearer ")
         .ok_or_else(|| unauthorized_response("Invalid authorization scheme, expected Bearer"))?;
 
-    let user_id = tokens::validate_token(&pool, token)
+    let stored = tokens::validate_token(&state.pool, token)
         .await
-        .map_err(|_| unauthorized_response("Token validation failed"))?
-        .ok_or_else(|| unauthorized_response("Invalid or expired token"))?;
+        .map_err(|_| unauthorized_response("Token validation failed"))?;
+    let (user_id, scope) = match stored {
+        Some(found) => found,
+        None => provisioned_user_id(&state, token)
+            .await
+            .map(|user_id| (user_id, Permission::ReadWrite))
+            .ok_or_else(|| unauthorized_response("Invalid or expired token"))?,
+    };
 
-    // Store user_id in request extensions
+    // Store user_id and scope in request extensions
     request.extensions_mut().insert(McpUserId(user_id));
+    request.extensions_mut().insert(McpTokenScope(scope));
 
     Ok(next.run(request).await)
 }
 
+/// Whether the request arrived over TLS, either on this server's own HTTPS
+/// listener or, with `trust_proxy`, via a reverse proxy that terminated TLS
+/// and set `X-Forwarded-Proto`. Without `trust_proxy` the header is ignored,
+/// since any client can send it.
+fn is_https(request: &Request, trust_proxy: bool) -> bool {
+    if request.uri().scheme_str() == Some("https")
+        || request.extensions().get::<ServedOverTls>().is_some()
+    {
+        return true;
+    }
+    trust_proxy
+        && request
+            .headers()
+            .get("x-forwarded-proto")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https"))
+}
+
+/// Resolve a provisioning token to a user ID, creating the user if needed.
+/// Returns `None` when provisioning is disabled or the token is not valid.
+async fn provisioned_user_id(state: &McpState, token: &str) -> Option<String> {
+    if !state.config.auto_provision {
+        return None;
+    }
+    let secret = state.config.auto_provision_secret.as_deref()?;
+    let username = provision::verify_token(token, secret, chrono::Utc::now().timestamp())?;
+    match provision::find_or_create_user(&state.pool, &username).await {
+        Ok(user) => Some(user.id),
+        Err(e) => {
+            tracing::warn!(username = %username, error = %e, "auto-provisioning failed");
+            None
+        }
+    }
+}
+
 /// Wrapper for the authenticated MCP user's ID.
 #[derive(Debug, Clone)]
 pub struct McpUserId(pub String);
 
+/// Wrapper for the scope of the token the MCP request authenticated with.
+#[derive(Debug, Clone, Copy)]
+pub struct McpTokenScope(pub Permission);
+
 fn unauthorized_response(msg: &str) -> Response {
     Response::builder()
         .status(StatusCode::UNAUTHORIZED)
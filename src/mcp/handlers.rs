@@ -2,25 +2,38 @@ use serde_json::{Value, json};
 use sqlx::SqlitePool;
 
 use super::jsonrpc::{JsonRpcErrorResponse, JsonRpcRequest, JsonRpcResponse};
-use super::session::SessionManager;
 use super::tools;
+use crate::config::Config;
+use crate::db::models::Permission;
 
 /// Handle an MCP JSON-RPC request. Returns the response value to serialize.
 pub async fn handle_request(
     pool: &SqlitePool,
-    sessions: &SessionManager,
     user_id: &str,
+    scope: Permission,
     request: &JsonRpcRequest,
-    tool_mode: &str,
+    config: &Config,
 ) -> Value {
+    let tool_mode = config.tool_mode.as_str();
+    // MCP only uses by-name params; a string, number or array is a client bug.
+    if !request.params.is_null() && !request.params.is_object() {
+        return serde_json::to_value(JsonRpcErrorResponse::invalid_params(
+            request.id.clone(),
+            format!(
+                "'params' must be an object, got {}",
+                json_type_name(&request.params)
+            ),
+        ))
+        .unwrap();
+    }
     match request.method.as_str() {
-        "initialize" => handle_initialize(sessions, user_id, request, tool_mode),
+        "initialize" => handle_initialize(request, tool_mode),
         "notifications/initialized" => {
             // Notification — no response needed
             Value::Null
         }
         "tools/list" => handle_tools_list(request, tool_mode),
-        "tools/call" => handle_tools_call(pool, user_id, request, tool_mode).await,
+        "tools/call" => handle_tools_call(pool, user_id, scope, request, config).await,
         "ping" => {
             serde_json::to_value(JsonRpcResponse::success(request.id.clone(), json!({}))).unwrap()
         }
@@ -29,15 +42,57 @@ pub async fn handle_request(
     }
 }
 
-/// Handle the MCP initialize request.
-fn handle_initialize(
-    sessions: &SessionManager,
-    user_id: &str,
-    request: &JsonRpcRequest,
-    tool_mode: &str,
-) -> Value {
-    let _session_id = sessions.create_session(user_id);
+/// The JSON type of a value, for error messages.
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
 
+/// The candidate closest to `name` by edit distance, if it is within two
+/// edits, to suggest for a misspelt argument.
+fn closest_name<'a>(name: &str, candidates: &'a [String]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c.as_str())
+}
+
+/// Levenshtein distance, counting an adjacent transposition as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Handle the MCP initialize request. The transport creates the session and
+/// returns its ID in the `Mcp-Session-Id` header.
+fn handle_initialize(request: &JsonRpcRequest, tool_mode: &str) -> Value {
     let instructions = if tool_mode == "simple" {
         "Calendar server. Tools: add_event (create event), delete_event (remove event), list_events (show events, optional time range filter)."
     } else {
@@ -86,8 +141,9 @@ fn handle_tools_list(request: &JsonRpcRequest, tool_mode: &str) -> Value {
 async fn handle_tools_call(
     pool: &SqlitePool,
     user_id: &str,
+    scope: Permission,
     request: &JsonRpcRequest,
-    tool_mode: &str,
+    config: &Config,
 ) -> Value {
     let tool_name = match request.params.get("name").and_then(|v| v.as_str()) {
         Some(name) => name,
@@ -100,13 +156,50 @@ async fn handle_tools_call(
         }
     };
 
-    let arguments = request
-        .params
-        .get("arguments")
-        .cloned()
-        .unwrap_or(json!({}));
+    let arguments = match request.params.get("arguments") {
+        None => json!({}),
+        Some(args) if args.is_object() => args.clone(),
+        Some(args) => {
+            return serde_json::to_value(JsonRpcErrorResponse::invalid_params(
+                request.id.clone(),
+                format!(
+                    "'arguments' must be an object, got {}",
+                    json_type_name(args)
+                ),
+            ))
+            .unwrap();
+        }
+    };
+
+    if config.strict_tool_args
+        && let Some(unknown) = tools::unknown_arguments(&config.tool_mode, tool_name, &arguments)
+        && !unknown.is_empty()
+    {
+        let allowed = tools::declared_arguments(&config.tool_mode, tool_name);
+        let hints: Vec<String> = unknown
+            .iter()
+            .map(|name| match closest_name(name, &allowed) {
+                Some(suggestion) => format!("'{name}' (did you mean '{suggestion}'?)"),
+                None => format!("'{name}'"),
+            })
+            .collect();
+        let message = format!(
+            "Unknown argument{} for {tool_name}: {}. Allowed arguments: {}",
+            if unknown.len() == 1 { "" } else { "s" },
+            hints.join(", "),
+            allowed.join(", ")
+        );
+        return serde_json::to_value(
+            JsonRpcErrorResponse::invalid_params(request.id.clone(), message).with_data(json!({
+                "tool": tool_name,
+                "unknown_arguments": unknown,
+                "allowed_arguments": allowed,
+            })),
+        )
+        .unwrap();
+    }
 
-    match tools::dispatch(pool, user_id, tool_name, &arguments, tool_mode).await {
+    match tools::dispatch(pool, user_id, scope, tool_name, &arguments, config).await {
         Ok(result) => {
             let mut content = json!({
                 "content": [{
@@ -125,8 +218,11 @@ async fn handle_tools_call(
             let content = json!({
                 "content": [{
                     "type": "text",
-                    "text": err
+                    "text": err.message
                 }],
+                "structuredContent": {
+                    "error": {"code": err.code, "message": err.message}
+                },
                 "isError": true
             });
             serde_json::to_value(JsonRpcResponse::success(request.id.clone(), content)).unwrap()
@@ -35,6 +35,9 @@ pub struct JsonRpcErrorResponse {
 pub struct JsonRpcError {
     pub code: i32,
     pub message: String,
+    /// Machine-readable detail about the error, when there is any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
 }
 
 impl JsonRpcResponse {
@@ -57,10 +60,22 @@ impl JsonRpcErrorResponse {
             error: JsonRpcError {
                 code,
                 message: message.into(),
+                data: None,
             },
         }
     }
 
+    /// Attach machine-readable `data` to the error.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.error.data = Some(data);
+        self
+    }
+
+    /// Construct a -32600 Invalid Request error response with a descriptive message.
+    pub fn invalid_request(id: Option<Value>, msg: impl Into<String>) -> Self {
+        Self::error(id, INVALID_REQUEST, msg)
+    }
+
     /// Construct a -32601 Method Not Found error response.
     pub fn method_not_found(id: Option<Value>) -> Self {
         Self::error(id, -32601, "Method not found")
@@ -80,7 +95,6 @@ impl JsonRpcErrorResponse {
 
 /// Standard MCP error codes
 pub const PARSE_ERROR: i32 = -32700;
-#[allow(dead_code)]
 pub const INVALID_REQUEST: i32 = -32600;
 #[allow(dead_code)]
 pub const METHOD_NOT_FOUND: i32 = -32601;
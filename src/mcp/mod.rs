@@ -1,25 +1,35 @@
 mod auth;
 mod handlers;
 mod jsonrpc;
+mod provision;
 mod session;
-mod tools;
+pub(crate) mod tools;
 mod transport;
 
+use std::sync::Arc;
+
 use axum::Router;
 use axum::middleware;
 use axum::routing::{delete, get, post};
 use sqlx::SqlitePool;
 use tower_http::trace::TraceLayer;
 
+use crate::config::Config;
 use session::SessionManager;
 use transport::McpState;
 
 /// Build the MCP router. Mounted on the MCP port.
-pub fn router(pool: SqlitePool, tool_mode: String) -> Router {
+pub fn router(pool: SqlitePool, config: Config) -> Router {
+    router_with_sessions(pool, config, SessionManager::new())
+}
+
+/// Build the MCP router around an existing session manager, so a caller
+/// holding a clone can push messages onto session streams.
+fn router_with_sessions(pool: SqlitePool, config: Config, sessions: SessionManager) -> Router {
     let state = McpState {
-        pool: pool.clone(),
-        sessions: SessionManager::new(),
-        tool_mode,
+        pool,
+        sessions,
+        config: Arc::new(config),
     };
 
     Router::new()
@@ -27,7 +37,7 @@ pub fn router(pool: SqlitePool, tool_mode: String) -> Router {
         .route("/mcp", get(transport::handle_get))
         .route("/mcp", delete(transport::handle_delete))
         .layer(middleware::from_fn_with_state(
-            pool.clone(),
+            state.clone(),
             auth::require_bearer_auth,
         ))
         .layer(TraceLayer::new_for_http())
@@ -44,17 +54,30 @@ mod tests {
     use tower::ServiceExt;
 
     use crate::db;
-    use crate::db::{calendars, tokens, users};
+    use crate::db::users::{self, PasswordPolicy};
+    use crate::db::{calendars, tokens};
 
     /// Create a test pool with a user and a valid MCP bearer token.
     async fn setup() -> (SqlitePool, String, String) {
         let pool = db::test_pool().await;
-        let user = users::create_user(&pool, "alice", Some("alice@example.com"), "secret123")
-            .await
-            .unwrap();
-        let (raw_token, _record) = tokens::create_token(&pool, &user.id, "test-token")
-            .await
-            .unwrap();
+        let user = users::create_user(
+            &pool,
+            "alice",
+            Some("alice@example.com"),
+            "secret123",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let (raw_token, _record) = tokens::create_token(
+            &pool,
+            &user.id,
+            "test-token",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
         (pool, user.id, raw_token)
     }
 
@@ -64,7 +87,17 @@ mod tests {
 
     /// Send a JSON-RPC request to /mcp and return (status, parsed body).
     async fn rpc_call(pool: &SqlitePool, token: &str, body: Value) -> (StatusCode, Value) {
-        let app = router(pool.clone(), "full".to_string());
+        rpc_call_with_config(pool, token, body, Config::default()).await
+    }
+
+    /// Like `rpc_call`, but against a router built with the given config.
+    async fn rpc_call_with_config(
+        pool: &SqlitePool,
+        token: &str,
+        body: Value,
+        config: Config,
+    ) -> (StatusCode, Value) {
+        let app = router(pool.clone(), config);
         let req = axum::http::Request::builder()
             .method(Method::POST)
             .uri("/mcp")
@@ -95,12 +128,40 @@ mod tests {
         resp["result"]["structuredContent"].clone()
     }
 
+    /// Helper: call a tool that is expected to fail and return its error text.
+    async fn tool_error(
+        pool: &SqlitePool,
+        token: &str,
+        tool_name: &str,
+        arguments: Value,
+    ) -> String {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": tool_name,
+                "arguments": arguments
+            }
+        });
+        let (status, resp) = rpc_call(pool, token, body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            resp["result"]["isError"], true,
+            "expected tool error: {resp}"
+        );
+        resp["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
     // ---- Auth tests ----
 
     #[tokio::test]
     async fn test_no_auth_returns_401() {
         let pool = db::test_pool().await;
-        let app = router(pool, "full".to_string());
+        let app = router(pool, Config::default());
         let req = axum::http::Request::builder()
             .method(Method::POST)
             .uri("/mcp")
@@ -114,7 +175,7 @@ mod tests {
     #[tokio::test]
     async fn test_invalid_token_returns_401() {
         let pool = db::test_pool().await;
-        let app = router(pool, "full".to_string());
+        let app = router(pool, Config::default());
         let req = axum::http::Request::builder()
             .method(Method::POST)
             .uri("/mcp")
@@ -126,6 +187,156 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 
+    /// Sign an HS256 provisioning token carrying the given claims.
+    fn provisioning_token(secret: &str, claims: Value) -> String {
+        use base64::Engine;
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use hmac::{Hmac, Mac};
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{header}.{claims}").as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{header}.{claims}.{signature}")
+    }
+
+    #[tokio::test]
+    async fn test_require_tls_for_mcp_refuses_plain_http() {
+        let (pool, _user_id, token) = setup().await;
+        let config = Config {
+            require_tls_for_mcp: true,
+            ..Config::default()
+        };
+        let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+
+        // Plain HTTP is refused even with a valid token
+        let (status, _) = rpc_call_with_config(&pool, &token, ping.clone(), config.clone()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // X-Forwarded-Proto counts only from a trusted proxy
+        let forwarded = |proto: &str| {
+            axum::http::Request::builder()
+                .method(Method::POST)
+                .uri("/mcp")
+                .header("Content-Type", "application/json")
+                .header("Authorization", bearer_header(&token))
+                .header("X-Forwarded-Proto", proto)
+                .body(Body::from(serde_json::to_vec(&ping).unwrap()))
+                .unwrap()
+        };
+        let resp = router(pool.clone(), config.clone())
+            .oneshot(forwarded("https"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let config = Config {
+            trust_proxy: true,
+            ..config
+        };
+        for proto in ["http", "https"] {
+            let resp = router(pool.clone(), config.clone())
+                .oneshot(forwarded(proto))
+                .await
+                .unwrap();
+            let expected = if proto == "https" {
+                StatusCode::OK
+            } else {
+                StatusCode::FORBIDDEN
+            };
+            assert_eq!(resp.status(), expected, "X-Forwarded-Proto: {proto}");
+        }
+
+        // Off by default
+        let (status, _) = rpc_call_with_config(&pool, &token, ping, Config::default()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_auto_provision_creates_user_on_first_use() {
+        let pool = db::test_pool().await;
+        let config = Config {
+            auto_provision: true,
+            auto_provision_secret: Some("provisioning-secret".to_string()),
+            ..Config::default()
+        };
+        let exp = chrono::Utc::now().timestamp() + 3600;
+        let token = provisioning_token(
+            "provisioning-secret",
+            json!({"username": "carol", "exp": exp}),
+        );
+        let create = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "create_calendar", "arguments": {"name": "Personal"}}
+        });
+
+        // Provisioning is off by default: the token is rejected
+        let (status, _) =
+            rpc_call_with_config(&pool, &token, create.clone(), Config::default()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(
+            users::get_user_by_username(&pool, "carol")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let (status, resp) =
+            rpc_call_with_config(&pool, &token, create.clone(), config.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp["result"]["isError"], false, "got: {resp}");
+        let carol = users::get_user_by_username(&pool, "carol")
+            .await
+            .unwrap()
+            .expect("user should be provisioned");
+
+        // A second use reuses the same user
+        let (status, _) = rpc_call_with_config(&pool, &token, create, config.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        let owned = calendars::list_calendars_for_user(&pool, &carol.id)
+            .await
+            .unwrap();
+        assert_eq!(owned.len(), 2);
+
+        // Wrong signature and expired tokens are rejected
+        let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+        let forged = provisioning_token("other-secret", json!({"username": "mallory", "exp": exp}));
+        let (status, _) = rpc_call_with_config(&pool, &forged, ping.clone(), config.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let expired = provisioning_token(
+            "provisioning-secret",
+            json!({"username": "dave", "exp": 1_000_000_000}),
+        );
+        let (status, _) = rpc_call_with_config(&pool, &expired, ping.clone(), config.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // Tokens without an expiry, or valid for more than 30 days, are rejected
+        let forever = provisioning_token("provisioning-secret", json!({"username": "dave"}));
+        let (status, _) = rpc_call_with_config(&pool, &forever, ping.clone(), config.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let distant = provisioning_token(
+            "provisioning-secret",
+            json!({"username": "dave", "exp": exp + 31 * 24 * 3600}),
+        );
+        let (status, _) = rpc_call_with_config(&pool, &distant, ping, config).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(
+            users::get_user_by_username(&pool, "mallory")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            users::get_user_by_username(&pool, "dave")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
     // ---- Protocol tests ----
 
     #[tokio::test]
@@ -162,13 +373,136 @@ mod tests {
         let (status, resp) = rpc_call(&pool, &token, body).await;
         assert_eq!(status, StatusCode::OK);
         let tools = resp["result"]["tools"].as_array().unwrap();
-        assert_eq!(tools.len(), 12);
+        assert_eq!(tools.len(), 41);
         let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
         assert!(names.contains(&"list_calendars"));
         assert!(names.contains(&"create_event"));
         assert!(names.contains(&"share_calendar"));
     }
 
+    #[tokio::test]
+    async fn test_tools_list_medium_mode_omits_sharing() {
+        let (pool, _user_id, token) = setup().await;
+        let config = Config {
+            tool_mode: "medium".to_string(),
+            ..Config::default()
+        };
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        let (status, resp) = rpc_call_with_config(&pool, &token, body, config).await;
+        assert_eq!(status, StatusCode::OK);
+        let tools = resp["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 38);
+        let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert!(names.contains(&"list_calendars"));
+        assert!(names.contains(&"create_event"));
+        assert!(names.contains(&"update_event"));
+        assert!(!names.contains(&"share_calendar"));
+        assert!(!names.contains(&"unshare_calendar"));
+    }
+
+    #[tokio::test]
+    async fn test_medium_mode_rejects_share_calendar() {
+        let (pool, _user_id, token) = setup().await;
+        let config = Config {
+            tool_mode: "medium".to_string(),
+            ..Config::default()
+        };
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "share_calendar",
+                "arguments": {"calendar_id": "x", "username": "bob", "permission": "read"}
+            }
+        });
+        let (status, resp) = rpc_call_with_config(&pool, &token, body, config).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp["result"]["isError"], true);
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Unknown tool"), "got: {text}");
+    }
+
+    #[tokio::test]
+    async fn test_read_only_token_cannot_write() {
+        let (pool, user_id, _token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let (token, _) = tokens::create_token(
+            &pool,
+            &user_id,
+            "viewer",
+            crate::db::models::Permission::Read,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let result = tool_call(&pool, &token, "list_calendars", json!({})).await;
+        assert_eq!(result["calendars"].as_array().unwrap().len(), 1);
+        let events = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id, "start": "20260301T000000Z", "end": "20260305T000000Z"}),
+        )
+        .await;
+        assert_eq!(events["count"], 0);
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Standup",
+                "start": "20260302T090000Z",
+                "end": "20260302T093000Z"
+            }),
+        )
+        .await;
+        assert!(err.contains("read-only"), "got: {err}");
+        let events = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id, "start": "20260301T000000Z", "end": "20260305T000000Z"}),
+        )
+        .await;
+        assert_eq!(events["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_rejected() {
+        let (pool, user_id, _token) = setup().await;
+        let now = chrono::Utc::now().naive_utc();
+        let (expired, _) = tokens::create_token(
+            &pool,
+            &user_id,
+            "old",
+            crate::db::models::Permission::ReadWrite,
+            Some(now - chrono::Duration::minutes(5)),
+        )
+        .await
+        .unwrap();
+        let (current, _) = tokens::create_token(
+            &pool,
+            &user_id,
+            "new",
+            crate::db::models::Permission::ReadWrite,
+            Some(now + chrono::Duration::days(1)),
+        )
+        .await
+        .unwrap();
+
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        let (status, _) = rpc_call(&pool, &expired, body.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = rpc_call(&pool, &current, body).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn test_unknown_method() {
         let (pool, _user_id, token) = setup().await;
@@ -181,7 +515,7 @@ mod tests {
     #[tokio::test]
     async fn test_notification_returns_202() {
         let (pool, _user_id, token) = setup().await;
-        let app = router(pool, "full".to_string());
+        let app = router(pool, Config::default());
         // Notification = no "id" field
         let body = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
         let req = axum::http::Request::builder()
@@ -234,178 +568,753 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_get_calendar() {
+    async fn test_list_calendars_detailed_includes_color_and_count() {
         let (pool, user_id, token) = setup().await;
-        let cal = calendars::create_calendar(&pool, &user_id, "Home", "Personal", "#00FF00", "UTC")
+        let work = calendars::create_calendar(&pool, &user_id, "Work", "Day job", "#FF0000", "UTC")
             .await
             .unwrap();
+        let _empty =
+            calendars::create_calendar(&pool, &user_id, "Empty", "", "#00FF00", "America/New_York")
+                .await
+                .unwrap();
+        for title in ["One", "Two"] {
+            tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({
+                    "calendar_id": work.id,
+                    "title": title,
+                    "start": "20260302T150000Z",
+                    "end": "20260302T160000Z"
+                }),
+            )
+            .await;
+        }
 
-        let result = tool_call(
+        // A calendar shared by another user is counted too
+        let bob = users::create_user(&pool, "bob", None, "password2", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let team = calendars::create_calendar(&pool, &bob.id, "Team", "", "#0000FF", "UTC")
+            .await
+            .unwrap();
+        crate::db::events::upsert_object(
             &pool,
-            &token,
-            "get_calendar",
-            json!({"calendar_id": cal.id}),
+            &team.id,
+            "team-event",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:team-event\r\nEND:VEVENT\r\nEND:VCALENDAR",
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: None,
+            },
         )
-        .await;
-        assert_eq!(result["name"], "Home");
-        assert_eq!(result["description"], "Personal");
-        assert_eq!(result["color"], "#00FF00");
+        .await
+        .unwrap();
+        crate::db::shares::share_calendar(
+            &pool,
+            &team.id,
+            &user_id,
+            crate::db::models::Permission::Read,
+        )
+        .await
+        .unwrap();
+
+        let result = tool_call(&pool, &token, "list_calendars_detailed", json!({})).await;
+        let cals = result["calendars"].as_array().unwrap();
+        assert_eq!(cals.len(), 3);
+        let by_name = |name: &str| cals.iter().find(|c| c["name"] == name).unwrap().clone();
+
+        let work_json = by_name("Work");
+        assert_eq!(work_json["color"], "#FF0000");
+        assert_eq!(work_json["description"], "Day job");
+        assert_eq!(work_json["event_count"], 2);
+        assert_eq!(work_json["shared"], false);
+        let ctag = calendars::get_calendar_by_id(&pool, &work.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+        assert_eq!(work_json["ctag"], ctag);
+
+        let empty_json = by_name("Empty");
+        assert_eq!(empty_json["event_count"], 0);
+        assert_eq!(empty_json["timezone"], "America/New_York");
+
+        let team_json = by_name("Team");
+        assert_eq!(team_json["event_count"], 1);
+        assert_eq!(team_json["shared"], true);
     }
 
     #[tokio::test]
-    async fn test_delete_calendar() {
+    async fn test_import_jcal_event_is_retrievable_as_ics() {
         let (pool, user_id, token) = setup().await;
-        let cal = calendars::create_calendar(&pool, &user_id, "Temp", "", "#000", "UTC")
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
             .await
             .unwrap();
+        let jcal = json!(["vcalendar", [["version", {}, "text", "2.0"]], [
+            ["vevent", [
+                ["uid", {}, "text", "jcal-standup"],
+                ["dtstart", {"tzid": "America/New_York"}, "date-time", "2026-03-02T09:00:00"],
+                ["dtend", {"tzid": "America/New_York"}, "date-time", "2026-03-02T09:15:00"],
+                ["summary", {}, "text", "Standup"],
+                ["rrule", {}, "recur", {"freq": "DAILY", "count": 5}]
+            ], []],
+            ["vtodo", [["summary", {}, "text", "No UID"]], []],
+            ["vcard", [["uid", {}, "text", "not-a-calendar-item"]], []]
+        ]]);
 
         let result = tool_call(
             &pool,
             &token,
-            "delete_calendar",
-            json!({"calendar_id": cal.id}),
+            "import_jcal",
+            json!({"calendar_id": cal.id, "jcal": jcal}),
         )
         .await;
-        assert_eq!(result["deleted"], true);
+        let imported = result["imported"].as_array().unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0]["uid"], "jcal-standup");
+        assert_eq!(imported[0]["created"], true);
+        assert_eq!(imported[1]["component_type"], "VTODO");
+        let errors = result["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["uid"], "not-a-calendar-item");
 
-        // Verify gone from DB
-        let db_cal = calendars::get_calendar_by_id(&pool, &cal.id).await.unwrap();
-        assert!(db_cal.is_none());
-    }
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": "jcal-standup"}),
+        )
+        .await;
+        let ics = event["ical_data"].as_str().unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("DTSTART;TZID=America/New_York:20260302T090000\r\n"));
+        assert!(ics.contains("SUMMARY:Standup\r\n"));
+        assert!(ics.contains("RRULE:FREQ=DAILY;COUNT=5\r\n"));
 
-    // ---- Event CRUD via MCP tools ----
+        // The same document may be passed as a JSON string
+        let err = tool_error(
+            &pool,
+            &token,
+            "import_jcal",
+            json!({"calendar_id": cal.id, "jcal": "[not json"}),
+        )
+        .await;
+        assert!(err.contains("Invalid jCal JSON"), "got: {err}");
+    }
 
     #[tokio::test]
-    async fn test_create_get_update_delete_event() {
+    async fn test_import_ics_splits_events_into_objects() {
         let (pool, user_id, token) = setup().await;
-        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
             .await
             .unwrap();
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   VERSION:2.0\r\n\
+                   PRODID:-//Google Inc//Google Calendar 70.9054//EN\r\n\
+                   BEGIN:VTIMEZONE\r\n\
+                   TZID:Europe/Berlin\r\n\
+                   BEGIN:STANDARD\r\n\
+                   DTSTART:19701025T030000\r\n\
+                   TZOFFSETFROM:+0200\r\n\
+                   TZOFFSETTO:+0100\r\n\
+                   END:STANDARD\r\n\
+                   END:VTIMEZONE\r\n\
+                   BEGIN:VEVENT\r\n\
+                   UID:kickoff@google.com\r\n\
+                   DTSTART;TZID=Europe/Berlin:20260302T100000\r\n\
+                   DTEND;TZID=Europe/Berlin:20260302T110000\r\n\
+                   SUMMARY:Kickoff\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   UID:review@google.com\r\n\
+                   DTSTART:20260303T140000Z\r\n\
+                   DTEND:20260303T150000Z\r\n\
+                   SUMMARY:Review\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   UID:retro@google.com\r\n\
+                   DTSTART:20260304T160000Z\r\n\
+                   DTEND:20260304T170000Z\r\n\
+                   SUMMARY:Retro\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VFREEBUSY\r\n\
+                   UID:busy@google.com\r\n\
+                   END:VFREEBUSY\r\n\
+                   END:VCALENDAR\r\n";
 
-        // Create
         let result = tool_call(
             &pool,
             &token,
-            "create_event",
-            json!({
-                "calendar_id": cal.id,
-                "title": "Team Standup",
-                "start": "20260301T090000Z",
-                "end": "20260301T093000Z",
-                "description": "Daily sync",
-                "location": "Room 42"
-            }),
+            "import_ics",
+            json!({"calendar_id": cal.id, "ics": ics}),
         )
         .await;
-        assert_eq!(result["title"], "Team Standup");
-        assert_eq!(result["calendar_id"], cal.id);
-        let uid = result["uid"].as_str().unwrap().to_string();
-        let etag = result["etag"].as_str().unwrap().to_string();
-
-        // Verify in DB
-        let db_obj = crate::db::events::get_object_by_uid(&pool, &cal.id, &uid)
-            .await
-            .unwrap()
-            .unwrap();
-        assert_eq!(db_obj.summary.as_deref(), Some("Team Standup"));
-        assert_eq!(db_obj.dtstart.as_deref(), Some("20260301T090000Z"));
+        assert_eq!(result["created"], 3);
+        assert_eq!(result["updated"], 0);
+        assert_eq!(result["failed"], 1);
+        assert_eq!(result["errors"][0]["uid"], "busy@google.com");
 
-        // Get
-        let result = tool_call(
+        let events = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id, "start": "20260301T000000Z", "end": "20260305T000000Z"}),
+        )
+        .await;
+        assert_eq!(events["count"], 3);
+        let kickoff = tool_call(
             &pool,
             &token,
             "get_event",
-            json!({
-                "calendar_id": cal.id,
-                "event_uid": uid
-            }),
+            json!({"calendar_id": cal.id, "event_uid": "kickoff@google.com"}),
         )
         .await;
-        assert_eq!(result["summary"], "Team Standup");
-        assert_eq!(result["etag"], etag);
-        assert!(result["ical_data"].as_str().unwrap().contains("VEVENT"));
+        let data = kickoff["ical_data"].as_str().unwrap();
+        assert!(data.contains("TZID:Europe/Berlin\r\n"), "got: {data}");
+        assert!(!data.contains("Review"), "got: {data}");
 
-        // Update
+        // Importing again updates in place
         let result = tool_call(
             &pool,
             &token,
-            "update_event",
-            json!({
-                "calendar_id": cal.id,
-                "event_uid": uid,
-                "title": "Team Standup v2",
-                "start": "20260301T100000Z",
-                "end": "20260301T103000Z"
-            }),
+            "import_ics",
+            json!({"calendar_id": cal.id, "ics": ics}),
         )
         .await;
-        assert_eq!(result["updated"], true);
-        assert_eq!(result["title"], "Team Standup v2");
-        let new_etag = result["etag"].as_str().unwrap().to_string();
-        assert_ne!(new_etag, etag, "ETag should change after update");
+        assert_eq!(result["created"], 0);
+        assert_eq!(result["updated"], 3);
+    }
 
-        // Verify update in DB
-        let db_obj = crate::db::events::get_object_by_uid(&pool, &cal.id, &uid)
+    #[tokio::test]
+    async fn test_import_ics_honours_content_etag_mode() {
+        let (pool, user_id, _token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   VERSION:2.0\r\n\
+                   BEGIN:VEVENT\r\n\
+                   UID:kickoff@ex.com\r\n\
+                   DTSTART:20260302T100000Z\r\n\
+                   SUMMARY:Kickoff\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let config = Config {
+            etag_mode: db::events::EtagMode::Content,
+            ..Config::default()
+        };
+        let args = json!({"calendar_id": cal.id, "ics": ics});
+        let import = || {
+            tools::dispatch(
+                &pool,
+                &user_id,
+                crate::db::models::Permission::ReadWrite,
+                "import_ics",
+                &args,
+                &config,
+            )
+        };
+
+        import().await.unwrap();
+        let first = db::events::get_object_by_uid(&pool, &cal.id, "kickoff@ex.com")
             .await
             .unwrap()
             .unwrap();
-        assert_eq!(db_obj.summary.as_deref(), Some("Team Standup v2"));
-        assert_eq!(db_obj.dtstart.as_deref(), Some("20260301T100000Z"));
+        let ctag = calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
 
-        // Delete
-        let result = tool_call(
-            &pool,
-            &token,
-            "delete_event",
-            json!({
-                "calendar_id": cal.id,
+        // Identical data is not rewritten, so the ETag and ctag stay put
+        import().await.unwrap();
+        let second = db::events::get_object_by_uid(&pool, &cal.id, "kickoff@ex.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.etag, first.etag);
+        let after = calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+        assert_eq!(after, ctag);
+    }
+
+    #[tokio::test]
+    async fn test_import_file_validates_before_creating_calendar() {
+        let (pool, user_id, _token) = setup().await;
+        let before = calendars::list_calendars_for_user(&pool, &user_id)
+            .await
+            .unwrap()
+            .len();
+
+        for (contents, expected) in [
+            ("not a calendar\n", "No VCALENDAR found"),
+            (
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n",
+                "No events found",
+            ),
+        ] {
+            let path = std::env::temp_dir().join(format!("import-{}.ics", uuid::Uuid::new_v4()));
+            std::fs::write(&path, contents).unwrap();
+            let err = tools::import::import_file(&pool, &user_id, None, &path, &Config::default())
+                .await
+                .unwrap_err();
+            std::fs::remove_file(&path).unwrap();
+            assert!(err.message.contains(expected), "got: {}", err.message);
+        }
+
+        let after = calendars::list_calendars_for_user(&pool, &user_id)
+            .await
+            .unwrap()
+            .len();
+        assert_eq!(after, before);
+    }
+
+    #[tokio::test]
+    async fn test_import_file_creates_calendar_named_after_file() {
+        let (pool, user_id, _token) = setup().await;
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/team-calendar.ics");
+
+        let result = tools::import::import_file(&pool, &user_id, None, &path, &Config::default())
+            .await
+            .unwrap();
+        assert_eq!(result["created"], 3);
+        assert_eq!(result["failed"], 0);
+
+        let calendar_id = result["calendar_id"].as_str().unwrap();
+        let cal = calendars::get_calendar_by_id(&pool, calendar_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cal.name, "team-calendar");
+        assert_eq!(cal.owner_id, user_id);
+        let objects = db::events::list_objects(&pool, calendar_id).await.unwrap();
+        let mut uids: Vec<&str> = objects.iter().map(|o| o.uid.as_str()).collect();
+        uids.sort();
+        assert_eq!(
+            uids,
+            [
+                "agenda@example.com",
+                "planning@example.com",
+                "standup@example.com"
+            ]
+        );
+        let standup = objects
+            .iter()
+            .find(|o| o.uid == "standup@example.com")
+            .unwrap();
+        assert!(standup.ical_data.contains("RECURRENCE-ID"));
+
+        // Importing into an existing calendar updates in place
+        let result = tools::import::import_file(
+            &pool,
+            &user_id,
+            Some(calendar_id),
+            &path,
+            &Config::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result["created"], 0);
+        assert_eq!(result["updated"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_move_event_between_calendars() {
+        let (pool, user_id, token) = setup().await;
+        let work = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let home = calendars::create_calendar(&pool, &user_id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": work.id,
+                "title": "Dentist",
+                "start": "20260301T090000Z",
+                "end": "20260301T100000Z"
+            }),
+        )
+        .await;
+        let uid = created["uid"].as_str().unwrap();
+        let work_before = calendars::get_calendar_by_id(&pool, &work.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let moved = tool_call(
+            &pool,
+            &token,
+            "move_event",
+            json!({
+                "source_calendar_id": work.id,
+                "target_calendar_id": home.id,
                 "event_uid": uid
             }),
         )
         .await;
-        assert_eq!(result["deleted"], true);
+        assert_eq!(moved["moved"], true);
+        assert_eq!(moved["event_uid"], uid);
 
-        // Verify gone from DB
-        let db_obj = crate::db::events::get_object_by_uid(&pool, &cal.id, &uid)
+        let err = tool_error(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": work.id, "event_uid": uid}),
+        )
+        .await;
+        assert!(err.contains("Event not found"), "got: {err}");
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": home.id, "event_uid": uid}),
+        )
+        .await;
+        assert_eq!(event["summary"], "Dentist");
+
+        let changes = db::events::get_sync_changes_since(&pool, &work.id, &work_before.sync_token)
             .await
+            .unwrap()
             .unwrap();
-        assert!(db_obj.is_none());
+        assert!(
+            changes
+                .iter()
+                .any(|c| c.object_uid == uid && c.change_type == "deleted")
+        );
+        let work_after = calendars::get_calendar_by_id(&pool, &work.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let home_after = calendars::get_calendar_by_id(&pool, &home.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(work_after.ctag, work_before.ctag);
+        assert_ne!(home_after.ctag, home.ctag);
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "move_event",
+            json!({
+                "source_calendar_id": home.id,
+                "target_calendar_id": home.id,
+                "event_uid": uid
+            }),
+        )
+        .await;
+        assert!(err.contains("the same"), "got: {err}");
     }
 
     #[tokio::test]
-    async fn test_query_events() {
+    async fn test_move_event_uses_content_etags() {
+        let (pool, user_id, _token) = setup().await;
+        let work = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let home = calendars::create_calendar(&pool, &user_id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        let (source, _) = db::events::upsert_object_with_etag_mode(
+            &pool,
+            &work.id,
+            "dentist",
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:dentist\r\nSUMMARY:Dentist\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: None,
+                dtend: None,
+                summary: Some("Dentist"),
+            },
+            db::events::EtagMode::Content,
+        )
+        .await
+        .unwrap();
+        let config = Config {
+            etag_mode: db::events::EtagMode::Content,
+            ..Config::default()
+        };
+
+        let moved = tools::dispatch(
+            &pool,
+            &user_id,
+            crate::db::models::Permission::ReadWrite,
+            "move_event",
+            &json!({
+                "source_calendar_id": work.id,
+                "target_calendar_id": home.id,
+                "event_uid": "dentist"
+            }),
+            &config,
+        )
+        .await
+        .unwrap();
+        // The content is unchanged, so its content-derived ETag is kept
+        assert_eq!(moved["etag"], source.etag);
+    }
+
+    #[tokio::test]
+    async fn test_restore_event_after_delete() {
         let (pool, user_id, token) = setup().await;
-        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
             .await
             .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Dentist",
+                "start": "20260302T150000Z",
+                "end": "20260302T160000Z"
+            }),
+        )
+        .await;
+        let uid = created["uid"].as_str().unwrap().to_string();
+        let args = json!({"calendar_id": cal.id, "event_uid": uid});
 
-        // Create two events
-        tool_call(
+        tool_call(&pool, &token, "delete_event", args.clone()).await;
+        let ctag_after_delete = calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .ctag;
+
+        let result = tool_call(&pool, &token, "restore_event", args.clone()).await;
+        assert_eq!(result["restored"], true);
+        assert_eq!(result["summary"], "Dentist");
+
+        let event = tool_call(&pool, &token, "get_event", args.clone()).await;
+        assert!(
+            event["ical_data"]
+                .as_str()
+                .unwrap()
+                .contains("SUMMARY:Dentist")
+        );
+        let cal_after = calendars::get_calendar_by_id(&pool, &cal.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(cal_after.ctag, ctag_after_delete);
+        let (change,): (String,) = sqlx::query_as(
+            "SELECT change_type FROM sync_changes WHERE calendar_id = ? AND object_uid = ?
+             ORDER BY id DESC LIMIT 1",
+        )
+        .bind(&cal.id)
+        .bind(&uid)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(change, "created");
+
+        // Nothing left to restore
+        let err = tool_error(&pool, &token, "restore_event", args).await;
+        assert!(err.contains("Failed to restore"), "got: {err}");
+        let err = tool_error(
+            &pool,
+            &token,
+            "restore_event",
+            json!({"calendar_id": cal.id, "event_uid": "never-existed"}),
+        )
+        .await;
+        assert!(err.contains("last 30 days"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_create_todo_is_reported_as_vtodo() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let todo = tool_call(
+            &pool,
+            &token,
+            "create_todo",
+            json!({
+                "calendar_id": cal.id,
+                "title": "File taxes",
+                "due": "20260315T170000Z",
+                "status": "IN-PROCESS",
+                "percent_complete": 40
+            }),
+        )
+        .await;
+        let todo_uid = todo["uid"].as_str().unwrap().to_string();
+        assert_eq!(todo["status"], "IN-PROCESS");
+        let event = tool_call(
             &pool,
             &token,
             "create_event",
             json!({
                 "calendar_id": cal.id,
-                "title": "Morning",
-                "start": "20260301T090000Z",
-                "end": "20260301T100000Z"
+                "title": "Standup",
+                "start": "20260310T090000Z",
+                "end": "20260310T093000Z"
             }),
         )
         .await;
-        tool_call(
+        let event_uid = event["uid"].as_str().unwrap().to_string();
+
+        // query_events filters by component type, with and without a range
+        for range in [
+            json!({}),
+            json!({"start": "20260301T000000Z", "end": "20260401T000000Z"}),
+        ] {
+            let mut args = json!({"calendar_id": cal.id, "component_type": "VTODO"});
+            args.as_object_mut()
+                .unwrap()
+                .extend(range.as_object().unwrap().clone());
+            let result = tool_call(&pool, &token, "query_events", args).await;
+            let events = result["events"].as_array().unwrap();
+            assert_eq!(events.len(), 1, "got: {result}");
+            assert_eq!(events[0]["uid"], todo_uid);
+            assert_eq!(events[0]["component_type"], "VTODO");
+            assert_eq!(events[0]["dtend"], "20260315T170000Z");
+        }
+        let all = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        assert_eq!(all["count"], 2);
+
+        // CalDAV clients asking for tasks get it back as a VTODO
+        let report = r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/><C:calendar-data/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VTODO"/>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#;
+        let req = axum::http::Request::builder()
+            .method(Method::from_bytes(b"REPORT").unwrap())
+            .uri(format!("/caldav/users/alice/{}/", cal.id))
+            .header("Depth", "1")
+            .body(Body::from(report))
+            .unwrap();
+        let resp = crate::caldav::router(pool.clone(), Config::default())
+            .oneshot(req)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains(&todo_uid), "got: {body}");
+        assert!(body.contains("BEGIN:VTODO"));
+        assert!(body.contains("PERCENT-COMPLETE:40"));
+        assert!(!body.contains(&event_uid));
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_todo",
+            json!({"calendar_id": cal.id, "title": "Bad", "status": "DONE"}),
+        )
+        .await;
+        assert!(err.contains("Invalid status"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_event_tools_refuse_other_users_calendar() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
             &pool,
             &token,
             "create_event",
             json!({
                 "calendar_id": cal.id,
-                "title": "Afternoon",
-                "start": "20260301T140000Z",
-                "end": "20260301T150000Z"
+                "title": "Dentist",
+                "start": "20260302T150000Z",
+                "end": "20260302T160000Z"
             }),
         )
         .await;
+        let uid = created["uid"].as_str().unwrap().to_string();
 
-        // Query all events
+        let bob = users::create_user(&pool, "bob", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-token",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let event = json!({
+            "calendar_id": cal.id,
+            "event_uid": uid,
+            "title": "Hijacked",
+            "start": "20260302T150000Z",
+            "end": "20260302T160000Z"
+        });
+        for tool in [
+            "create_event",
+            "get_event",
+            "update_event",
+            "delete_event",
+            "query_events",
+        ] {
+            let err = tool_error(&pool, &bob_token, tool, event.clone()).await;
+            assert!(err.contains("Access denied"), "{tool} got: {err}");
+        }
+        let err = tool_error(
+            &pool,
+            &bob_token,
+            "get_calendar",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        assert!(err.contains("Access denied"), "get_calendar got: {err}");
+
+        // Only the owner may change who the calendar is shared with
+        let err = tool_error(
+            &pool,
+            &bob_token,
+            "share_calendar",
+            json!({"calendar_id": cal.id, "username": "bob", "permission": "read-write"}),
+        )
+        .await;
+        assert!(
+            err.contains("not owned by you"),
+            "share_calendar got: {err}"
+        );
+        let err = tool_error(
+            &pool,
+            &bob_token,
+            "unshare_calendar",
+            json!({"calendar_id": cal.id, "username": "alice"}),
+        )
+        .await;
+        assert!(
+            err.contains("not owned by you"),
+            "unshare_calendar got: {err}"
+        );
+
+        // Alice's event is untouched and still the only one
         let result = tool_call(
             &pool,
             &token,
@@ -413,91 +1322,2499 @@ mod tests {
             json!({"calendar_id": cal.id}),
         )
         .await;
-        assert_eq!(result["count"], 2);
-        assert_eq!(result["events"].as_array().unwrap().len(), 2);
+        let events = result["events"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["summary"], "Dentist");
+    }
+
+    #[tokio::test]
+    async fn test_set_calendar_components_is_owner_only_and_enforced() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Tasks", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
 
-        // Query with time range — only morning event
         let result = tool_call(
             &pool,
             &token,
-            "query_events",
+            "set_calendar_components",
+            json!({"calendar_id": cal.id, "component_types": ["vtodo", "VTODO"]}),
+        )
+        .await;
+        assert_eq!(result["component_types"], json!(["VTODO"]));
+
+        // New events are refused while only tasks are accepted
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_event",
             json!({
                 "calendar_id": cal.id,
-                "start": "20260301T080000Z",
-                "end": "20260301T110000Z"
+                "title": "Meeting",
+                "start": "20260302T150000Z",
+                "end": "20260302T160000Z"
             }),
         )
         .await;
-        assert_eq!(result["count"], 1);
-        assert_eq!(result["events"][0]["summary"], "Morning");
-    }
+        assert!(err.contains("does not accept VEVENT"), "got: {err}");
 
-    // ---- Sharing via MCP tools ----
+        let err = tool_error(
+            &pool,
+            &token,
+            "set_calendar_components",
+            json!({"calendar_id": cal.id, "component_types": ["VCARD"]}),
+        )
+        .await;
+        assert!(err.contains("Unsupported component type"), "got: {err}");
 
-    #[tokio::test]
-    async fn test_share_and_list_shared_calendars() {
-        let pool = db::test_pool().await;
-        let alice = users::create_user(&pool, "alice", None, "pass1")
+        // A user the calendar is shared with cannot change it
+        let bob = users::create_user(&pool, "bob", None, "password2", &PasswordPolicy::default())
             .await
             .unwrap();
-        let bob = users::create_user(&pool, "bob", None, "pass2")
+        crate::db::shares::share_calendar(
+            &pool,
+            &cal.id,
+            &bob.id,
+            crate::db::models::Permission::ReadWrite,
+        )
+        .await
+        .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-tok",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+        let err = tool_error(
+            &pool,
+            &bob_token,
+            "set_calendar_components",
+            json!({"calendar_id": cal.id, "component_types": ["VEVENT"]}),
+        )
+        .await;
+        assert!(err.contains("not owned by you"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_set_calendar_timezone_normalizes_floating_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Home", "", "#FF0000", "UTC")
             .await
             .unwrap();
-        let (alice_token, _) = tokens::create_token(&pool, &alice.id, "alice-tok")
+        for (uid, dtstart, dtend) in [
+            ("floating", "20260302T090000", "20260302T100000"),
+            ("utc", "20260302T120000Z", "20260302T130000Z"),
+        ] {
+            let data = format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:{uid}\r\n\
+                 DTSTART:{dtstart}\r\nDTEND:{dtend}\r\nSUMMARY:{uid}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+            );
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                uid,
+                &data,
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some(dtstart),
+                    dtend: Some(dtend),
+                    summary: Some(uid),
+                },
+            )
             .await
             .unwrap();
-        let (bob_token, _) = tokens::create_token(&pool, &bob.id, "bob-tok")
+        }
+        let ctag_before = calendars::get_calendar_by_id(&pool, &cal.id)
             .await
-            .unwrap();
+            .unwrap()
+            .unwrap()
+            .ctag;
 
-        // Alice creates a calendar
         let result = tool_call(
             &pool,
-            &alice_token,
-            "create_calendar",
-            json!({"name": "Shared Cal"}),
+            &token,
+            "set_calendar_timezone",
+            json!({"calendar_id": cal.id, "timezone": "Europe/Berlin", "normalize_existing": true}),
+        )
+        .await;
+        assert_eq!(result["timezone"], "Europe/Berlin");
+        assert_eq!(result["normalized"], json!(["floating"]));
+        assert_ne!(result["ctag"], json!(ctag_before));
+
+        // Same wall clock, now anchored to the calendar's zone
+        let floating = crate::db::events::get_object_by_uid(&pool, &cal.id, "floating")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            floating
+                .ical_data
+                .contains("DTSTART;TZID=Europe/Berlin:20260302T090000"),
+            "got: {}",
+            floating.ical_data
+        );
+        assert!(
+            floating
+                .ical_data
+                .contains("DTEND;TZID=Europe/Berlin:20260302T100000")
+        );
+        assert!(floating.ical_data.contains("BEGIN:VTIMEZONE"));
+
+        let utc = crate::db::events::get_object_by_uid(&pool, &cal.id, "utc")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(utc.ical_data.contains("DTSTART:20260302T120000Z"));
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "set_calendar_timezone",
+            json!({"calendar_id": cal.id, "timezone": "Mars/Olympus"}),
+        )
+        .await;
+        assert!(err.contains("Mars/Olympus"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_export_calendar_merges_into_one_vcalendar() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+
+        let mut uids = Vec::new();
+        for (title, start, end) in [
+            ("Planning", "20260302T150000", "20260302T160000"),
+            ("Review", "20260303T150000", "20260303T160000"),
+        ] {
+            let created = tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({
+                    "calendar_id": cal.id,
+                    "title": title,
+                    "start": start,
+                    "end": end,
+                    "timezone": "Europe/Berlin"
+                }),
+            )
+            .await;
+            uids.push(created["uid"].as_str().unwrap().to_string());
+        }
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "export_calendar",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        assert_eq!(result["filename"], "Work.ics");
+        assert_eq!(result["count"], 2);
+        let ics = result["ics"].as_str().unwrap();
+        assert_eq!(ics.matches("BEGIN:VCALENDAR").count(), 1, "got: {ics}");
+        assert_eq!(ics.matches("END:VCALENDAR").count(), 1);
+        assert_eq!(ics.matches("BEGIN:VTIMEZONE").count(), 1);
+        for uid in &uids {
+            assert!(ics.contains(&format!("UID:{uid}")), "missing {uid}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_owned_calendar_to_file() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let mut uids = Vec::new();
+        for (title, start, end) in [
+            ("Planning", "20260302T150000Z", "20260302T160000Z"),
+            ("Review", "20260303T150000Z", "20260303T160000Z"),
+        ] {
+            let created = tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": start, "end": end}),
+            )
+            .await;
+            uids.push(created["uid"].as_str().unwrap().to_string());
+        }
+
+        let path = std::env::temp_dir().join(format!("export-{}.ics", uuid::Uuid::new_v4()));
+        let result = tools::calendars::export_owned_calendar(&pool, &user_id, &cal.id, Some(&path))
+            .await
+            .unwrap();
+        assert_eq!(result["count"], 2);
+        let ics = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ics.matches("BEGIN:VCALENDAR").count(), 1, "got: {ics}");
+        assert_eq!(ics.matches("END:VCALENDAR").count(), 1);
+        for uid in &uids {
+            assert!(ics.contains(&format!("UID:{uid}")), "missing {uid}");
+        }
+
+        // Only the owner can export, even with a share
+        let bob = users::create_user(&pool, "bob", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        db::shares::share_calendar(&pool, &cal.id, &bob.id, db::models::Permission::Read)
+            .await
+            .unwrap();
+        let err = tools::calendars::export_owned_calendar(&pool, &bob.id, &cal.id, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, tools::ToolError::FORBIDDEN);
+        assert!(err.message.contains("Access denied"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_export_activity_csv() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let cal_id = cal.id;
+
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal_id,
+                "title": "Planning",
+                "start": "20260302T150000Z",
+                "end": "20260302T160000Z"
+            }),
+        )
+        .await;
+        let uid = created["uid"].as_str().unwrap();
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "export_activity_csv",
+            json!({"calendar_id": cal_id}),
+        )
+        .await;
+        assert_eq!(result["count"], 1);
+        let csv = result["csv"].as_str().unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "timestamp,change_type,object_uid,sync_token");
+        assert!(lines[1].contains(&format!(",created,{uid},")), "got: {csv}");
+
+        // A range that ends before the change excludes it
+        let result = tool_call(
+            &pool,
+            &token,
+            "export_activity_csv",
+            json!({"calendar_id": cal_id, "end": "20000101T000000Z"}),
+        )
+        .await;
+        assert_eq!(result["count"], 0);
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "export_activity_csv",
+            json!({"calendar_id": "not-a-calendar"}),
+        )
+        .await;
+        assert!(err.contains("Calendar not found"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_get_calendar() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Home", "Personal", "#00FF00", "UTC")
+            .await
+            .unwrap();
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "get_calendar",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        assert_eq!(result["name"], "Home");
+        assert_eq!(result["description"], "Personal");
+        assert_eq!(result["color"], "#00FF00");
+    }
+
+    #[tokio::test]
+    async fn test_delete_calendar() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Temp", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "delete_calendar",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        assert_eq!(result["deleted"], true);
+
+        // Verify gone from DB
+        let db_cal = calendars::get_calendar_by_id(&pool, &cal.id).await.unwrap();
+        assert!(db_cal.is_none());
+    }
+
+    // ---- Event CRUD via MCP tools ----
+
+    #[tokio::test]
+    async fn test_create_get_update_delete_event() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        // Create
+        let result = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Team Standup",
+                "start": "20260301T090000Z",
+                "end": "20260301T093000Z",
+                "description": "Daily sync",
+                "location": "Room 42"
+            }),
+        )
+        .await;
+        assert_eq!(result["title"], "Team Standup");
+        assert_eq!(result["calendar_id"], cal.id);
+        let uid = result["uid"].as_str().unwrap().to_string();
+        let etag = result["etag"].as_str().unwrap().to_string();
+
+        // Verify in DB
+        let db_obj = crate::db::events::get_object_by_uid(&pool, &cal.id, &uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(db_obj.summary.as_deref(), Some("Team Standup"));
+        assert_eq!(db_obj.dtstart.as_deref(), Some("20260301T090000Z"));
+
+        // Get
+        let result = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": uid
+            }),
+        )
+        .await;
+        assert_eq!(result["summary"], "Team Standup");
+        assert_eq!(result["etag"], etag);
+        assert!(result["ical_data"].as_str().unwrap().contains("VEVENT"));
+
+        // Update
+        let result = tool_call(
+            &pool,
+            &token,
+            "update_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": uid,
+                "title": "Team Standup v2",
+                "start": "20260301T100000Z",
+                "end": "20260301T103000Z"
+            }),
+        )
+        .await;
+        assert_eq!(result["updated"], true);
+        assert_eq!(result["title"], "Team Standup v2");
+        let new_etag = result["etag"].as_str().unwrap().to_string();
+        assert_ne!(new_etag, etag, "ETag should change after update");
+
+        // Verify update in DB
+        let db_obj = crate::db::events::get_object_by_uid(&pool, &cal.id, &uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(db_obj.summary.as_deref(), Some("Team Standup v2"));
+        assert_eq!(db_obj.dtstart.as_deref(), Some("20260301T100000Z"));
+
+        // Delete
+        let result = tool_call(
+            &pool,
+            &token,
+            "delete_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": uid
+            }),
+        )
+        .await;
+        assert_eq!(result["deleted"], true);
+
+        // Verify gone from DB
+        let db_obj = crate::db::events::get_object_by_uid(&pool, &cal.id, &uid)
+            .await
+            .unwrap();
+        assert!(db_obj.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_event_rejects_timezone_outside_allow_list() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let config = Config {
+            allowed_timezones: vec!["America/New_York".to_string(), "UTC".to_string()],
+            ..Config::default()
+        };
+        let create = |timezone: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": {
+                    "name": "create_event",
+                    "arguments": {
+                        "calendar_id": cal.id,
+                        "title": "Sync",
+                        "start": "20260301T090000",
+                        "end": "20260301T100000",
+                        "timezone": timezone
+                    }
+                }
+            })
+        };
+
+        let (_, resp) =
+            rpc_call_with_config(&pool, &token, create("Europe/Berlin"), config.clone()).await;
+        assert_eq!(resp["result"]["isError"], true);
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Europe/Berlin is not allowed"), "{text}");
+        assert!(text.contains("America/New_York, UTC"), "{text}");
+
+        let (_, resp) =
+            rpc_call_with_config(&pool, &token, create("America/New_York"), config).await;
+        assert_eq!(resp["result"]["isError"], false);
+        assert!(resp["result"]["structuredContent"]["uid"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_create_event_rejects_unknown_timezone() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Sync",
+                "start": "20260301T090000",
+                "end": "20260301T100000",
+                "timezone": "Mars/Olympus_Mons"
+            }),
+        )
+        .await;
+        assert_eq!(err, "Unknown timezone: Mars/Olympus_Mons");
+    }
+
+    #[tokio::test]
+    async fn test_create_event_with_geo_round_trips() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Site visit",
+                "start": "20260301T090000Z",
+                "end": "20260301T100000Z",
+                "location": "Ferry Building",
+                "geo": {"lat": 37.7955, "lon": -122.3937}
+            }),
+        )
+        .await;
+        let get = json!({"calendar_id": cal.id, "event_uid": created["uid"]});
+
+        let event = tool_call(&pool, &token, "get_event", get.clone()).await;
+        assert_eq!(event["geo"], json!({"lat": 37.7955, "lon": -122.3937}));
+        assert!(
+            event["ical_data"]
+                .as_str()
+                .unwrap()
+                .contains("GEO:37.7955;-122.3937")
+        );
+
+        // Patching another field keeps the coordinates; geo: null removes them
+        tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "title": "Site walk"}),
+        )
+        .await;
+        let event = tool_call(&pool, &token, "get_event", get.clone()).await;
+        assert_eq!(event["geo"]["lat"], 37.7955);
+        tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "geo": null}),
+        )
+        .await;
+        let event = tool_call(&pool, &token, "get_event", get).await;
+        assert!(event["geo"].is_null());
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Nowhere",
+                "start": "20260301T090000Z",
+                "end": "20260301T100000Z",
+                "geo": {"lat": 95.0, "lon": 0.0}
+            }),
+        )
+        .await;
+        assert!(err.contains("between -90 and 90"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_create_weekly_recurring_event() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Standup",
+                "start": "20260302T090000",
+                "end": "20260302T091500",
+                "timezone": "America/New_York",
+                "recurrence": {
+                    "freq": "weekly",
+                    "byday": ["MO", "WE", "FR"],
+                    "until": "20260331T235959"
+                }
+            }),
+        )
+        .await;
+        assert_eq!(
+            created["rrule"],
+            "FREQ=WEEKLY;UNTIL=20260401T035959Z;BYDAY=MO,WE,FR"
+        );
+
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"]}),
+        )
+        .await;
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(
+            ical.contains("RRULE:FREQ=WEEKLY;UNTIL=20260401T035959Z;BYDAY=MO,WE,FR\r\n"),
+            "got: {ical}"
+        );
+
+        // A raw RRULE string is stored as given
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Retro",
+                "start": "20260306T150000Z",
+                "end": "20260306T160000Z",
+                "recurrence": "RRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=6"
+            }),
+        )
+        .await;
+        assert_eq!(created["rrule"], "FREQ=WEEKLY;INTERVAL=2;COUNT=6");
+
+        for recurrence in [json!({"freq": "HOURLY"}), json!("FREQ=SECONDLY")] {
+            let err = tool_error(
+                &pool,
+                &token,
+                "create_event",
+                json!({
+                    "calendar_id": cal.id,
+                    "title": "Bad",
+                    "start": "20260306T150000Z",
+                    "end": "20260306T160000Z",
+                    "recurrence": recurrence
+                }),
+            )
+            .await;
+            assert!(err.contains("Invalid recurrence freq"), "got: {err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_all_day_event_uses_value_date() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Offsite",
+                "start": "2026-03-02",
+                "end": "20260302T170000",
+                "timezone": "America/New_York",
+                "all_day": true
+            }),
+        )
+        .await;
+        assert_eq!(created["start"], "20260302");
+        assert_eq!(created["end"], "20260303");
+
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"]}),
+        )
+        .await;
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(
+            ical.contains("DTSTART;VALUE=DATE:20260302\r\n"),
+            "got: {ical}"
+        );
+        assert!(
+            ical.contains("DTEND;VALUE=DATE:20260303\r\n"),
+            "got: {ical}"
+        );
+        assert!(!ical.contains("VTIMEZONE"), "got: {ical}");
+
+        // The day matches a range inside it, but not the evening before
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id, "start": "20260302T120000Z", "end": "20260302T130000Z"}),
+        )
+        .await;
+        assert_eq!(result["count"], 1);
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id, "start": "20260301T120000Z", "end": "20260302T000000Z"}),
+        )
+        .await;
+        assert_eq!(result["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_event_with_url_round_trips() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let meeting = "https://meet.example.com/abc-defg-hij?pwd=x1;y2";
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Standup",
+                "start": "20260301T090000Z",
+                "end": "20260301T091500Z",
+                "url": meeting
+            }),
+        )
+        .await;
+        let get = json!({"calendar_id": cal.id, "event_uid": created["uid"]});
+
+        let event = tool_call(&pool, &token, "get_event", get.clone()).await;
+        assert_eq!(event["url"], meeting);
+        assert!(
+            event["ical_data"]
+                .as_str()
+                .unwrap()
+                .contains(&format!("URL:{meeting}"))
+        );
+
+        // Patching another field keeps the URL; an empty string removes it
+        tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "title": "Daily standup"}),
+        )
+        .await;
+        let event = tool_call(&pool, &token, "get_event", get.clone()).await;
+        assert_eq!(event["url"], meeting);
+        tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "url": ""}),
+        )
+        .await;
+        let event = tool_call(&pool, &token, "get_event", get).await;
+        assert!(event["url"].is_null());
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Bad link",
+                "start": "20260301T090000Z",
+                "end": "20260301T100000Z",
+                "url": "meet.example.com/abc"
+            }),
+        )
+        .await;
+        assert!(err.contains("absolute URI"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_create_event_rejects_out_of_range_year() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let event = |start: &str, end: &str| {
+            json!({
+                "calendar_id": cal.id,
+                "title": "Review",
+                "start": start,
+                "end": end
+            })
+        };
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "create_event",
+            event("02260301T090000Z", "02260301T100000Z"),
+        )
+        .await;
+        assert!(err.contains("Year 226"), "got: {err}");
+        assert!(err.contains("1970-2200"), "got: {err}");
+
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            event("20260301T090000Z", "20260301T100000Z"),
+        )
+        .await;
+        assert!(created["uid"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_patch_event_location_keeps_description() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Planning",
+                "start": "20260301T090000",
+                "end": "20260301T100000",
+                "timezone": "America/New_York",
+                "description": "Quarterly roadmap",
+                "location": "Room 42"
+            }),
+        )
+        .await;
+        let uid = created["uid"].as_str().unwrap().to_string();
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": uid,
+                "location": "Room 7"
+            }),
+        )
+        .await;
+        assert_eq!(result["patched"], json!(["location"]));
+        assert_ne!(result["etag"], created["etag"]);
+
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": uid}),
+        )
+        .await;
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(ical.contains("LOCATION:Room 7"));
+        assert!(!ical.contains("Room 42"));
+        assert!(ical.contains("DESCRIPTION:Quarterly roadmap"));
+        assert!(ical.contains("SUMMARY:Planning"));
+        assert!(ical.contains("DTSTART;TZID=America/New_York:20260301T090000"));
+        assert!(ical.contains("BEGIN:VTIMEZONE"));
+        assert_eq!(event["summary"], "Planning");
+
+        // Moving the start keeps the event's timezone
+        tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": uid, "start": "20260301T083000"}),
+        )
+        .await;
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": uid}),
+        )
+        .await;
+        assert_eq!(event["dtstart"], "20260301T083000");
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(ical.contains("DTSTART;TZID=America/New_York:20260301T083000"));
+        assert!(ical.contains("LOCATION:Room 7"));
+    }
+
+    #[tokio::test]
+    async fn test_patch_event_keeps_all_day_dates() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Offsite",
+                "start": "20260301",
+                "end": "20260302",
+                "all_day": true
+            }),
+        )
+        .await;
+        let uid = created["uid"].as_str().unwrap().to_string();
+
+        tool_call(
+            &pool,
+            &token,
+            "patch_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": uid,
+                "start": "20260305",
+                "end": "20260306",
+                "timezone": "Europe/Berlin"
+            }),
+        )
+        .await;
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": uid}),
+        )
+        .await;
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(
+            ical.contains("DTSTART;VALUE=DATE:20260305\r\n"),
+            "got: {ical}"
+        );
+        assert!(
+            ical.contains("DTEND;VALUE=DATE:20260306\r\n"),
+            "got: {ical}"
+        );
+        assert!(!ical.contains("TZID"), "got: {ical}");
+
+        // A date-time start would leave DTEND a DATE
+        let err = tool_error(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": uid, "start": "20260305T090000"}),
+        )
+        .await;
+        assert!(err.contains("both be dates"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_patch_event_requires_a_field() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Planning",
+                "start": "20260301T090000Z",
+                "end": "20260301T100000Z"
+            }),
+        )
+        .await;
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "patch_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"]}),
+        )
+        .await;
+        assert!(err.contains("No fields"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_convert_utc_event_to_timezone_keeps_instant() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Sync",
+                "start": "20260301T140000Z",
+                "end": "20260301T150000Z"
+            }),
+        )
+        .await;
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "convert_event_time_type",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": created["uid"],
+                "to": "local",
+                "timezone": "America/New_York"
+            }),
+        )
+        .await;
+        assert_eq!(result["converted"], 2);
+        assert_eq!(result["timezone"], "America/New_York");
+
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"]}),
+        )
+        .await;
+        let ical = event["ical_data"].as_str().unwrap();
+        // 14:00 UTC is 09:00 in New York (EST)
+        assert!(ical.contains("DTSTART;TZID=America/New_York:20260301T090000\r\n"));
+        assert!(ical.contains("DTEND;TZID=America/New_York:20260301T100000\r\n"));
+        assert!(ical.contains("TZID:America/New_York"), "VTIMEZONE added");
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "convert_event_time_type",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "to": "local"}),
+        )
+        .await;
+        assert!(err.contains("requires timezone"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_convert_event_to_floating_time() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Morning run",
+                "start": "20260301T070000",
+                "end": "20260301T080000",
+                "timezone": "Europe/Paris"
+            }),
+        )
+        .await;
+        let get = json!({"calendar_id": cal.id, "event_uid": created["uid"]});
+
+        // Keeping the wall clock: 07:00 stays 07:00 wherever the user is
+        tool_call(
+            &pool,
+            &token,
+            "convert_event_time_type",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": created["uid"],
+                "to": "floating",
+                "preserve": "wall_clock"
+            }),
+        )
+        .await;
+        let event = tool_call(&pool, &token, "get_event", get.clone()).await;
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(
+            ical.contains("\r\nDTSTART:20260301T070000\r\n"),
+            "got: {ical}"
+        );
+        assert!(ical.contains("\r\nDTEND:20260301T080000\r\n"));
+        assert_eq!(event["dtstart"], "20260301T070000");
+
+        // Back to UTC: a floating time has no instant, so its clock is kept
+        tool_call(
+            &pool,
+            &token,
+            "convert_event_time_type",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "to": "utc"}),
+        )
+        .await;
+        let event = tool_call(&pool, &token, "get_event", get).await;
+        assert_eq!(event["dtstart"], "20260301T070000Z");
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_event_keeps_duration() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Design review",
+                "start": "20260302T140000",
+                "end": "20260302T150000",
+                "timezone": "America/New_York",
+                "location": "Room 4"
+            }),
+        )
+        .await;
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "reschedule_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": created["uid"],
+                "start": "20260302T150000"
+            }),
+        )
+        .await;
+        assert_eq!(result["dtstart"], "20260302T150000");
+        assert_eq!(result["dtend"], "20260302T160000");
+        assert_eq!(result["timezone"], "America/New_York");
+
+        let event = tool_call(
+            &pool,
+            &token,
+            "get_event",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"]}),
+        )
+        .await;
+        let ical = event["ical_data"].as_str().unwrap();
+        assert!(ical.contains("DTSTART;TZID=America/New_York:20260302T150000"));
+        assert!(ical.contains("DTEND;TZID=America/New_York:20260302T160000"));
+        assert!(ical.contains("LOCATION:Room 4"));
+
+        // An explicit end overrides the original duration
+        let result = tool_call(
+            &pool,
+            &token,
+            "reschedule_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": created["uid"],
+                "start": "20260303T090000Z",
+                "end": "20260303T093000Z"
+            }),
+        )
+        .await;
+        assert_eq!(result["dtstart"], "20260303T090000Z");
+        assert_eq!(result["dtend"], "20260303T093000Z");
+        assert!(result["timezone"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_recurring_event_shifts_until() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let data = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\n\
+                    UID:daily@example.com\r\n\
+                    DTSTART:20260302T090000Z\r\n\
+                    DURATION:PT30M\r\n\
+                    RRULE:FREQ=DAILY;UNTIL=20260306T090000Z\r\n\
+                    SUMMARY:Daily\r\n\
+                    END:VEVENT\r\nEND:VCALENDAR\r\n";
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "daily@example.com",
+            data,
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260302T090000Z"),
+                dtend: None,
+                summary: Some("Daily"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "reschedule_event",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": "daily@example.com",
+                "start": "20260302T110000Z"
+            }),
+        )
+        .await;
+        assert_eq!(result["rrule"], "FREQ=DAILY;UNTIL=20260306T110000Z");
+
+        let obj = crate::db::events::get_object_by_uid(&pool, &cal.id, "daily@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(obj.ical_data.contains("DTSTART:20260302T110000Z"));
+        assert!(obj.ical_data.contains("DURATION:PT30M"));
+        let calendar = crate::ical::parser::parse_calendar(&obj.ical_data).unwrap();
+        let occurrences = crate::ical::recurrence::occurrences(
+            &calendar.components[0],
+            chrono::NaiveDate::from_ymd_opt(2026, 12, 31)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        );
+        assert_eq!(occurrences.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_query_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        // Create two events
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Morning",
+                "start": "20260301T090000Z",
+                "end": "20260301T100000Z"
+            }),
+        )
+        .await;
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Afternoon",
+                "start": "20260301T140000Z",
+                "end": "20260301T150000Z"
+            }),
+        )
+        .await;
+
+        // Query all events
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        assert_eq!(result["count"], 2);
+        assert_eq!(result["events"].as_array().unwrap().len(), 2);
+
+        // Query with time range — only morning event
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events",
+            json!({
+                "calendar_id": cal.id,
+                "start": "20260301T080000Z",
+                "end": "20260301T110000Z"
+            }),
+        )
+        .await;
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["events"][0]["summary"], "Morning");
+    }
+
+    #[tokio::test]
+    async fn test_query_events_pages_with_offset() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        for i in 0..120 {
+            let start = format!("20260301T{:02}{:02}00Z", i / 60, i % 60);
+            crate::db::events::upsert_object(
+                &pool,
+                &cal.id,
+                &format!("page-{i:03}@test.com"),
+                "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n",
+                crate::db::events::ObjectFields {
+                    component_type: "VEVENT",
+                    dtstart: Some(start.as_str()),
+                    dtend: None,
+                    summary: Some("Paged"),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut offset = json!(0);
+        let mut pages = 0;
+        while !offset.is_null() {
+            let result = tool_call(
+                &pool,
+                &token,
+                "query_events",
+                json!({"calendar_id": cal.id, "limit": 50, "offset": offset}),
+            )
+            .await;
+            assert_eq!(result["total"], 120);
+            for event in result["events"].as_array().unwrap() {
+                seen.push(event["uid"].as_str().unwrap().to_string());
+            }
+            offset = result["next_offset"].clone();
+            pages += 1;
+        }
+
+        assert_eq!(pages, 3);
+        assert_eq!(seen.len(), 120);
+        let expected: Vec<String> = (0..120).map(|i| format!("page-{i:03}@test.com")).collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[tokio::test]
+    async fn test_get_freebusy_merges_overlapping_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        for (title, start, end) in [
+            ("Standup", "20260303T090000Z", "20260303T100000Z"),
+            ("Review", "20260303T093000Z", "20260303T113000Z"),
+            ("Outside", "20260304T090000Z", "20260304T100000Z"),
+        ] {
+            tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": start, "end": end}),
+            )
+            .await;
+        }
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "get_freebusy",
+            json!({
+                "calendar_id": cal.id,
+                "start": "20260303T000000Z",
+                "end": "20260304T000000Z"
+            }),
+        )
+        .await;
+        assert_eq!(
+            result["busy"],
+            json!([{"start": "20260303T090000Z", "end": "20260303T113000Z"}])
+        );
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "get_freebusy",
+            json!({
+                "calendar_id": cal.id,
+                "start": "20260304T000000Z",
+                "end": "20260303T000000Z"
+            }),
+        )
+        .await;
+        assert!(err.contains("end must be after start"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_get_freebusy_expands_recurring_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Weekly sync",
+                "start": "20260302T090000Z",
+                "end": "20260302T100000Z",
+                "recurrence": "RRULE:FREQ=WEEKLY;COUNT=10"
+            }),
+        )
+        .await;
+
+        // Three weeks after the first instance
+        let result = tool_call(
+            &pool,
+            &token,
+            "get_freebusy",
+            json!({
+                "calendar_id": cal.id,
+                "start": "20260323T000000Z",
+                "end": "20260324T000000Z"
+            }),
+        )
+        .await;
+        assert_eq!(
+            result["busy"],
+            json!([{"start": "20260323T090000Z", "end": "20260323T100000Z"}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_events_grouped_by_day() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        for (title, start, end) in [
+            ("Review", "20260302T150000Z", "20260302T160000Z"),
+            ("Late call", "20260302T030000Z", "20260302T040000Z"),
+            ("Standup", "20260302T140000Z", "20260302T143000Z"),
+        ] {
+            tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": start, "end": end}),
+            )
+            .await;
+        }
+
+        // 03:00Z on March 2 is still March 1 in New York
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events_grouped",
+            json!({"calendar_id": cal.id, "timezone": "America/New_York"}),
+        )
+        .await;
+        assert_eq!(result["count"], 3);
+        let days = result["days"].as_object().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days["2026-03-01"][0]["summary"], "Late call");
+        assert_eq!(days["2026-03-01"][0]["local_start"], "2026-03-01T22:00:00");
+        let march_2: Vec<_> = days["2026-03-02"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["summary"].as_str().unwrap())
+            .collect();
+        assert_eq!(march_2, ["Standup", "Review"]);
+
+        // Defaults to the calendar's timezone (UTC here) and honours the limit
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events_grouped",
+            json!({"calendar_id": cal.id, "limit": 2}),
+        )
+        .await;
+        assert_eq!(result["timezone"], "UTC");
+        assert_eq!(result["count"], 2);
+        let days = result["days"].as_object().unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days["2026-03-02"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_events_grouped_expands_recurring_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Daily standup",
+                "start": "20260302T140000Z",
+                "end": "20260302T141500Z",
+                "recurrence": "FREQ=DAILY;COUNT=30"
+            }),
+        )
+        .await;
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "query_events_grouped",
+            json!({
+                "calendar_id": cal.id,
+                "start": "20260316T000000Z",
+                "end": "20260319T000000Z",
+                "timezone": "America/New_York"
+            }),
+        )
+        .await;
+        assert_eq!(result["count"], 3);
+        let days = result["days"].as_object().unwrap();
+        let dates: Vec<_> = days.keys().map(String::as_str).collect();
+        assert_eq!(dates, ["2026-03-16", "2026-03-17", "2026-03-18"]);
+        let event = &days["2026-03-17"][0];
+        assert_eq!(event["summary"], "Daily standup");
+        assert_eq!(event["dtstart"], "20260317T140000Z");
+        assert_eq!(event["dtend"], "20260317T141500Z");
+        assert_eq!(event["local_start"], "2026-03-17T10:00:00");
+    }
+
+    #[tokio::test]
+    async fn test_week_summary_buckets_events_by_day() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        for (title, start, end) in [
+            ("Before week", "20260308T150000Z", "20260308T160000Z"),
+            ("Monday sync", "20260309T150000Z", "20260309T160000Z"),
+            ("Wednesday review", "20260311T170000Z", "20260311T180000Z"),
+            ("Wednesday retro", "20260311T200000Z", "20260311T210000Z"),
+            // 02:00Z on March 16 is still Sunday March 15 in Los Angeles
+            ("Sunday night", "20260316T020000Z", "20260316T030000Z"),
+            ("After week", "20260316T170000Z", "20260316T180000Z"),
+        ] {
+            tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": start, "end": end}),
+            )
+            .await;
+        }
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "week_summary",
+            json!({
+                "calendar_id": cal.id,
+                "week_start": "2026-03-09",
+                "timezone": "America/Los_Angeles"
+            }),
+        )
+        .await;
+        assert_eq!(result["week_start"], "2026-03-09");
+        assert_eq!(result["week_end"], "2026-03-15");
+        assert_eq!(result["total"], 4);
+        assert_eq!(result["busiest_day"]["date"], "2026-03-11");
+        assert_eq!(result["busiest_day"]["count"], 2);
+
+        let days = result["days"].as_array().unwrap();
+        assert_eq!(days.len(), 7);
+        let counts: Vec<_> = days.iter().map(|d| d["count"].as_u64().unwrap()).collect();
+        assert_eq!(counts, [1, 0, 2, 0, 0, 0, 1]);
+        assert_eq!(days[0]["weekday"], "Monday");
+        assert_eq!(days[2]["events"][0]["summary"], "Wednesday review");
+        assert_eq!(days[2]["events"][1]["summary"], "Wednesday retro");
+        assert_eq!(days[6]["events"][0]["summary"], "Sunday night");
+        assert_eq!(days[6]["events"][0]["local_start"], "2026-03-15T19:00:00");
+
+        // An empty week has no busiest day
+        let result = tool_call(
+            &pool,
+            &token,
+            "week_summary",
+            json!({"calendar_id": cal.id, "week_start": "20260401"}),
+        )
+        .await;
+        assert_eq!(result["timezone"], "UTC");
+        assert_eq!(result["total"], 0);
+        assert!(result["busiest_day"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_week_summary_expands_recurring_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Weekly sync",
+                "start": "20260302T150000Z",
+                "end": "20260302T160000Z",
+                "recurrence": {"freq": "WEEKLY", "byday": ["MO", "TH"]}
+            }),
+        )
+        .await;
+
+        // Two weeks after the series starts, in Los Angeles
+        let result = tool_call(
+            &pool,
+            &token,
+            "week_summary",
+            json!({
+                "calendar_id": cal.id,
+                "week_start": "2026-03-16",
+                "timezone": "America/Los_Angeles"
+            }),
+        )
+        .await;
+        assert_eq!(result["total"], 2);
+        let days = result["days"].as_array().unwrap();
+        let counts: Vec<_> = days.iter().map(|d| d["count"].as_u64().unwrap()).collect();
+        assert_eq!(counts, [1, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(days[0]["events"][0]["summary"], "Weekly sync");
+        assert_eq!(days[0]["events"][0]["local_start"], "2026-03-16T08:00:00");
+        assert_eq!(days[3]["events"][0]["local_start"], "2026-03-19T08:00:00");
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicates_groups_identical_events() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let mut uids = Vec::new();
+        for (title, start) in [
+            ("Team  Sync", "20260302T150000Z"),
+            ("team sync", "20260302T150000Z"),
+            ("Team Sync", "20260303T150000Z"),
+        ] {
+            let created = tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({
+                    "calendar_id": cal.id,
+                    "title": title,
+                    "start": start,
+                    "end": start.replace("T15", "T16")
+                }),
+            )
+            .await;
+            uids.push(created["uid"].as_str().unwrap().to_string());
+        }
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "find_duplicates",
+            json!({"calendar_id": cal.id}),
+        )
+        .await;
+        let groups = result["groups"].as_array().unwrap();
+        assert_eq!(groups.len(), 1);
+        let mut members: Vec<_> = groups[0]["uids"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|u| u.as_str().unwrap().to_string())
+            .collect();
+        members.sort();
+        let mut expected = uids[..2].to_vec();
+        expected.sort();
+        assert_eq!(members, expected);
+        assert!(result["deleted"].as_array().unwrap().is_empty());
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "find_duplicates",
+            json!({"calendar_id": cal.id, "auto_delete": true}),
+        )
+        .await;
+        let keep = result["groups"][0]["keep"].as_str().unwrap();
+        assert_eq!(result["deleted"].as_array().unwrap().len(), 1);
+        assert_ne!(result["deleted"][0], keep);
+
+        let remaining = crate::db::events::list_objects(&pool, &cal.id)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().any(|o| o.uid == keep));
+        assert!(remaining.iter().any(|o| o.uid == uids[2]));
+    }
+
+    #[tokio::test]
+    async fn test_list_etags_returns_uid_etag_map() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let mut expected = Vec::new();
+        for title in ["One", "Two"] {
+            let created = tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": "20260301T090000Z", "end": "20260301T100000Z"}),
+            )
+            .await;
+            expected.push((
+                created["uid"].as_str().unwrap().to_string(),
+                created["etag"].as_str().unwrap().to_string(),
+            ));
+        }
+        expected.sort();
+
+        let result = tool_call(&pool, &token, "list_etags", json!({"calendar_id": cal.id})).await;
+        assert_eq!(result["count"], 2);
+        let entries = result["etags"].as_array().unwrap();
+        let actual: Vec<_> = entries
+            .iter()
+            .map(|e| {
+                assert_eq!(e.as_object().unwrap().len(), 2, "only uid and etag: {e}");
+                (
+                    e["uid"].as_str().unwrap().to_string(),
+                    e["etag"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(actual, expected);
+        assert!(!result.to_string().contains("BEGIN:VCALENDAR"));
+    }
+
+    #[tokio::test]
+    async fn test_tag_events_by_title_substring() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let mut uids = Vec::new();
+        for title in ["Daily Standup", "standup retro", "Lunch"] {
+            let created = tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": "20260301T090000Z", "end": "20260301T100000Z"}),
+            )
+            .await;
+            uids.push(created["uid"].as_str().unwrap().to_string());
+        }
+        let categories = |uid: &str| {
+            let pool = pool.clone();
+            let cal_id = cal.id.clone();
+            let uid = uid.to_string();
+            async move {
+                crate::db::events::get_object_by_uid(&pool, &cal_id, &uid)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .ical_data
+                    .lines()
+                    .find_map(|l| l.strip_prefix("CATEGORIES:").map(str::to_string))
+            }
+        };
+
+        // Without confirm nothing is written
+        let args = json!({"calendar_id": cal.id, "query": "STANDUP", "add": ["work"]});
+        let preview = tool_call(&pool, &token, "tag_events", args.clone()).await;
+        assert_eq!(preview["matched"], 2);
+        assert_eq!(preview["updated"], 0);
+        assert_eq!(categories(&uids[0]).await, None);
+
+        let mut confirmed = args;
+        confirmed["confirm"] = json!(true);
+        let result = tool_call(&pool, &token, "tag_events", confirmed.clone()).await;
+        assert_eq!(result["updated"], 2);
+        assert_eq!(categories(&uids[0]).await.as_deref(), Some("work"));
+        assert_eq!(categories(&uids[1]).await.as_deref(), Some("work"));
+        assert_eq!(categories(&uids[2]).await, None);
+
+        // Re-running is a no-op; removing clears the line
+        let result = tool_call(&pool, &token, "tag_events", confirmed).await;
+        assert_eq!(result["updated"], 0);
+        let result = tool_call(
+            &pool,
+            &token,
+            "tag_events",
+            json!({"calendar_id": cal.id, "query": "retro", "remove": ["Work"], "confirm": true}),
+        )
+        .await;
+        assert_eq!(result["updated"], 1);
+        assert_eq!(categories(&uids[1]).await, None);
+        assert_eq!(categories(&uids[0]).await.as_deref(), Some("work"));
+    }
+
+    // ---- Recurring events ----
+
+    /// Weekly standup on Mondays (5 instances from 2026-03-02) with the
+    /// 2026-03-09 instance moved to 10:00.
+    const WEEKLY_STANDUP: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:standup@example.com\r\n\
+        DTSTART:20260302T090000Z\r\n\
+        DTEND:20260302T091500Z\r\n\
+        RRULE:FREQ=WEEKLY;COUNT=5\r\n\
+        SUMMARY:Standup\r\n\
+        DESCRIPTION:Daily sync\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:standup@example.com\r\n\
+        RECURRENCE-ID:20260309T090000Z\r\n\
+        DTSTART:20260309T100000Z\r\n\
+        DTEND:20260309T101500Z\r\n\
+        SUMMARY:Standup (moved)\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    async fn insert_weekly_standup(pool: &SqlitePool, calendar_id: &str) {
+        crate::db::events::upsert_object(
+            pool,
+            calendar_id,
+            "standup@example.com",
+            WEEKLY_STANDUP,
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260302T090000Z"),
+                dtend: Some("20260302T091500Z"),
+                summary: Some("Standup"),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_occurrence_valid_date() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        insert_weekly_standup(&pool, &cal.id).await;
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "get_occurrence",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": "standup@example.com",
+                "occurrence_date": "2026-03-16"
+            }),
+        )
+        .await;
+        assert_eq!(result["summary"], "Standup");
+        assert_eq!(result["dtstart"], "20260316T090000Z");
+        assert_eq!(result["dtend"], "20260316T091500Z");
+        assert_eq!(result["description"], "Daily sync");
+        assert_eq!(result["is_override"], false);
+
+        // The overridden instance reports the moved time and new summary
+        let result = tool_call(
+            &pool,
+            &token,
+            "get_occurrence",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": "standup@example.com",
+                "occurrence_date": "20260309"
+            }),
+        )
+        .await;
+        assert_eq!(result["summary"], "Standup (moved)");
+        assert_eq!(result["dtstart"], "20260309T100000Z");
+        assert_eq!(result["recurrence_id"], "20260309T090000Z");
+        assert_eq!(result["description"], "Daily sync");
+        assert_eq!(result["is_override"], true);
+    }
+
+    #[tokio::test]
+    async fn test_get_occurrence_invalid_date() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        insert_weekly_standup(&pool, &cal.id).await;
+
+        // A Tuesday is not an instance of a Monday standup
+        let err = tool_error(
+            &pool,
+            &token,
+            "get_occurrence",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": "standup@example.com",
+                "occurrence_date": "2026-03-10"
+            }),
+        )
+        .await;
+        assert!(err.contains("not an occurrence"), "got: {err}");
+
+        // Past the COUNT=5 limit
+        let err = tool_error(
+            &pool,
+            &token,
+            "get_occurrence",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": "standup@example.com",
+                "occurrence_date": "2026-04-06"
+            }),
+        )
+        .await;
+        assert!(err.contains("not an occurrence"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_clone_series_weekly_into_daily() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let source = WEEKLY_STANDUP.replacen(
+            "DESCRIPTION:Daily sync\r\n",
+            "DESCRIPTION:Daily sync\r\n\
+             LOCATION:Room 4\r\n\
+             BEGIN:VALARM\r\n\
+             ACTION:DISPLAY\r\n\
+             TRIGGER:-PT10M\r\n\
+             END:VALARM\r\n",
+            1,
+        );
+        crate::db::events::upsert_object(
+            &pool,
+            &cal.id,
+            "standup@example.com",
+            &source,
+            crate::db::events::ObjectFields {
+                component_type: "VEVENT",
+                dtstart: Some("20260302T090000Z"),
+                dtend: Some("20260302T091500Z"),
+                summary: Some("Standup"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "clone_series",
+            json!({
+                "calendar_id": cal.id,
+                "event_uid": "standup@example.com",
+                "rrule": "FREQ=DAILY;COUNT=5"
+            }),
+        )
+        .await;
+        let uid = result["uid"].as_str().unwrap();
+        assert_ne!(uid, "standup@example.com");
+        assert_eq!(result["summary"], "Standup");
+
+        let clone = crate::db::events::get_object_by_uid(&pool, &cal.id, uid)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(clone.ical_data.contains("RRULE:FREQ=DAILY;COUNT=5"));
+        assert!(clone.ical_data.contains("LOCATION:Room 4"));
+        assert!(clone.ical_data.contains("BEGIN:VALARM"));
+        assert!(!clone.ical_data.contains("RECURRENCE-ID"));
+
+        // Tuesday is an instance of the daily copy but not of the weekly source
+        let occurrence = |event_uid: &str| json!({"calendar_id": cal.id, "event_uid": event_uid, "occurrence_date": "2026-03-03"});
+        let result = tool_call(&pool, &token, "get_occurrence", occurrence(uid)).await;
+        assert_eq!(result["dtstart"], "20260303T090000Z");
+        let err = tool_error(
+            &pool,
+            &token,
+            "get_occurrence",
+            occurrence("standup@example.com"),
+        )
+        .await;
+        assert!(err.contains("not an occurrence"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_clone_series_rejects_non_recurring_event() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({"calendar_id": cal.id, "title": "Once", "start": "20260301T090000Z", "end": "20260301T100000Z"}),
+        )
+        .await;
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "clone_series",
+            json!({"calendar_id": cal.id, "event_uid": created["uid"], "rrule": "FREQ=DAILY"}),
+        )
+        .await;
+        assert!(err.contains("not recurring"), "got: {err}");
+    }
+
+    // ---- Sharing via MCP tools ----
+
+    #[tokio::test]
+    async fn test_share_and_list_shared_calendars() {
+        let pool = db::test_pool().await;
+        let alice = users::create_user(
+            &pool,
+            "alice",
+            None,
+            "password1",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let bob = users::create_user(&pool, "bob", None, "password2", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let (alice_token, _) = tokens::create_token(
+            &pool,
+            &alice.id,
+            "alice-tok",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-tok",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Alice creates a calendar
+        let result = tool_call(
+            &pool,
+            &alice_token,
+            "create_calendar",
+            json!({"name": "Shared Cal"}),
+        )
+        .await;
+        let cal_id = result["id"].as_str().unwrap().to_string();
+
+        // Alice shares it with Bob (read-only)
+        let result = tool_call(
+            &pool,
+            &alice_token,
+            "share_calendar",
+            json!({
+                "calendar_id": cal_id,
+                "username": "bob",
+                "permission": "read"
+            }),
+        )
+        .await;
+        assert_eq!(result["shared_with"], "bob");
+        assert_eq!(result["permission"], "read");
+
+        // Bob lists shared calendars
+        let result = tool_call(&pool, &bob_token, "list_shared_calendars", json!({})).await;
+        let shared = result["shared_calendars"].as_array().unwrap();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0]["name"], "Shared Cal");
+        assert_eq!(shared[0]["permission"], "read");
+
+        // Alice unshares
+        let result = tool_call(
+            &pool,
+            &alice_token,
+            "unshare_calendar",
+            json!({
+                "calendar_id": cal_id,
+                "username": "bob"
+            }),
+        )
+        .await;
+        assert_eq!(result["unshared"], true);
+
+        // Bob should see no shared calendars now
+        let result = tool_call(&pool, &bob_token, "list_shared_calendars", json!({})).await;
+        assert_eq!(result["shared_calendars"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_share_refuses_event_writes() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let created = tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Standup",
+                "start": "20260302T150000Z",
+                "end": "20260302T160000Z"
+            }),
+        )
+        .await;
+        let uid = created["uid"].as_str().unwrap().to_string();
+
+        let bob = users::create_user(&pool, "bob", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        db::shares::share_calendar(&pool, &cal.id, &bob.id, db::models::Permission::Read)
+            .await
+            .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-token",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let update = json!({
+            "calendar_id": cal.id,
+            "event_uid": uid,
+            "title": "Cancelled",
+            "start": "20260302T150000Z",
+            "end": "20260302T160000Z"
+        });
+        let event = tool_call(&pool, &bob_token, "get_event", update.clone()).await;
+        assert!(
+            event["ical_data"]
+                .as_str()
+                .unwrap()
+                .contains("SUMMARY:Standup")
+        );
+        for tool in ["create_event", "update_event", "delete_event"] {
+            let err = tool_error(&pool, &bob_token, tool, update.clone()).await;
+            assert!(err.contains("read-only"), "{tool} got: {err}");
+        }
+        // Finding duplicates only reads, unless it is asked to delete them
+        let find = json!({"calendar_id": cal.id});
+        tool_call(&pool, &bob_token, "find_duplicates", find).await;
+        let find = json!({"calendar_id": cal.id, "auto_delete": true});
+        let err = tool_error(&pool, &bob_token, "find_duplicates", find).await;
+        assert!(err.contains("read-only"), "find_duplicates got: {err}");
+
+        // Upgrading the share lets the same call through
+        db::shares::share_calendar(&pool, &cal.id, &bob.id, db::models::Permission::ReadWrite)
+            .await
+            .unwrap();
+        let result = tool_call(&pool, &bob_token, "update_event", update).await;
+        assert_eq!(result["title"], "Cancelled");
+    }
+
+    // ---- Availability and scheduling ----
+
+    #[tokio::test]
+    async fn test_find_conflicts_flags_slot_outside_working_hours() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+
+        let days: Vec<Value> = ["monday", "tuesday", "wednesday", "thursday", "friday"]
+            .iter()
+            .map(|day| json!({"day": day, "start": "09:00", "end": "17:00"}))
+            .collect();
+        let result = tool_call(
+            &pool,
+            &token,
+            "set_availability",
+            json!({"timezone": "America/New_York", "days": days}),
+        )
+        .await;
+        assert_eq!(result["configured"], true);
+
+        let result = tool_call(&pool, &token, "get_availability", json!({})).await;
+        assert_eq!(result["timezone"], "America/New_York");
+        assert_eq!(result["days"].as_array().unwrap().len(), 5);
+        assert_eq!(
+            result["days"][0],
+            json!({"day": "monday", "start": "09:00", "end": "17:00"})
+        );
+
+        // Monday 2026-03-02 20:00-21:00 in New York
+        let evening = json!({
+            "start": "20260303T010000Z",
+            "end": "20260303T020000Z",
+            "respect_working_hours": true
+        });
+        let result = tool_call(&pool, &token, "find_conflicts", evening).await;
+        assert_eq!(result["available"], false);
+        assert_eq!(result["outside_working_hours"], true);
+        assert!(result["conflicts"].as_array().unwrap().is_empty());
+
+        // Same slot without the working-hours check is free
+        let result = tool_call(
+            &pool,
+            &token,
+            "find_conflicts",
+            json!({"start": "20260303T010000Z", "end": "20260303T020000Z"}),
+        )
+        .await;
+        assert_eq!(result["available"], true);
+
+        // Monday 10:00-11:00 New York is within hours but overlaps an event
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": cal.id,
+                "title": "Design review",
+                "start": "20260302T153000Z",
+                "end": "20260302T163000Z"
+            }),
+        )
+        .await;
+        let result = tool_call(
+            &pool,
+            &token,
+            "find_conflicts",
+            json!({
+                "start": "2026-03-02T10:00:00-05:00",
+                "end": "2026-03-02T11:00:00-05:00",
+                "respect_working_hours": true
+            }),
+        )
+        .await;
+        assert_eq!(result["outside_working_hours"], false);
+        assert_eq!(result["available"], false);
+        assert_eq!(result["conflicts"][0]["summary"], "Design review");
+    }
+
+    #[tokio::test]
+    async fn test_scheduling_ignores_transparent_calendars() {
+        let (pool, user_id, token) = setup().await;
+        let holidays = calendars::create_calendar(&pool, &user_id, "Holidays", "", "#000", "UTC")
+            .await
+            .unwrap();
+        calendars::set_calendar_transp(&pool, &holidays.id, "transparent")
+            .await
+            .unwrap();
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({
+                "calendar_id": holidays.id,
+                "title": "Pi Day",
+                "start": "20260314T000000Z",
+                "end": "20260315T000000Z"
+            }),
+        )
+        .await;
+        let window = json!({"start": "20260314T100000Z", "end": "20260314T110000Z"});
+
+        let result = tool_call(&pool, &token, "find_conflicts", window.clone()).await;
+        assert_eq!(result["available"], true);
+        assert!(result["conflicts"].as_array().unwrap().is_empty());
+
+        let mut slot = window.clone();
+        slot["duration"] = json!("PT1H");
+        let result = tool_call(&pool, &token, "find_free_slot", slot).await;
+        assert_eq!(result["slot"]["start"], "20260314T100000Z");
+
+        // Naming the calendar still checks it
+        let mut named = window;
+        named["calendar_id"] = json!(holidays.id);
+        let result = tool_call(&pool, &token, "find_conflicts", named).await;
+        assert_eq!(result["available"], false);
+    }
+
+    #[tokio::test]
+    async fn test_set_availability_rejects_bad_window() {
+        let (pool, _user_id, token) = setup().await;
+        let err = tool_error(
+            &pool,
+            &token,
+            "set_availability",
+            json!({"days": [{"day": "monday", "start": "17:00", "end": "09:00"}]}),
+        )
+        .await;
+        assert_eq!(err, "monday: start must be before end");
+
+        let err = tool_error(
+            &pool,
+            &token,
+            "set_availability",
+            json!({"days": [{"day": "funday", "start": "09:00", "end": "17:00"}]}),
+        )
+        .await;
+        assert_eq!(err, "Unknown day: funday");
+    }
+
+    #[tokio::test]
+    async fn test_find_free_slot_skips_meeting_and_working_hours() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        for (title, start, end) in [
+            ("Planning", "20260302T130000Z", "20260302T140000Z"),
+            ("1:1", "20260302T133000Z", "20260302T141500Z"),
+        ] {
+            tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({"calendar_id": cal.id, "title": title, "start": start, "end": end}),
+            )
+            .await;
+        }
+
+        // The overlapping meetings are merged; the next gap starts at 14:15
+        let result = tool_call(
+            &pool,
+            &token,
+            "find_free_slot",
+            json!({"start": "20260302T130000Z", "end": "20260302T170000Z", "duration": "PT30M"}),
+        )
+        .await;
+        assert_eq!(
+            result["slot"],
+            json!({"start": "20260302T141500Z", "end": "20260302T144500Z"})
+        );
+
+        // No gap long enough
+        let result = tool_call(
+            &pool,
+            &token,
+            "find_free_slot",
+            json!({"start": "20260302T130000Z", "end": "20260302T143000Z", "duration": "PT30M"}),
+        )
+        .await;
+        assert!(result["slot"].is_null());
+
+        // 16:30 New York leaves only 30 minutes of Monday's working hours,
+        // so a one-hour slot moves to Tuesday 09:00 (14:00Z)
+        let days: Vec<Value> = ["monday", "tuesday"]
+            .iter()
+            .map(|day| json!({"day": day, "start": "09:00", "end": "17:00"}))
+            .collect();
+        tool_call(
+            &pool,
+            &token,
+            "set_availability",
+            json!({"timezone": "America/New_York", "days": days}),
+        )
+        .await;
+        let window =
+            json!({"start": "20260302T213000Z", "end": "20260303T180000Z", "duration": "PT1H"});
+        let result = tool_call(&pool, &token, "find_free_slot", window.clone()).await;
+        assert_eq!(result["slot"]["start"], "20260303T140000Z");
+
+        let mut anytime = window;
+        anytime["respect_working_hours"] = json!(false);
+        let result = tool_call(&pool, &token, "find_free_slot", anytime).await;
+        assert_eq!(result["slot"]["start"], "20260302T213000Z");
+    }
+
+    #[tokio::test]
+    async fn test_group_freebusy_finds_common_slot() {
+        let (pool, user_id, token) = setup().await;
+        let mine = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        tool_call(
+            &pool,
+            &token,
+            "create_event",
+            json!({"calendar_id": mine.id, "title": "Review", "start": "20260302T130000Z", "end": "20260302T150000Z"}),
+        )
+        .await;
+
+        let bob = users::create_user(
+            &pool,
+            "bob",
+            Some("bob@example.com"),
+            "password2",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let bobs = calendars::create_calendar(&pool, &bob.id, "Bob", "", "#000", "UTC")
+            .await
+            .unwrap();
+        crate::db::shares::share_calendar(
+            &pool,
+            &bobs.id,
+            &user_id,
+            crate::db::models::Permission::Read,
+        )
+        .await
+        .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-tok",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+        tool_call(
+            &pool,
+            &bob_token,
+            "create_event",
+            json!({"calendar_id": bobs.id, "title": "Offsite", "start": "20260302T140000Z", "end": "20260302T163000Z"}),
         )
         .await;
-        let cal_id = result["id"].as_str().unwrap().to_string();
 
-        // Alice shares it with Bob (read-only)
+        // Busy 13:00-15:00 and 14:00-16:30 leave 16:30-17:00 free for both
         let result = tool_call(
             &pool,
-            &alice_token,
-            "share_calendar",
+            &token,
+            "group_freebusy",
             json!({
-                "calendar_id": cal_id,
-                "username": "bob",
-                "permission": "read"
+                "people": ["alice", "bob@example.com"],
+                "start": "20260302T120000Z",
+                "end": "20260302T170000Z",
+                "duration": "PT30M"
             }),
         )
         .await;
-        assert_eq!(result["shared_with"], "bob");
-        assert_eq!(result["permission"], "read");
-
-        // Bob lists shared calendars
-        let result = tool_call(&pool, &bob_token, "list_shared_calendars", json!({})).await;
-        let shared = result["shared_calendars"].as_array().unwrap();
-        assert_eq!(shared.len(), 1);
-        assert_eq!(shared[0]["name"], "Shared Cal");
-        assert_eq!(shared[0]["permission"], "read");
+        assert_eq!(
+            result["busy"],
+            json!([{"start": "20260302T130000Z", "end": "20260302T163000Z"}])
+        );
+        assert_eq!(
+            result["free_slots"],
+            json!([
+                {"start": "20260302T120000Z", "end": "20260302T130000Z"},
+                {"start": "20260302T163000Z", "end": "20260302T170000Z"}
+            ])
+        );
+        assert_eq!(
+            result["people"][1]["busy"],
+            json!([{"start": "20260302T140000Z", "end": "20260302T163000Z"}])
+        );
 
-        // Alice unshares
+        // A longer meeting only fits before the first block
         let result = tool_call(
             &pool,
-            &alice_token,
-            "unshare_calendar",
+            &token,
+            "group_freebusy",
             json!({
-                "calendar_id": cal_id,
-                "username": "bob"
+                "people": ["alice", "bob"],
+                "start": "20260302T120000Z",
+                "end": "20260302T170000Z",
+                "duration": "PT45M"
             }),
         )
         .await;
-        assert_eq!(result["unshared"], true);
+        assert_eq!(
+            result["free_slots"],
+            json!([{"start": "20260302T120000Z", "end": "20260302T130000Z"}])
+        );
 
-        // Bob should see no shared calendars now
-        let result = tool_call(&pool, &bob_token, "list_shared_calendars", json!({})).await;
-        assert_eq!(result["shared_calendars"].as_array().unwrap().len(), 0);
+        // People who have not shared anything with the caller cannot be queried
+        users::create_user(
+            &pool,
+            "carol",
+            None,
+            "password3",
+            &PasswordPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let err = tool_error(
+            &pool,
+            &token,
+            "group_freebusy",
+            json!({
+                "people": ["alice", "carol"],
+                "start": "20260302T120000Z",
+                "end": "20260302T170000Z",
+                "duration": "PT30M"
+            }),
+        )
+        .await;
+        assert!(err.contains("No calendars of 'carol'"), "got: {err}");
     }
 
     // ---- Error handling ----
@@ -516,6 +3833,81 @@ mod tests {
         assert_eq!(resp["error"]["code"], -32602);
     }
 
+    #[tokio::test]
+    async fn test_tool_call_params_not_object() {
+        let (pool, _user_id, token) = setup().await;
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": "x"
+        });
+        let (status, resp) = rpc_call(&pool, &token, body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp["error"]["code"], -32602);
+        assert_eq!(
+            resp["error"]["message"],
+            "'params' must be an object, got string"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_arguments_not_object() {
+        let (pool, _user_id, token) = setup().await;
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "list_calendars", "arguments": []}
+        });
+        let (status, resp) = rpc_call(&pool, &token, body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp["error"]["code"], -32602);
+        assert_eq!(
+            resp["error"]["message"],
+            "'arguments' must be an object, got array"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strict_tool_args_rejects_unknown_argument() {
+        let (pool, user_id, token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "create_event",
+                "arguments": {
+                    "calendar_id": cal.id,
+                    "titel": "x",
+                    "start": "20260302T150000Z",
+                    "end": "20260302T160000Z"
+                }
+            }
+        });
+        let strict = Config {
+            strict_tool_args: true,
+            ..Config::default()
+        };
+        let (status, resp) = rpc_call_with_config(&pool, &token, body.clone(), strict).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp["error"]["code"], -32602);
+        let message = resp["error"]["message"].as_str().unwrap();
+        assert!(
+            message.contains("'titel' (did you mean 'title'?)"),
+            "got: {message}"
+        );
+        assert_eq!(resp["error"]["data"]["unknown_arguments"], json!(["titel"]));
+
+        // Without the flag the extra argument is ignored as before
+        let (_, resp) = rpc_call(&pool, &token, body).await;
+        assert!(resp.get("error").is_none(), "got: {resp}");
+    }
+
     #[tokio::test]
     async fn test_tool_call_unknown_tool() {
         let (pool, _user_id, token) = setup().await;
@@ -531,6 +3923,67 @@ mod tests {
         assert_eq!(resp["result"]["isError"], true);
     }
 
+    #[tokio::test]
+    async fn test_search_events_across_calendars() {
+        let (pool, user_id, token) = setup().await;
+        let work = calendars::create_calendar(&pool, &user_id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        let home = calendars::create_calendar(&pool, &user_id, "Home", "", "#00FF00", "UTC")
+            .await
+            .unwrap();
+        for (cal, title, location) in [
+            (&work, "Quarterly review", "Room 4"),
+            (&home, "Dentist appointment", "Main St"),
+            (&home, "Checkup", "Dentist office"),
+            (&work, "Team lunch", "Cafe"),
+        ] {
+            tool_call(
+                &pool,
+                &token,
+                "create_event",
+                json!({
+                    "calendar_id": cal.id,
+                    "title": title,
+                    "start": "20260302T150000Z",
+                    "end": "20260302T160000Z",
+                    "location": location
+                }),
+            )
+            .await;
+        }
+
+        let result = tool_call(&pool, &token, "search_events", json!({"query": "DENTIST"})).await;
+        assert_eq!(result["count"], 2);
+        let mut titles: Vec<&str> = result["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["summary"].as_str().unwrap())
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Checkup", "Dentist appointment"]);
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "search_events",
+            json!({"query": "review", "calendar_id": work.id}),
+        )
+        .await;
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["events"][0]["calendar_id"], work.id);
+
+        let result = tool_call(
+            &pool,
+            &token,
+            "search_events",
+            json!({"query": "review", "calendar_id": home.id}),
+        )
+        .await;
+        assert_eq!(result["count"], 0);
+    }
+
     #[tokio::test]
     async fn test_get_event_not_found() {
         let (pool, user_id, token) = setup().await;
@@ -556,12 +4009,54 @@ mod tests {
                 .unwrap()
                 .contains("not found")
         );
+        assert_eq!(
+            resp["result"]["structuredContent"]["error"]["code"],
+            "not_found"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_error_code_forbidden_for_other_users_calendar() {
+        let (pool, user_id, _token) = setup().await;
+        let cal = calendars::create_calendar(&pool, &user_id, "Work", "", "#000", "UTC")
+            .await
+            .unwrap();
+        let bob = users::create_user(&pool, "bob", None, "secret456", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-tok",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "get_event",
+                "arguments": {"calendar_id": cal.id, "event_uid": "any-uid"}
+            }
+        });
+        let (status, resp) = rpc_call(&pool, &bob_token, body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(resp["result"]["isError"], true);
+        assert!(resp["result"]["content"][0]["text"].is_string());
+        assert_eq!(
+            resp["result"]["structuredContent"]["error"]["code"],
+            "forbidden"
+        );
     }
 
     #[tokio::test]
     async fn test_invalid_json_returns_parse_error() {
         let (pool, _user_id, token) = setup().await;
-        let app = router(pool, "full".to_string());
+        let app = router(pool, Config::default());
         let req = axum::http::Request::builder()
             .method(Method::POST)
             .uri("/mcp")
@@ -576,28 +4071,236 @@ mod tests {
         assert_eq!(json["error"]["code"], -32700);
     }
 
-    // ---- DELETE session ----
+    #[tokio::test]
+    async fn test_batch_request_returns_responses_in_order() {
+        let (pool, _user_id, token) = setup().await;
+        let body = json!([
+            {"jsonrpc": "2.0", "id": "list", "method": "tools/list"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": 7, "method": "ping"}
+        ]);
+        let (status, resp) = rpc_call(&pool, &token, body).await;
+        assert_eq!(status, StatusCode::OK);
+        let responses = resp.as_array().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], "list");
+        assert_eq!(
+            responses[0]["result"]["tools"].as_array().unwrap().len(),
+            41
+        );
+        assert_eq!(responses[1]["id"], 7);
+        assert_eq!(responses[1]["result"], json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_empty_batch_is_invalid_request() {
+        let (pool, _user_id, token) = setup().await;
+        let (status, resp) = rpc_call(&pool, &token, json!([])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(resp.is_object(), "got: {resp}");
+        assert_eq!(resp["error"]["code"], -32600);
+        assert!(resp["id"].is_null());
+    }
+
+    // ---- Sessions ----
+
+    /// Send `initialize` to the router and return the `Mcp-Session-Id` it issues.
+    async fn initialize_session(app: &Router, token: &str) -> String {
+        let init = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {"protocolVersion": "2025-03-26"}
+        });
+        let req = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/mcp")
+            .header("Content-Type", "application/json")
+            .header("Authorization", bearer_header(token))
+            .body(Body::from(serde_json::to_vec(&init).unwrap()))
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        resp.headers()["Mcp-Session-Id"]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Send `ping` to the router with the given `Mcp-Session-Id`.
+    async fn ping_with_session(app: &Router, token: &str, session_id: &str) -> StatusCode {
+        let ping = json!({"jsonrpc": "2.0", "id": 2, "method": "ping"});
+        let req = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/mcp")
+            .header("Content-Type", "application/json")
+            .header("Authorization", bearer_header(token))
+            .header("Mcp-Session-Id", session_id)
+            .body(Body::from(serde_json::to_vec(&ping).unwrap()))
+            .unwrap();
+        app.clone().oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_issues_session_id() {
+        let (pool, _user_id, token) = setup().await;
+        let app = router(pool, Config::default());
+
+        let first = initialize_session(&app, &token).await;
+        let second = initialize_session(&app, &token).await;
+        assert!(!first.is_empty());
+        assert_ne!(first, second);
+        assert_eq!(
+            ping_with_session(&app, &token, &first).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_session_id_is_rejected() {
+        let (pool, _user_id, token) = setup().await;
+        let app = router(pool.clone(), Config::default());
+        let session_id = initialize_session(&app, &token).await;
+
+        assert_eq!(
+            ping_with_session(&app, &token, "bogus-session-id").await,
+            StatusCode::NOT_FOUND
+        );
+
+        // Another user cannot use alice's session
+        let bob = users::create_user(&pool, "bob", None, "secret456", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let (bob_token, _) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob-tok",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            ping_with_session(&app, &bob_token, &session_id).await,
+            StatusCode::NOT_FOUND
+        );
+    }
 
     #[tokio::test]
     async fn test_delete_session() {
         let (pool, _user_id, token) = setup().await;
-        let app = router(pool, "full".to_string());
+        let app = router(pool, Config::default());
+        let session_id = initialize_session(&app, &token).await;
+
+        let delete_request = |id: &str| {
+            axum::http::Request::builder()
+                .method(Method::DELETE)
+                .uri("/mcp")
+                .header("Authorization", bearer_header(&token))
+                .header("Mcp-Session-Id", id)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app
+            .clone()
+            .oneshot(delete_request(&session_id))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The session is gone for later requests and a second DELETE
+        assert_eq!(
+            ping_with_session(&app, &token, &session_id).await,
+            StatusCode::NOT_FOUND
+        );
+        let resp = app
+            .clone()
+            .oneshot(delete_request(&session_id))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = app
+            .oneshot(delete_request("some-session-id"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    // ---- GET event stream ----
+
+    #[tokio::test]
+    async fn test_event_stream_delivers_pushed_messages() {
+        let (pool, _user_id, token) = setup().await;
+        let sessions = SessionManager::new();
+        let app = router_with_sessions(pool, Config::default(), sessions.clone());
+        let session_id = initialize_session(&app, &token).await;
+
         let req = axum::http::Request::builder()
-            .method(Method::DELETE)
+            .method(Method::GET)
             .uri("/mcp")
             .header("Authorization", bearer_header(&token))
-            .header("Mcp-Session-Id", "some-session-id")
+            .header("Mcp-Session-Id", &session_id)
             .body(Body::empty())
             .unwrap();
-        let resp = app.oneshot(req).await.unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+        let mut body = resp.into_body();
+
+        let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        let text = String::from_utf8(frame.to_vec()).unwrap();
+        assert!(
+            text.starts_with("event: endpoint\ndata: /mcp\n"),
+            "got: {text}"
+        );
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"});
+        assert!(sessions.push(&session_id, notification));
+        let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        let text = String::from_utf8(frame.to_vec()).unwrap();
+        assert!(text.starts_with("event: message\n"), "got: {text}");
+        assert!(
+            text.contains("notifications/tools/list_changed"),
+            "got: {text}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_requires_known_session() {
+        let (pool, _user_id, token) = setup().await;
+        let app = router(pool, Config::default());
+
+        let req = axum::http::Request::builder()
+            .method(Method::GET)
+            .uri("/mcp")
+            .header("Authorization", bearer_header(&token))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = axum::http::Request::builder()
+            .method(Method::GET)
+            .uri("/mcp")
+            .header("Authorization", bearer_header(&token))
+            .header("Mcp-Session-Id", "no-such-session")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 
     // ==== Simple mode helpers ====
 
     /// Send a JSON-RPC request in simple mode.
     async fn simple_rpc_call(pool: &SqlitePool, token: &str, body: Value) -> (StatusCode, Value) {
-        let app = router(pool.clone(), "simple".to_string());
+        let app = router(
+            pool.clone(),
+            Config {
+                tool_mode: "simple".to_string(),
+                ..Config::default()
+            },
+        );
         let req = axum::http::Request::builder()
             .method(Method::POST)
             .uri("/mcp")
@@ -907,4 +4610,75 @@ mod tests {
         // Full-mode tool names should not work in simple mode
         assert_eq!(resp["result"]["isError"], true);
     }
+
+    #[tokio::test]
+    async fn test_rotate_my_token_replaces_secret() {
+        let (pool, user_id, token) = setup().await;
+        let id = tokens::list_tokens_for_user(&pool, &user_id).await.unwrap()[0]
+            .id
+            .clone();
+
+        let result = tool_call(&pool, &token, "rotate_my_token", json!({"id": id})).await;
+        assert_eq!(result["id"], id);
+        assert_eq!(result["name"], "test-token");
+        let new_token = result["token"].as_str().unwrap().to_string();
+
+        // The old secret is refused; the new one authenticates as the same user
+        let list = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        let (status, _) = rpc_call(&pool, &token, list.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = rpc_call(&pool, &new_token, list).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Another user's token cannot be rotated
+        let bob = users::create_user(&pool, "bob", None, "secret123", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let (_, bob_token) = tokens::create_token(
+            &pool,
+            &bob.id,
+            "bob",
+            crate::db::models::Permission::ReadWrite,
+            None,
+        )
+        .await
+        .unwrap();
+        let err = tool_error(
+            &pool,
+            &new_token,
+            "rotate_my_token",
+            json!({"id": bob_token.id}),
+        )
+        .await;
+        assert!(err.contains("Token not found"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_read_only_token_cannot_rotate_read_write_token() {
+        let (pool, user_id, token) = setup().await;
+        let (viewer, _) = tokens::create_token(
+            &pool,
+            &user_id,
+            "viewer",
+            crate::db::models::Permission::Read,
+            None,
+        )
+        .await
+        .unwrap();
+        let id = tokens::list_tokens_for_user(&pool, &user_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "test-token")
+            .unwrap()
+            .id;
+
+        let err = tool_error(&pool, &viewer, "rotate_my_token", json!({"id": id})).await;
+        assert!(err.contains("read-only"), "got: {err}");
+
+        // The read-write secret still works
+        let list = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        let (status, _) = rpc_call(&pool, &token, list).await;
+        assert_eq!(status, StatusCode::OK);
+    }
 }
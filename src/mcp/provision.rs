@@ -0,0 +1,75 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::db::models::User;
+use crate::db::users::{self, PasswordPolicy};
+use crate::error::AppResult;
+
+/// Longest a provisioning token may remain valid, in seconds. A token that
+/// never expires would be a permanent credential for its user.
+const MAX_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Verify an HS256-signed JWT issued for `AUTO_PROVISION` and return its
+/// `username` claim. Returns `None` if the token is malformed, the signature
+/// does not match `secret`, it has no `exp` or one more than
+/// [`MAX_LIFETIME_SECS`] away, or it is expired or not yet valid (`nbf`) at
+/// `now` (seconds since the Unix epoch).
+pub fn verify_token(token: &str, secret: &str, now: i64) -> Option<String> {
+    let mut parts = token.split('.');
+    let (header_b64, claims_b64, signature_b64) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let header: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header_b64).ok()?).ok()?;
+    if header["alg"] != "HS256" {
+        return None;
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(format!("{header_b64}.{claims_b64}").as_bytes());
+    mac.verify_slice(&URL_SAFE_NO_PAD.decode(signature_b64).ok()?)
+        .ok()?;
+
+    let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims_b64).ok()?).ok()?;
+    let exp = claims["exp"].as_i64()?;
+    if exp <= now
+        || exp - now > MAX_LIFETIME_SECS
+        || claims["nbf"].as_i64().is_some_and(|nbf| nbf > now)
+    {
+        return None;
+    }
+
+    let username = claims["username"].as_str()?.trim();
+    if username.is_empty()
+        || username.contains(['/', '%'])
+        || username.contains(char::is_whitespace)
+    {
+        return None;
+    }
+    Some(username.to_string())
+}
+
+/// Return the user with `username`, creating it if it does not exist yet.
+/// Provisioned users get a random password, so they can only sign in over
+/// CalDAV after an administrator runs `reset-password`.
+pub async fn find_or_create_user(pool: &SqlitePool, username: &str) -> AppResult<User> {
+    if let Some(user) = users::get_user_by_username(pool, username).await? {
+        return Ok(user);
+    }
+
+    let password = Uuid::new_v4().simple().to_string();
+    match users::create_user(pool, username, None, &password, &PasswordPolicy::default()).await {
+        Ok(user) => {
+            tracing::info!(username = %username, "provisioned MCP user");
+            Ok(user)
+        }
+        // A concurrent request may have created the same user
+        Err(e) => users::get_user_by_username(pool, username).await?.ok_or(e),
+    }
+}
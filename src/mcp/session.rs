@@ -1,36 +1,130 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
-/// Manages MCP session IDs and their associated user IDs.
+/// How long a session may go unused before it is dropped. A session with an
+/// open event stream is in use for as long as the stream is connected.
+const IDLE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most sessions one user may hold; creating another drops their least
+/// recently used one.
+const MAX_SESSIONS_PER_USER: usize = 32;
+
+/// Manages MCP session IDs, their associated user IDs, and the event stream
+/// (GET /mcp) each session may have open.
 #[derive(Debug, Clone)]
 pub struct SessionManager {
-    sessions: Arc<Mutex<HashMap<String, String>>>,
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    idle_ttl: Duration,
+    max_per_user: usize,
+}
+
+#[derive(Debug)]
+struct Session {
+    user_id: String,
+    stream: Option<mpsc::UnboundedSender<Value>>,
+    last_used: Instant,
+}
+
+impl Session {
+    /// Whether the session has gone unused for longer than `ttl`.
+    fn is_idle(&self, ttl: Duration) -> bool {
+        let streaming = self.stream.as_ref().is_some_and(|tx| !tx.is_closed());
+        !streaming && self.last_used.elapsed() > ttl
+    }
 }
 
 impl SessionManager {
     /// Create a new empty session manager.
     pub fn new() -> Self {
+        Self::with_limits(IDLE_TTL, MAX_SESSIONS_PER_USER)
+    }
+
+    /// A session manager with its own idle timeout and per-user cap.
+    fn with_limits(idle_ttl: Duration, max_per_user: usize) -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            idle_ttl,
+            max_per_user,
         }
     }
 
-    /// Create a new session for a user. Returns the session ID.
+    /// Create a new session for a user. Returns the session ID. Idle
+    /// sessions are dropped first, then the user's least recently used
+    /// sessions while they are at the cap.
     pub fn create_session(&self, user_id: &str) -> String {
         let session_id = Uuid::new_v4().to_string();
         let mut sessions = self.sessions.lock().unwrap();
-        sessions.insert(session_id.clone(), user_id.to_string());
+        sessions.retain(|_, s| !s.is_idle(self.idle_ttl));
+        loop {
+            let mut own: Vec<_> = sessions
+                .iter()
+                .filter(|(_, s)| s.user_id == user_id)
+                .map(|(id, s)| (s.last_used, id.clone()))
+                .collect();
+            if own.len() < self.max_per_user {
+                break;
+            }
+            own.sort();
+            sessions.remove(&own[0].1);
+        }
+        sessions.insert(
+            session_id.clone(),
+            Session {
+                user_id: user_id.to_string(),
+                stream: None,
+                last_used: Instant::now(),
+            },
+        );
         session_id
     }
 
-    /// Look up the user ID for a session.
+    /// Look up the user ID for a session, marking it as used. An idle
+    /// session is dropped and treated as unknown.
     pub fn get_user_id(&self, session_id: &str) -> Option<String> {
-        let sessions = self.sessions.lock().unwrap();
-        sessions.get(session_id).cloned()
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(session_id)?;
+        if session.is_idle(self.idle_ttl) {
+            sessions.remove(session_id);
+            return None;
+        }
+        session.last_used = Instant::now();
+        Some(session.user_id.clone())
     }
 
-    /// Remove a session.
+    /// Open the server-to-client stream for a session, replacing any stream
+    /// already open. Returns `None` for an unknown session.
+    pub fn open_stream(&self, session_id: &str) -> Option<mpsc::UnboundedReceiver<Value>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(session_id)?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        session.stream = Some(tx);
+        Some(rx)
+    }
+
+    /// Send a JSON-RPC message to a session's open stream. Returns `false`
+    /// when the session has no stream or the client has disconnected.
+    #[allow(dead_code)]
+    pub fn push(&self, session_id: &str, message: Value) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+        let sent = session
+            .stream
+            .as_ref()
+            .is_some_and(|tx| tx.send(message).is_ok());
+        if !sent {
+            session.stream = None;
+        }
+        sent
+    }
+
+    /// Remove a session, closing its stream.
     pub fn remove_session(&self, session_id: &str) {
         let mut sessions = self.sessions.lock().unwrap();
         sessions.remove(session_id);
@@ -61,4 +155,49 @@ mod tests {
         let mgr = SessionManager::new();
         assert_eq!(mgr.get_user_id("nonexistent"), None);
     }
+
+    #[test]
+    fn test_idle_session_expires() {
+        let mgr = SessionManager::with_limits(Duration::from_millis(20), 8);
+        let idle = mgr.create_session("user-123");
+        let streaming = mgr.create_session("user-123");
+        let _rx = mgr.open_stream(&streaming).unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+
+        assert_eq!(mgr.get_user_id(&idle), None);
+        assert_eq!(mgr.get_user_id(&streaming), Some("user-123".to_string()));
+    }
+
+    #[test]
+    fn test_session_cap_drops_least_recently_used() {
+        let mgr = SessionManager::with_limits(IDLE_TTL, 2);
+        let first = mgr.create_session("user-123");
+        std::thread::sleep(Duration::from_millis(2));
+        let second = mgr.create_session("user-123");
+        std::thread::sleep(Duration::from_millis(2));
+        // Using the first session makes the second the least recent
+        assert!(mgr.get_user_id(&first).is_some());
+        let other = mgr.create_session("user-456");
+        let third = mgr.create_session("user-123");
+
+        assert!(mgr.get_user_id(&first).is_some());
+        assert_eq!(mgr.get_user_id(&second), None);
+        assert!(mgr.get_user_id(&third).is_some());
+        assert!(mgr.get_user_id(&other).is_some());
+    }
+
+    #[test]
+    fn test_push_to_open_stream() {
+        let mgr = SessionManager::new();
+        let sid = mgr.create_session("user-123");
+        assert!(!mgr.push(&sid, serde_json::json!({"n": 1})));
+
+        let mut rx = mgr.open_stream(&sid).unwrap();
+        assert!(mgr.push(&sid, serde_json::json!({"n": 2})));
+        assert_eq!(rx.try_recv().unwrap(), serde_json::json!({"n": 2}));
+
+        drop(rx);
+        assert!(!mgr.push(&sid, serde_json::json!({"n": 3})));
+        assert!(mgr.open_stream("nonexistent").is_none());
+    }
 }
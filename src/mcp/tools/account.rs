@@ -0,0 +1,85 @@
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+
+use super::{ToolDef, ToolError};
+use crate::db::{sync_clients, tokens};
+
+/// Return the MCP tool definitions for the authenticated user's account.
+pub fn tool_defs() -> Vec<ToolDef> {
+    vec![
+        ToolDef {
+            name: "list_sync_clients",
+            description: "List the CalDAV clients (by User-Agent) that have synced as the authenticated user, most recently seen first. Useful for spotting an unknown device",
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "rotate_my_token",
+            description: "Generate a new secret for one of the authenticated user's MCP tokens. The token keeps its ID and name; the old secret stops working immediately. The new token is returned once and cannot be retrieved again",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "ID of the token to rotate" }
+                },
+                "required": ["id"],
+                "additionalProperties": false
+            }),
+        },
+    ]
+}
+
+/// List the user's CalDAV clients with when each was first and last seen.
+pub async fn list_sync_clients(
+    pool: &SqlitePool,
+    user_id: &str,
+    _args: &Value,
+) -> Result<Value, ToolError> {
+    let clients = sync_clients::list_clients(pool, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let result: Vec<Value> = clients
+        .iter()
+        .map(|c| {
+            json!({
+                "user_agent": c.user_agent,
+                "first_seen": c.first_seen.and_utc().to_rfc3339(),
+                "last_seen": c.last_seen.and_utc().to_rfc3339(),
+            })
+        })
+        .collect();
+
+    Ok(json!({ "clients": result }))
+}
+
+/// Rotate the secret of one of the user's own MCP tokens.
+pub async fn rotate_my_token(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let id = args["id"].as_str().ok_or("Missing id")?;
+
+    // Tokens of other users are reported as missing rather than forbidden
+    let owned = tokens::list_tokens_for_user(pool, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .iter()
+        .any(|t| t.id == id);
+    if !owned {
+        return Err(ToolError::not_found(format!("Token not found: {id}")));
+    }
+
+    let (raw_token, record) = tokens::rotate_token(pool, id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    Ok(json!({
+        "id": record.id,
+        "name": record.name,
+        "token": raw_token,
+    }))
+}
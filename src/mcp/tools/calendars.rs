@@ -1,8 +1,13 @@
+use std::path::Path;
+
 use serde_json::{Value, json};
 use sqlx::SqlitePool;
 
-use super::ToolDef;
+use super::{ToolDef, ToolError};
+use crate::config::Config;
 use crate::db::calendars as cal_db;
+use crate::db::events as event_db;
+use crate::ical::{builder, datetime, parser};
 
 /// Return the MCP tool definitions for calendar management operations.
 pub fn tool_defs() -> Vec<ToolDef> {
@@ -16,6 +21,15 @@ pub fn tool_defs() -> Vec<ToolDef> {
                 "additionalProperties": false
             }),
         },
+        ToolDef {
+            name: "list_calendars_detailed",
+            description: "List all accessible calendars with color, timezone, description, ctag and event count in one call",
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false
+            }),
+        },
         ToolDef {
             name: "get_calendar",
             description: "Get details about a specific calendar",
@@ -55,6 +69,65 @@ pub fn tool_defs() -> Vec<ToolDef> {
                 "additionalProperties": false
             }),
         },
+        ToolDef {
+            name: "set_calendar_components",
+            description: "Set which component types (VEVENT, VTODO, VJOURNAL) a calendar accepts for new objects. Owner only; existing objects are kept",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "component_types": {
+                        "type": "array",
+                        "items": {"type": "string", "enum": ["VEVENT", "VTODO", "VJOURNAL"]},
+                        "minItems": 1,
+                        "description": "Component types to accept"
+                    }
+                },
+                "required": ["calendar_id", "component_types"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "set_calendar_timezone",
+            description: "Change a calendar's timezone. Owner only. With normalize_existing, floating-time events (no TZID) are rewritten to carry the new TZID at the same wall-clock time",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "timezone": {"type": "string", "description": "IANA timezone, e.g. 'America/New_York'"},
+                    "normalize_existing": {"type": "boolean", "description": "Anchor existing floating events to the new timezone (default false)"}
+                },
+                "required": ["calendar_id", "timezone"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "export_activity_csv",
+            description: "Export a calendar's change history (created, modified and deleted objects) as CSV text with columns timestamp, change_type, object_uid, sync_token. Read-only",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "start": {"type": "string", "description": "Only changes at or after this time (iCal format or ISO 8601)"},
+                    "end": {"type": "string", "description": "Only changes before this time (iCal format or ISO 8601)"},
+                    "limit": {"type": "integer", "description": "Max rows (default 1000)", "minimum": 1, "maximum": 10000}
+                },
+                "required": ["calendar_id"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "export_calendar",
+            description: "Export every event and task in a calendar as a single .ics (one VCALENDAR), with a suggested filename. Read-only",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"}
+                },
+                "required": ["calendar_id"],
+                "additionalProperties": false
+            }),
+        },
     ]
 }
 
@@ -63,10 +136,41 @@ pub async fn list_calendars(
     pool: &SqlitePool,
     user_id: &str,
     _args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
+    let cals = cal_db::list_calendars_for_user(pool, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Failed to list calendars", e))?;
+
+    let result: Vec<Value> = cals
+        .iter()
+        .map(|c| {
+            json!({
+                "id": c.id,
+                "name": c.name,
+                "description": c.description,
+                "color": c.color,
+                "timezone": c.timezone,
+                "owner_id": c.owner_id,
+            })
+        })
+        .collect();
+
+    Ok(json!({ "calendars": result }))
+}
+
+/// List accessible calendars with the metadata a UI needs to render them,
+/// including a per-calendar object count from one grouped query.
+pub async fn list_calendars_detailed(
+    pool: &SqlitePool,
+    user_id: &str,
+    _args: &Value,
+) -> Result<Value, ToolError> {
     let cals = cal_db::list_calendars_for_user(pool, user_id)
         .await
-        .map_err(|e| format!("Failed to list calendars: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to list calendars", e))?;
+    let counts = cal_db::object_counts_for_user(pool, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
 
     let result: Vec<Value> = cals
         .iter()
@@ -78,6 +182,9 @@ pub async fn list_calendars(
                 "color": c.color,
                 "timezone": c.timezone,
                 "owner_id": c.owner_id,
+                "shared": c.owner_id != user_id,
+                "ctag": c.ctag,
+                "event_count": counts.get(&c.id).copied().unwrap_or(0),
             })
         })
         .collect();
@@ -88,15 +195,17 @@ pub async fn list_calendars(
 /// Get details about a specific calendar by ID.
 pub async fn get_calendar(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
 
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
     let cal = cal_db::get_calendar_by_id(pool, calendar_id)
         .await
-        .map_err(|e| format!("Database error: {e}"))?
-        .ok_or("Calendar not found")?;
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Calendar not found"))?;
 
     Ok(json!({
         "id": cal.id,
@@ -106,6 +215,7 @@ pub async fn get_calendar(
         "timezone": cal.timezone,
         "owner_id": cal.owner_id,
         "ctag": cal.ctag,
+        "component_types": cal.component_types(),
     }))
 }
 
@@ -114,7 +224,7 @@ pub async fn create_calendar(
     pool: &SqlitePool,
     user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
     let name = args["name"].as_str().ok_or("Missing name")?;
     let description = args["description"].as_str().unwrap_or("");
     let color = args["color"].as_str().unwrap_or("#0E61B9");
@@ -122,7 +232,7 @@ pub async fn create_calendar(
 
     let cal = cal_db::create_calendar(pool, user_id, name, description, color, timezone)
         .await
-        .map_err(|e| format!("Failed to create calendar: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to create calendar", e))?;
 
     Ok(json!({
         "id": cal.id,
@@ -136,14 +246,252 @@ pub async fn create_calendar(
 /// Delete a calendar and all its events by ID.
 pub async fn delete_calendar_tool(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    cal_db::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .filter(|c| c.owner_id == user_id)
+        .ok_or_else(|| ToolError::not_found("Calendar not found or not owned by you"))?;
 
     cal_db::delete_calendar(pool, calendar_id)
         .await
-        .map_err(|e| format!("Failed to delete calendar: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to delete calendar", e))?;
 
     Ok(json!({"deleted": true, "calendar_id": calendar_id}))
 }
+
+/// Restrict the component types a calendar accepts for new objects. Only the
+/// owner may change this; objects already stored are not affected.
+pub async fn set_calendar_components(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let requested = args["component_types"]
+        .as_array()
+        .ok_or("Missing component_types")?;
+
+    let mut component_types: Vec<&str> = Vec::new();
+    for value in requested {
+        let name = value
+            .as_str()
+            .and_then(|v| {
+                cal_db::COMPONENT_TYPES
+                    .iter()
+                    .find(|c| c.eq_ignore_ascii_case(v))
+                    .copied()
+            })
+            .ok_or_else(|| format!("Unsupported component type: {value}"))?;
+        if !component_types.contains(&name) {
+            component_types.push(name);
+        }
+    }
+    if component_types.is_empty() {
+        return Err(ToolError::validation("component_types must not be empty"));
+    }
+
+    let cal = cal_db::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .filter(|c| c.owner_id == user_id)
+        .ok_or_else(|| ToolError::not_found("Calendar not found or not owned by you"))?;
+
+    let cal = cal_db::set_component_types(pool, &cal.id, &component_types)
+        .await
+        .map_err(|e| ToolError::storage("Failed to update calendar", e))?;
+
+    Ok(json!({
+        "calendar_id": cal.id,
+        "component_types": cal.component_types(),
+    }))
+}
+
+/// Change a calendar's timezone. Only the owner may do this. With
+/// `normalize_existing`, floating events are anchored to the new zone at the
+/// same wall-clock time; events with a TZID or in UTC already name an
+/// instant and are left alone.
+pub async fn set_calendar_timezone(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let timezone = args["timezone"].as_str().ok_or("Missing timezone")?;
+    let normalize_existing = args["normalize_existing"].as_bool().unwrap_or(false);
+    super::validate_timezone(config, timezone)?;
+
+    let cal = cal_db::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .filter(|c| c.owner_id == user_id)
+        .ok_or_else(|| ToolError::not_found("Calendar not found or not owned by you"))?;
+
+    let cal = cal_db::update_calendar(pool, &cal.id, None, None, None, Some(timezone))
+        .await
+        .map_err(|e| ToolError::storage("Failed to update calendar", e))?;
+
+    let mut normalized = Vec::new();
+    if normalize_existing {
+        let objects = event_db::list_objects(pool, &cal.id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?;
+        for obj in objects {
+            let Some(mut calendar) = parser::parse_calendar(&obj.ical_data) else {
+                continue;
+            };
+            if !super::events::anchor_floating_events(&mut calendar, timezone) {
+                continue;
+            }
+            let ical_data = calendar.to_ical();
+            let fields = parser::extract_fields(&ical_data);
+            event_db::upsert_object_with_etag_mode(
+                pool,
+                &cal.id,
+                &obj.uid,
+                &ical_data,
+                event_db::ObjectFields {
+                    component_type: &fields.component_type,
+                    dtstart: fields.dtstart.as_deref(),
+                    dtend: fields.dtend.as_deref(),
+                    summary: fields.summary.as_deref(),
+                },
+                config.etag_mode,
+            )
+            .await
+            .map_err(|e| ToolError::storage(format!("Failed to normalize event {}", obj.uid), e))?;
+            normalized.push(obj.uid);
+        }
+    }
+
+    // The calendar-timezone property changed even if no event did
+    let ctag = cal_db::bump_ctag(pool, &cal.id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    Ok(json!({
+        "calendar_id": cal.id,
+        "timezone": cal.timezone,
+        "normalized": normalized,
+        "ctag": ctag,
+    }))
+}
+
+/// Export a calendar's sync change log as CSV, oldest change first.
+pub async fn export_activity_csv(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let bound = |name: &str| -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+        match args[name].as_str() {
+            Some(value) => datetime::parse_ical_datetime(value, None)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid {name}: {value}")),
+            None => Ok(None),
+        }
+    };
+    let start = bound("start")?;
+    let end = bound("end")?;
+    let limit = args["limit"].as_u64().unwrap_or(1000).clamp(1, 10_000) as i64;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    let changes = event_db::list_sync_changes(pool, calendar_id, start, end, limit)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let mut csv = String::from("timestamp,change_type,object_uid,sync_token\r\n");
+    for change in &changes {
+        let timestamp = change.created_at.and_utc().to_rfc3339();
+        let row: [&str; 4] = [
+            &timestamp,
+            &change.change_type,
+            &change.object_uid,
+            &change.sync_token,
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "count": changes.len(),
+        "csv": csv,
+    }))
+}
+
+/// Export a calendar as one merged VCALENDAR.
+pub async fn export_calendar(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    let cal = cal_db::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Calendar not found"))?;
+    let objects = event_db::list_objects(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let ics = builder::merge_calendars(
+        objects.iter().map(|obj| obj.ical_data.as_str()),
+        Some(&cal.name),
+    );
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "filename": builder::export_filename(&cal.name),
+        "count": objects.len(),
+        "ics": ics,
+    }))
+}
+
+/// Export a calendar the user owns for the `export-ics` command, writing the
+/// merged VCALENDAR to `out` when given. Returns the same summary as
+/// `export_calendar`, including the `ics` text.
+pub async fn export_owned_calendar(
+    pool: &SqlitePool,
+    user_id: &str,
+    calendar_id: &str,
+    out: Option<&Path>,
+) -> Result<Value, ToolError> {
+    let cal = cal_db::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Calendar not found"))?;
+    if cal.owner_id != user_id {
+        return Err(ToolError::forbidden(format!(
+            "Access denied to calendar {calendar_id}"
+        )));
+    }
+
+    let result = export_calendar(pool, user_id, &json!({"calendar_id": calendar_id})).await?;
+    if let Some(path) = out {
+        let ics = result["ics"].as_str().unwrap_or_default();
+        tokio::fs::write(path, ics)
+            .await
+            .map_err(|e| ToolError::internal(format!("Failed to write {}: {e}", path.display())))?;
+    }
+    Ok(result)
+}
+
+/// Quote a CSV field (RFC 4180) if it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
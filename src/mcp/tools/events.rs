@@ -1,9 +1,18 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
 use serde_json::{Value, json};
 use sqlx::SqlitePool;
 
-use super::ToolDef;
+use super::{ToolDef, ToolError};
+use crate::config::Config;
+use crate::db::calendars as cal_db;
 use crate::db::events as event_db;
-use crate::ical::builder;
+use crate::db::models::CalendarObject;
+use crate::error::AppError;
+use crate::ical::datetime::{self, IcalTime};
+use crate::ical::{builder, jcal, parser, recurrence};
 
 /// Return the MCP tool definitions for calendar event CRUD and query operations.
 pub fn tool_defs() -> Vec<ToolDef> {
@@ -20,12 +29,53 @@ pub fn tool_defs() -> Vec<ToolDef> {
                     "end": {"type": "string", "description": "Local end time in iCal format"},
                     "timezone": {"type": "string", "description": "IANA timezone, e.g. America/Los_Angeles. Omit only for explicit UTC times (Z suffix)."},
                     "description": {"type": "string", "description": "Event description"},
-                    "location": {"type": "string", "description": "Event location"}
+                    "location": {"type": "string", "description": "Event location"},
+                    "geo": {
+                        "type": "object",
+                        "description": "Coordinates of the location",
+                        "properties": {
+                            "lat": {"type": "number", "description": "Latitude, -90 to 90"},
+                            "lon": {"type": "number", "description": "Longitude, -180 to 180"}
+                        },
+                        "required": ["lat", "lon"],
+                        "additionalProperties": false
+                    },
+                    "url": {"type": "string", "description": "Link for the event, e.g. a video-call URL (https://...)"},
+                    "all_day": {"type": "boolean", "description": "Create an all-day event. start and end are dates (20260301); end is the day after the last day; an end on or before start makes a one-day event. timezone is ignored."},
+                    "recurrence": {
+                        "type": ["object", "string"],
+                        "description": "Make the event repeat: a raw RRULE such as 'FREQ=WEEKLY;BYDAY=MO,WE' or an object. start/end are the first occurrence",
+                        "properties": {
+                            "freq": {"type": "string", "enum": ["DAILY", "WEEKLY", "MONTHLY", "YEARLY"]},
+                            "interval": {"type": "integer", "description": "Repeat every N periods (default 1)", "minimum": 1},
+                            "count": {"type": "integer", "description": "Number of occurrences", "minimum": 1},
+                            "until": {"type": "string", "description": "Last possible start, in the event's timezone (iCal format or ISO 8601)"},
+                            "byday": {"type": "array", "items": {"type": "string"}, "description": "Weekdays, e.g. [\"MO\", \"WE\"] or [\"-1FR\"] for the last Friday"}
+                        },
+                        "required": ["freq"],
+                        "additionalProperties": false
+                    }
                 },
                 "required": ["calendar_id", "title", "start", "end"],
                 "additionalProperties": false
             }),
         },
+        ToolDef {
+            name: "create_todo",
+            description: "Create a task (VTODO) in a calendar, optionally with a due date, status and progress",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The target calendar ID"},
+                    "title": {"type": "string", "description": "Task title/summary"},
+                    "due": {"type": "string", "description": "Due time in iCal format, e.g. 20260301T170000Z, or a date such as 20260301"},
+                    "status": {"type": "string", "enum": TODO_STATUSES, "description": "Task status (default NEEDS-ACTION when omitted by clients)"},
+                    "percent_complete": {"type": "integer", "description": "Progress from 0 to 100", "minimum": 0, "maximum": 100}
+                },
+                "required": ["calendar_id", "title"],
+                "additionalProperties": false
+            }),
+        },
         ToolDef {
             name: "get_event",
             description: "Get a specific event by its UID",
@@ -52,12 +102,85 @@ pub fn tool_defs() -> Vec<ToolDef> {
                     "end": {"type": "string", "description": "New local end time in iCal format"},
                     "timezone": {"type": "string", "description": "IANA timezone, e.g. America/Los_Angeles"},
                     "description": {"type": "string", "description": "New description"},
-                    "location": {"type": "string", "description": "New location"}
+                    "location": {"type": "string", "description": "New location"},
+                    "geo": {
+                        "type": "object",
+                        "description": "Coordinates of the location",
+                        "properties": {
+                            "lat": {"type": "number", "description": "Latitude, -90 to 90"},
+                            "lon": {"type": "number", "description": "Longitude, -180 to 180"}
+                        },
+                        "required": ["lat", "lon"],
+                        "additionalProperties": false
+                    },
+                    "url": {"type": "string", "description": "Link for the event, e.g. a video-call URL (https://...)"}
                 },
                 "required": ["calendar_id", "event_uid", "title", "start", "end"],
                 "additionalProperties": false
             }),
         },
+        ToolDef {
+            name: "patch_event",
+            description: "Change only the given fields of an existing event, keeping everything else (reminders, attendees, other properties) intact",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "event_uid": {"type": "string", "description": "The event UID to patch"},
+                    "title": {"type": "string", "description": "New event title"},
+                    "start": {"type": "string", "description": "New local start time in iCal format, or a date (20260301) for an all-day event"},
+                    "end": {"type": "string", "description": "New local end time in iCal format, or a date (20260302) for an all-day event"},
+                    "timezone": {"type": "string", "description": "IANA timezone for start/end, e.g. America/Los_Angeles"},
+                    "description": {"type": "string", "description": "New description (empty string removes it)"},
+                    "location": {"type": "string", "description": "New location (empty string removes it)"},
+                    "geo": {
+                        "type": ["object", "null"],
+                        "description": "Coordinates of the location (null removes them)",
+                        "properties": {
+                            "lat": {"type": "number", "description": "Latitude, -90 to 90"},
+                            "lon": {"type": "number", "description": "Longitude, -180 to 180"}
+                        },
+                        "required": ["lat", "lon"],
+                        "additionalProperties": false
+                    },
+                    "url": {"type": "string", "description": "New link for the event (empty string removes it)"}
+                },
+                "required": ["calendar_id", "event_uid"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "reschedule_event",
+            description: "Move an event to a new start time, keeping its duration unless a new end is given. All other properties are kept; for recurring events the whole series moves and UNTIL shifts with it",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "event_uid": {"type": "string", "description": "The event UID to move"},
+                    "start": {"type": "string", "description": "New local start time in iCal format, e.g. 20260302T150000"},
+                    "end": {"type": "string", "description": "New local end time (default: keep the original duration)"},
+                    "timezone": {"type": "string", "description": "IANA timezone for start/end (default: the event's current timezone)"}
+                },
+                "required": ["calendar_id", "event_uid", "start"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "convert_event_time_type",
+            description: "Rewrite an event's times as UTC, as local times anchored to a timezone (TZID), or as floating times. preserve=instant keeps the moment the event happens; preserve=wall_clock keeps the clock reading (e.g. 09:00 stays 09:00)",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "event_uid": {"type": "string", "description": "The event UID to convert"},
+                    "to": {"type": "string", "enum": ["utc", "local", "floating"], "description": "Target representation"},
+                    "timezone": {"type": "string", "description": "IANA timezone. Required for to=local; for to=floating with preserve=instant, the zone whose clock the floating times show (default: the event's own timezone, else UTC)"},
+                    "preserve": {"type": "string", "enum": ["instant", "wall_clock"], "description": "What to keep unchanged (default instant). Floating times have no instant, so their wall clock is always kept"}
+                },
+                "required": ["calendar_id", "event_uid", "to"],
+                "additionalProperties": false
+            }),
+        },
         ToolDef {
             name: "delete_event",
             description: "Delete a calendar event",
@@ -71,42 +194,251 @@ pub fn tool_defs() -> Vec<ToolDef> {
                 "additionalProperties": false
             }),
         },
+        ToolDef {
+            name: "restore_event",
+            description: "Restore an event deleted within the last 30 days",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "event_uid": {"type": "string", "description": "The UID of the deleted event"}
+                },
+                "required": ["calendar_id", "event_uid"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "move_event",
+            description: "Move an event to another calendar you own, keeping its UID",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "source_calendar_id": {"type": "string", "description": "The calendar the event is in"},
+                    "target_calendar_id": {"type": "string", "description": "The calendar to move it to"},
+                    "event_uid": {"type": "string", "description": "The event UID"}
+                },
+                "required": ["source_calendar_id", "target_calendar_id", "event_uid"],
+                "additionalProperties": false
+            }),
+        },
         ToolDef {
             name: "query_events",
-            description: "Query events in a calendar, optionally filtered by time range",
+            description: "Query events in a calendar, optionally filtered by time range. Results are paged: use offset and next_offset to fetch more",
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "calendar_id": {"type": "string", "description": "The calendar ID"},
                     "start": {"type": "string", "description": "Range start (iCal format, e.g. 20260301T000000Z)"},
                     "end": {"type": "string", "description": "Range end (iCal format)"},
+                    "limit": {"type": "integer", "description": "Max events to return (default 50)", "minimum": 1, "maximum": 500},
+                    "component_type": {"type": "string", "enum": ["VEVENT", "VTODO", "VJOURNAL"], "description": "Only return objects of this type, e.g. VTODO for tasks (default: all)"},
+                    "offset": {"type": "integer", "description": "Number of events to skip; pass the previous result's next_offset to get the next page (default 0)", "minimum": 0}
+                },
+                "required": ["calendar_id"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "search_events",
+            description: "Find events whose title, description or location contains a word or phrase, case-insensitively, with no time range needed. Searches one calendar, or every calendar you can access when calendar_id is omitted",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Text to look for, e.g. dentist"},
+                    "calendar_id": {"type": "string", "description": "Only search this calendar (default: all accessible calendars)"},
                     "limit": {"type": "integer", "description": "Max events to return (default 50)", "minimum": 1, "maximum": 500}
                 },
+                "required": ["query"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "get_freebusy",
+            description: "Busy time in a calendar between start and end, as merged {start, end} intervals in UTC. Use this to answer availability questions without listing every event",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "start": {"type": "string", "description": "Range start (iCal format or ISO 8601)"},
+                    "end": {"type": "string", "description": "Range end (iCal format or ISO 8601)"}
+                },
+                "required": ["calendar_id", "start", "end"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "list_etags",
+            description: "List the UID and ETag of every event in a calendar, ordered by UID. Compare against cached ETags and fetch only changed events with get_event",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"}
+                },
+                "required": ["calendar_id"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "query_events_grouped",
+            description: "Query events in a calendar grouped by day, for agenda views. Days are computed in the given timezone (default: the calendar's timezone)",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "start": {"type": "string", "description": "Range start (iCal format, e.g. 20260301T000000Z)"},
+                    "end": {"type": "string", "description": "Range end (iCal format)"},
+                    "timezone": {"type": "string", "description": "IANA timezone used to bucket events into days, e.g. America/Los_Angeles"},
+                    "limit": {"type": "integer", "description": "Max events to return across all days (default 50)", "minimum": 1, "maximum": 500}
+                },
+                "required": ["calendar_id"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "week_summary",
+            description: "Summarize the 7 days starting at week_start: events grouped by day with per-day counts and the busiest day. Days are computed in the given timezone (default: the calendar's timezone)",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "week_start": {"type": "string", "description": "First day of the week, e.g. 2026-03-09 or 20260309"},
+                    "timezone": {"type": "string", "description": "IANA timezone used to bucket events into days, e.g. America/Los_Angeles"}
+                },
+                "required": ["calendar_id", "week_start"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "get_occurrence",
+            description: "Get the details of a single occurrence of a recurring event on a given date",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "event_uid": {"type": "string", "description": "The recurring event's UID"},
+                    "occurrence_date": {"type": "string", "description": "Date of the occurrence, e.g. 2026-03-09 or 20260309"}
+                },
+                "required": ["calendar_id", "event_uid", "occurrence_date"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "clone_series",
+            description: "Copy a recurring event into a new event (new UID) with a different recurrence rule. Reminders, location and other properties are kept; overridden instances and exceptions are not",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "Calendar containing the source event"},
+                    "event_uid": {"type": "string", "description": "The recurring event's UID"},
+                    "rrule": {"type": "string", "description": "Recurrence rule for the copy, e.g. FREQ=DAILY;COUNT=10"},
+                    "target_calendar_id": {"type": "string", "description": "Calendar to create the copy in (default: the source calendar)"}
+                },
+                "required": ["calendar_id", "event_uid", "rrule"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "tag_events",
+            description: "Add or remove CATEGORIES on every event in a calendar whose title contains a search string and/or that overlaps a time range. Without confirm=true, only previews the matching events",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "query": {"type": "string", "description": "Case-insensitive substring to match against event titles"},
+                    "start": {"type": "string", "description": "Range start (iCal format, e.g. 20260301T000000Z)"},
+                    "end": {"type": "string", "description": "Range end (iCal format)"},
+                    "add": {"type": "array", "items": {"type": "string"}, "description": "Categories to add"},
+                    "remove": {"type": "array", "items": {"type": "string"}, "description": "Categories to remove"},
+                    "confirm": {"type": "boolean", "description": "Apply the changes (default false: preview only)"}
+                },
+                "required": ["calendar_id"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "find_duplicates",
+            description: "Find events in a calendar that share the same title, start and end (titles compared case- and whitespace-insensitively). With auto_delete=true, keeps the earliest-created event of each group and deletes the rest",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "auto_delete": {"type": "boolean", "description": "Delete all but the earliest-created event in each group (default false)"}
+                },
                 "required": ["calendar_id"],
                 "additionalProperties": false
             }),
         },
+        ToolDef {
+            name: "import_jcal",
+            description: "Import events, tasks or journals from a jCal (RFC 7265 JSON iCalendar) document. Components sharing a UID are stored together; failures are reported per item",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "calendar_id": {"type": "string", "description": "The calendar ID"},
+                    "jcal": {
+                        "type": ["array", "string"],
+                        "description": "A jCal vcalendar, e.g. [\"vcalendar\", [], [[\"vevent\", [[\"uid\", {}, \"text\", \"...\"], ...], []]]], an array of them, or the same as a JSON string"
+                    }
+                },
+                "required": ["calendar_id", "jcal"],
+                "additionalProperties": false
+            }),
+        },
     ]
 }
 
 /// Create a new calendar event in the specified calendar.
 pub async fn create_event(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+    config: &Config,
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
     let title = args["title"].as_str().ok_or("Missing title")?;
     let start = args["start"].as_str().ok_or("Missing start")?;
     let end = args["end"].as_str().ok_or("Missing end")?;
     let description = args["description"].as_str();
     let location = args["location"].as_str();
-    let timezone = args["timezone"].as_str();
+    let geo = geo_arg(&args["geo"])?;
+    let url = args["url"].as_str();
+    if let Some(url) = url {
+        validate_url(url)?;
+    }
+    let all_day = args["all_day"].as_bool().unwrap_or(false);
+    let timezone = args["timezone"].as_str().filter(|_| !all_day);
+    if let Some(tz) = timezone {
+        super::validate_timezone(config, tz)?;
+    }
+    let (start, end) = if all_day {
+        all_day_dates(start, end)?
+    } else {
+        (start.to_string(), end.to_string())
+    };
+    let (start, end) = (start.as_str(), end.as_str());
+    for value in [start, end] {
+        config.check_event_year(value)?;
+    }
+    let rrule = recurrence_arg(&args["recurrence"], all_day, timezone)?;
+
+    super::verify_component_allowed(pool, calendar_id, "VEVENT").await?;
 
     let uid = builder::generate_uid();
-    let ical_data = builder::build_vevent(&uid, title, start, end, description, location, timezone);
+    let ical_data = builder::build_vevent(
+        &uid,
+        title,
+        start,
+        end,
+        description,
+        location,
+        geo,
+        url,
+        rrule.as_deref(),
+        timezone,
+    );
 
-    let (obj, _) = event_db::upsert_object(
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
         pool,
         calendar_id,
         &uid,
@@ -117,9 +449,10 @@ pub async fn create_event(
             dtend: Some(end),
             summary: Some(title),
         },
+        config.etag_mode,
     )
     .await
-    .map_err(|e| format!("Failed to create event: {e}"))?;
+    .map_err(|e| ToolError::storage("Failed to create event", e))?;
 
     Ok(json!({
         "uid": obj.uid,
@@ -127,19 +460,225 @@ pub async fn create_event(
         "title": title,
         "start": start,
         "end": end,
+        "rrule": rrule,
+        "etag": obj.etag,
+    }))
+}
+
+/// `STATUS` values RFC 5545 allows on a VTODO.
+const TODO_STATUSES: &[&str] = &["NEEDS-ACTION", "IN-PROCESS", "COMPLETED", "CANCELLED"];
+
+/// Create a new task (VTODO) in the specified calendar. The due date is
+/// indexed in the `dtend` column, which is where time-range queries on
+/// VTODOs look for it.
+pub async fn create_todo(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+    let title = args["title"].as_str().ok_or("Missing title")?;
+    let due = args["due"].as_str();
+    if let Some(due) = due {
+        IcalTime::parse(due).ok_or_else(|| format!("Invalid due: {due}"))?;
+        config.check_event_year(due)?;
+    }
+    let status = args["status"].as_str();
+    if let Some(status) = status
+        && !TODO_STATUSES.contains(&status)
+    {
+        return Err(ToolError::validation(format!(
+            "Invalid status: {status}. Expected one of {}",
+            TODO_STATUSES.join(", ")
+        )));
+    }
+    let percent_complete = match args["percent_complete"].as_u64() {
+        Some(p) if p > 100 => {
+            return Err(ToolError::validation(
+                "percent_complete must be between 0 and 100",
+            ));
+        }
+        p => p.map(|p| p as u8),
+    };
+
+    super::verify_component_allowed(pool, calendar_id, "VTODO").await?;
+
+    let uid = builder::generate_uid();
+    let ical_data = builder::build_vtodo(&uid, title, due, status, percent_complete);
+
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
+        pool,
+        calendar_id,
+        &uid,
+        &ical_data,
+        event_db::ObjectFields {
+            component_type: "VTODO",
+            dtstart: None,
+            dtend: due,
+            summary: Some(title),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to create todo", e))?;
+
+    Ok(json!({
+        "uid": obj.uid,
+        "calendar_id": calendar_id,
+        "title": title,
+        "due": due,
+        "status": status,
+        "percent_complete": percent_complete,
         "etag": obj.etag,
     }))
 }
 
+/// Frequencies accepted in a `recurrence` argument.
+const RECURRENCE_FREQS: &[&str] = &["DAILY", "WEEKLY", "MONTHLY", "YEARLY"];
+
+/// Read a `recurrence` argument, either a raw RRULE (optionally prefixed
+/// with `RRULE:`) or `{freq, interval, count, until, byday}`, as an RRULE
+/// value. `until` is read in `timezone` and written as a UTC date-time, or
+/// as a date for all-day events, as RFC 5545 requires.
+fn recurrence_arg(
+    value: &Value,
+    all_day: bool,
+    timezone: Option<&str>,
+) -> Result<Option<String>, ToolError> {
+    let rule = match value {
+        Value::Null => return Ok(None),
+        Value::String(raw) => {
+            let raw = raw.trim();
+            let raw = raw.strip_prefix("RRULE:").unwrap_or(raw).to_string();
+            let freq = raw
+                .split(';')
+                .filter_map(|part| part.split_once('='))
+                .find(|(key, _)| key.eq_ignore_ascii_case("FREQ"))
+                .map(|(_, freq)| freq)
+                .ok_or("recurrence is missing FREQ")?;
+            check_freq(freq)?;
+            raw
+        }
+        Value::Object(fields) => {
+            let freq = fields
+                .get("freq")
+                .and_then(Value::as_str)
+                .ok_or("recurrence is missing freq")?;
+            check_freq(freq)?;
+            let mut parts = vec![format!("FREQ={}", freq.to_ascii_uppercase())];
+            if let Some(interval) = fields.get("interval") {
+                let interval = interval
+                    .as_u64()
+                    .filter(|n| *n >= 1)
+                    .ok_or("recurrence interval must be a positive integer")?;
+                parts.push(format!("INTERVAL={interval}"));
+            }
+            let count = fields.get("count");
+            let until = fields.get("until");
+            if count.is_some() && until.is_some() {
+                return Err(ToolError::validation(
+                    "recurrence can have count or until, not both",
+                ));
+            }
+            if let Some(count) = count {
+                let count = count
+                    .as_u64()
+                    .filter(|n| *n >= 1)
+                    .ok_or("recurrence count must be a positive integer")?;
+                parts.push(format!("COUNT={count}"));
+            }
+            if let Some(until) = until {
+                let until = until.as_str().ok_or("recurrence until must be a string")?;
+                let formatted = if all_day {
+                    datetime::parse_date(until).map(|d| d.format("%Y%m%d").to_string())
+                } else {
+                    datetime::parse_ical_datetime(until, timezone)
+                        .map(|t| t.format("%Y%m%dT%H%M%SZ").to_string())
+                };
+                let formatted =
+                    formatted.ok_or_else(|| format!("Invalid recurrence until: {until}"))?;
+                parts.push(format!("UNTIL={formatted}"));
+            }
+            if let Some(byday) = fields.get("byday") {
+                let days: Vec<String> = byday
+                    .as_array()
+                    .ok_or("recurrence byday must be an array of weekdays")?
+                    .iter()
+                    .map(|d| d.as_str().map(str::to_ascii_uppercase))
+                    .collect::<Option<_>>()
+                    .ok_or("recurrence byday must be an array of weekdays")?;
+                if !days.is_empty() {
+                    parts.push(format!("BYDAY={}", days.join(",")));
+                }
+            }
+            parts.join(";")
+        }
+        _ => {
+            return Err(ToolError::validation(
+                "recurrence must be an RRULE string or an object",
+            ));
+        }
+    };
+
+    recurrence::RecurrenceRule::parse(&rule)
+        .ok_or_else(|| format!("Invalid recurrence rule: {rule}"))?;
+    Ok(Some(rule))
+}
+
+/// Reject a recurrence frequency other than those in [`RECURRENCE_FREQS`].
+fn check_freq(freq: &str) -> Result<(), String> {
+    if RECURRENCE_FREQS.contains(&freq.to_ascii_uppercase().as_str()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid recurrence freq '{freq}': expected DAILY, WEEKLY, MONTHLY or YEARLY"
+        ))
+    }
+}
+
+/// The `VALUE=DATE` start and end of an all-day event. Date-times are cut to
+/// their date, and since the end date is exclusive, an end on or before the
+/// start becomes the day after it.
+fn all_day_dates(start: &str, end: &str) -> Result<(String, String), ToolError> {
+    let first = datetime::parse_date(start).ok_or_else(|| format!("Invalid start: {start}"))?;
+    let last = datetime::parse_date(end).ok_or_else(|| format!("Invalid end: {end}"))?;
+    let last = if last <= first {
+        first + Duration::days(1)
+    } else {
+        last
+    };
+    Ok((
+        first.format("%Y%m%d").to_string(),
+        last.format("%Y%m%d").to_string(),
+    ))
+}
+
 /// Get a specific calendar event by its UID.
-pub async fn get_event(pool: &SqlitePool, _user_id: &str, args: &Value) -> Result<Value, String> {
+pub async fn get_event(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
     let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
 
     let obj = event_db::get_object_by_uid(pool, calendar_id, event_uid)
         .await
-        .map_err(|e| format!("Database error: {e}"))?
-        .ok_or("Event not found")?;
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+
+    let master = parser::parse_calendar(&obj.ical_data).and_then(|cal| {
+        cal.components
+            .into_iter()
+            .find(|c| c.name == obj.component_type && c.property("RECURRENCE-ID").is_none())
+    });
+    let geo = master
+        .as_ref()
+        .and_then(|event| event.property_value("GEO").and_then(parser::parse_geo))
+        .map(|(lat, lon)| json!({"lat": lat, "lon": lon}));
+    let url = master
+        .as_ref()
+        .and_then(|event| event.property_value("URL"))
+        .map(str::to_string);
 
     Ok(json!({
         "uid": obj.uid,
@@ -147,6 +686,8 @@ pub async fn get_event(pool: &SqlitePool, _user_id: &str, args: &Value) -> Resul
         "summary": obj.summary,
         "dtstart": obj.dtstart,
         "dtend": obj.dtend,
+        "geo": geo,
+        "url": url,
         "etag": obj.etag,
         "ical_data": obj.ical_data,
     }))
@@ -155,23 +696,36 @@ pub async fn get_event(pool: &SqlitePool, _user_id: &str, args: &Value) -> Resul
 /// Update an existing calendar event, replacing all fields.
 pub async fn update_event(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+    config: &Config,
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
     let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
     let title = args["title"].as_str().ok_or("Missing title")?;
     let start = args["start"].as_str().ok_or("Missing start")?;
     let end = args["end"].as_str().ok_or("Missing end")?;
     let description = args["description"].as_str();
     let location = args["location"].as_str();
+    let geo = geo_arg(&args["geo"])?;
+    let url = args["url"].as_str();
+    if let Some(url) = url {
+        validate_url(url)?;
+    }
     let timezone = args["timezone"].as_str();
+    if let Some(tz) = timezone {
+        super::validate_timezone(config, tz)?;
+    }
+    for value in [start, end] {
+        config.check_event_year(value)?;
+    }
 
     // Verify the event exists
     event_db::get_object_by_uid(pool, calendar_id, event_uid)
         .await
-        .map_err(|e| format!("Database error: {e}"))?
-        .ok_or("Event not found")?;
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
 
     let ical_data = builder::build_vevent(
         event_uid,
@@ -180,10 +734,13 @@ pub async fn update_event(
         end,
         description,
         location,
+        geo,
+        url,
+        None,
         timezone,
     );
 
-    let (obj, _) = event_db::upsert_object(
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
         pool,
         calendar_id,
         event_uid,
@@ -194,9 +751,10 @@ pub async fn update_event(
             dtend: Some(end),
             summary: Some(title),
         },
+        config.etag_mode,
     )
     .await
-    .map_err(|e| format!("Failed to update event: {e}"))?;
+    .map_err(|e| ToolError::storage("Failed to update event", e))?;
 
     Ok(json!({
         "uid": obj.uid,
@@ -207,48 +765,715 @@ pub async fn update_event(
     }))
 }
 
-/// Delete a calendar event by UID.
-pub async fn delete_event(
+/// Apply a partial update to an existing event. Only the supplied fields are
+/// changed; all other properties and sub-components are preserved as stored.
+pub async fn patch_event(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+    config: &Config,
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
     let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    let title = args["title"].as_str();
+    let start = args["start"].as_str();
+    let end = args["end"].as_str();
+    let timezone = args["timezone"].as_str();
+    let description = args["description"].as_str();
+    let location = args["location"].as_str();
+    let url = args["url"].as_str();
+    if let Some(url) = url
+        && !url.is_empty()
+    {
+        validate_url(url)?;
+    }
+    // `geo: null` removes the coordinates; an absent key leaves them alone
+    let geo = match args.get("geo") {
+        Some(value) => Some(geo_arg(value)?),
+        None => None,
+    };
 
-    event_db::delete_object(pool, calendar_id, event_uid)
+    if [title, start, end, timezone, description, location, url]
+        .iter()
+        .all(Option::is_none)
+        && geo.is_none()
+    {
+        return Err(ToolError::validation("No fields to update"));
+    }
+    if timezone.is_some() && start.is_none() && end.is_none() {
+        return Err(ToolError::validation("timezone requires start and/or end"));
+    }
+    if let Some(tz) = timezone {
+        super::validate_timezone(config, tz)?;
+    }
+    for value in [start, end].into_iter().flatten() {
+        config.check_event_year(value)?;
+    }
+
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+
+    let obj = event_db::get_object_by_uid(pool, calendar_id, event_uid)
         .await
-        .map_err(|e| format!("Failed to delete event: {e}"))?;
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+    let mut calendar =
+        parser::parse_calendar(&obj.ical_data).ok_or("Event data is not valid iCalendar")?;
 
-    Ok(json!({"deleted": true, "event_uid": event_uid}))
+    let event = calendar
+        .components
+        .iter_mut()
+        .find(|c| c.name == "VEVENT" && c.property("RECURRENCE-ID").is_none())
+        .ok_or("Event has no VEVENT to patch")?;
+
+    let mut patched = Vec::new();
+    if let Some(title) = title {
+        event.set_property(parser::Property::new(
+            "SUMMARY",
+            &builder::escape_text(title),
+        ));
+        patched.push("title");
+    }
+    let mut zoned = false;
+    for (name, field, value) in [("DTSTART", "start", start), ("DTEND", "end", end)] {
+        let Some(value) = value else { continue };
+        let time = IcalTime::parse(value).ok_or_else(|| format!("Invalid {field}: {value}"))?;
+        let mut prop = parser::Property::new(name, &time.format());
+        if time.date_only {
+            // All-day values are DATEs and never carry a TZID
+            prop.params.push(("VALUE".to_string(), "DATE".to_string()));
+        } else if !time.utc {
+            // Keep the existing TZID unless the caller supplied a new zone
+            let tzid = timezone.or_else(|| event.property(name).and_then(|p| p.param("TZID")));
+            if let Some(tz) = tzid {
+                prop.params.push(("TZID".to_string(), tz.to_string()));
+                zoned = true;
+            }
+        }
+        event.set_property(prop);
+        patched.push(field);
+    }
+    let is_date = |name| {
+        event
+            .property_value(name)
+            .and_then(IcalTime::parse)
+            .map(|t| t.date_only)
+    };
+    if let (Some(start), Some(end)) = (is_date("DTSTART"), is_date("DTEND"))
+        && start != end
+    {
+        return Err(ToolError::validation(
+            "start and end must both be dates or both date-times",
+        ));
+    }
+    for (name, field, value) in [
+        ("DESCRIPTION", "description", description),
+        ("LOCATION", "location", location),
+        ("URL", "url", url),
+    ] {
+        match value {
+            Some("") => event.remove_property(name),
+            Some(value) if name == "URL" => event.set_property(parser::Property::new(name, value)),
+            Some(value) => {
+                event.set_property(parser::Property::new(name, &builder::escape_text(value)))
+            }
+            None => continue,
+        }
+        patched.push(field);
+    }
+    if let Some(geo) = geo {
+        match geo {
+            Some((lat, lon)) => {
+                event.set_property(parser::Property::new("GEO", &format!("{lat};{lon}")))
+            }
+            None => event.remove_property("GEO"),
+        }
+        patched.push("geo");
+    }
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    event.set_property(parser::Property::new("DTSTAMP", &now));
+
+    if let Some(tz) = timezone.filter(|_| zoned) {
+        ensure_vtimezone(&mut calendar, tz);
+    }
+
+    let ical_data = calendar.to_ical();
+    let fields = parser::extract_fields(&ical_data);
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
+        pool,
+        calendar_id,
+        event_uid,
+        &ical_data,
+        event_db::ObjectFields {
+            component_type: &fields.component_type,
+            dtstart: fields.dtstart.as_deref(),
+            dtend: fields.dtend.as_deref(),
+            summary: fields.summary.as_deref(),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to patch event", e))?;
+
+    Ok(json!({
+        "uid": obj.uid,
+        "calendar_id": calendar_id,
+        "summary": obj.summary,
+        "dtstart": obj.dtstart,
+        "dtend": obj.dtend,
+        "etag": obj.etag,
+        "patched": patched,
+    }))
 }
 
-/// Query events in a calendar, with an optional time-range filter.
-pub async fn query_events(
+/// Add a VTIMEZONE for `tz` ahead of the other components unless the
+/// calendar already has one.
+fn ensure_vtimezone(calendar: &mut parser::Component, tz: &str) {
+    if calendar
+        .components
+        .iter()
+        .any(|c| c.name == "VTIMEZONE" && c.property_value("TZID") == Some(tz))
+    {
+        return;
+    }
+    let vtimezone = builder::build_vtimezone(tz).join("\r\n");
+    let position = calendar
+        .components
+        .iter()
+        .position(|c| c.name != "VTIMEZONE")
+        .unwrap_or(0);
+    for (offset, component) in parser::parse_components(&vtimezone).into_iter().enumerate() {
+        calendar.components.insert(position + offset, component);
+    }
+}
+
+/// Move an event to a new start, keeping its length unless `end` is given.
+///
+/// The master VEVENT is edited in place, so every other property survives.
+/// For a recurring master the whole series moves: an UNTIL in the RRULE is
+/// shifted by the same amount so the series keeps its number of instances.
+pub async fn reschedule_event(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+    config: &Config,
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
-    let start = args["start"].as_str();
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    let start = args["start"].as_str().ok_or("Missing start")?;
     let end = args["end"].as_str();
-    let limit = args["limit"].as_u64().unwrap_or(50) as usize;
+    let timezone = args["timezone"].as_str();
+    if let Some(tz) = timezone {
+        super::validate_timezone(config, tz)?;
+    }
+    for value in [Some(start), end].into_iter().flatten() {
+        config.check_event_year(value)?;
+    }
+    let new_start = IcalTime::parse(start).ok_or_else(|| format!("Invalid start: {start}"))?;
+    let new_end = end
+        .map(|end| IcalTime::parse(end).ok_or_else(|| format!("Invalid end: {end}")))
+        .transpose()?;
 
-    let objects = match (start, end) {
-        (Some(s), Some(e)) => event_db::list_objects_in_range(pool, calendar_id, s, e)
-            .await
-            .map_err(|e| format!("Database error: {e}"))?,
-        _ => event_db::list_objects(pool, calendar_id)
-            .await
-            .map_err(|e| format!("Database error: {e}"))?,
-    };
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
 
-    let events: Vec<Value> = objects
-        .iter()
-        .take(limit)
+    let obj = event_db::get_object_by_uid(pool, calendar_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+    let mut calendar =
+        parser::parse_calendar(&obj.ical_data).ok_or("Event data is not valid iCalendar")?;
+    let event = calendar
+        .components
+        .iter_mut()
+        .find(|c| c.name == "VEVENT" && c.property("RECURRENCE-ID").is_none())
+        .ok_or("Event has no VEVENT to reschedule")?;
+
+    let old_start_prop = event.property("DTSTART").ok_or("Event has no start time")?;
+    let old_start =
+        IcalTime::parse(&old_start_prop.value).ok_or("Event has an invalid start time")?;
+    let old_tzid = old_start_prop.param("TZID").map(str::to_string);
+    let duration = recurrence::event_duration(event, old_start, old_tzid.as_deref());
+
+    // UTC and all-day values carry no TZID
+    let tzid = timezone
+        .map(str::to_string)
+        .or(old_tzid.clone())
+        .filter(|_| !new_start.utc && !new_start.date_only);
+    let shift = new_start.to_utc(tzid.as_deref()) - old_start.to_utc(old_tzid.as_deref());
+
+    let with_tzid = |name: &str, time: IcalTime| {
+        let mut prop = parser::Property::new(name, &time.format());
+        if let Some(tz) = &tzid
+            && !time.utc
+            && !time.date_only
+        {
+            prop.params.push(("TZID".to_string(), tz.clone()));
+        }
+        prop
+    };
+    event.set_property(with_tzid("DTSTART", new_start));
+
+    // Keep the original length; an explicit end always becomes a DTEND
+    let end_time = match new_end {
+        Some(end) => Some(end),
+        None if event.property("DTEND").is_some() => {
+            Some(shifted_by(new_start, tzid.as_deref(), duration))
+        }
+        // A DURATION (or the implicit length) already moves with DTSTART
+        None => None,
+    };
+    if let Some(end_time) = end_time {
+        if end_time.to_utc(tzid.as_deref()) < new_start.to_utc(tzid.as_deref()) {
+            return Err(ToolError::validation("end must not be before start"));
+        }
+        event.remove_property("DURATION");
+        event.set_property(with_tzid("DTEND", end_time));
+    }
+
+    let mut rrule = None;
+    if let Some(rule) = event.property_value("RRULE") {
+        let shifted = shift_until(rule, shift);
+        event.set_property(parser::Property::new("RRULE", &shifted));
+        rrule = Some(shifted);
+    }
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    event.set_property(parser::Property::new("DTSTAMP", &now));
+
+    if let Some(tz) = &tzid {
+        ensure_vtimezone(&mut calendar, tz);
+    }
+
+    let ical_data = calendar.to_ical();
+    let fields = parser::extract_fields(&ical_data);
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
+        pool,
+        calendar_id,
+        event_uid,
+        &ical_data,
+        event_db::ObjectFields {
+            component_type: &fields.component_type,
+            dtstart: fields.dtstart.as_deref(),
+            dtend: fields.dtend.as_deref(),
+            summary: fields.summary.as_deref(),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to reschedule event", e))?;
+
+    Ok(json!({
+        "uid": obj.uid,
+        "calendar_id": calendar_id,
+        "summary": obj.summary,
+        "dtstart": obj.dtstart,
+        "dtend": obj.dtend,
+        "timezone": tzid,
+        "rrule": rrule,
+        "etag": obj.etag,
+    }))
+}
+
+/// `start` moved forward by `duration`, expressed in the same form
+/// (all-day, UTC, or local time in `tzid`).
+fn shifted_by(start: IcalTime, tzid: Option<&str>, duration: Duration) -> IcalTime {
+    let tz = tzid.and_then(|id| id.parse::<Tz>().ok());
+    match tz {
+        Some(tz) if !start.utc && !start.date_only => {
+            let end = (start.to_utc(tzid) + duration).with_timezone(&tz);
+            start.with_value(end.naive_local())
+        }
+        _ => start.with_value(start.value + duration),
+    }
+}
+
+/// Shift the UNTIL part of an RRULE value by `shift`, keeping its form.
+fn shift_until(rule: &str, shift: Duration) -> String {
+    rule.split(';')
+        .map(|part| match part.split_once('=') {
+            Some((key, value)) if key.eq_ignore_ascii_case("UNTIL") => {
+                match IcalTime::parse(value) {
+                    Some(until) if until.date_only => {
+                        let days = Duration::days(shift.num_days());
+                        format!("{key}={}", until.with_value(until.value + days).format())
+                    }
+                    Some(until) => {
+                        format!("{key}={}", until.with_value(until.value + shift).format())
+                    }
+                    None => part.to_string(),
+                }
+            }
+            _ => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// The representation [`convert_event_time_type`] rewrites times into.
+enum TimeType<'a> {
+    Utc,
+    Local(&'a str),
+    Floating,
+}
+
+/// Rewrite every DATE-TIME of an event (DTSTART, DTEND, RECURRENCE-ID,
+/// EXDATE, RDATE and the RRULE's UNTIL, in the master and its overrides) as
+/// UTC, TZID-anchored local or floating time. All-day dates are left alone.
+pub async fn convert_event_time_type(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    let timezone = args["timezone"].as_str();
+    if let Some(tz) = timezone {
+        super::validate_timezone(config, tz)?;
+    }
+    let target = match args["to"].as_str().ok_or("Missing to")? {
+        "utc" => TimeType::Utc,
+        "local" => TimeType::Local(timezone.ok_or("to=local requires timezone")?),
+        "floating" => TimeType::Floating,
+        other => {
+            return Err(ToolError::validation(format!(
+                "to must be utc, local or floating, got '{other}'"
+            )));
+        }
+    };
+    let keep_instant = match args["preserve"].as_str().unwrap_or("instant") {
+        "instant" => true,
+        "wall_clock" => false,
+        other => {
+            return Err(ToolError::validation(format!(
+                "preserve must be instant or wall_clock, got '{other}'"
+            )));
+        }
+    };
+    // Where a floating result reads its clock from when keeping the instant
+    let floating_zone = timezone.and_then(|tz| tz.parse::<chrono_tz::Tz>().ok());
+
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+
+    let obj = event_db::get_object_by_uid(pool, calendar_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+    let mut calendar =
+        parser::parse_calendar(&obj.ical_data).ok_or("Event data is not valid iCalendar")?;
+
+    let mut converted = 0;
+    for event in calendar
+        .components
+        .iter_mut()
+        .filter(|c| c.name == "VEVENT")
+    {
+        converted += convert_component_times(event, &target, keep_instant, floating_zone);
+    }
+    if converted == 0 {
+        return Err(ToolError::validation(
+            "Event has no date-time values to convert",
+        ));
+    }
+
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    for event in calendar
+        .components
+        .iter_mut()
+        .filter(|c| c.name == "VEVENT")
+    {
+        event.set_property(parser::Property::new("DTSTAMP", &now));
+    }
+    if let TimeType::Local(tz) = target {
+        ensure_vtimezone(&mut calendar, tz);
+    }
+
+    let ical_data = calendar.to_ical();
+    let fields = parser::extract_fields(&ical_data);
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
+        pool,
+        calendar_id,
+        event_uid,
+        &ical_data,
+        event_db::ObjectFields {
+            component_type: &fields.component_type,
+            dtstart: fields.dtstart.as_deref(),
+            dtend: fields.dtend.as_deref(),
+            summary: fields.summary.as_deref(),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to convert event", e))?;
+
+    let anchored_to = match target {
+        TimeType::Local(tz) => Some(tz),
+        _ => None,
+    };
+
+    Ok(json!({
+        "uid": obj.uid,
+        "calendar_id": calendar_id,
+        "dtstart": obj.dtstart,
+        "dtend": obj.dtend,
+        "timezone": anchored_to,
+        "converted": converted,
+        "etag": obj.etag,
+    }))
+}
+
+/// Rewrite the DATE-TIMEs of one VEVENT for `target`; see
+/// [`convert_event_time_type`]. Returns the number of properties rewritten.
+fn convert_component_times(
+    event: &mut parser::Component,
+    target: &TimeType,
+    keep_instant: bool,
+    floating_zone: Option<chrono_tz::Tz>,
+) -> usize {
+    let mut converted = 0;
+    let start_tzid = event
+        .property("DTSTART")
+        .and_then(|p| p.param("TZID"))
+        .map(str::to_string);
+    for prop in event.properties.iter_mut() {
+        if !matches!(
+            prop.name.as_str(),
+            "DTSTART" | "DTEND" | "RECURRENCE-ID" | "EXDATE" | "RDATE"
+        ) || prop
+            .param("VALUE")
+            .is_some_and(|v| !v.eq_ignore_ascii_case("DATE-TIME"))
+        {
+            continue;
+        }
+        let tzid = prop.param("TZID").map(str::to_string);
+        let Some(times) = prop
+            .value
+            .split(',')
+            .map(IcalTime::parse)
+            .collect::<Option<Vec<_>>>()
+            .filter(|times| times.iter().all(|t| !t.date_only))
+        else {
+            continue;
+        };
+        prop.value = times
+            .into_iter()
+            .map(|time| {
+                let zone = floating_zone.or_else(|| tzid.as_deref()?.parse().ok());
+                convert_time(time, tzid.as_deref(), target, keep_instant, zone).format()
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        prop.params
+            .retain(|(key, _)| !key.eq_ignore_ascii_case("TZID"));
+        if let TimeType::Local(tz) = target {
+            prop.params.push(("TZID".to_string(), tz.to_string()));
+        }
+        converted += 1;
+    }
+
+    // UNTIL is UTC unless DTSTART is floating (RFC 5545 §3.3.10)
+    if let Some(rule) = event.property_value("RRULE") {
+        let zone = floating_zone.or_else(|| start_tzid.as_deref()?.parse().ok());
+        let rule = convert_until(rule, start_tzid.as_deref(), target, keep_instant, zone);
+        event.set_property(parser::Property::new("RRULE", &rule));
+    }
+    converted
+}
+
+/// Give every floating VEVENT in `calendar` the TZID `tz`, keeping its wall
+/// clock. Events with a TZID, UTC times or all-day dates are left alone.
+/// Returns whether anything changed.
+pub(super) fn anchor_floating_events(calendar: &mut parser::Component, tz: &str) -> bool {
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let target = TimeType::Local(tz);
+    let mut changed = false;
+    for event in calendar
+        .components
+        .iter_mut()
+        .filter(|c| c.name == "VEVENT")
+    {
+        let floating = event.property("DTSTART").is_some_and(|p| {
+            p.param("TZID").is_none()
+                && IcalTime::parse(&p.value).is_some_and(|t| !t.utc && !t.date_only)
+        });
+        if floating && convert_component_times(event, &target, false, None) > 0 {
+            event.set_property(parser::Property::new("DTSTAMP", &now));
+            changed = true;
+        }
+    }
+    if changed {
+        ensure_vtimezone(calendar, tz);
+    }
+    changed
+}
+
+/// Rewrite one DATE-TIME for `target`. With `keep_instant` the moment in
+/// time is kept; floating values have none, so their wall clock is kept
+/// regardless. A floating result shows the clock of `floating_zone` (UTC if
+/// unset).
+fn convert_time(
+    time: IcalTime,
+    tzid: Option<&str>,
+    target: &TimeType,
+    keep_instant: bool,
+    floating_zone: Option<chrono_tz::Tz>,
+) -> IcalTime {
+    let has_instant = time.utc || tzid.is_some_and(|id| id.parse::<chrono_tz::Tz>().is_ok());
+    let value = if keep_instant && has_instant {
+        let zone = match target {
+            TimeType::Utc => chrono_tz::UTC,
+            TimeType::Local(tz) => tz.parse().unwrap_or(chrono_tz::UTC),
+            TimeType::Floating => floating_zone.unwrap_or(chrono_tz::UTC),
+        };
+        time.in_timezone(tzid, zone)
+    } else {
+        time.value
+    };
+    IcalTime {
+        value,
+        date_only: false,
+        utc: matches!(target, TimeType::Utc),
+    }
+}
+
+/// Convert the UNTIL part of an RRULE alongside its event. `start_tzid` is
+/// the original DTSTART's TZID, which a non-UTC UNTIL is read in. The result
+/// is floating for floating events and UTC otherwise.
+fn convert_until(
+    rule: &str,
+    start_tzid: Option<&str>,
+    target: &TimeType,
+    keep_instant: bool,
+    floating_zone: Option<chrono_tz::Tz>,
+) -> String {
+    rule.split(';')
+        .map(|part| match part.split_once('=') {
+            Some((key, value)) if key.eq_ignore_ascii_case("UNTIL") => {
+                match IcalTime::parse(value) {
+                    Some(until) if !until.date_only => {
+                        let tzid = start_tzid.filter(|_| !until.utc);
+                        let time = convert_time(until, tzid, target, keep_instant, floating_zone);
+                        let time = match target {
+                            TimeType::Local(tz) => IcalTime {
+                                value: time.to_utc(Some(tz)).naive_utc(),
+                                date_only: false,
+                                utc: true,
+                            },
+                            _ => time,
+                        };
+                        format!("{key}={}", time.format())
+                    }
+                    _ => part.to_string(),
+                }
+            }
+            _ => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Delete a calendar event by UID.
+pub async fn delete_event(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+
+    event_db::delete_object(pool, calendar_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Failed to delete event", e))?;
+
+    Ok(json!({"deleted": true, "event_uid": event_uid}))
+}
+
+/// Undo a recent delete by restoring the event from its tombstone.
+pub async fn restore_event(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+
+    let obj = event_db::restore_object(pool, calendar_id, event_uid)
+        .await
+        .map_err(|e| match e {
+            AppError::NotFound(_) => ToolError::not_found(format!(
+                "No event {event_uid} was deleted from this calendar in the last {} days",
+                event_db::TOMBSTONE_RETENTION_DAYS
+            )),
+            e => ToolError::storage("Failed to restore event", e),
+        })?;
+
+    Ok(json!({
+        "restored": true,
+        "event_uid": obj.uid,
+        "etag": obj.etag,
+        "summary": obj.summary,
+    }))
+}
+
+/// Query events in a calendar, with optional time-range and component-type
+/// filters. A time range on VTODOs matches on DUE as well as DTSTART.
+pub async fn query_events(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+    let start = args["start"].as_str();
+    let end = args["end"].as_str();
+    let limit = args["limit"].as_u64().unwrap_or(50).clamp(1, 500) as usize;
+    let offset = args["offset"].as_u64().unwrap_or(0) as usize;
+    let component_type = args["component_type"].as_str();
+    if let Some(component) = component_type
+        && !cal_db::COMPONENT_TYPES.contains(&component)
+    {
+        return Err(ToolError::validation(format!(
+            "Unsupported component type: {component}"
+        )));
+    }
+
+    let (objects, total) = match (start, end) {
+        // Range matching compares instants in Rust, so the page is cut here.
+        (Some(s), Some(e)) => {
+            let in_range = match component_type {
+                Some("VTODO") => event_db::list_todos_in_range(pool, calendar_id, s, e).await,
+                _ => event_db::list_objects_in_range(pool, calendar_id, s, e).await,
+            }
+            .map_err(|e| ToolError::storage("Database error", e))?;
+            let in_range: Vec<_> = in_range
+                .into_iter()
+                .filter(|obj| component_type.is_none_or(|c| obj.component_type == c))
+                .collect();
+            let total = in_range.len();
+            let page = in_range.into_iter().skip(offset).take(limit).collect();
+            (page, total)
+        }
+        _ => {
+            let (page, total) = event_db::list_objects_paged(
+                pool,
+                calendar_id,
+                component_type,
+                limit as i64,
+                offset as i64,
+            )
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?;
+            (page, total as usize)
+        }
+    };
+    let next_offset = (offset + objects.len() < total).then_some(offset + objects.len());
+
+    let events: Vec<Value> = objects
+        .iter()
         .map(|obj| {
             json!({
                 "uid": obj.uid,
+                "component_type": obj.component_type,
                 "summary": obj.summary,
                 "dtstart": obj.dtstart,
                 "dtend": obj.dtend,
@@ -260,6 +1485,1111 @@ pub async fn query_events(
     Ok(json!({
         "calendar_id": calendar_id,
         "count": events.len(),
+        "total": total,
+        "next_offset": next_offset,
+        "events": events,
+    }))
+}
+
+/// Find events whose summary, DESCRIPTION or LOCATION contains `query`,
+/// ignoring case, across one calendar or all the user can access.
+pub async fn search_events(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let query = args["query"]
+        .as_str()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .ok_or("Missing query")?;
+    let limit = args["limit"].as_u64().unwrap_or(50).clamp(1, 500) as usize;
+
+    let calendar_ids = match args["calendar_id"].as_str() {
+        Some(calendar_id) => {
+            super::verify_calendar_access(pool, user_id, calendar_id).await?;
+            vec![calendar_id.to_string()]
+        }
+        None => cal_db::list_calendars_for_user(pool, user_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?
+            .into_iter()
+            .map(|c| c.id)
+            .collect(),
+    };
+
+    let objects = event_db::search_objects(pool, &calendar_ids, query)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    // The database matches raw iCalendar text; confirm against the
+    // unescaped properties so a UID or attendee address is not a hit
+    let needle = query.to_lowercase();
+    let events: Vec<Value> = objects
+        .iter()
+        .filter(|obj| obj.component_type == "VEVENT")
+        .filter_map(|obj| {
+            let master = parser::parse_calendar(&obj.ical_data).and_then(|cal| {
+                cal.components
+                    .into_iter()
+                    .find(|c| c.name == "VEVENT" && c.property("RECURRENCE-ID").is_none())
+            });
+            let text = |name: &str| {
+                master
+                    .as_ref()
+                    .and_then(|m| m.property_value(name))
+                    .map(parser::unescape_text)
+            };
+            let summary = obj.summary.clone().or_else(|| text("SUMMARY"));
+            let description = text("DESCRIPTION");
+            let location = text("LOCATION");
+            let matches = [&summary, &description, &location].iter().any(|field| {
+                field
+                    .as_deref()
+                    .is_some_and(|v| v.to_lowercase().contains(&needle))
+            });
+            matches.then(|| {
+                json!({
+                    "calendar_id": obj.calendar_id,
+                    "uid": obj.uid,
+                    "summary": summary,
+                    "dtstart": obj.dtstart,
+                    "dtend": obj.dtend,
+                    "location": location,
+                    "description": description,
+                })
+            })
+        })
+        .take(limit)
+        .collect();
+
+    Ok(json!({
+        "query": query,
+        "count": events.len(),
         "events": events,
     }))
 }
+
+/// Busy intervals in a calendar over a range: the spans of the events that
+/// overlap it, clipped to the range, with overlapping spans merged.
+pub async fn get_freebusy(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let start_arg = args["start"].as_str().ok_or("Missing start")?;
+    let end_arg = args["end"].as_str().ok_or("Missing end")?;
+    let start = datetime::parse_ical_datetime(start_arg, None)
+        .ok_or_else(|| format!("Invalid start: {start_arg}"))?;
+    let end = datetime::parse_ical_datetime(end_arg, None)
+        .ok_or_else(|| format!("Invalid end: {end_arg}"))?;
+    if end <= start {
+        return Err(ToolError::validation("end must be after start"));
+    }
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    // A transparent calendar's events never make anyone busy
+    let calendar = cal_db::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Calendar not found"))?;
+    // Recurring events are expanded, so every occurrence in the range counts
+    let busy = if calendar.is_transparent() {
+        Vec::new()
+    } else {
+        let events =
+            super::scheduling::busy_events(pool, &[calendar_id.to_string()], start, end).await?;
+        super::scheduling::merged_busy(&events, start, end)
+    };
+    let busy: Vec<Value> = busy
+        .iter()
+        .map(|&(from, to)| super::scheduling::interval_json(from, to))
+        .collect();
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "start": start.format("%Y%m%dT%H%M%SZ").to_string(),
+        "end": end.format("%Y%m%dT%H%M%SZ").to_string(),
+        "busy": busy,
+    }))
+}
+
+/// Read a `geo` argument (`{"lat": .., "lon": ..}`) as a coordinate pair,
+/// checking the ranges. `null` or a missing value means no coordinates.
+fn geo_arg(value: &Value) -> Result<Option<(f64, f64)>, ToolError> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let lat = value["lat"].as_f64().ok_or("geo.lat must be a number")?;
+    let lon = value["lon"].as_f64().ok_or("geo.lon must be a number")?;
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(ToolError::validation(format!(
+            "geo.lat must be between -90 and 90, got {lat}"
+        )));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(ToolError::validation(format!(
+            "geo.lon must be between -180 and 180, got {lon}"
+        )));
+    }
+    Ok(Some((lat, lon)))
+}
+
+/// Check that a URL argument is an absolute URI: an RFC 3986 scheme, a colon
+/// and a non-empty remainder with no whitespace or control characters.
+fn validate_url(url: &str) -> Result<(), ToolError> {
+    let valid = url.split_once(':').is_some_and(|(scheme, rest)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            && !rest.is_empty()
+            && !rest.chars().any(|c| c.is_whitespace() || c.is_control())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(ToolError::validation(format!(
+            "url must be an absolute URI, got '{url}'"
+        )))
+    }
+}
+
+/// List the UID→ETag map for a calendar without loading event bodies.
+pub async fn list_etags(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    let etags: Vec<Value> = event_db::list_etags(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .into_iter()
+        .map(|(uid, etag)| json!({"uid": uid, "etag": etag}))
+        .collect();
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "count": etags.len(),
+        "etags": etags,
+    }))
+}
+
+/// Query events in a calendar and bucket them by local start date in the
+/// requested timezone. Each day's events are sorted by start time.
+pub async fn query_events_grouped(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let start = args["start"].as_str();
+    let end = args["end"].as_str();
+    let limit = args["limit"].as_u64().unwrap_or(50) as usize;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    let timezone = match args["timezone"].as_str() {
+        Some(tz) => tz.to_string(),
+        None => {
+            cal_db::get_calendar_by_id(pool, calendar_id)
+                .await
+                .map_err(|e| ToolError::storage("Database error", e))?
+                .ok_or_else(|| ToolError::not_found("Calendar not found"))?
+                .timezone
+        }
+    };
+    let tz: Tz = timezone
+        .parse()
+        .map_err(|_| format!("Unknown timezone: {timezone}"))?;
+
+    // With a range, recurring events are expanded into their occurrences.
+    // Without one a series has no end, so it is listed once at its start.
+    let mut dated = match (start, end) {
+        (Some(s), Some(e)) => dated_occurrences(pool, calendar_id, s, e, tz).await?,
+        _ => event_db::list_objects(pool, calendar_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?
+            .into_iter()
+            .filter_map(|obj| Dated::stored(obj, tz))
+            .collect(),
+    };
+    dated.sort_by_key(|d| d.local);
+    dated.truncate(limit);
+
+    let mut days = serde_json::Map::new();
+    for occurrence in &dated {
+        let day = days
+            .entry(occurrence.local.format("%Y-%m-%d").to_string())
+            .or_insert_with(|| json!([]));
+        if let Some(events) = day.as_array_mut() {
+            events.push(json!({
+                "uid": occurrence.obj.uid,
+                "summary": occurrence.obj.summary,
+                "dtstart": occurrence.dtstart,
+                "dtend": occurrence.dtend,
+                "local_start": occurrence.local.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                "etag": occurrence.obj.etag,
+            }));
+        }
+    }
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "timezone": timezone,
+        "count": dated.len(),
+        "days": days,
+    }))
+}
+
+/// Summarize the seven days starting at `week_start`: every day is listed
+/// (empty days included) with its events in start order, and the day with
+/// the most events is reported as `busiest_day` (the earliest on a tie).
+pub async fn week_summary(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let week_start = args["week_start"].as_str().ok_or("Missing week_start")?;
+    let first_day = datetime::parse_date(week_start)
+        .ok_or_else(|| format!("Invalid week_start: {week_start}"))?;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    let timezone = match args["timezone"].as_str() {
+        Some(tz) => tz.to_string(),
+        None => {
+            cal_db::get_calendar_by_id(pool, calendar_id)
+                .await
+                .map_err(|e| ToolError::storage("Database error", e))?
+                .ok_or_else(|| ToolError::not_found("Calendar not found"))?
+                .timezone
+        }
+    };
+    let tz: Tz = timezone
+        .parse()
+        .map_err(|_| format!("Unknown timezone: {timezone}"))?;
+
+    // Stored times may be floating or in any zone, so widen the range by a day
+    // on each side and filter on the local start date below.
+    let days: Vec<NaiveDate> = (0..7).map(|i| first_day + Duration::days(i)).collect();
+    let range_start = (first_day - Duration::days(1)).format("%Y%m%dT000000Z");
+    let range_end = (first_day + Duration::days(8)).format("%Y%m%dT000000Z");
+    let mut dated: Vec<_> = dated_occurrences(
+        pool,
+        calendar_id,
+        &range_start.to_string(),
+        &range_end.to_string(),
+        tz,
+    )
+    .await?
+    .into_iter()
+    .filter(|d| days.contains(&d.local.date()))
+    .collect();
+    dated.sort_by_key(|d| d.local);
+
+    let mut busiest: Option<(NaiveDate, usize)> = None;
+    let summary: Vec<Value> = days
+        .iter()
+        .map(|day| {
+            let events: Vec<Value> = dated
+                .iter()
+                .filter(|d| d.local.date() == *day)
+                .map(|d| {
+                    json!({
+                        "uid": d.obj.uid,
+                        "summary": d.obj.summary,
+                        "local_start": d.local.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                    })
+                })
+                .collect();
+            if !events.is_empty() && busiest.is_none_or(|(_, count)| events.len() > count) {
+                busiest = Some((*day, events.len()));
+            }
+            json!({
+                "date": day.format("%Y-%m-%d").to_string(),
+                "weekday": day.format("%A").to_string(),
+                "count": events.len(),
+                "events": events,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "timezone": timezone,
+        "week_start": first_day.format("%Y-%m-%d").to_string(),
+        "week_end": days[6].format("%Y-%m-%d").to_string(),
+        "total": dated.len(),
+        "busiest_day": busiest.map(|(day, count)| json!({
+            "date": day.format("%Y-%m-%d").to_string(),
+            "count": count,
+        })),
+        "days": summary,
+    }))
+}
+
+/// One occurrence of a stored object: its start in the requested zone, and
+/// its DTSTART and DTEND in the form the object stores them.
+struct Dated {
+    local: NaiveDateTime,
+    dtstart: Option<String>,
+    dtend: Option<String>,
+    obj: CalendarObject,
+}
+
+impl Dated {
+    /// The object at its stored start, without expanding recurrences.
+    fn stored(obj: CalendarObject, tz: Tz) -> Option<Self> {
+        Some(Self {
+            local: local_start(&obj, tz)?,
+            dtstart: obj.dtstart.clone(),
+            dtend: obj.dtend.clone(),
+            obj,
+        })
+    }
+}
+
+/// Every occurrence of the objects in `[start, end)`. Recurring events are
+/// expanded, so a series appears once per occurrence rather than only at
+/// its first instance.
+async fn dated_occurrences(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    start: &str,
+    end: &str,
+    tz: Tz,
+) -> Result<Vec<Dated>, ToolError> {
+    let range_start = datetime::parse_ical_datetime(start, None)
+        .ok_or_else(|| format!("Invalid start: {start}"))?;
+    let range_end =
+        datetime::parse_ical_datetime(end, None).ok_or_else(|| format!("Invalid end: {end}"))?;
+    let (in_range, recurring) =
+        event_db::list_objects_in_range_with_recurring(pool, calendar_id, start, end)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let mut dated = Vec::new();
+    for obj in in_range {
+        if obj.component_type == "VEVENT" {
+            dated.extend(event_instances(&obj, range_start, range_end, tz));
+        } else {
+            dated.extend(Dated::stored(obj, tz));
+        }
+    }
+    // Recurring events whose first instance lies outside the range
+    for obj in recurring.iter().filter(|o| o.component_type == "VEVENT") {
+        dated.extend(event_instances(obj, range_start, range_end, tz));
+    }
+    Ok(dated)
+}
+
+/// Each instance of a stored VEVENT that overlaps `[start, end)`. Instances
+/// take the form of the master's DTSTART, so dates and floating times keep
+/// their wall-clock value as in [`local_start`].
+fn event_instances(
+    obj: &CalendarObject,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tz: Tz,
+) -> Vec<Dated> {
+    let Some(calendar) = parser::parse_calendar(&obj.ical_data) else {
+        return Vec::new();
+    };
+    let Some(dtstart) = calendar
+        .components
+        .iter()
+        .find(|c| c.name == "VEVENT" && c.property("RECURRENCE-ID").is_none())
+        .and_then(|c| c.property("DTSTART"))
+    else {
+        return Vec::new();
+    };
+    let Some(form) = IcalTime::parse(&dtstart.value) else {
+        return Vec::new();
+    };
+    let tzid = dtstart.param("TZID");
+    let zone = tzid
+        .and_then(|id| id.parse::<Tz>().ok())
+        .filter(|_| !form.utc && !form.date_only);
+    let written = |at: DateTime<Utc>| {
+        form.with_value(match zone {
+            Some(zone) => at.with_timezone(&zone).naive_local(),
+            None => at.naive_utc(),
+        })
+    };
+
+    recurrence::instances_in_range(&calendar, start, end)
+        .into_iter()
+        .map(|instance| {
+            let (from, to) = (written(instance.start), written(instance.end));
+            Dated {
+                local: from.in_timezone(tzid, tz),
+                dtstart: Some(from.format()),
+                dtend: Some(to.format()),
+                obj: obj.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Start of a stored object in `tz`, honouring the DTSTART TZID parameter.
+fn local_start(obj: &CalendarObject, tz: Tz) -> Option<NaiveDateTime> {
+    let tzid = parser::parse_calendar(&obj.ical_data).and_then(|cal| {
+        cal.components
+            .iter()
+            .find(|c| c.name == obj.component_type && c.property("RECURRENCE-ID").is_none())
+            .and_then(|c| c.property("DTSTART"))
+            .and_then(|p| p.param("TZID").map(str::to_string))
+    });
+    let dtstart = IcalTime::parse(obj.dtstart.as_deref()?)?;
+    Some(dtstart.in_timezone(tzid.as_deref(), tz))
+}
+
+/// Get the effective details of one occurrence of a recurring event.
+///
+/// The master VEVENT's RRULE/RDATE/EXDATE are expanded to confirm the date is
+/// an instance; a RECURRENCE-ID override for that date takes precedence over
+/// the master's properties.
+pub async fn get_occurrence(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    let occurrence_date = args["occurrence_date"]
+        .as_str()
+        .ok_or("Missing occurrence_date")?;
+    let date = datetime::parse_date(occurrence_date)
+        .ok_or_else(|| format!("Invalid occurrence_date: {occurrence_date}"))?;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+
+    let obj = event_db::get_object_by_uid(pool, calendar_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+    let calendar =
+        parser::parse_calendar(&obj.ical_data).ok_or("Event data is not valid iCalendar")?;
+
+    let mut events = calendar.components.iter().filter(|c| c.name == "VEVENT");
+    let master = events
+        .clone()
+        .find(|c| c.property("RECURRENCE-ID").is_none())
+        .ok_or("Event has no master VEVENT")?;
+    let start = master
+        .property_value("DTSTART")
+        .and_then(IcalTime::parse)
+        .ok_or("Event has no valid DTSTART")?;
+
+    let end_of_day = date
+        .and_hms_opt(23, 59, 59)
+        .ok_or("Invalid occurrence_date")?;
+    let instance = recurrence::occurrences(master, end_of_day)
+        .into_iter()
+        .find(|i| i.date() == date)
+        .ok_or_else(|| format!("{occurrence_date} is not an occurrence of this event"))?;
+    let recurrence_id = start.with_value(instance);
+
+    let overridden = events.find(|c| {
+        c.property_value("RECURRENCE-ID")
+            .and_then(IcalTime::parse)
+            .is_some_and(|rid| rid.value.date() == date)
+    });
+
+    let (dtstart, dtend) = match overridden {
+        Some(ov) => (
+            ov.property_value("DTSTART").map(str::to_string),
+            ov.property_value("DTEND").map(str::to_string),
+        ),
+        None => {
+            let end = master
+                .property_value("DTEND")
+                .and_then(IcalTime::parse)
+                .map(|end| {
+                    end.with_value(instance + (end.value - start.value))
+                        .format()
+                });
+            (Some(recurrence_id.format()), end)
+        }
+    };
+    let effective = overridden.unwrap_or(master);
+    let prop = |name: &str| {
+        effective
+            .property_value(name)
+            .or_else(|| master.property_value(name))
+            .map(parser::unescape_text)
+    };
+
+    Ok(json!({
+        "uid": obj.uid,
+        "calendar_id": obj.calendar_id,
+        "occurrence_date": date.format("%Y-%m-%d").to_string(),
+        "recurrence_id": recurrence_id.format(),
+        "summary": prop("SUMMARY"),
+        "dtstart": dtstart,
+        "dtend": dtend,
+        "timezone": master.property("DTSTART").and_then(|p| p.param("TZID")),
+        "description": prop("DESCRIPTION"),
+        "location": prop("LOCATION"),
+        "is_override": overridden.is_some(),
+    }))
+}
+
+/// Copy a recurring event's master VEVENT into a new event with a fresh UID
+/// and a replacement RRULE. RECURRENCE-ID overrides, RDATE and EXDATE belong
+/// to the old pattern and are dropped; VALARMs and other properties are kept.
+pub async fn clone_series(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    let rrule = args["rrule"].as_str().ok_or("Missing rrule")?;
+    let target_calendar_id = args["target_calendar_id"].as_str().unwrap_or(calendar_id);
+    recurrence::RecurrenceRule::parse(rrule).ok_or_else(|| format!("Invalid rrule: {rrule}"))?;
+
+    super::verify_calendar_access(pool, user_id, calendar_id).await?;
+    super::verify_calendar_write_access(pool, user_id, target_calendar_id).await?;
+    super::verify_component_allowed(pool, target_calendar_id, "VEVENT").await?;
+
+    let obj = event_db::get_object_by_uid(pool, calendar_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+    let mut calendar =
+        parser::parse_calendar(&obj.ical_data).ok_or("Event data is not valid iCalendar")?;
+
+    let mut event = calendar
+        .components
+        .iter()
+        .find(|c| c.name == "VEVENT" && c.property("RECURRENCE-ID").is_none())
+        .cloned()
+        .ok_or("Event has no VEVENT to clone")?;
+    if event.property("RRULE").is_none() && event.property("RDATE").is_none() {
+        return Err(ToolError::validation("Event is not recurring"));
+    }
+
+    let uid = builder::generate_uid();
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    event.set_property(parser::Property::new("UID", &uid));
+    event.set_property(parser::Property::new("RRULE", rrule));
+    event.set_property(parser::Property::new("DTSTAMP", &now));
+    for name in ["RDATE", "EXDATE", "SEQUENCE"] {
+        event.remove_property(name);
+    }
+    calendar.components.retain(|c| c.name != "VEVENT");
+    calendar.components.push(event);
+
+    let ical_data = calendar.to_ical();
+    let fields = parser::extract_fields(&ical_data);
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
+        pool,
+        target_calendar_id,
+        &uid,
+        &ical_data,
+        event_db::ObjectFields {
+            component_type: &fields.component_type,
+            dtstart: fields.dtstart.as_deref(),
+            dtend: fields.dtend.as_deref(),
+            summary: fields.summary.as_deref(),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to clone event", e))?;
+
+    Ok(json!({
+        "uid": obj.uid,
+        "calendar_id": target_calendar_id,
+        "source_uid": event_uid,
+        "summary": obj.summary,
+        "dtstart": obj.dtstart,
+        "dtend": obj.dtend,
+        "rrule": rrule,
+        "etag": obj.etag,
+    }))
+}
+
+/// Add and remove categories on every event matching a title substring
+/// and/or time range. Every VEVENT in a matching object (including
+/// RECURRENCE-ID overrides) gets the same CATEGORIES line. Nothing is
+/// written unless `confirm` is true.
+pub async fn tag_events(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let query = args["query"].as_str().map(str::to_lowercase);
+    let range = match (args["start"].as_str(), args["end"].as_str()) {
+        (Some(start), Some(end)) => Some((start, end)),
+        (None, None) => None,
+        _ => {
+            return Err(ToolError::validation(
+                "start and end must be given together",
+            ));
+        }
+    };
+    if query.is_none() && range.is_none() {
+        return Err(ToolError::validation(
+            "Give a query and/or a start/end range to select events",
+        ));
+    }
+    let add = category_list(&args["add"], "add")?;
+    let remove = category_list(&args["remove"], "remove")?;
+    if add.is_empty() && remove.is_empty() {
+        return Err(ToolError::validation(
+            "Nothing to do: add and remove are both empty",
+        ));
+    }
+    let confirm = args["confirm"].as_bool().unwrap_or(false);
+
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+
+    let objects = match range {
+        Some((start, end)) => event_db::list_objects_in_range(pool, calendar_id, start, end).await,
+        None => event_db::list_objects(pool, calendar_id).await,
+    }
+    .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let mut results = Vec::new();
+    let mut updated = 0;
+    for obj in objects.iter().filter(|o| o.component_type == "VEVENT") {
+        if let Some(query) = &query
+            && !obj
+                .summary
+                .as_deref()
+                .is_some_and(|s| s.to_lowercase().contains(query))
+        {
+            continue;
+        }
+        let Some(mut calendar) = parser::parse_calendar(&obj.ical_data) else {
+            continue;
+        };
+
+        let mut categories = Vec::new();
+        let mut changed = false;
+        for event in calendar
+            .components
+            .iter_mut()
+            .filter(|c| c.name == "VEVENT")
+        {
+            let current: Vec<String> = event
+                .properties_named("CATEGORIES")
+                .flat_map(|p| p.value.split(','))
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect();
+            let mut next: Vec<String> = current
+                .iter()
+                .filter(|c| !remove.iter().any(|r| r.eq_ignore_ascii_case(c)))
+                .cloned()
+                .collect();
+            for category in &add {
+                if !next.iter().any(|c| c.eq_ignore_ascii_case(category)) {
+                    next.push(category.clone());
+                }
+            }
+            if next != current {
+                changed = true;
+                if next.is_empty() {
+                    event.remove_property("CATEGORIES");
+                } else {
+                    event.set_property(parser::Property::new("CATEGORIES", &next.join(",")));
+                }
+            }
+            if event.property("RECURRENCE-ID").is_none() {
+                categories = next;
+            }
+        }
+
+        if changed && confirm {
+            let ical_data = calendar.to_ical();
+            let fields = parser::extract_fields(&ical_data);
+            event_db::upsert_object_with_etag_mode(
+                pool,
+                calendar_id,
+                &obj.uid,
+                &ical_data,
+                event_db::ObjectFields {
+                    component_type: &fields.component_type,
+                    dtstart: fields.dtstart.as_deref(),
+                    dtend: fields.dtend.as_deref(),
+                    summary: fields.summary.as_deref(),
+                },
+                config.etag_mode,
+            )
+            .await
+            .map_err(|e| ToolError::storage(format!("Failed to tag event {}", obj.uid), e))?;
+            updated += 1;
+        }
+        results.push(json!({
+            "uid": obj.uid,
+            "summary": obj.summary,
+            "categories": categories,
+            "changed": changed,
+        }));
+    }
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "confirmed": confirm,
+        "matched": results.len(),
+        "updated": updated,
+        "events": results,
+    }))
+}
+
+/// Group events with the same normalized (summary, dtstart, dtend) and
+/// report every group with more than one member. Members are ordered by
+/// creation time; with `auto_delete` all but the first are deleted.
+pub async fn find_duplicates(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let auto_delete = args["auto_delete"].as_bool().unwrap_or(false);
+
+    if auto_delete {
+        super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+    } else {
+        super::verify_calendar_access(pool, user_id, calendar_id).await?;
+    }
+
+    let objects = event_db::list_objects(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    // Keyed by start first so groups come out in chronological order
+    let mut groups: BTreeMap<(String, String, String), Vec<&CalendarObject>> = BTreeMap::new();
+    for obj in &objects {
+        let Some(dtstart) = obj.dtstart.as_deref() else {
+            continue;
+        };
+        let summary = obj
+            .summary
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        let key = (
+            dtstart.trim().to_string(),
+            obj.dtend.as_deref().unwrap_or_default().trim().to_string(),
+            summary,
+        );
+        groups.entry(key).or_default().push(obj);
+    }
+
+    let mut result = Vec::new();
+    let mut deleted = Vec::new();
+    for members in groups.values_mut().filter(|m| m.len() > 1) {
+        members.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.uid.cmp(&b.uid)));
+        if auto_delete {
+            for obj in &members[1..] {
+                event_db::delete_object(pool, calendar_id, &obj.uid)
+                    .await
+                    .map_err(|e| ToolError::storage("Failed to delete event", e))?;
+                deleted.push(obj.uid.clone());
+            }
+        }
+        result.push(json!({
+            "summary": members[0].summary,
+            "dtstart": members[0].dtstart,
+            "dtend": members[0].dtend,
+            "keep": members[0].uid,
+            "uids": members.iter().map(|o| o.uid.as_str()).collect::<Vec<_>>(),
+        }));
+    }
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "groups": result,
+        "deleted": deleted,
+    }))
+}
+
+/// Move an event between two calendars the user owns. The object keeps its
+/// UID and data; it is written to the target and then deleted from the
+/// source, so both calendars log a sync change and bump their ctag.
+pub async fn move_event(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let source_id = args["source_calendar_id"]
+        .as_str()
+        .ok_or("Missing source_calendar_id")?;
+    let target_id = args["target_calendar_id"]
+        .as_str()
+        .ok_or("Missing target_calendar_id")?;
+    let event_uid = args["event_uid"].as_str().ok_or("Missing event_uid")?;
+    if source_id == target_id {
+        return Err(ToolError::validation(
+            "Source and target calendars are the same",
+        ));
+    }
+    for calendar_id in [source_id, target_id] {
+        cal_db::get_calendar_by_id(pool, calendar_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?
+            .filter(|c| c.owner_id == user_id)
+            .ok_or_else(|| {
+                ToolError::not_found(format!(
+                    "Calendar not found or not owned by you: {calendar_id}"
+                ))
+            })?;
+    }
+
+    // Check and move in one transaction, so a failure part-way leaves the
+    // event where it was rather than in both calendars, and a concurrent
+    // write cannot slip in between the checks and the move
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+    let obj = event_db::get_object_by_uid(&mut *tx, source_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Event not found"))?;
+    if event_db::get_object_by_uid(&mut *tx, target_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .is_some()
+    {
+        return Err(ToolError::validation(format!(
+            "Event {event_uid} already exists in the target calendar"
+        )));
+    }
+    super::verify_component_allowed(&mut *tx, target_id, &obj.component_type).await?;
+    let (moved, _) = event_db::upsert_object_in(
+        &mut tx,
+        target_id,
+        &obj.uid,
+        &obj.ical_data,
+        event_db::ObjectFields {
+            component_type: &obj.component_type,
+            dtstart: obj.dtstart.as_deref(),
+            dtend: obj.dtend.as_deref(),
+            summary: obj.summary.as_deref(),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to move event", e))?;
+    event_db::delete_object_in(&mut tx, source_id, event_uid)
+        .await
+        .map_err(|e| ToolError::storage("Failed to move event", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| ToolError::storage("Failed to move event", e))?;
+
+    Ok(json!({
+        "moved": true,
+        "event_uid": moved.uid,
+        "source_calendar_id": source_id,
+        "target_calendar_id": target_id,
+        "etag": moved.etag,
+    }))
+}
+
+/// Import a jCal document. Each item (all components sharing a UID, so
+/// overrides stay with their series) becomes one object; a missing UID is
+/// generated. Items that fail are listed in `errors` with the index of the
+/// vcalendar they came from, and do not stop the rest of the import.
+pub async fn import_jcal(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let document = match &args["jcal"] {
+        Value::Null => return Err(ToolError::validation("Missing jcal")),
+        Value::String(text) => {
+            serde_json::from_str(text).map_err(|e| format!("Invalid jCal JSON: {e}"))?
+        }
+        other => other.clone(),
+    };
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+
+    // A document is one vcalendar, or an array of them
+    let documents: Vec<&Value> = match document.as_array() {
+        Some(items) if items.first().is_some_and(Value::is_array) => items.iter().collect(),
+        _ => vec![&document],
+    };
+
+    let mut imported = Vec::new();
+    let mut errors = Vec::new();
+    for (index, value) in documents.into_iter().enumerate() {
+        let vcalendar = match jcal::parse_component(value) {
+            Ok(c) if c.name == "VCALENDAR" => c,
+            Ok(c) => {
+                errors.push(json!({
+                    "index": index,
+                    "error": format!("Expected vcalendar, got {}", c.name.to_ascii_lowercase()),
+                }));
+                continue;
+            }
+            Err(e) => {
+                errors.push(json!({"index": index, "error": e}));
+                continue;
+            }
+        };
+
+        let (timezones, items): (Vec<_>, Vec<_>) = vcalendar
+            .components
+            .into_iter()
+            .partition(|c| c.name == "VTIMEZONE");
+
+        for (uid, components) in group_by_uid(items) {
+            match import_item(pool, calendar_id, &uid, &timezones, components, config).await {
+                Ok((obj, created)) => imported.push(json!({
+                    "uid": obj.uid,
+                    "component_type": obj.component_type,
+                    "summary": obj.summary,
+                    "etag": obj.etag,
+                    "created": created,
+                })),
+                Err(e) => errors.push(json!({"index": index, "uid": uid, "error": e.message})),
+            }
+        }
+    }
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "imported": imported,
+        "errors": errors,
+    }))
+}
+
+/// Group components by UID in order of first appearance, so a series and
+/// its overrides become one item. Components without a UID get a new one.
+pub(super) fn group_by_uid(items: Vec<parser::Component>) -> Vec<(String, Vec<parser::Component>)> {
+    let mut groups: Vec<(String, Vec<parser::Component>)> = Vec::new();
+    for mut item in items {
+        let uid = match item.property_value("UID") {
+            Some(uid) => uid.to_string(),
+            None => {
+                let uid = builder::generate_uid();
+                item.properties
+                    .insert(0, parser::Property::new("UID", &uid));
+                uid
+            }
+        };
+        match groups.iter_mut().find(|(u, _)| *u == uid) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((uid, vec![item])),
+        }
+    }
+    groups
+}
+
+/// Store the components of one imported item as a single object, with the
+/// VTIMEZONEs its TZIDs refer to.
+pub(super) async fn import_item(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    uid: &str,
+    timezones: &[parser::Component],
+    components: Vec<parser::Component>,
+    config: &Config,
+) -> Result<(CalendarObject, bool), ToolError> {
+    let component_type = components[0].name.clone();
+    if !matches!(component_type.as_str(), "VEVENT" | "VTODO" | "VJOURNAL") {
+        return Err(ToolError::validation(format!(
+            "Unsupported component: {component_type}"
+        )));
+    }
+    if components.iter().any(|c| c.name != component_type) {
+        return Err(ToolError::validation(format!(
+            "Components with UID {uid} have different types"
+        )));
+    }
+    let exists = event_db::get_object_by_uid(pool, calendar_id, uid)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .is_some();
+    if !exists {
+        super::verify_component_allowed(pool, calendar_id, &component_type).await?;
+    }
+
+    let tzids: Vec<&str> = components
+        .iter()
+        .flat_map(|c| c.properties.iter())
+        .filter_map(|p| p.param("TZID"))
+        .collect();
+    let mut calendar = parser::Component::new("VCALENDAR");
+    calendar
+        .properties
+        .push(parser::Property::new("VERSION", "2.0"));
+    calendar
+        .properties
+        .push(parser::Property::new("PRODID", "-//CalDAV Server//EN"));
+    calendar.components.extend(
+        timezones
+            .iter()
+            .filter(|tz| {
+                tz.property_value("TZID")
+                    .is_some_and(|id| tzids.contains(&id))
+            })
+            .cloned(),
+    );
+    calendar.components.extend(components);
+
+    let ical_data = calendar.to_ical();
+    let fields = parser::extract_fields(&ical_data);
+    event_db::upsert_object_with_etag_mode(
+        pool,
+        calendar_id,
+        uid,
+        &ical_data,
+        event_db::ObjectFields {
+            component_type: &component_type,
+            dtstart: fields.dtstart.as_deref(),
+            dtend: fields.dtend.as_deref(),
+            summary: fields.summary.as_deref(),
+        },
+        config.etag_mode,
+    )
+    .await
+    .map_err(|e| ToolError::storage("Failed to save", e))
+}
+
+/// Read an optional array of category names. Commas separate values on a
+/// CATEGORIES line, so they are not allowed inside a name.
+fn category_list(value: &Value, name: &str) -> Result<Vec<String>, ToolError> {
+    let Some(items) = value.as_array() else {
+        return Ok(Vec::new());
+    };
+    items
+        .iter()
+        .map(|item| {
+            let category = item
+                .as_str()
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .ok_or_else(|| format!("{name} must contain non-empty strings"))?;
+            if category.contains(',') {
+                return Err(ToolError::validation(format!(
+                    "Category names cannot contain commas: {category}"
+                )));
+            }
+            Ok(category.to_string())
+        })
+        .collect()
+}
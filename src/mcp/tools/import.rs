@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+
+use super::events::{group_by_uid, import_item};
+use super::{ToolDef, ToolError};
+use crate::config::Config;
+use crate::db::calendars as cal_db;
+use crate::ical::parser;
+
+/// Tool definitions for importing iCalendar files.
+pub fn tool_defs() -> Vec<ToolDef> {
+    vec![ToolDef {
+        name: "import_ics",
+        description: "Import an .ics file (e.g. a Google or Apple Calendar export). Each event or task is stored as its own object; components sharing a UID are stored together. Returns created/updated/failed counts with per-item errors",
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "calendar_id": {"type": "string", "description": "The calendar ID"},
+                "ics": {"type": "string", "description": "Raw iCalendar text: one or more VCALENDARs"}
+            },
+            "required": ["calendar_id", "ics"],
+            "additionalProperties": false
+        }),
+    }]
+}
+
+/// Import a raw iCalendar file. Every VCALENDAR in it is split into items
+/// (all components sharing a UID, so overrides stay with their series),
+/// and each item is stored as one object wrapped in its own VCALENDAR with
+/// the VTIMEZONEs it refers to. A failed item is reported and does not stop
+/// the rest.
+pub async fn import_ics(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
+    let ics = args["ics"].as_str().ok_or("Missing ics")?;
+    super::verify_calendar_write_access(pool, user_id, calendar_id).await?;
+    import_into(pool, calendar_id, parse_ics(ics)?, config).await
+}
+
+/// Import an .ics file from disk for the `import-ics` command. Without a
+/// `calendar_id`, a calendar named after the file is created for the user,
+/// once the file has been parsed and found to hold something to import.
+pub async fn import_file(
+    pool: &SqlitePool,
+    user_id: &str,
+    calendar_id: Option<&str>,
+    path: &Path,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let ics = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ToolError::internal(format!("Failed to read {}: {e}", path.display())))?;
+    let calendars = parse_ics(&ics)?;
+    let calendar_id = match calendar_id {
+        Some(id) => {
+            super::verify_calendar_write_access(pool, user_id, id).await?;
+            id.to_string()
+        }
+        None => {
+            if !calendars
+                .iter()
+                .any(|c| c.components.iter().any(|c| c.name != "VTIMEZONE"))
+            {
+                return Err(ToolError::validation(format!(
+                    "No events found in {}",
+                    path.display()
+                )));
+            }
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .filter(|s| !s.is_empty())
+                .unwrap_or("Imported");
+            cal_db::create_calendar(pool, user_id, name, "", "#0E61B9", "UTC")
+                .await
+                .map_err(|e| ToolError::storage("Failed to create calendar", e))?
+                .id
+        }
+    };
+    import_into(pool, &calendar_id, calendars, config).await
+}
+
+/// The VCALENDARs in `ics`; an error if there are none.
+fn parse_ics(ics: &str) -> Result<Vec<parser::Component>, ToolError> {
+    let calendars: Vec<parser::Component> = parser::parse_components(ics)
+        .into_iter()
+        .filter(|c| c.name == "VCALENDAR")
+        .collect();
+    if calendars.is_empty() {
+        return Err(ToolError::validation("No VCALENDAR found in ics"));
+    }
+    Ok(calendars)
+}
+
+/// Split the parsed VCALENDARs into items and upsert each into the calendar.
+async fn import_into(
+    pool: &SqlitePool,
+    calendar_id: &str,
+    calendars: Vec<parser::Component>,
+    config: &Config,
+) -> Result<Value, ToolError> {
+    let (mut created, mut updated) = (0, 0);
+    let mut errors = Vec::new();
+    for vcalendar in calendars {
+        let (timezones, items): (Vec<_>, Vec<_>) = vcalendar
+            .components
+            .into_iter()
+            .partition(|c| c.name == "VTIMEZONE");
+        for (uid, components) in group_by_uid(items) {
+            match import_item(pool, calendar_id, &uid, &timezones, components, config).await {
+                Ok((_, true)) => created += 1,
+                Ok((_, false)) => updated += 1,
+                Err(e) => errors.push(json!({"uid": uid, "error": e.message})),
+            }
+        }
+    }
+
+    Ok(json!({
+        "calendar_id": calendar_id,
+        "created": created,
+        "updated": updated,
+        "failed": errors.len(),
+        "errors": errors,
+    }))
+}
@@ -1,10 +1,18 @@
+pub mod account;
 pub mod calendars;
 pub mod events;
+pub mod import;
+pub mod scheduling;
 pub mod sharing;
 pub mod simple;
 
 use serde_json::Value;
-use sqlx::SqlitePool;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+use crate::config::Config;
+use crate::db::models::Permission;
+use crate::db::shares;
+use crate::error::AppError;
 
 /// A tool definition for the MCP tools/list response.
 pub struct ToolDef {
@@ -21,34 +29,362 @@ pub fn all_tools(tool_mode: &str) -> Vec<ToolDef> {
     let mut tools = Vec::new();
     tools.extend(calendars::tool_defs());
     tools.extend(events::tool_defs());
-    tools.extend(sharing::tool_defs());
+    tools.extend(import::tool_defs());
+    if tool_mode != "medium" {
+        tools.extend(sharing::tool_defs());
+    }
+    tools.extend(scheduling::tool_defs());
+    tools.extend(account::tool_defs());
     tools
 }
 
+/// Argument names a tool's schema does not declare, for a tool whose schema
+/// sets `additionalProperties: false`. Returns `None` for an unknown tool.
+pub fn unknown_arguments(
+    tool_mode: &str,
+    tool_name: &str,
+    arguments: &Value,
+) -> Option<Vec<String>> {
+    let tool = all_tools(tool_mode)
+        .into_iter()
+        .find(|t| t.name == tool_name)?;
+    if tool.input_schema["additionalProperties"] != Value::Bool(false) {
+        return Some(Vec::new());
+    }
+    let declared = tool.input_schema["properties"].as_object();
+    Some(
+        arguments
+            .as_object()
+            .into_iter()
+            .flat_map(|args| args.keys())
+            .filter(|key| !declared.is_some_and(|props| props.contains_key(*key)))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// The declared argument names of a tool, sorted.
+pub fn declared_arguments(tool_mode: &str, tool_name: &str) -> Vec<String> {
+    all_tools(tool_mode)
+        .into_iter()
+        .find(|t| t.name == tool_name)
+        .and_then(|t| t.input_schema["properties"].as_object().cloned())
+        .map(|props| props.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Tools that change calendar data or credentials, refused for read-only
+/// tokens. `rotate_my_token` is here so a read-only token cannot mint a new
+/// secret for one of the user's read-write tokens.
+const WRITE_TOOLS: &[&str] = &[
+    "create_calendar",
+    "delete_calendar",
+    "set_calendar_components",
+    "set_calendar_timezone",
+    "create_event",
+    "create_todo",
+    "update_event",
+    "patch_event",
+    "reschedule_event",
+    "convert_event_time_type",
+    "delete_event",
+    "restore_event",
+    "move_event",
+    "clone_series",
+    "tag_events",
+    "import_jcal",
+    "import_ics",
+    "set_availability",
+    "share_calendar",
+    "unshare_calendar",
+    "add_event",
+    "rotate_my_token",
+];
+
+/// Whether a call changes data. `find_duplicates` only does so when
+/// asked to delete what it finds.
+fn is_write_call(tool_name: &str, arguments: &Value) -> bool {
+    WRITE_TOOLS.contains(&tool_name)
+        || (tool_name == "find_duplicates" && arguments["auto_delete"] == Value::Bool(true))
+}
+
+/// A failed tool call: a stable code clients can branch on, and the message
+/// shown to people.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ToolError {
+    /// The calendar, event, user or token does not exist.
+    pub const NOT_FOUND: &'static str = "not_found";
+    /// The caller may not touch the calendar, or the token is read-only.
+    pub const FORBIDDEN: &'static str = "forbidden";
+    /// The arguments were missing or invalid.
+    pub const VALIDATION: &'static str = "validation";
+    /// No tool by that name in the current tool mode.
+    pub const UNKNOWN_TOOL: &'static str = "unknown_tool";
+    /// A database or storage failure.
+    pub const INTERNAL: &'static str = "internal";
+
+    /// Construct an error with an explicit code.
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(Self::NOT_FOUND, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(Self::FORBIDDEN, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(Self::VALIDATION, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(Self::INTERNAL, message)
+    }
+
+    /// A failed database call, prefixed with what was being done. The code
+    /// follows the kind of [`AppError`].
+    pub fn storage(context: impl std::fmt::Display, error: impl Into<AppError>) -> Self {
+        let error = error.into();
+        let code = match error {
+            AppError::NotFound(_) => Self::NOT_FOUND,
+            AppError::Forbidden(_) | AppError::Unauthorized => Self::FORBIDDEN,
+            AppError::BadRequest(_) | AppError::Conflict(_) | AppError::PreconditionFailed(_) => {
+                Self::VALIDATION
+            }
+            AppError::Database(_) | AppError::Internal(_) => Self::INTERNAL,
+        };
+        Self::new(code, format!("{context}: {error}"))
+    }
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Most tool failures are bad or missing arguments, so a bare message is a
+/// validation error. Other codes are built with the named constructors.
+impl From<String> for ToolError {
+    fn from(message: String) -> Self {
+        Self::validation(message)
+    }
+}
+
+impl From<&str> for ToolError {
+    fn from(message: &str) -> Self {
+        Self::validation(message)
+    }
+}
+
 /// Dispatch a tools/call request to the appropriate handler.
 pub async fn dispatch(
     pool: &SqlitePool,
     user_id: &str,
+    scope: Permission,
     tool_name: &str,
     arguments: &Value,
-    tool_mode: &str,
-) -> Result<Value, String> {
-    if tool_mode == "simple" {
-        return simple::dispatch(pool, user_id, tool_name, arguments).await;
+    config: &Config,
+) -> Result<Value, ToolError> {
+    if !scope.can_write() && is_write_call(tool_name, arguments) {
+        return Err(ToolError::forbidden(format!(
+            "{tool_name} changes data and this MCP token is read-only"
+        )));
+    }
+    if config.tool_mode == "medium" && sharing::tool_defs().iter().any(|t| t.name == tool_name) {
+        return Err(ToolError::new(
+            ToolError::UNKNOWN_TOOL,
+            format!("Unknown tool: {tool_name}"),
+        ));
     }
+    if config.tool_mode == "simple" {
+        simple::dispatch(pool, user_id, tool_name, arguments, config).await
+    } else {
+        call_tool(pool, user_id, tool_name, arguments, config).await
+    }
+}
+
+/// Run a full-mode tool by name.
+async fn call_tool(
+    pool: &SqlitePool,
+    user_id: &str,
+    tool_name: &str,
+    arguments: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
     match tool_name {
         "list_calendars" => calendars::list_calendars(pool, user_id, arguments).await,
+        "list_calendars_detailed" => {
+            calendars::list_calendars_detailed(pool, user_id, arguments).await
+        }
         "get_calendar" => calendars::get_calendar(pool, user_id, arguments).await,
         "create_calendar" => calendars::create_calendar(pool, user_id, arguments).await,
         "delete_calendar" => calendars::delete_calendar_tool(pool, user_id, arguments).await,
-        "create_event" => events::create_event(pool, user_id, arguments).await,
+        "set_calendar_components" => {
+            calendars::set_calendar_components(pool, user_id, arguments).await
+        }
+        "set_calendar_timezone" => {
+            calendars::set_calendar_timezone(pool, user_id, arguments, config).await
+        }
+        "export_activity_csv" => calendars::export_activity_csv(pool, user_id, arguments).await,
+        "export_calendar" => calendars::export_calendar(pool, user_id, arguments).await,
+        "create_event" => events::create_event(pool, user_id, arguments, config).await,
+        "create_todo" => events::create_todo(pool, user_id, arguments, config).await,
         "get_event" => events::get_event(pool, user_id, arguments).await,
-        "update_event" => events::update_event(pool, user_id, arguments).await,
+        "update_event" => events::update_event(pool, user_id, arguments, config).await,
+        "patch_event" => events::patch_event(pool, user_id, arguments, config).await,
+        "reschedule_event" => events::reschedule_event(pool, user_id, arguments, config).await,
+        "convert_event_time_type" => {
+            events::convert_event_time_type(pool, user_id, arguments, config).await
+        }
         "delete_event" => events::delete_event(pool, user_id, arguments).await,
+        "restore_event" => events::restore_event(pool, user_id, arguments).await,
+        "move_event" => events::move_event(pool, user_id, arguments, config).await,
         "query_events" => events::query_events(pool, user_id, arguments).await,
+        "search_events" => events::search_events(pool, user_id, arguments).await,
+        "get_freebusy" => events::get_freebusy(pool, user_id, arguments).await,
+        "list_etags" => events::list_etags(pool, user_id, arguments).await,
+        "query_events_grouped" => events::query_events_grouped(pool, user_id, arguments).await,
+        "week_summary" => events::week_summary(pool, user_id, arguments).await,
+        "get_occurrence" => events::get_occurrence(pool, user_id, arguments).await,
+        "clone_series" => events::clone_series(pool, user_id, arguments, config).await,
+        "tag_events" => events::tag_events(pool, user_id, arguments, config).await,
+        "find_duplicates" => events::find_duplicates(pool, user_id, arguments).await,
+        "import_jcal" => events::import_jcal(pool, user_id, arguments, config).await,
+        "import_ics" => import::import_ics(pool, user_id, arguments, config).await,
         "share_calendar" => sharing::share_calendar(pool, user_id, arguments).await,
         "unshare_calendar" => sharing::unshare_calendar(pool, user_id, arguments).await,
         "list_shared_calendars" => sharing::list_shared_calendars(pool, user_id, arguments).await,
-        _ => Err(format!("Unknown tool: {tool_name}")),
+        "set_availability" => scheduling::set_availability(pool, user_id, arguments).await,
+        "get_availability" => scheduling::get_availability(pool, user_id, arguments).await,
+        "find_conflicts" => scheduling::find_conflicts(pool, user_id, arguments).await,
+        "find_free_slot" => scheduling::find_free_slot(pool, user_id, arguments).await,
+        "group_freebusy" => scheduling::group_freebusy(pool, user_id, arguments).await,
+        "list_sync_clients" => account::list_sync_clients(pool, user_id, arguments).await,
+        "rotate_my_token" => account::rotate_my_token(pool, user_id, arguments).await,
+        _ => Err(ToolError::new(
+            ToolError::UNKNOWN_TOOL,
+            format!("Unknown tool: {tool_name}"),
+        )),
+    }
+}
+
+/// Ensure the user owns the calendar or has had it shared with them. A
+/// calendar that exists but is not the user's is refused with "Access
+/// denied", so the error code can tell it apart from an unknown one.
+pub async fn verify_calendar_access(
+    pool: &SqlitePool,
+    user_id: &str,
+    calendar_id: &str,
+) -> Result<(), ToolError> {
+    let permission = shares::get_user_permission(pool, calendar_id, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+    if permission.is_some() {
+        return Ok(());
+    }
+    let exists = crate::db::calendars::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .is_some();
+    if exists {
+        Err(ToolError::forbidden(format!(
+            "Access denied to calendar {calendar_id}"
+        )))
+    } else {
+        Err(ToolError::not_found("Calendar not found"))
+    }
+}
+
+/// Like `verify_calendar_access`, but also refuses a calendar the user can
+/// only see through a read-only share. Owners always have write access.
+pub async fn verify_calendar_write_access(
+    pool: &SqlitePool,
+    user_id: &str,
+    calendar_id: &str,
+) -> Result<(), ToolError> {
+    verify_calendar_access(pool, user_id, calendar_id).await?;
+    let permission = shares::get_user_permission(pool, calendar_id, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+    if permission.is_some_and(|p| p.can_write()) {
+        Ok(())
+    } else {
+        Err(ToolError::forbidden(format!(
+            "Access denied: calendar {calendar_id} is shared with you read-only"
+        )))
+    }
+}
+
+/// Ensure a calendar accepts new objects of `component_type`.
+pub async fn verify_component_allowed(
+    executor: impl SqliteExecutor<'_>,
+    calendar_id: &str,
+    component_type: &str,
+) -> Result<(), ToolError> {
+    let cal = crate::db::calendars::get_calendar_by_id(executor, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found("Calendar not found"))?;
+    if !cal.accepts_component(component_type) {
+        return Err(ToolError::validation(format!(
+            "Calendar {calendar_id} does not accept {component_type} components"
+        )));
+    }
+    Ok(())
+}
+
+/// Reject a timezone that is not a valid IANA zone or, when `ALLOWED_TIMEZONES`
+/// is configured, one that is not in the allow-list.
+pub fn validate_timezone(config: &Config, tz: &str) -> Result<(), String> {
+    if tz.parse::<chrono_tz::Tz>().is_err() {
+        return Err(format!("Unknown timezone: {tz}"));
+    }
+    if !config.allowed_timezones.is_empty() && !config.allowed_timezones.iter().any(|a| a == tz) {
+        return Err(format!(
+            "Timezone {tz} is not allowed. Allowed timezones: {}",
+            config.allowed_timezones.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_error_codes() {
+        // Wording no longer decides the code
+        let plain = ToolError::from("Event not found".to_string());
+        assert_eq!(plain.code, ToolError::VALIDATION);
+        assert_eq!(
+            ToolError::not_found("Event not found").code,
+            ToolError::NOT_FOUND
+        );
+
+        let storage = |e: AppError| ToolError::storage("Failed to save", e);
+        let missing = storage(AppError::NotFound("no such object".into()));
+        assert_eq!(missing.code, ToolError::NOT_FOUND);
+        assert_eq!(missing.message, "Failed to save: not found: no such object");
+        assert_eq!(
+            storage(AppError::Conflict("exists".into())).code,
+            ToolError::VALIDATION
+        );
+        assert_eq!(
+            storage(AppError::Internal(anyhow::anyhow!("disk full"))).code,
+            ToolError::INTERNAL
+        );
     }
 }
@@ -0,0 +1,615 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+
+use super::{ToolDef, ToolError};
+use crate::db::availability::{self, Availability};
+use crate::db::calendars as cal_db;
+use crate::db::events as event_db;
+use crate::db::models::{AvailabilityWindow, User};
+use crate::db::users;
+use crate::ical::{datetime, parser, recurrence};
+
+/// Day names accepted and returned by the availability tools, Monday first.
+const DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Return the MCP tool definitions for availability and scheduling.
+pub fn tool_defs() -> Vec<ToolDef> {
+    vec![
+        ToolDef {
+            name: "set_availability",
+            description: "Set the user's working hours (replaces any existing template). Scheduling tools can treat time outside these hours as busy",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "timezone": {"type": "string", "description": "IANA timezone the hours are in (default UTC)"},
+                    "days": {
+                        "type": "array",
+                        "description": "Working-hours windows. A day may appear more than once (e.g. around lunch). An empty list clears the template",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "day": {"type": "string", "description": "Day of the week, e.g. monday"},
+                                "start": {"type": "string", "description": "Start time, HH:MM"},
+                                "end": {"type": "string", "description": "End time, HH:MM"}
+                            },
+                            "required": ["day", "start", "end"],
+                            "additionalProperties": false
+                        }
+                    }
+                },
+                "required": ["days"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "get_availability",
+            description: "Get the user's working hours",
+            input_schema: json!({
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "find_conflicts",
+            description: "Check whether a time slot is free: lists events that overlap it, optionally treating time outside working hours as unavailable",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "start": {"type": "string", "description": "Slot start (e.g. 20260301T090000Z or 2026-03-01T09:00:00-05:00)"},
+                    "end": {"type": "string", "description": "Slot end"},
+                    "calendar_id": {"type": "string", "description": "Only check this calendar (default: all accessible calendars except transparent ones)"},
+                    "respect_working_hours": {"type": "boolean", "description": "Flag the slot as unavailable if it falls outside working hours (default false)"}
+                },
+                "required": ["start", "end"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "find_free_slot",
+            description: "Find the earliest free interval of a given length within a search window, skipping existing events and optionally time outside working hours",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "start": {"type": "string", "description": "Search window start (e.g. 20260301T120000Z or 2026-03-01T12:00:00-05:00)"},
+                    "end": {"type": "string", "description": "Search window end"},
+                    "duration": {"type": "string", "description": "Slot length as an ISO 8601 duration, e.g. PT30M or PT1H"},
+                    "calendar_id": {"type": "string", "description": "Only consider this calendar (default: all accessible calendars except transparent ones)"},
+                    "respect_working_hours": {"type": "boolean", "description": "Only return slots inside working hours (default true)"}
+                },
+                "required": ["start", "end", "duration"],
+                "additionalProperties": false
+            }),
+        },
+        ToolDef {
+            name: "group_freebusy",
+            description: "Combined availability of several people: the union of their busy time and the common free slots of a given length. Each person must be you or have shared a calendar with you",
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "people": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "minItems": 1,
+                        "description": "Usernames or email addresses"
+                    },
+                    "start": {"type": "string", "description": "Window start (e.g. 20260301T120000Z or 2026-03-01T12:00:00-05:00)"},
+                    "end": {"type": "string", "description": "Window end"},
+                    "duration": {"type": "string", "description": "Minimum free slot length as an ISO 8601 duration, e.g. PT30M or PT1H"},
+                    "respect_working_hours": {"type": "boolean", "description": "Only count time inside everyone's working hours as free (default true)"}
+                },
+                "required": ["people", "start", "end", "duration"],
+                "additionalProperties": false
+            }),
+        },
+    ]
+}
+
+/// Replace the user's working-hours template.
+pub async fn set_availability(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let timezone = args["timezone"].as_str().unwrap_or("UTC");
+    timezone
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone: {timezone}"))?;
+    let days = args["days"].as_array().ok_or("Missing days")?;
+
+    let mut windows = Vec::new();
+    for entry in days {
+        let day = entry["day"].as_str().ok_or("Each day needs a day name")?;
+        let weekday = parse_day(day).ok_or_else(|| format!("Unknown day: {day}"))?;
+        let start = parse_clock(entry["start"].as_str().ok_or("Each day needs a start")?)?;
+        let end = parse_clock(entry["end"].as_str().ok_or("Each day needs an end")?)?;
+        if start >= end {
+            return Err(ToolError::validation(format!(
+                "{day}: start must be before end"
+            )));
+        }
+        windows.push(AvailabilityWindow {
+            weekday,
+            start_time: start.format("%H:%M").to_string(),
+            end_time: end.format("%H:%M").to_string(),
+        });
+    }
+
+    availability::set_availability(pool, user_id, timezone, &windows)
+        .await
+        .map_err(|e| ToolError::storage("Failed to save availability", e))?;
+
+    get_availability(pool, user_id, args).await
+}
+
+/// Return the user's working-hours template.
+pub async fn get_availability(
+    pool: &SqlitePool,
+    user_id: &str,
+    _args: &Value,
+) -> Result<Value, ToolError> {
+    let availability = availability::get_availability(pool, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let Some(availability) = availability else {
+        return Ok(json!({"configured": false, "timezone": null, "days": []}));
+    };
+
+    let days: Vec<Value> = availability
+        .windows
+        .iter()
+        .map(|w| {
+            json!({
+                "day": DAY_NAMES.get(w.weekday as usize).copied().unwrap_or("unknown"),
+                "start": w.start_time,
+                "end": w.end_time,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "configured": true,
+        "timezone": availability.timezone,
+        "days": days,
+    }))
+}
+
+/// List events overlapping a slot and report whether the slot is available.
+pub async fn find_conflicts(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let start = parse_instant_arg(args, "start")?;
+    let end = parse_instant_arg(args, "end")?;
+    if end <= start {
+        return Err(ToolError::validation("end must be after start"));
+    }
+    let respect_working_hours = args["respect_working_hours"].as_bool().unwrap_or(false);
+
+    let calendar_ids = accessible_calendar_ids(pool, user_id, args["calendar_id"].as_str()).await?;
+    let conflicts = busy_events(pool, &calendar_ids, start, end).await?;
+
+    let outside_working_hours = if respect_working_hours {
+        availability::get_availability(pool, user_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?
+            .is_some_and(|a| !within_working_hours(&a, start, end))
+    } else {
+        false
+    };
+
+    Ok(json!({
+        "start": start.format("%Y%m%dT%H%M%SZ").to_string(),
+        "end": end.format("%Y%m%dT%H%M%SZ").to_string(),
+        "available": conflicts.is_empty() && !outside_working_hours,
+        "outside_working_hours": outside_working_hours,
+        "conflicts": conflicts.iter().map(BusyEvent::to_json).collect::<Vec<_>>(),
+    }))
+}
+
+/// Return the earliest free `[start, start + duration)` inside the search
+/// window, or a null slot if there is none.
+pub async fn find_free_slot(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let start = parse_instant_arg(args, "start")?;
+    let end = parse_instant_arg(args, "end")?;
+    if end <= start {
+        return Err(ToolError::validation("end must be after start"));
+    }
+    let duration_arg = args["duration"].as_str().ok_or("Missing duration")?;
+    let duration = datetime::parse_duration(duration_arg)
+        .filter(|d| *d > Duration::zero())
+        .ok_or_else(|| format!("Invalid duration: {duration_arg}"))?;
+    let respect_working_hours = args["respect_working_hours"].as_bool().unwrap_or(true);
+
+    let calendar_ids = accessible_calendar_ids(pool, user_id, args["calendar_id"].as_str()).await?;
+    let busy = busy_events(pool, &calendar_ids, start, end).await?;
+
+    let working_hours = if respect_working_hours {
+        availability::get_availability(pool, user_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?
+            .map(|a| working_intervals(&a, start, end))
+    } else {
+        None
+    };
+    let allowed = working_hours.unwrap_or_else(|| vec![(start, end)]);
+
+    let slot = free_intervals(&busy, start, end)
+        .into_iter()
+        .flat_map(|(free_start, free_end)| {
+            allowed.iter().filter_map(move |&(open, close)| {
+                let from = free_start.max(open);
+                let to = free_end.min(close);
+                (to - from >= duration).then_some(from)
+            })
+        })
+        .min()
+        .map(|from| {
+            json!({
+                "start": from.format("%Y%m%dT%H%M%SZ").to_string(),
+                "end": (from + duration).format("%Y%m%dT%H%M%SZ").to_string(),
+            })
+        });
+
+    Ok(json!({
+        "start": start.format("%Y%m%dT%H%M%SZ").to_string(),
+        "end": end.format("%Y%m%dT%H%M%SZ").to_string(),
+        "duration": duration_arg,
+        "slot": slot,
+    }))
+}
+
+/// Merge several people's busy time and return the gaps of at least
+/// `duration` in which all of them are free.
+pub async fn group_freebusy(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+) -> Result<Value, ToolError> {
+    let people = args["people"].as_array().ok_or("Missing people")?;
+    if people.is_empty() {
+        return Err(ToolError::validation("people must not be empty"));
+    }
+    let start = parse_instant_arg(args, "start")?;
+    let end = parse_instant_arg(args, "end")?;
+    if end <= start {
+        return Err(ToolError::validation("end must be after start"));
+    }
+    let duration_arg = args["duration"].as_str().ok_or("Missing duration")?;
+    let duration = datetime::parse_duration(duration_arg)
+        .filter(|d| *d > Duration::zero())
+        .ok_or_else(|| format!("Invalid duration: {duration_arg}"))?;
+    let respect_working_hours = args["respect_working_hours"].as_bool().unwrap_or(true);
+
+    let accessible = cal_db::list_calendars_for_user(pool, user_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?;
+
+    let mut busy = Vec::new();
+    let mut allowed = vec![(start, end)];
+    let mut per_person = Vec::new();
+    for person in people {
+        let name = person.as_str().ok_or("people must be strings")?;
+        let user = find_person(pool, name)
+            .await?
+            .ok_or_else(|| ToolError::not_found(format!("User '{name}' not found")))?;
+        // Only calendars the person owns and the caller can see count towards
+        // their busy time, so nothing private is revealed. Transparent
+        // calendars are visible but never busy.
+        let visible: Vec<_> = accessible
+            .iter()
+            .filter(|c| c.owner_id == user.id)
+            .collect();
+        if visible.is_empty() && user.id != user_id {
+            return Err(ToolError::not_found(format!(
+                "No calendars of '{name}' are shared with you"
+            )));
+        }
+        let calendar_ids: Vec<String> = visible
+            .iter()
+            .filter(|c| !c.is_transparent())
+            .map(|c| c.id.clone())
+            .collect();
+
+        let mut person_busy = busy_events(pool, &calendar_ids, start, end).await?;
+        per_person.push(json!({
+            "person": name,
+            "busy": merged_busy(&person_busy, start, end)
+                .iter()
+                .map(|&(s, e)| interval_json(s, e))
+                .collect::<Vec<_>>(),
+        }));
+        busy.append(&mut person_busy);
+
+        if respect_working_hours
+            && let Some(a) = availability::get_availability(pool, &user.id)
+                .await
+                .map_err(|e| ToolError::storage("Database error", e))?
+        {
+            allowed = intersect_intervals(&allowed, &working_intervals(&a, start, end));
+        }
+    }
+    busy.sort_by_key(|b| b.start);
+
+    let free_slots: Vec<Value> = intersect_intervals(&free_intervals(&busy, start, end), &allowed)
+        .into_iter()
+        .filter(|&(from, to)| to - from >= duration)
+        .map(|(from, to)| interval_json(from, to))
+        .collect();
+
+    Ok(json!({
+        "start": start.format("%Y%m%dT%H%M%SZ").to_string(),
+        "end": end.format("%Y%m%dT%H%M%SZ").to_string(),
+        "duration": duration_arg,
+        "people": per_person,
+        "busy": merged_busy(&busy, start, end)
+            .iter()
+            .map(|&(s, e)| interval_json(s, e))
+            .collect::<Vec<_>>(),
+        "free_slots": free_slots,
+    }))
+}
+
+/// Look a person up by email (if the name contains `@`) or username.
+async fn find_person(pool: &SqlitePool, name: &str) -> Result<Option<User>, ToolError> {
+    let user = if name.contains('@') {
+        users::get_user_by_email(pool, name).await
+    } else {
+        users::get_user_by_username(pool, name).await
+    };
+    user.map_err(|e| ToolError::storage("Database error", e))
+}
+
+/// One busy interval contributed by an event instance.
+pub(super) struct BusyEvent {
+    calendar_id: String,
+    uid: String,
+    summary: Option<String>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+impl BusyEvent {
+    fn to_json(&self) -> Value {
+        json!({
+            "calendar_id": self.calendar_id,
+            "uid": self.uid,
+            "summary": self.summary,
+            "start": self.start.format("%Y%m%dT%H%M%SZ").to_string(),
+            "end": self.end.format("%Y%m%dT%H%M%SZ").to_string(),
+        })
+    }
+}
+
+/// The calendars to consider: the given one (after an access check), or
+/// every calendar the user owns or has been shared that blocks time.
+/// Transparent calendars are left out, as in `group_freebusy`.
+async fn accessible_calendar_ids(
+    pool: &SqlitePool,
+    user_id: &str,
+    calendar_id: Option<&str>,
+) -> Result<Vec<String>, ToolError> {
+    match calendar_id {
+        Some(id) => {
+            super::verify_calendar_access(pool, user_id, id).await?;
+            Ok(vec![id.to_string()])
+        }
+        None => Ok(cal_db::list_calendars_for_user(pool, user_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?
+            .into_iter()
+            .filter(|c| !c.is_transparent())
+            .map(|c| c.id)
+            .collect()),
+    }
+}
+
+/// Every event instance (recurrences expanded) overlapping `[start, end)`
+/// across the given calendars, sorted by start time.
+pub(super) async fn busy_events(
+    pool: &SqlitePool,
+    calendar_ids: &[String],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<BusyEvent>, ToolError> {
+    let mut busy = Vec::new();
+    for calendar_id in calendar_ids {
+        let objects = event_db::list_objects(pool, calendar_id)
+            .await
+            .map_err(|e| ToolError::storage("Database error", e))?;
+        for obj in objects.iter().filter(|o| o.component_type == "VEVENT") {
+            let Some(calendar) = parser::parse_calendar(&obj.ical_data) else {
+                continue;
+            };
+            for instance in recurrence::instances_in_range(&calendar, start, end) {
+                busy.push(BusyEvent {
+                    calendar_id: calendar_id.clone(),
+                    uid: obj.uid.clone(),
+                    summary: obj.summary.clone(),
+                    start: instance.start,
+                    end: instance.end,
+                });
+            }
+        }
+    }
+    busy.sort_by_key(|b| b.start);
+    Ok(busy)
+}
+
+/// The gaps in `[start, end)` not covered by any busy event. `busy` must be
+/// sorted by start time; overlapping events are merged as they are scanned.
+fn free_intervals(
+    busy: &[BusyEvent],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut free = Vec::new();
+    let mut cursor = start;
+    for event in busy {
+        if event.start > cursor {
+            free.push((cursor, event.start.min(end)));
+        }
+        cursor = cursor.max(event.end);
+        if cursor >= end {
+            return free;
+        }
+    }
+    free.push((cursor, end));
+    free
+}
+
+/// The busy time in `[start, end)` as disjoint intervals. `busy` must be
+/// sorted by start time.
+pub(super) fn merged_busy(
+    busy: &[BusyEvent],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for event in busy {
+        let (from, to) = (event.start.max(start), event.end.min(end));
+        if from >= to {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if from <= last.1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    merged
+}
+
+/// The overlap of two sorted lists of disjoint intervals.
+fn intersect_intervals(
+    a: &[(DateTime<Utc>, DateTime<Utc>)],
+    b: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut result = Vec::new();
+    for &(a_start, a_end) in a {
+        for &(b_start, b_end) in b {
+            let (from, to) = (a_start.max(b_start), a_end.min(b_end));
+            if from < to {
+                result.push((from, to));
+            }
+        }
+    }
+    result.sort();
+    result
+}
+
+/// Format an interval as `{"start", "end"}` in UTC.
+pub(super) fn interval_json(start: DateTime<Utc>, end: DateTime<Utc>) -> Value {
+    json!({
+        "start": start.format("%Y%m%dT%H%M%SZ").to_string(),
+        "end": end.format("%Y%m%dT%H%M%SZ").to_string(),
+    })
+}
+
+/// The working-hours windows that fall within `[start, end)`, converted to
+/// UTC and clipped to the range, in chronological order.
+fn working_intervals(
+    availability: &Availability,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let Ok(tz) = availability.timezone.parse::<Tz>() else {
+        return vec![(start, end)];
+    };
+    let first_day = start.with_timezone(&tz).date_naive();
+    let last_day = end.with_timezone(&tz).date_naive();
+
+    let mut intervals = Vec::new();
+    for day in first_day.iter_days().take_while(|d| *d <= last_day) {
+        let weekday = i64::from(day.weekday().num_days_from_monday());
+        for window in availability.windows.iter().filter(|w| w.weekday == weekday) {
+            let (Ok(open), Ok(close)) = (
+                parse_clock(&window.start_time),
+                parse_clock(&window.end_time),
+            ) else {
+                continue;
+            };
+            let (Some(open), Some(close)) = (
+                tz.from_local_datetime(&day.and_time(open)).earliest(),
+                tz.from_local_datetime(&day.and_time(close)).latest(),
+            ) else {
+                continue;
+            };
+            let open = open.with_timezone(&Utc).max(start);
+            let close = close.with_timezone(&Utc).min(end);
+            if open < close {
+                intervals.push((open, close));
+            }
+        }
+    }
+    intervals.sort();
+    intervals
+}
+
+/// Whether `[start, end)` lies entirely inside one working-hours window.
+fn within_working_hours(
+    availability: &Availability,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> bool {
+    let Ok(tz) = availability.timezone.parse::<Tz>() else {
+        return true;
+    };
+    let local_start = start.with_timezone(&tz).naive_local();
+    let local_end = end.with_timezone(&tz).naive_local();
+    let weekday = i64::from(local_start.weekday().num_days_from_monday());
+
+    availability
+        .windows
+        .iter()
+        .filter(|w| w.weekday == weekday)
+        .any(|w| {
+            let (Ok(open), Ok(close)) = (parse_clock(&w.start_time), parse_clock(&w.end_time))
+            else {
+                return false;
+            };
+            let day = local_start.date();
+            local_start >= day.and_time(open) && local_end <= day.and_time(close)
+        })
+}
+
+/// Parse a required instant argument.
+fn parse_instant_arg(args: &Value, name: &str) -> Result<DateTime<Utc>, String> {
+    let value = args[name]
+        .as_str()
+        .ok_or_else(|| format!("Missing {name}"))?;
+    datetime::parse_ical_datetime(value, None).ok_or_else(|| format!("Invalid {name}: {value}"))
+}
+
+/// Map a day name (`monday`, `Mon`) to 0 (Monday) through 6 (Sunday).
+fn parse_day(day: &str) -> Option<i64> {
+    let day = day.trim().to_ascii_lowercase();
+    if day.len() < 3 {
+        return None;
+    }
+    DAY_NAMES
+        .iter()
+        .position(|name| name.starts_with(&day))
+        .map(|i| i as i64)
+}
+
+/// Parse an `HH:MM` time of day.
+fn parse_clock(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time: {value} (expected HH:MM)"))
+}
@@ -1,9 +1,9 @@
 use serde_json::{Value, json};
 use sqlx::SqlitePool;
 
-use super::ToolDef;
+use super::{ToolDef, ToolError};
 use crate::db::models::Permission;
-use crate::db::{shares, users};
+use crate::db::{calendars, shares, users};
 
 /// Return the MCP tool definitions for calendar sharing operations.
 pub fn tool_defs() -> Vec<ToolDef> {
@@ -47,27 +47,42 @@ pub fn tool_defs() -> Vec<ToolDef> {
     ]
 }
 
+/// Ensure `user_id` owns the calendar. Only owners may change its shares.
+async fn verify_owner(
+    pool: &SqlitePool,
+    user_id: &str,
+    calendar_id: &str,
+) -> Result<(), ToolError> {
+    calendars::get_calendar_by_id(pool, calendar_id)
+        .await
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .filter(|c| c.owner_id == user_id)
+        .ok_or_else(|| ToolError::not_found("Calendar not found or not owned by you"))?;
+    Ok(())
+}
+
 /// Share a calendar with another user, granting the specified access level.
 pub async fn share_calendar(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
     let username = args["username"].as_str().ok_or("Missing username")?;
     let permission_str = args["permission"].as_str().ok_or("Missing permission")?;
 
     let permission =
         Permission::from_str_value(permission_str).ok_or("Invalid permission value")?;
+    verify_owner(pool, user_id, calendar_id).await?;
 
     let target_user = users::get_user_by_username(pool, username)
         .await
-        .map_err(|e| format!("Database error: {e}"))?
-        .ok_or_else(|| format!("User '{username}' not found"))?;
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found(format!("User '{username}' not found")))?;
 
     let share = shares::share_calendar(pool, calendar_id, &target_user.id, permission)
         .await
-        .map_err(|e| format!("Failed to share calendar: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to share calendar", e))?;
 
     Ok(json!({
         "calendar_id": share.calendar_id,
@@ -79,20 +94,21 @@ pub async fn share_calendar(
 /// Revoke a user's access to a shared calendar.
 pub async fn unshare_calendar(
     pool: &SqlitePool,
-    _user_id: &str,
+    user_id: &str,
     args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
     let calendar_id = args["calendar_id"].as_str().ok_or("Missing calendar_id")?;
     let username = args["username"].as_str().ok_or("Missing username")?;
+    verify_owner(pool, user_id, calendar_id).await?;
 
     let target_user = users::get_user_by_username(pool, username)
         .await
-        .map_err(|e| format!("Database error: {e}"))?
-        .ok_or_else(|| format!("User '{username}' not found"))?;
+        .map_err(|e| ToolError::storage("Database error", e))?
+        .ok_or_else(|| ToolError::not_found(format!("User '{username}' not found")))?;
 
     shares::unshare_calendar(pool, calendar_id, &target_user.id)
         .await
-        .map_err(|e| format!("Failed to unshare calendar: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to unshare calendar", e))?;
 
     Ok(json!({"unshared": true, "calendar_id": calendar_id, "username": username}))
 }
@@ -102,10 +118,10 @@ pub async fn list_shared_calendars(
     pool: &SqlitePool,
     user_id: &str,
     _args: &Value,
-) -> Result<Value, String> {
+) -> Result<Value, ToolError> {
     let shared = shares::list_shared_calendars(pool, user_id)
         .await
-        .map_err(|e| format!("Database error: {e}"))?;
+        .map_err(|e| ToolError::storage("Database error", e))?;
 
     let result: Vec<Value> = shared
         .iter()
@@ -1,7 +1,8 @@
 use serde_json::{Value, json};
 use sqlx::SqlitePool;
 
-use super::ToolDef;
+use super::{ToolDef, ToolError};
+use crate::config::Config;
 use crate::db::calendars as cal_db;
 use crate::db::events as event_db;
 use crate::ical::builder;
@@ -61,21 +62,25 @@ pub async fn dispatch(
     user_id: &str,
     tool_name: &str,
     args: &Value,
-) -> Result<Value, String> {
+    config: &Config,
+) -> Result<Value, ToolError> {
     match tool_name {
-        "add_event" => handle_add(pool, user_id, args).await,
+        "add_event" => handle_add(pool, user_id, args, config).await,
         "delete_event" => handle_delete(pool, user_id, args).await,
         "list_events" => handle_list(pool, user_id, args).await,
-        _ => Err(format!("Unknown tool: {tool_name}")),
+        _ => Err(ToolError::new(
+            ToolError::UNKNOWN_TOOL,
+            format!("Unknown tool: {tool_name}"),
+        )),
     }
 }
 
 /// Get or create the user's default calendar.
 /// Returns the first calendar owned by/shared with the user. Creates one if none exist.
-async fn resolve_calendar(pool: &SqlitePool, user_id: &str) -> Result<String, String> {
+async fn resolve_calendar(pool: &SqlitePool, user_id: &str) -> Result<String, ToolError> {
     let cals = cal_db::list_calendars_for_user(pool, user_id)
         .await
-        .map_err(|e| format!("Failed to list calendars: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to list calendars", e))?;
 
     if let Some(cal) = cals.first() {
         return Ok(cal.id.clone());
@@ -84,12 +89,17 @@ async fn resolve_calendar(pool: &SqlitePool, user_id: &str) -> Result<String, St
     // Create a default calendar
     let cal = cal_db::create_calendar(pool, user_id, "Calendar", "", "#0E61B9", "UTC")
         .await
-        .map_err(|e| format!("Failed to create default calendar: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to create default calendar", e))?;
     Ok(cal.id)
 }
 
 /// Add: always creates an event in the user's calendar.
-async fn handle_add(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Value, String> {
+async fn handle_add(
+    pool: &SqlitePool,
+    user_id: &str,
+    args: &Value,
+    config: &Config,
+) -> Result<Value, ToolError> {
     let title = args
         .get("title")
         .and_then(|v| v.as_str())
@@ -105,13 +115,32 @@ async fn handle_add(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Va
     let description = args.get("description").and_then(|v| v.as_str());
     let location = args.get("location").and_then(|v| v.as_str());
     let timezone = args.get("timezone").and_then(|v| v.as_str());
+    if let Some(tz) = timezone {
+        super::validate_timezone(config, tz)?;
+    }
+    for value in [start, end] {
+        config.check_event_year(value)?;
+    }
 
     let calendar_id = resolve_calendar(pool, user_id).await?;
+    super::verify_calendar_write_access(pool, user_id, &calendar_id).await?;
+    super::verify_component_allowed(pool, &calendar_id, "VEVENT").await?;
 
     let uid = builder::generate_uid();
-    let ical_data = builder::build_vevent(&uid, title, start, end, description, location, timezone);
-
-    let (obj, _) = event_db::upsert_object(
+    let ical_data = builder::build_vevent(
+        &uid,
+        title,
+        start,
+        end,
+        description,
+        location,
+        None,
+        None,
+        None,
+        timezone,
+    );
+
+    let (obj, _) = event_db::upsert_object_with_etag_mode(
         pool,
         &calendar_id,
         &uid,
@@ -122,9 +151,10 @@ async fn handle_add(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Va
             dtend: Some(end),
             summary: Some(title),
         },
+        config.etag_mode,
     )
     .await
-    .map_err(|e| format!("Failed to create event: {e}"))?;
+    .map_err(|e| ToolError::storage("Failed to create event", e))?;
 
     Ok(json!({
         "uid": obj.uid,
@@ -135,23 +165,24 @@ async fn handle_add(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Va
 }
 
 /// Delete: removes an event by UID from the user's calendar.
-async fn handle_delete(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Value, String> {
+async fn handle_delete(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Value, ToolError> {
     let event_uid = args
         .get("event_uid")
         .and_then(|v| v.as_str())
         .ok_or("Missing event_uid")?;
 
     let calendar_id = resolve_calendar(pool, user_id).await?;
+    super::verify_calendar_write_access(pool, user_id, &calendar_id).await?;
 
     event_db::delete_object(pool, &calendar_id, event_uid)
         .await
-        .map_err(|e| format!("Failed to delete event: {e}"))?;
+        .map_err(|e| ToolError::storage("Failed to delete event", e))?;
 
     Ok(json!({"deleted": true, "event_uid": event_uid}))
 }
 
 /// List: returns events from the user's calendar, optionally filtered by time range.
-async fn handle_list(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Value, String> {
+async fn handle_list(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<Value, ToolError> {
     let calendar_id = resolve_calendar(pool, user_id).await?;
     let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
 
@@ -161,10 +192,10 @@ async fn handle_list(pool: &SqlitePool, user_id: &str, args: &Value) -> Result<V
     let objects = match (start, end) {
         (Some(s), Some(e)) => event_db::list_objects_in_range(pool, &calendar_id, s, e)
             .await
-            .map_err(|e| format!("Database error: {e}"))?,
+            .map_err(|e| ToolError::storage("Database error", e))?,
         _ => event_db::list_objects(pool, &calendar_id)
             .await
-            .map_err(|e| format!("Database error: {e}"))?,
+            .map_err(|e| ToolError::storage("Database error", e))?,
     };
 
     let events: Vec<Value> = objects
@@ -1,20 +1,33 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
 use axum::body::Body;
 use axum::extract::State;
 use axum::http::{Request, StatusCode, header};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
+use serde_json::Value;
 use sqlx::SqlitePool;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 
-use super::auth::McpUserId;
+use super::auth::{McpTokenScope, McpUserId};
 use super::handlers;
-use super::jsonrpc::{JsonRpcRequest, PARSE_ERROR};
+use super::jsonrpc::{JsonRpcErrorResponse, JsonRpcRequest, PARSE_ERROR};
 use super::session::SessionManager;
+use crate::config::Config;
+use crate::db::models::Permission;
+
+/// How often an idle SSE stream sends a keep-alive comment.
+const SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
 
 /// Shared state for the MCP server.
 #[derive(Clone)]
 pub struct McpState {
     pub pool: SqlitePool,
     pub sessions: SessionManager,
-    pub tool_mode: String,
+    pub config: Arc<Config>,
 }
 
 /// Handle POST /mcp — receive JSON-RPC messages from the client.
@@ -24,6 +37,12 @@ pub async fn handle_post(State(state): State<McpState>, request: Request<Body>)
         .get::<McpUserId>()
         .map(|u| u.0.clone())
         .unwrap_or_default();
+    let scope = request
+        .extensions()
+        .get::<McpTokenScope>()
+        .map(|s| s.0)
+        .unwrap_or(Permission::Read);
+    let session_id = session_header(&request).map(str::to_string);
 
     let body = match axum::body::to_bytes(request.into_body(), 1024 * 1024).await {
         Ok(b) => b,
@@ -32,83 +51,196 @@ pub async fn handle_post(State(state): State<McpState>, request: Request<Body>)
         }
     };
 
-    let rpc_request: JsonRpcRequest = match serde_json::from_slice(&body) {
-        Ok(req) => req,
-        Err(e) => {
-            let error = serde_json::json!({
-                "jsonrpc": "2.0",
-                "id": null,
-                "error": {"code": PARSE_ERROR, "message": format!("Parse error: {e}")}
-            });
-            return Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, "application/json")
-                .body(Body::from(serde_json::to_vec(&error).unwrap()))
-                .unwrap();
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(value) => value,
+        Err(e) => return parse_error_response(&e),
+    };
+
+    // A batch is an array of requests, answered with an array of responses
+    if let Value::Array(batch) = message {
+        if let Some(id) = &session_id
+            && !owns_session(&state, id, &user_id)
+        {
+            return session_not_found();
         }
+        return handle_batch(&state, &user_id, scope, batch).await;
+    }
+
+    let rpc_request: JsonRpcRequest = match serde_json::from_value(message) {
+        Ok(req) => req,
+        Err(e) => return parse_error_response(&e),
     };
 
+    // A session ID from an earlier initialize must still be live; initialize
+    // itself starts a new session whatever the client sends
+    if rpc_request.method != "initialize"
+        && let Some(id) = &session_id
+        && !owns_session(&state, id, &user_id)
+    {
+        return session_not_found();
+    }
+
     // Handle notifications (no id) — return 202 Accepted
     if rpc_request.id.is_none() {
         // Still process the notification
-        handlers::handle_request(
-            &state.pool,
-            &state.sessions,
-            &user_id,
-            &rpc_request,
-            &state.tool_mode,
-        )
-        .await;
+        handlers::handle_request(&state.pool, &user_id, scope, &rpc_request, &state.config).await;
         return (StatusCode::ACCEPTED, "").into_response();
     }
 
-    let response = handlers::handle_request(
-        &state.pool,
-        &state.sessions,
+    let response =
+        handlers::handle_request(&state.pool, &user_id, scope, &rpc_request, &state.config).await;
+
+    json_response(
+        &state,
         &user_id,
-        &rpc_request,
-        &state.tool_mode,
+        rpc_request.method == "initialize",
+        &response,
     )
-    .await;
+}
 
+/// Handle a JSON-RPC batch. Each element is dispatched in order; requests get
+/// a response in the returned array, notifications do not. A batch of only
+/// notifications is answered with 202 Accepted, like a single notification.
+async fn handle_batch(
+    state: &McpState,
+    user_id: &str,
+    scope: Permission,
+    batch: Vec<Value>,
+) -> Response {
+    if batch.is_empty() {
+        let error = JsonRpcErrorResponse::invalid_request(Some(Value::Null), "Empty batch");
+        return json_response(state, user_id, false, &serde_json::to_value(error).unwrap());
+    }
+
+    let mut responses = Vec::new();
+    let mut initialized = false;
+    for element in batch {
+        let rpc_request: JsonRpcRequest = match serde_json::from_value(element) {
+            Ok(req) => req,
+            Err(e) => {
+                let error = JsonRpcErrorResponse::invalid_request(
+                    Some(Value::Null),
+                    format!("Invalid request: {e}"),
+                );
+                responses.push(serde_json::to_value(error).unwrap());
+                continue;
+            }
+        };
+        let response =
+            handlers::handle_request(&state.pool, user_id, scope, &rpc_request, &state.config)
+                .await;
+        if rpc_request.id.is_some() {
+            initialized |= rpc_request.method == "initialize";
+            responses.push(response);
+        }
+    }
+
+    if responses.is_empty() {
+        return (StatusCode::ACCEPTED, "").into_response();
+    }
+    json_response(state, user_id, initialized, &Value::Array(responses))
+}
+
+/// Serialize a JSON-RPC response body. After an `initialize` request, a new
+/// session is created and its ID returned in the `Mcp-Session-Id` header.
+fn json_response(state: &McpState, user_id: &str, initialized: bool, body: &Value) -> Response {
     let mut http_response = Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, "application/json");
 
-    // Include session ID header if we just created one
-    if rpc_request.method == "initialize"
-        && let Some(session_id) = state.sessions.get_user_id(&user_id)
-    {
+    if initialized {
+        let session_id = state.sessions.create_session(user_id);
         http_response = http_response.header("Mcp-Session-Id", session_id);
     }
 
     http_response
-        .body(Body::from(serde_json::to_vec(&response).unwrap()))
+        .body(Body::from(serde_json::to_vec(body).unwrap()))
         .unwrap()
 }
 
-/// Handle GET /mcp — SSE stream for server-initiated messages.
-/// For our simple server, we just keep the connection open.
-pub async fn handle_get() -> Response {
-    // For now, return 200 with an empty SSE stream
-    // A full implementation would keep this open for server-push notifications
+/// A -32700 Parse Error response for a body that is not a JSON-RPC message.
+fn parse_error_response(e: &serde_json::Error) -> Response {
+    let error = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": null,
+        "error": {"code": PARSE_ERROR, "message": format!("Parse error: {e}")}
+    });
     Response::builder()
         .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "text/event-stream")
-        .header(header::CACHE_CONTROL, "no-cache")
-        .body(Body::empty())
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&error).unwrap()))
         .unwrap()
 }
 
-/// Handle DELETE /mcp — terminate a session.
+/// Handle GET /mcp — open the Server-Sent Events stream for a session.
+///
+/// The client names its session with `Mcp-Session-Id`. The stream starts with
+/// an `endpoint` event naming the URL to POST to; after that, each message
+/// pushed onto the session arrives as a `message` event. Comment lines are
+/// sent every `SSE_KEEP_ALIVE` so proxies do not close an idle connection.
+pub async fn handle_get(State(state): State<McpState>, request: Request<Body>) -> Response {
+    let user_id = request
+        .extensions()
+        .get::<McpUserId>()
+        .map(|u| u.0.clone())
+        .unwrap_or_default();
+    let Some(session_id) = session_header(&request) else {
+        return missing_session_header();
+    };
+    if !owns_session(&state, session_id, &user_id) {
+        return session_not_found();
+    }
+    let Some(receiver) = state.sessions.open_stream(session_id) else {
+        return session_not_found();
+    };
+
+    let endpoint = tokio_stream::once(Event::default().event("endpoint").data("/mcp"));
+    let messages = UnboundedReceiverStream::new(receiver)
+        .map(|message| Event::default().event("message").data(message.to_string()));
+    let events = endpoint.chain(messages).map(Ok::<_, Infallible>);
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::new().interval(SSE_KEEP_ALIVE))
+        .into_response()
+}
+
+/// Handle DELETE /mcp — terminate a session. Later requests presenting its
+/// ID get 404 Not Found.
 pub async fn handle_delete(State(state): State<McpState>, request: Request<Body>) -> Response {
-    if let Some(session_id) = request
+    let user_id = request
+        .extensions()
+        .get::<McpUserId>()
+        .map(|u| u.0.clone())
+        .unwrap_or_default();
+    let Some(session_id) = session_header(&request) else {
+        return missing_session_header();
+    };
+    if !owns_session(&state, session_id, &user_id) {
+        return session_not_found();
+    }
+    state.sessions.remove_session(session_id);
+
+    (StatusCode::OK, "Session terminated").into_response()
+}
+
+/// The `Mcp-Session-Id` request header, if present.
+fn session_header(request: &Request<Body>) -> Option<&str> {
+    request
         .headers()
         .get("Mcp-Session-Id")
         .and_then(|v| v.to_str().ok())
-    {
-        state.sessions.remove_session(session_id);
-    }
+}
 
-    (StatusCode::OK, "Session terminated").into_response()
+/// Whether a session exists and belongs to the user. Another user's session
+/// is treated as unknown.
+fn owns_session(state: &McpState, session_id: &str, user_id: &str) -> bool {
+    state.sessions.get_user_id(session_id).as_deref() == Some(user_id)
+}
+
+fn missing_session_header() -> Response {
+    (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response()
+}
+
+fn session_not_found() -> Response {
+    (StatusCode::NOT_FOUND, "Session not found").into_response()
 }
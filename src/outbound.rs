@@ -0,0 +1,164 @@
+//! Outbound HTTP(S) for background tasks (currently the reminder webhook).
+//!
+//! Every request is bounded by [`OutboundLimits`]: the whole exchange —
+//! connect, send and read — must finish within the timeout, and the response
+//! may not exceed the size limit, so a slow or hostile remote cannot hang
+//! or exhaust the task that called it.
+
+use std::time::Duration;
+
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use serde_json::Value;
+
+/// Bounds applied to every outbound request.
+#[derive(Debug, Clone, Copy)]
+pub struct OutboundLimits {
+    /// Time allowed for the whole request, from connect to the last byte.
+    pub timeout: Duration,
+    /// Largest response body accepted.
+    pub max_response_bytes: usize,
+}
+
+/// POST a JSON body to an `http://` or `https://` URL and require a 2xx
+/// response. Redirects are not followed, since following one would turn the
+/// POST into a GET and drop the body.
+pub async fn post_json(url: &str, body: &Value, limits: OutboundLimits) -> Result<(), String> {
+    // reqwest is built without a crypto provider of its own; install the
+    // aws-lc-rs one rustls already carries. Later calls are no-ops.
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .map_err(|e| format!("{url}: {e}"))?;
+    let describe = |e: reqwest::Error| {
+        if e.is_timeout() {
+            return format!("no response from {url} within {:?}", limits.timeout);
+        }
+        // reqwest's own message is generic; the cause (refused connection,
+        // bad certificate) is in the source chain.
+        let mut message = format!("{url}: {e}");
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {cause}"));
+            source = cause.source();
+        }
+        message
+    };
+
+    let mut response = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .timeout(limits.timeout)
+        .send()
+        .await
+        .map_err(describe)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{url} returned status {}", status.as_u16()));
+    }
+
+    // Read the body within the size limit, so a huge response cannot
+    // exhaust memory before the timeout fires.
+    let mut received = 0;
+    while let Some(chunk) = response.chunk().await.map_err(describe)? {
+        received += chunk.len();
+        if received > limits.max_response_bytes {
+            return Err(format!(
+                "{url}: response larger than {} bytes",
+                limits.max_response_bytes
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn limits(timeout_ms: u64, max_response_bytes: usize) -> OutboundLimits {
+        OutboundLimits {
+            timeout: Duration::from_millis(timeout_ms),
+            max_response_bytes,
+        }
+    }
+
+    /// A server on `bind` that answers every connection with `response`.
+    async fn serve_on(bind: &str, response: &'static [u8]) -> String {
+        let listener = TcpListener::bind(bind).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let _ = socket.write_all(response).await;
+            }
+        });
+        format!("http://{addr}/hook")
+    }
+
+    async fn serve(response: &'static [u8]) -> String {
+        serve_on("127.0.0.1:0", response).await
+    }
+
+    #[tokio::test]
+    async fn test_post_json_status_and_size_limit() {
+        let url = serve(b"HTTP/1.1 204 No Content\r\n\r\n").await;
+        assert!(
+            post_json(&url, &json!({}), limits(2000, 1024))
+                .await
+                .is_ok()
+        );
+
+        let url = serve(b"HTTP/1.1 500 Internal Server Error\r\n\r\n").await;
+        let err = post_json(&url, &json!({}), limits(2000, 1024))
+            .await
+            .unwrap_err();
+        assert!(err.contains("500"), "got: {err}");
+
+        let url = serve(b"HTTP/1.1 200 OK\r\n\r\n0123456789012345678901234567890123456789").await;
+        let err = post_json(&url, &json!({}), limits(2000, 32))
+            .await
+            .unwrap_err();
+        assert!(err.contains("larger than 32 bytes"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn test_post_json_to_ipv6_literal() {
+        let url = serve_on("[::1]:0", b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(url.starts_with("http://[::1]:"), "got: {url}");
+        assert!(
+            post_json(&url, &json!({}), limits(2000, 1024))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_json_speaks_https() {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let tls = axum_server::tls_rustls::RustlsConfig::from_pem(
+            certified.cert.pem().into_bytes(),
+            certified.key_pair.serialize_pem().into_bytes(),
+        )
+        .await
+        .unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = axum::Router::new().route("/hook", axum::routing::post(|| async { "ok" }));
+        tokio::spawn(axum_server::from_tcp_rustls(listener, tls).serve(app.into_make_service()));
+
+        // The handshake happens and the self-signed certificate is refused
+        let url = format!("https://localhost:{port}/hook");
+        let err = post_json(&url, &json!({}), limits(2000, 1024))
+            .await
+            .unwrap_err();
+        assert!(err.contains("certificate"), "got: {err}");
+    }
+}
@@ -0,0 +1,469 @@
+//! Background reminder dispatcher.
+//!
+//! When enabled, a task wakes every interval, finds VALARM triggers that fall
+//! within the next interval and hands each one to a [`ReminderSink`]. Fired
+//! triggers are recorded in `fired_alarms` so an alarm is delivered once,
+//! even across restarts, and forgotten a day after their trigger time. A
+//! trigger whose delivery fails stays due for an hour and is retried with
+//! exponential backoff; webhook requests are bounded by [`OutboundLimits`]
+//! so an unresponsive endpoint cannot stall the loop.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+
+use crate::db::alarms;
+use crate::error::AppResult;
+use crate::ical::datetime;
+use crate::ical::parser::{self, Component};
+use crate::ical::recurrence;
+use crate::outbound::{self, OutboundLimits};
+
+/// Where due reminders are delivered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ReminderSink {
+    /// Write each reminder to the server log.
+    #[default]
+    Log,
+    /// POST each reminder as JSON to an `http://` or `https://` URL.
+    Webhook(String),
+}
+
+impl FromStr for ReminderSink {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("log") {
+            Ok(Self::Log)
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(Self::Webhook(s.to_string()))
+        } else {
+            Err(format!("unknown reminder sink: {s}"))
+        }
+    }
+}
+
+/// One alarm trigger that is due for delivery.
+#[derive(Debug, Clone)]
+pub struct DueAlarm {
+    pub calendar_id: String,
+    pub object_uid: String,
+    /// Identifies the VALARM within the object: the occurrence's RECURRENCE-ID
+    /// (empty for the master) and the alarm's position.
+    pub alarm_key: String,
+    pub trigger_at: DateTime<Utc>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub action: String,
+}
+
+impl DueAlarm {
+    fn to_json(&self) -> Value {
+        json!({
+            "calendar_id": self.calendar_id,
+            "uid": self.object_uid,
+            "summary": self.summary,
+            "description": self.description,
+            "action": self.action,
+            "trigger_at": self.trigger_at.to_rfc3339(),
+        })
+    }
+}
+
+/// How long a trigger stays deliverable after its time has passed, so that
+/// failed deliveries can be retried and a restart does not drop alarms.
+const LATE_GRACE: Duration = Duration::hours(1);
+
+/// How long a delivered trigger is remembered. It must outlast
+/// [`LATE_GRACE`], after which the trigger can no longer come due.
+const FIRED_RETENTION: Duration = Duration::days(1);
+
+/// First retry delay after a failed delivery; it doubles per failure.
+const BACKOFF_BASE: Duration = Duration::seconds(30);
+
+/// Longest delay between retries of one reminder.
+const BACKOFF_MAX: Duration = Duration::minutes(15);
+
+/// Identifies one alarm trigger: calendar, object UID, alarm key, trigger time.
+type AlarmKey = (String, String, String, DateTime<Utc>);
+
+/// Delivers due reminders to a sink, remembering which deliveries failed so
+/// they are retried with exponential backoff instead of on every scan.
+pub struct Dispatcher {
+    pool: SqlitePool,
+    sink: ReminderSink,
+    limits: OutboundLimits,
+    /// Failure count and earliest next attempt, per alarm trigger.
+    backoff: HashMap<AlarmKey, (u32, DateTime<Utc>)>,
+}
+
+impl Dispatcher {
+    pub fn new(pool: SqlitePool, sink: ReminderSink, limits: OutboundLimits) -> Self {
+        Self {
+            pool,
+            sink,
+            limits,
+            backoff: HashMap::new(),
+        }
+    }
+
+    /// Spawn the dispatcher loop. It runs until the process exits.
+    pub fn spawn(mut self, interval_secs: u64) {
+        let interval_secs = interval_secs.max(1);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(StdDuration::from_secs(interval_secs));
+            let window = Duration::seconds(interval_secs as i64);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once(Utc::now(), window).await {
+                    tracing::error!("Reminder scan failed: {e}");
+                }
+            }
+        });
+    }
+
+    /// Dispatch every alarm due by `now + window` that has not fired yet.
+    /// An alarm is recorded as fired only after the sink accepts it; a failed
+    /// delivery is retried on a later scan once its backoff has elapsed.
+    /// Returns the number delivered.
+    pub async fn run_once(&mut self, now: DateTime<Utc>, window: Duration) -> AppResult<usize> {
+        alarms::prune_fired(&self.pool, now - FIRED_RETENTION).await?;
+        let due = due_alarms(&self.pool, now, window).await?;
+        self.backoff
+            .retain(|(_, _, _, trigger_at), _| *trigger_at >= now - LATE_GRACE);
+
+        let mut delivered = 0;
+        for alarm in due {
+            let key = (
+                alarm.calendar_id.clone(),
+                alarm.object_uid.clone(),
+                alarm.alarm_key.clone(),
+                alarm.trigger_at,
+            );
+            if self
+                .backoff
+                .get(&key)
+                .is_some_and(|(_, retry_at)| *retry_at > now)
+            {
+                continue;
+            }
+            if let Err(e) = dispatch(&self.sink, &alarm, self.limits).await {
+                let failures = self.backoff.get(&key).map_or(0, |(n, _)| *n) + 1;
+                let delay = (BACKOFF_BASE * 2i32.pow(failures.min(10) - 1)).min(BACKOFF_MAX);
+                tracing::warn!(
+                    uid = %alarm.object_uid,
+                    failures,
+                    retry_in_secs = delay.num_seconds(),
+                    "Reminder delivery failed: {e}"
+                );
+                self.backoff.insert(key, (failures, now + delay));
+                continue;
+            }
+            self.backoff.remove(&key);
+            if alarms::mark_fired(
+                &self.pool,
+                &alarm.calendar_id,
+                &alarm.object_uid,
+                &alarm.alarm_key,
+                alarm.trigger_at,
+            )
+            .await?
+            {
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+}
+
+/// Alarm triggers in `[now - LATE_GRACE, now + window)` that have not fired
+/// yet.
+pub async fn due_alarms(
+    pool: &SqlitePool,
+    now: DateTime<Utc>,
+    window: Duration,
+) -> AppResult<Vec<DueAlarm>> {
+    let (start, end) = (now - LATE_GRACE, now + window);
+    let mut due = Vec::new();
+    for obj in alarms::objects_with_alarms(pool).await? {
+        let Some(calendar) = parser::parse_calendar(&obj.ical_data) else {
+            continue;
+        };
+        for (event, valarm, alarm_key, trigger_at) in triggers_in_range(&calendar, start, end) {
+            if alarms::is_fired(pool, &obj.calendar_id, &obj.uid, &alarm_key, trigger_at).await? {
+                continue;
+            }
+            let text = |name: &str| event.property_value(name).map(parser::unescape_text);
+            due.push(DueAlarm {
+                calendar_id: obj.calendar_id.clone(),
+                object_uid: obj.uid.clone(),
+                alarm_key,
+                trigger_at,
+                summary: text("SUMMARY"),
+                description: text("DESCRIPTION"),
+                action: valarm
+                    .property_value("ACTION")
+                    .unwrap_or("DISPLAY")
+                    .to_string(),
+            });
+        }
+    }
+    due.sort_by_key(|a| a.trigger_at);
+    Ok(due)
+}
+
+/// Every `(event, alarm, alarm key, trigger time)` in `[start, end)` for
+/// the VEVENTs of a calendar. Relative triggers are applied to each occurrence; an
+/// override's alarms apply only to the occurrence it replaces.
+fn triggers_in_range(
+    calendar: &Component,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<(&Component, &Component, String, DateTime<Utc>)> {
+    let events: Vec<&Component> = calendar
+        .components
+        .iter()
+        .filter(|c| c.name == "VEVENT")
+        .collect();
+
+    let mut found = Vec::new();
+    for &event in &events {
+        let recurrence_id = event.property_value("RECURRENCE-ID").unwrap_or_default();
+        let valarms = event.components.iter().filter(|c| c.name == "VALARM");
+        for (index, valarm) in valarms.enumerate() {
+            let Some(trigger) = valarm.property("TRIGGER") else {
+                continue;
+            };
+            let alarm_key = format!("{recurrence_id}#{index}");
+
+            if trigger
+                .param("VALUE")
+                .is_some_and(|v| v.eq_ignore_ascii_case("DATE-TIME"))
+            {
+                if let Some(at) = datetime::parse_ical_datetime(&trigger.value, None)
+                    && at >= start
+                    && at < end
+                {
+                    found.push((event, valarm, alarm_key, at));
+                }
+                continue;
+            }
+
+            let Some(offset) = datetime::parse_duration(&trigger.value) else {
+                continue;
+            };
+            let from_end = trigger
+                .param("RELATED")
+                .is_some_and(|r| r.eq_ignore_ascii_case("END"));
+            for instance in occurrences_of(event, &events, start - offset, end - offset) {
+                let anchor = if from_end {
+                    instance.end
+                } else {
+                    instance.start
+                };
+                let at = anchor + offset;
+                if at >= start && at < end {
+                    found.push((event, valarm, alarm_key.clone(), at));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// The instances of one VEVENT near `[start, end)`. The range is widened by
+/// a day so that alarms relative to the end of long events are not missed.
+fn occurrences_of(
+    event: &Component,
+    events: &[&Component],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<recurrence::Instance> {
+    let mut single = Component::new("VCALENDAR");
+    single.components.push(event.clone());
+    if event.property("RECURRENCE-ID").is_none() {
+        // Stubs carrying only RECURRENCE-ID exclude the overridden
+        // occurrences without contributing instances of their own.
+        for other in events {
+            if let Some(rid) = other.property("RECURRENCE-ID") {
+                let mut stub = Component::new("VEVENT");
+                stub.properties.push(rid.clone());
+                single.components.push(stub);
+            }
+        }
+    }
+    recurrence::instances_in_range(&single, start - Duration::days(1), end + Duration::days(1))
+}
+
+/// Deliver one reminder to the sink.
+async fn dispatch(
+    sink: &ReminderSink,
+    alarm: &DueAlarm,
+    limits: OutboundLimits,
+) -> Result<(), String> {
+    match sink {
+        ReminderSink::Log => {
+            tracing::info!(
+                calendar_id = %alarm.calendar_id,
+                uid = %alarm.object_uid,
+                trigger_at = %alarm.trigger_at,
+                summary = alarm.summary.as_deref().unwrap_or(""),
+                "Reminder"
+            );
+            Ok(())
+        }
+        ReminderSink::Webhook(url) => outbound::post_json(url, &alarm.to_json(), limits).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::events::{self, ObjectFields};
+    use crate::db::users::{self, PasswordPolicy};
+    use crate::db::{self, calendars};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    async fn setup() -> (SqlitePool, String) {
+        let pool = db::test_pool().await;
+        let user = users::create_user(&pool, "alice", None, "password", &PasswordPolicy::default())
+            .await
+            .unwrap();
+        let cal = calendars::create_calendar(&pool, &user.id, "Work", "", "#FF0000", "UTC")
+            .await
+            .unwrap();
+        (pool, cal.id)
+    }
+
+    fn limits(timeout_ms: u64) -> OutboundLimits {
+        OutboundLimits {
+            timeout: StdDuration::from_millis(timeout_ms),
+            max_response_bytes: 1024,
+        }
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        datetime::parse_ical_datetime(value, None).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_due_alarm_dispatched_exactly_once() {
+        let (pool, cal_id) = setup().await;
+        let ical = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:standup@ex.com\r\n\
+                    DTSTART:20260302T091000Z\r\nDTEND:20260302T093000Z\r\nSUMMARY:Standup\r\n\
+                    BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT15M\r\nEND:VALARM\r\n\
+                    BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT3H\r\nEND:VALARM\r\n\
+                    END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let fields = ObjectFields {
+            component_type: "VEVENT",
+            dtstart: Some("20260302T091000Z"),
+            dtend: Some("20260302T093000Z"),
+            summary: Some("Standup"),
+        };
+        events::upsert_object(&pool, &cal_id, "standup@ex.com", ical, fields)
+            .await
+            .unwrap();
+
+        // The 15-minute alarm (08:55) is due; the three-hour alarm (06:10) is
+        // past its late grace
+        let now = at("20260302T085000Z");
+        let window = Duration::minutes(10);
+        let due = due_alarms(&pool, now, window).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].trigger_at, at("20260302T085500Z"));
+        assert_eq!(due[0].summary.as_deref(), Some("Standup"));
+
+        let mut dispatcher = Dispatcher::new(pool.clone(), ReminderSink::Log, limits(1000));
+        assert_eq!(dispatcher.run_once(now, window).await.unwrap(), 1);
+        assert_eq!(dispatcher.run_once(now, window).await.unwrap(), 0);
+        assert!(due_alarms(&pool, now, window).await.unwrap().is_empty());
+
+        // A day later the delivered trigger is forgotten
+        let fired = || async {
+            sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM fired_alarms")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+                .0
+        };
+        assert_eq!(fired().await, 1);
+        let later = now + FIRED_RETENTION + Duration::minutes(10);
+        assert_eq!(dispatcher.run_once(later, window).await.unwrap(), 0);
+        assert_eq!(fired().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_webhook_times_out_and_backs_off() {
+        let (pool, cal_id) = setup().await;
+        let ical = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:review@ex.com\r\n\
+                    DTSTART:20260302T100000Z\r\nDTEND:20260302T103000Z\r\nSUMMARY:Review\r\n\
+                    BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT5M\r\nEND:VALARM\r\n\
+                    END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let fields = ObjectFields {
+            component_type: "VEVENT",
+            dtstart: Some("20260302T100000Z"),
+            dtend: Some("20260302T103000Z"),
+            summary: Some("Review"),
+        };
+        events::upsert_object(&pool, &cal_id, "review@ex.com", ical, fields)
+            .await
+            .unwrap();
+
+        // A webhook that accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                held.push(socket);
+            }
+        });
+
+        let sink = ReminderSink::Webhook(format!("http://{addr}/hook"));
+        let mut dispatcher = Dispatcher::new(pool.clone(), sink, limits(200));
+        let now = at("20260302T095800Z");
+        let window = Duration::minutes(1);
+
+        let started = std::time::Instant::now();
+        assert_eq!(dispatcher.run_once(now, window).await.unwrap(), 0);
+        assert!(started.elapsed() < StdDuration::from_secs(5));
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // Not marked fired, but the next scan waits out the backoff
+        assert_eq!(due_alarms(&pool, now, window).await.unwrap().len(), 1);
+        assert_eq!(dispatcher.run_once(now, window).await.unwrap(), 0);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // Once the backoff has passed the delivery is attempted again
+        let later = now + BACKOFF_BASE;
+        assert_eq!(dispatcher.run_once(later, window).await.unwrap(), 0);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_reminder_sink_from_str() {
+        assert_eq!("log".parse::<ReminderSink>(), Ok(ReminderSink::Log));
+        assert_eq!(
+            "http://localhost:9000/hook".parse::<ReminderSink>(),
+            Ok(ReminderSink::Webhook(
+                "http://localhost:9000/hook".to_string()
+            ))
+        );
+        assert_eq!(
+            "https://hooks.example.com/remind".parse::<ReminderSink>(),
+            Ok(ReminderSink::Webhook(
+                "https://hooks.example.com/remind".to_string()
+            ))
+        );
+        assert!("ftp://example.com".parse::<ReminderSink>().is_err());
+    }
+}
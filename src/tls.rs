@@ -0,0 +1,133 @@
+//! Optional HTTPS for the CalDAV and MCP listeners, enabled by setting both
+//! `TLS_CERT_PATH` and `TLS_KEY_PATH`.
+
+use axum::{Extension, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::net::TcpListener;
+
+use crate::config::Config;
+
+/// Marks a request that arrived over this server's own TLS listener, so
+/// `REQUIRE_TLS_FOR_MCP` accepts it without a trusted proxy's
+/// `X-Forwarded-Proto`.
+#[derive(Debug, Clone, Copy)]
+pub struct ServedOverTls;
+
+/// Load the certificate chain and private key named in the config.
+///
+/// `Ok(None)` when neither path is set. Setting only one of them, or a file
+/// that can't be read or parsed, is an error so the server refuses to start
+/// rather than silently falling back to plain HTTP.
+pub async fn load(config: &Config) -> anyhow::Result<Option<RustlsConfig>> {
+    let (cert, key) = match (&config.tls_cert_path, &config.tls_key_path) {
+        (None, None) => return Ok(None),
+        (Some(cert), Some(key)) => (cert, key),
+        _ => anyhow::bail!("TLS_CERT_PATH and TLS_KEY_PATH must be set together"),
+    };
+    let tls = RustlsConfig::from_pem_file(cert, key).await.map_err(|e| {
+        anyhow::anyhow!("Failed to load TLS certificate '{cert}' and key '{key}': {e}")
+    })?;
+    Ok(Some(tls))
+}
+
+/// Serve `app` on `listener`, over HTTPS when `tls` is given.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    tls: Option<RustlsConfig>,
+) -> std::io::Result<()> {
+    match tls {
+        Some(tls) => {
+            let app = app.layer(Extension(ServedOverTls));
+            axum_server::from_tcp_rustls(listener.into_std()?, tls)
+                .serve(app.into_make_service())
+                .await
+        }
+        None => axum::serve(listener, app).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsConnector;
+    use tokio_rustls::rustls::pki_types::ServerName;
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+    use super::*;
+    use crate::db;
+
+    /// Write a self-signed certificate for `localhost` to temporary files,
+    /// returning a config pointing at them and the certificate.
+    fn self_signed() -> (Config, rcgen::CertifiedKey) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+        let config = Config {
+            tls_cert_path: Some(cert_path.to_string_lossy().into_owned()),
+            tls_key_path: Some(key_path.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        (config, certified)
+    }
+
+    #[tokio::test]
+    async fn test_tls_handshake_on_caldav_listener() {
+        let (config, certified) = self_signed();
+        let tls = load(&config).await.unwrap();
+        assert!(tls.is_some());
+
+        let pool = db::test_pool().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = crate::caldav::router(pool, config);
+        tokio::spawn(serve(listener, app, tls));
+
+        let mut roots = RootCertStore::empty();
+        roots.add(certified.cert.der().clone()).unwrap();
+        let client = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsConnector::from(Arc::new(client))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+            .unwrap();
+
+        stream
+            .write_all(
+                b"GET /.well-known/caldav HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 "), "got: {response}");
+    }
+
+    #[tokio::test]
+    async fn test_load_rejects_incomplete_or_missing_files() {
+        assert!(load(&Config::default()).await.unwrap().is_none());
+
+        let (config, _) = self_signed();
+        let cert_only = Config {
+            tls_key_path: None,
+            ..config.clone()
+        };
+        let err = load(&cert_only).await.unwrap_err().to_string();
+        assert!(err.contains("must be set together"), "got: {err}");
+
+        let missing = Config {
+            tls_key_path: Some("/nonexistent/key.pem".to_string()),
+            ..config
+        };
+        let err = load(&missing).await.unwrap_err().to_string();
+        assert!(err.contains("/nonexistent/key.pem"), "got: {err}");
+    }
+}